@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use saffron_core::domain::auth::{ApiKeyLocation, Auth, OAuth2Grant};
+use saffron_core::domain::environment::{ConnectSettings, ProxySettings, TlsSettings};
+use saffron_http::RetryPolicy;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "saffron")]
@@ -10,19 +14,38 @@ pub struct Cli {
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum Commands {
     #[command(about = "Send an HTTP request")]
     Send {
         #[arg(help = "The URL to send the request to (optional if using --from-collection)")]
         url: Option<String>,
 
-        #[arg(short, long, default_value = "GET", help = "HTTP method")]
+        #[arg(
+            short,
+            long,
+            default_value = "GET",
+            help = "HTTP method, including extension methods such as PURGE"
+        )]
         method: String,
 
         #[arg(short = 'H', long, help = "Headers in key:value format", value_parser = parse_header)]
         header: Vec<(String, String)>,
 
-        #[arg(short, long, help = "Request body (text or JSON)")]
+        #[arg(
+            short,
+            long,
+            value_name = "KEY=VALUE",
+            help = "Query parameter, percent-encoded when sent (repeatable)",
+            value_parser = parse_form
+        )]
+        query: Vec<(String, String)>,
+
+        #[arg(
+            short,
+            long,
+            help = "Request body (text or JSON); @FILE streams a file, @- reads stdin"
+        )]
         body: Option<String>,
 
         #[arg(short, long, help = "JSON request body")]
@@ -31,6 +54,14 @@ pub enum Commands {
         #[arg(short = 'd', long, help = "Form data in key=value format", value_parser = parse_form)]
         data: Vec<(String, String)>,
 
+        #[arg(
+            short = 'F',
+            long,
+            help = "Multipart field in key=value format; key=@FILE[;type=MIME] uploads a file",
+            value_parser = parse_form
+        )]
+        form: Vec<(String, String)>,
+
         #[arg(short, long, help = "Timeout in seconds")]
         timeout: Option<u64>,
 
@@ -40,15 +71,377 @@ pub enum Commands {
         #[arg(short = 'e', long, help = "Environment name to use")]
         env: Option<String>,
 
+        #[arg(
+            long,
+            help = "Send placeholders that can't be resolved as written instead of failing"
+        )]
+        allow_unresolved: bool,
+
         #[arg(short = 'v', long, help = "Verbose output (show headers)")]
         verbose: bool,
 
+        #[arg(
+            long,
+            help = "Show a timing breakdown (DNS, connect, TLS, first byte, transfer)"
+        )]
+        timing: bool,
+
         #[arg(
             short = 'f',
             long = "from-collection",
             help = "Load request from collection (format: collection_name/request_name)"
         )]
         from_collection: Option<String>,
+
+        #[arg(
+            short = 'o',
+            long,
+            value_name = "FILE",
+            help = "Write the response body to a file with a progress bar"
+        )]
+        output: Option<String>,
+
+        #[arg(
+            short = 'C',
+            long = "continue",
+            requires = "output",
+            help = "Resume a partial --output download with a Range request"
+        )]
+        resume: bool,
+
+        #[arg(
+            long,
+            help = "Request a compressed response (gzip, deflate, br, zstd) and decode it"
+        )]
+        compressed: bool,
+
+        #[arg(
+            long,
+            help = "Don't decode compressed responses; keep the body as received"
+        )]
+        raw: bool,
+
+        #[command(flatten)]
+        tls: TlsArgs,
+
+        #[command(flatten)]
+        proxy: ProxyArgs,
+
+        #[command(flatten)]
+        connect: ConnectArgs,
+
+        #[command(flatten)]
+        retry: RetryArgs,
+
+        #[command(flatten)]
+        auth: AuthArgs,
+    },
+
+    #[command(about = "Stream Server-Sent Events and print them as they arrive")]
+    Sse {
+        #[arg(help = "The event stream URL")]
+        url: String,
+
+        #[arg(short = 'H', long, help = "Headers in key:value format", value_parser = parse_header)]
+        header: Vec<(String, String)>,
+
+        #[arg(short = 'e', long, help = "Environment name to use")]
+        env: Option<String>,
+
+        #[arg(
+            long,
+            help = "Send placeholders that can't be resolved as written instead of failing"
+        )]
+        allow_unresolved: bool,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 60,
+            help = "Reconnect after this many seconds without data"
+        )]
+        timeout: u64,
+
+        #[arg(short = 'n', long, value_name = "N", help = "Stop after N events")]
+        max_events: Option<usize>,
+
+        #[arg(
+            long,
+            value_name = "ID",
+            help = "Resume the stream after this event ID (sent as Last-Event-ID)"
+        )]
+        last_event_id: Option<String>,
+
+        #[arg(long, help = "Stop when the stream ends instead of reconnecting")]
+        no_reconnect: bool,
+
+        #[command(flatten)]
+        tls: TlsArgs,
+
+        #[command(flatten)]
+        proxy: ProxyArgs,
+
+        #[command(flatten)]
+        connect: ConnectArgs,
+
+        #[command(flatten)]
+        auth: AuthArgs,
+    },
+
+    #[command(about = "Send a GraphQL query or dump a GraphQL schema")]
+    Gql {
+        #[arg(help = "The GraphQL endpoint URL")]
+        url: String,
+
+        #[arg(
+            short,
+            long,
+            required_unless_present = "introspect",
+            help = "The query or mutation; @FILE reads it from a file"
+        )]
+        query: Option<String>,
+
+        #[arg(
+            long = "var",
+            value_name = "KEY=VALUE",
+            help = "Set a variable; VALUE is read as JSON when it parses, as a string otherwise",
+            value_parser = parse_form
+        )]
+        var: Vec<(String, String)>,
+
+        #[arg(
+            long,
+            value_name = "JSON",
+            help = "All variables as a JSON object; @FILE reads them from a file"
+        )]
+        variables: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Operation to run when the query has several"
+        )]
+        operation: Option<String>,
+
+        #[arg(
+            long,
+            conflicts_with_all = ["query", "var", "variables", "operation"],
+            help = "Print the server's schema as SDL"
+        )]
+        introspect: bool,
+
+        #[arg(
+            short = 'o',
+            long,
+            value_name = "FILE",
+            requires = "introspect",
+            help = "Write the schema to a file instead of printing it"
+        )]
+        output: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "COLLECTION/NAME",
+            conflicts_with = "introspect",
+            help = "Save the request to a collection after sending it"
+        )]
+        save: Option<String>,
+
+        #[arg(short = 'H', long, help = "Headers in key:value format", value_parser = parse_header)]
+        header: Vec<(String, String)>,
+
+        #[arg(short = 'e', long, help = "Environment name to use")]
+        env: Option<String>,
+
+        #[arg(
+            long,
+            help = "Send placeholders that can't be resolved as written instead of failing"
+        )]
+        allow_unresolved: bool,
+
+        #[arg(short, long, default_value_t = 30, help = "Timeout in seconds")]
+        timeout: u64,
+
+        #[arg(short = 'v', long, help = "Verbose output (show headers)")]
+        verbose: bool,
+
+        #[command(flatten)]
+        tls: TlsArgs,
+
+        #[command(flatten)]
+        proxy: ProxyArgs,
+
+        #[command(flatten)]
+        connect: ConnectArgs,
+
+        #[command(flatten)]
+        auth: AuthArgs,
+    },
+
+    #[command(about = "Open a WebSocket connection, interactively or from a script")]
+    Ws {
+        #[arg(
+            help = "The ws:// or wss:// URL",
+            required_unless_present = "saved",
+            conflicts_with = "saved"
+        )]
+        url: Option<String>,
+
+        #[arg(
+            short = 'c',
+            long,
+            value_name = "COLLECTION/NAME",
+            help = "Use a WebSocket connection saved in a collection"
+        )]
+        saved: Option<String>,
+
+        #[arg(short = 'H', long, help = "Headers in key:value format", value_parser = parse_header)]
+        header: Vec<(String, String)>,
+
+        #[arg(short = 'e', long, help = "Environment name to use")]
+        env: Option<String>,
+
+        #[arg(
+            long,
+            help = "Send placeholders that can't be resolved as written instead of failing"
+        )]
+        allow_unresolved: bool,
+
+        #[arg(
+            short,
+            long,
+            value_name = "NAME",
+            help = "Subprotocol to ask for (repeatable, in order of preference)"
+        )]
+        protocol: Vec<String>,
+
+        #[arg(
+            short,
+            long,
+            value_name = "FILE",
+            help = "Send messages and check replies from a script instead of stdin"
+        )]
+        script: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            default_value_t = 30,
+            help = "Connect timeout and how long a script waits for each reply, in seconds"
+        )]
+        timeout: u64,
+
+        #[command(flatten)]
+        tls: TlsArgs,
+
+        #[command(flatten)]
+        proxy: ProxyArgs,
+
+        #[command(flatten)]
+        connect: ConnectArgs,
+
+        #[command(flatten)]
+        auth: AuthArgs,
+    },
+
+    #[command(about = "Call a gRPC method described by .proto files")]
+    Grpc {
+        #[arg(
+            help = "The server address, such as http://localhost:50051",
+            required_unless_present_any = ["saved", "list"],
+            conflicts_with = "saved"
+        )]
+        url: Option<String>,
+
+        #[arg(
+            help = "The method, as package.Service/Method",
+            required_unless_present_any = ["saved", "list"],
+            conflicts_with = "saved"
+        )]
+        method: Option<String>,
+
+        #[arg(
+            short = 'c',
+            long,
+            value_name = "COLLECTION/NAME",
+            help = "Use a gRPC call saved in a collection"
+        )]
+        saved: Option<String>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            help = ".proto file describing the service (repeatable)"
+        )]
+        proto: Vec<String>,
+
+        #[arg(
+            short = 'I',
+            long,
+            value_name = "DIR",
+            help = "Directory to look up imports in (repeatable)"
+        )]
+        import_path: Vec<String>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with = "proto",
+            help = "Binary FileDescriptorSet to use instead of .proto files"
+        )]
+        descriptor_set: Option<String>,
+
+        #[arg(
+            short,
+            long,
+            value_name = "JSON",
+            help = "The request message as JSON; @FILE reads it from a file"
+        )]
+        data: Option<String>,
+
+        #[arg(long, help = "List the services and methods in the schema")]
+        list: bool,
+
+        #[arg(long, help = "Call over gRPC-Web (HTTP/1.1) instead of HTTP/2")]
+        web: bool,
+
+        #[arg(
+            long,
+            value_name = "COLLECTION/NAME",
+            conflicts_with_all = ["saved", "list"],
+            help = "Save the call to a collection after making it"
+        )]
+        save: Option<String>,
+
+        #[arg(short = 'H', long, help = "Metadata in key:value format", value_parser = parse_header)]
+        header: Vec<(String, String)>,
+
+        #[arg(short = 'e', long, help = "Environment name to use")]
+        env: Option<String>,
+
+        #[arg(
+            long,
+            help = "Send placeholders that can't be resolved as written instead of failing"
+        )]
+        allow_unresolved: bool,
+
+        #[arg(short, long, default_value_t = 30, help = "Timeout in seconds")]
+        timeout: u64,
+
+        #[arg(short = 'v', long, help = "Verbose output (show headers and trailers)")]
+        verbose: bool,
+
+        #[command(flatten)]
+        tls: TlsArgs,
+
+        #[command(flatten)]
+        proxy: ProxyArgs,
+
+        #[command(flatten)]
+        connect: ConnectArgs,
+
+        #[command(flatten)]
+        auth: AuthArgs,
     },
 
     #[command(about = "Manage collections")]
@@ -68,6 +461,54 @@ pub enum Commands {
         #[command(subcommand)]
         action: HistoryAction,
     },
+
+    #[command(about = "Manage stored cookies")]
+    Cookies {
+        #[command(subcommand)]
+        action: CookieAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CookieAction {
+    #[command(about = "List stored cookies")]
+    List {
+        #[arg(short, long, help = "Only show cookies for this domain")]
+        domain: Option<String>,
+
+        #[arg(short = 'e', long, help = "Environment whose cookies to use")]
+        env: Option<String>,
+    },
+
+    #[command(about = "Store a cookie as if a server had set it")]
+    Set {
+        #[arg(help = "URL the cookie belongs to")]
+        url: String,
+
+        #[arg(help = "Cookie in Set-Cookie format, e.g. 'session=abc; Path=/; Secure'")]
+        cookie: String,
+
+        #[arg(short = 'e', long, help = "Environment whose cookies to use")]
+        env: Option<String>,
+    },
+
+    #[command(about = "Delete cookies by name")]
+    Delete {
+        #[arg(help = "Cookie name")]
+        name: String,
+
+        #[arg(short, long, help = "Only delete the cookie for this domain")]
+        domain: Option<String>,
+
+        #[arg(short = 'e', long, help = "Environment whose cookies to use")]
+        env: Option<String>,
+    },
+
+    #[command(about = "Delete all stored cookies")]
+    Clear {
+        #[arg(short = 'e', long, help = "Environment whose cookies to use")]
+        env: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -106,6 +547,9 @@ pub enum CollectionAction {
 
         #[arg(short, long, help = "Collection description")]
         description: Option<String>,
+
+        #[command(flatten)]
+        auth: AuthArgs,
     },
 
     #[command(about = "List all collections")]
@@ -131,6 +575,9 @@ pub enum CollectionAction {
         #[arg(short, long, default_value = "GET")]
         method: String,
 
+        #[arg(short, long, value_name = "KEY=VALUE", value_parser = parse_form)]
+        query: Vec<(String, String)>,
+
         #[arg(short = 'H', long, value_parser = parse_header)]
         header: Vec<(String, String)>,
 
@@ -139,6 +586,123 @@ pub enum CollectionAction {
 
         #[arg(short, long)]
         description: Option<String>,
+
+        #[command(flatten)]
+        auth: AuthArgs,
+    },
+
+    #[command(name = "add-ws", about = "Add a WebSocket connection to a collection")]
+    AddWs {
+        #[arg(help = "Collection name")]
+        collection: String,
+
+        #[arg(help = "Connection name")]
+        name: String,
+
+        #[arg(help = "The ws:// or wss:// URL")]
+        url: String,
+
+        #[arg(short = 'H', long, value_parser = parse_header)]
+        header: Vec<(String, String)>,
+
+        #[arg(
+            short,
+            long,
+            value_name = "NAME",
+            help = "Subprotocol to ask for (repeatable)"
+        )]
+        protocol: Vec<String>,
+
+        #[arg(
+            short = 'M',
+            long,
+            value_name = "TEXT",
+            help = "Message to send once connected (repeatable)"
+        )]
+        message: Vec<String>,
+
+        #[arg(short, long)]
+        description: Option<String>,
+
+        #[command(flatten)]
+        auth: AuthArgs,
+    },
+
+    #[command(name = "add-grpc", about = "Add a gRPC call to a collection")]
+    AddGrpc {
+        #[arg(help = "Collection name")]
+        collection: String,
+
+        #[arg(help = "Call name")]
+        name: String,
+
+        #[arg(help = "The server address, such as http://localhost:50051")]
+        url: String,
+
+        #[arg(help = "The method, as package.Service/Method")]
+        method: String,
+
+        #[arg(long, value_name = "FILE", help = ".proto file (repeatable)")]
+        proto: Vec<String>,
+
+        #[arg(
+            short = 'I',
+            long,
+            value_name = "DIR",
+            help = "Directory to look up imports in (repeatable)"
+        )]
+        import_path: Vec<String>,
+
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with = "proto",
+            help = "Binary FileDescriptorSet to use instead of .proto files"
+        )]
+        descriptor_set: Option<String>,
+
+        #[arg(
+            short = 'M',
+            long,
+            value_name = "JSON",
+            help = "The request message as JSON; @FILE reads it from a file"
+        )]
+        message: Option<String>,
+
+        #[arg(long, help = "Call over gRPC-Web (HTTP/1.1) instead of HTTP/2")]
+        web: bool,
+
+        #[arg(short = 'H', long, value_parser = parse_header)]
+        header: Vec<(String, String)>,
+
+        #[arg(short, long)]
+        description: Option<String>,
+
+        #[command(flatten)]
+        auth: AuthArgs,
+    },
+
+    #[command(about = "Send every request in a collection")]
+    Run {
+        #[arg(help = "Collection name")]
+        name: String,
+
+        #[arg(short = 'e', long, help = "Environment to use")]
+        env: Option<String>,
+
+        #[arg(
+            long,
+            help = "Send placeholders that can't be resolved as written instead of failing"
+        )]
+        allow_unresolved: bool,
+
+        #[arg(
+            short,
+            long,
+            default_value = "1",
+            help = "Number of requests to send at once"
+        )]
+        parallel: usize,
     },
 
     #[command(about = "Delete a collection")]
@@ -160,10 +724,17 @@ pub enum CollectionAction {
     Import {
         #[arg(help = "Input file path")]
         input: String,
+
+        #[arg(
+            long,
+            help = "Let {{$env}} and {{$file}} in the collection read environment variables and files"
+        )]
+        allow_local_access: bool,
     },
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)]
 pub enum EnvAction {
     #[command(about = "List all environments")]
     List,
@@ -175,6 +746,15 @@ pub enum EnvAction {
 
         #[arg(help = "Variables in key=value format", value_parser = parse_env_var)]
         variables: Vec<(String, String)>,
+
+        #[command(flatten)]
+        tls: TlsArgs,
+
+        #[command(flatten)]
+        proxy: ProxyArgs,
+
+        #[command(flatten)]
+        connect: ConnectArgs,
     },
 
     #[command(about = "Show environment variables")]
@@ -194,6 +774,389 @@ pub enum EnvAction {
         #[arg(help = "Environment name")]
         name: String,
     },
+
+    #[command(about = "Show the variables a collection needs and which environments lack them")]
+    Check {
+        #[arg(help = "Collection name")]
+        collection: String,
+    },
+}
+
+#[derive(Args, Debug, Default)]
+pub struct TlsArgs {
+    #[arg(
+        short = 'k',
+        long,
+        help = "Skip TLS certificate verification (insecure)"
+    )]
+    pub insecure: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "insecure",
+        help = "Verify TLS certificates even if the environment skips verification"
+    )]
+    pub no_insecure: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Trust an extra CA certificate (PEM, repeatable)"
+    )]
+    pub cacert: Vec<String>,
+
+    #[arg(
+        short = 'E',
+        long,
+        value_name = "FILE",
+        help = "Client certificate for mutual TLS (PEM)"
+    )]
+    pub cert: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Private key for --cert (PEM, defaults to the cert file)"
+    )]
+    pub key: Option<String>,
+
+    #[arg(
+        long = "tls-min",
+        value_name = "VERSION",
+        help = "Minimum TLS version (1.2 or 1.3)"
+    )]
+    pub tls_min: Option<String>,
+}
+
+impl TlsArgs {
+    /// Layers the command-line flags on top of stored settings.
+    pub fn apply_to(&self, settings: &mut TlsSettings) {
+        if self.insecure {
+            settings.insecure = true;
+        } else if self.no_insecure {
+            settings.insecure = false;
+        }
+        settings.ca_certificates.extend(self.cacert.iter().cloned());
+
+        if let Some(cert) = &self.cert {
+            settings.client_certificate = Some(cert.clone());
+            settings.client_key = self.key.clone();
+        } else if let Some(key) = &self.key {
+            settings.client_key = Some(key.clone());
+        }
+
+        if let Some(version) = &self.tls_min {
+            settings.min_version = Some(version.clone());
+        }
+    }
+}
+
+#[derive(Args, Debug, Default)]
+pub struct ProxyArgs {
+    #[arg(
+        short = 'x',
+        long,
+        value_name = "URL",
+        help = "Proxy to use (http://, socks4://, socks4a:// or socks5://)"
+    )]
+    pub proxy: Option<String>,
+
+    #[arg(
+        long = "proxy-user",
+        value_name = "USER:PASSWORD",
+        help = "Credentials for the proxy"
+    )]
+    pub proxy_user: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "HOSTS",
+        value_delimiter = ',',
+        help = "Comma-separated hosts that bypass the proxy (NO_PROXY syntax)"
+    )]
+    pub noproxy: Vec<String>,
+}
+
+impl ProxyArgs {
+    /// Layers the command-line flags on top of stored settings.
+    pub fn apply_to(&self, settings: &mut ProxySettings) {
+        if let Some(url) = &self.proxy {
+            settings.url = Some(url.clone());
+        }
+
+        if let Some(credentials) = &self.proxy_user {
+            let (user, password) = match credentials.split_once(':') {
+                Some((user, password)) => (user, Some(password.to_string())),
+                None => (credentials.as_str(), None),
+            };
+            settings.username = Some(user.to_string());
+            settings.password = password;
+        }
+
+        if !self.noproxy.is_empty() {
+            settings.no_proxy = self.noproxy.clone();
+        }
+    }
+}
+
+#[derive(Args, Debug, Default)]
+pub struct ConnectArgs {
+    #[arg(
+        long,
+        value_name = "HOST:PORT:ADDR",
+        help = "Connect to ADDR for HOST:PORT, keeping Host and SNI (repeatable)"
+    )]
+    pub resolve: Vec<String>,
+
+    #[arg(
+        long = "connect-to",
+        value_name = "HOST1:PORT1:HOST2:PORT2",
+        help = "Connect to HOST2:PORT2 for requests to HOST1:PORT1 (repeatable)"
+    )]
+    pub connect_to: Vec<String>,
+
+    #[arg(
+        short = '4',
+        long,
+        conflicts_with = "ipv6",
+        help = "Only connect over IPv4"
+    )]
+    pub ipv4: bool,
+
+    #[arg(short = '6', long, help = "Only connect over IPv6")]
+    pub ipv6: bool,
+
+    #[arg(
+        long,
+        value_name = "NAME|ADDR",
+        help = "Connect from this local interface or IP address"
+    )]
+    pub interface: Option<String>,
+
+    #[arg(
+        long = "unix-socket",
+        value_name = "PATH",
+        help = "Send requests through this Unix domain socket instead of TCP"
+    )]
+    pub unix_socket: Option<String>,
+}
+
+impl ConnectArgs {
+    /// Layers the command-line flags on top of stored settings. Overrides
+    /// given here are tried before stored ones.
+    pub fn apply_to(&self, settings: &mut ConnectSettings) {
+        settings.resolve.splice(0..0, self.resolve.iter().cloned());
+        settings
+            .connect_to
+            .splice(0..0, self.connect_to.iter().cloned());
+
+        if self.ipv4 {
+            settings.ip_version = Some("4".to_string());
+        } else if self.ipv6 {
+            settings.ip_version = Some("6".to_string());
+        }
+
+        if let Some(interface) = &self.interface {
+            settings.local_address = Some(interface.clone());
+        }
+
+        if let Some(socket) = &self.unix_socket {
+            settings.unix_socket = Some(socket.clone());
+        }
+    }
+}
+
+#[derive(Args, Debug, Default)]
+pub struct RetryArgs {
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Retry timeouts, connection errors and 429/502/503/504 up to N times"
+    )]
+    pub retry: Option<u32>,
+
+    #[arg(
+        long = "retry-delay",
+        value_name = "MS",
+        requires = "retry",
+        help = "Initial backoff in milliseconds, doubled on each retry (default: 500)"
+    )]
+    pub retry_delay: Option<u64>,
+
+    #[arg(
+        long = "retry-max-delay",
+        value_name = "MS",
+        requires = "retry",
+        help = "Longest wait between attempts, including Retry-After (default: 30000)"
+    )]
+    pub retry_max_delay: Option<u64>,
+
+    #[arg(
+        long = "retry-on",
+        value_name = "CODES",
+        value_delimiter = ',',
+        requires = "retry",
+        help = "Comma-separated status codes to retry"
+    )]
+    pub retry_on: Vec<u16>,
+
+    #[arg(
+        long = "retry-all-methods",
+        requires = "retry",
+        help = "Also retry non-idempotent methods such as POST and PATCH"
+    )]
+    pub retry_all_methods: bool,
+}
+
+impl RetryArgs {
+    pub fn to_policy(&self) -> RetryPolicy {
+        let Some(retries) = self.retry else {
+            return RetryPolicy::none();
+        };
+
+        let mut policy = RetryPolicy::with_retries(retries);
+        if let Some(ms) = self.retry_delay {
+            policy.initial_backoff = Duration::from_millis(ms);
+        }
+        if let Some(ms) = self.retry_max_delay {
+            policy.max_backoff = Duration::from_millis(ms);
+        }
+        if !self.retry_on.is_empty() {
+            policy.retry_statuses = self.retry_on.clone();
+        }
+        policy.retry_non_idempotent = self.retry_all_methods;
+        policy
+    }
+}
+
+#[derive(Args, Debug, Default)]
+pub struct AuthArgs {
+    #[arg(
+        short = 'a',
+        long,
+        value_name = "SCHEME:CREDENTIALS",
+        value_parser = parse_auth,
+        help = "Authentication: basic:USER:PASS, digest:USER:PASS, bearer:TOKEN, apikey:NAME:VALUE or apikey-query:NAME:VALUE"
+    )]
+    pub auth: Option<Auth>,
+
+    #[arg(
+        short = 'u',
+        long,
+        value_name = "USER:PASSWORD",
+        conflicts_with = "auth",
+        help = "HTTP Basic credentials"
+    )]
+    pub user: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        conflicts_with_all = ["auth", "user"],
+        help = "Bearer token"
+    )]
+    pub bearer: Option<String>,
+
+    #[arg(
+        long = "oauth2-token-url",
+        value_name = "URL",
+        requires = "client_id",
+        conflicts_with_all = ["auth", "user", "bearer"],
+        help = "Fetch an OAuth2 access token from this endpoint"
+    )]
+    pub oauth2_token_url: Option<String>,
+
+    #[arg(
+        long = "client-id",
+        value_name = "ID",
+        requires = "oauth2_token_url",
+        help = "OAuth2 client id"
+    )]
+    pub client_id: Option<String>,
+
+    #[arg(
+        long = "client-secret",
+        value_name = "SECRET",
+        requires = "oauth2_token_url",
+        help = "OAuth2 client secret"
+    )]
+    pub client_secret: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "SCOPE",
+        requires = "oauth2_token_url",
+        help = "OAuth2 scope to request"
+    )]
+    pub scope: Option<String>,
+
+    #[arg(
+        long = "oauth2-user",
+        value_name = "USER:PASSWORD",
+        requires = "oauth2_token_url",
+        help = "Use the OAuth2 password grant instead of client credentials"
+    )]
+    pub oauth2_user: Option<String>,
+}
+
+impl AuthArgs {
+    pub fn to_auth(&self) -> Option<Auth> {
+        if let Some(auth) = &self.auth {
+            return Some(auth.clone());
+        }
+
+        if let Some(credentials) = &self.user {
+            let (user, password) = credentials.split_once(':').unwrap_or((credentials, ""));
+            return Some(Auth::basic(user, password));
+        }
+
+        if let Some(token) = &self.bearer {
+            return Some(Auth::bearer(token));
+        }
+
+        let token_url = self.oauth2_token_url.clone()?;
+        let grant = match &self.oauth2_user {
+            Some(credentials) => {
+                let (user, password) = credentials.split_once(':').unwrap_or((credentials, ""));
+                OAuth2Grant::Password {
+                    username: user.to_string(),
+                    password: password.to_string(),
+                }
+            }
+            None => OAuth2Grant::ClientCredentials,
+        };
+
+        Some(Auth::OAuth2 {
+            token_url,
+            client_id: self.client_id.clone().unwrap_or_default(),
+            client_secret: self.client_secret.clone(),
+            scope: self.scope.clone(),
+            grant,
+        })
+    }
+}
+
+fn parse_auth(s: &str) -> Result<Auth, String> {
+    let invalid = || {
+        format!(
+            "Invalid auth: '{}'. Expected basic:USER:PASS, digest:USER:PASS, bearer:TOKEN, apikey:NAME:VALUE or apikey-query:NAME:VALUE",
+            s
+        )
+    };
+
+    let (scheme, credentials) = s.split_once(':').ok_or_else(invalid)?;
+    let pair = || credentials.split_once(':').ok_or_else(invalid);
+
+    match scheme.to_lowercase().as_str() {
+        "basic" => pair().map(|(user, password)| Auth::basic(user, password)),
+        "digest" => pair().map(|(user, password)| Auth::digest(user, password)),
+        "bearer" => Ok(Auth::bearer(credentials)),
+        "apikey" => pair().map(|(name, value)| Auth::api_key(name, value, ApiKeyLocation::Header)),
+        "apikey-query" => {
+            pair().map(|(name, value)| Auth::api_key(name, value, ApiKeyLocation::Query))
+        }
+        _ => Err(invalid()),
+    }
 }
 
 fn parse_header(s: &str) -> Result<(String, String), String> {
@@ -0,0 +1,54 @@
+use saffron_http::{Cookie, SameSite};
+use serde::{Deserialize, Serialize};
+
+/// On-disk form of a cookie. Times are Unix timestamps in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub host_only: bool,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<i64>,
+    pub secure: bool,
+    pub http_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<String>,
+    pub created: i64,
+}
+
+impl StoredCookie {
+    pub fn from_cookie(cookie: &Cookie) -> Self {
+        Self {
+            name: cookie.name.clone(),
+            value: cookie.value.clone(),
+            domain: cookie.domain.clone(),
+            host_only: cookie.host_only,
+            path: cookie.path.clone(),
+            expires: cookie.expires.map(|t| t.timestamp()),
+            secure: cookie.secure,
+            http_only: cookie.http_only,
+            same_site: cookie.same_site.map(|s| s.as_str().to_string()),
+            created: cookie.created.timestamp(),
+        }
+    }
+
+    pub fn to_cookie(&self) -> Cookie {
+        let time = |secs: i64| {
+            chrono::DateTime::from_timestamp(secs, 0).unwrap_or(chrono::DateTime::UNIX_EPOCH)
+        };
+        Cookie {
+            name: self.name.clone(),
+            value: self.value.clone(),
+            domain: self.domain.clone(),
+            host_only: self.host_only,
+            path: self.path.clone(),
+            expires: self.expires.map(time),
+            secure: self.secure,
+            http_only: self.http_only,
+            same_site: self.same_site.as_deref().and_then(SameSite::parse),
+            created: time(self.created),
+        }
+    }
+}
@@ -3,29 +3,60 @@ use crate::history::{HistoryEntry, HistoryRequest, HistoryResponse};
 use crate::output::*;
 use crate::storage::Storage;
 use colored::Colorize;
-use saffron_core::domain::collection::{Collection, SavedRequest, SerializableRequest};
-use saffron_core::domain::environment::Environment;
-use saffron_core::domain::request::{HttpMethod, HttpRequest, RequestBody};
+use saffron_core::domain::collection::{
+    Collection, SavedGrpcCall, SavedRequest, SavedWebSocket, SerializableRequest,
+};
+use saffron_core::domain::environment::{ConnectSettings, Environment, ProxySettings, TlsSettings};
+use saffron_core::domain::request::{
+    BodyStream, FormDataContent, FormDataPart, HttpMethod, HttpRequest, RequestBody,
+};
+use saffron_core::domain::request_body::{encode_form_urlencoded, encode_graphql};
+use saffron_core::domain::template::{Template, TemplateError, TemplateLocation, Unresolved};
 use saffron_data::importers::{ImportedCollection, auto_import};
-use saffron_http::{HttpClient, HttpClientConfig};
-use std::collections::HashMap;
-use std::time::Instant;
+use saffron_data::json::{Json, JsonElement};
+use saffron_data::parse::Parse;
+use saffron_data::proto::ProtoSchema;
+use saffron_http::{
+    BatchConfig, CloseFrame, ConnectConfig, Cookie, GraphQLError, HttpClient, HttpClientConfig,
+    HttpError, Message, ProxyConfig, SseConfig, TlsConfig, WebSocket, helpers,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::io::BufRead;
+use std::path::Path;
+use std::sync::mpsc::{self, TryRecvError};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Cookie jar used when no environment is selected.
+const DEFAULT_COOKIE_JAR: &str = "default";
 
 #[allow(clippy::too_many_arguments)]
 pub fn handle_send(
     url: Option<String>,
     method: String,
     headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
     body: Option<String>,
     json: Option<String>,
     data: Vec<(String, String)>,
+    form: Vec<(String, String)>,
     timeout: Option<u64>,
     follow_redirects: bool,
     env: Option<String>,
+    allow_unresolved: bool,
     verbose: bool,
+    timing: bool,
     from_collection: Option<String>,
+    output: Option<String>,
+    resume: bool,
+    compressed: bool,
+    raw: bool,
+    tls: TlsArgs,
+    proxy: ProxyArgs,
+    connect: ConnectArgs,
+    retry: RetryArgs,
+    auth: AuthArgs,
 ) {
-    let x = 10;
     let storage = match Storage::new() {
         Ok(s) => s,
         Err(e) => {
@@ -35,189 +66,1290 @@ pub fn handle_send(
     };
 
     // Load request from collection if specified
-    let (final_url, final_method, final_headers, final_body, final_json) =
-        if let Some(ref collection_path) = from_collection {
-            let parts: Vec<&str> = collection_path.split('/').collect();
-            if parts.len() != 2 {
-                print_error("Invalid format. Use: collection_name/request_name");
+    let (
+        final_url,
+        final_method,
+        final_headers,
+        final_query,
+        final_body,
+        final_json,
+        saved_auth,
+        saved_graphql,
+        local_access,
+    ) = if let Some(ref collection_path) = from_collection {
+        let parts: Vec<&str> = collection_path.split('/').collect();
+        if parts.len() != 2 {
+            print_error("Invalid format. Use: collection_name/request_name");
+            return;
+        }
+
+        let collection_name = parts[0];
+        let request_name = parts[1];
+
+        let collections = storage.load_collections().unwrap_or_default();
+        let collection = collections.iter().find(|c| c.name == collection_name);
+
+        if collection.is_none() {
+            print_error(&format!("Collection '{}' not found", collection_name));
+            return;
+        }
+
+        let saved_request = collection
+            .unwrap()
+            .requests
+            .iter()
+            .find(|r| r.name == request_name);
+
+        if saved_request.is_none() {
+            print_error(&format!(
+                "Request '{}' not found in collection '{}'",
+                request_name, collection_name
+            ));
+            return;
+        }
+
+        let req = saved_request.unwrap();
+        let loaded_auth = req
+            .request
+            .auth
+            .clone()
+            .or_else(|| collection.unwrap().auth.clone());
+        let loaded_url = req.request.url.clone();
+        let loaded_method = req.request.method.clone();
+        let loaded_headers = req.request.headers.clone();
+        let loaded_query = req.request.query.clone();
+        let loaded_body = req.request.body.clone();
+        let loaded_graphql = req.request.graphql.clone();
+
+        // CLI args override collection values
+        let use_url = url.unwrap_or(loaded_url);
+        let use_headers = if headers.is_empty() {
+            loaded_headers
+        } else {
+            headers
+        };
+        // A -q parameter replaces saved ones of the same name
+        let mut use_query: Vec<(String, String)> = loaded_query
+            .into_iter()
+            .filter(|(name, _)| !query.iter().any(|(n, _)| n == name))
+            .collect();
+        use_query.extend(query);
+        let use_body = body.or(loaded_body.clone());
+        let use_json = json.or(loaded_body);
+
+        (
+            use_url,
+            loaded_method,
+            use_headers,
+            use_query,
+            use_body,
+            use_json,
+            loaded_auth,
+            loaded_graphql,
+            collection.unwrap().local_access,
+        )
+    } else {
+        if url.is_none() {
+            print_error("URL is required when not using --from-collection");
+            return;
+        }
+        (
+            url.unwrap(),
+            method,
+            headers,
+            query,
+            body,
+            json,
+            None,
+            None,
+            true,
+        )
+    };
+
+    let env_set = storage.load_environment_set().unwrap_or_default();
+    let active_env = match &env {
+        Some(name) => match env_set.get(name) {
+            Some(environment) => Some(environment),
+            None => {
+                print_error(&format!("Environment '{}' not found", name));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let http_method = match final_method.parse::<HttpMethod>() {
+        Ok(m) => m,
+        Err(e) => {
+            print_error(&e.to_string());
+            return;
+        }
+    };
+
+    let mut request = HttpRequest::new(http_method, final_url);
+
+    for (key, value) in final_headers {
+        request = request.with_header(key, value);
+    }
+
+    for (name, value) in final_query {
+        request = request.with_query(name, value);
+    }
+
+    if let Some(json_body) = final_json {
+        request = request.with_json_body(json_body);
+    } else if !data.is_empty() {
+        request = request.with_body(RequestBody::FormUrlEncoded(data));
+    } else if !form.is_empty() {
+        let mut parts = Vec::new();
+        for (name, value) in form {
+            match form_part(name, value) {
+                Ok(part) => parts.push(part),
+                Err(e) => {
+                    print_error(&e);
+                    return;
+                }
+            }
+        }
+        request = request.with_body(RequestBody::FormData(parts));
+    } else if let Some(source) = final_body.as_deref().and_then(|b| b.strip_prefix('@')) {
+        match open_body_stream(source) {
+            Ok(stream) => request = request.with_stream_body(stream),
+            Err(e) => {
+                print_error(&format!("Failed to read body from '{}': {}", source, e));
+                return;
+            }
+        }
+    } else if let Some(text_body) = final_body {
+        request = request.with_text_body(text_body);
+    } else if let Some(graphql) = saved_graphql {
+        request = request.with_body(RequestBody::GraphQL {
+            query: graphql.query,
+            variables: graphql.variables,
+            operation_name: graphql.operation_name,
+        });
+    }
+
+    if let Some(t) = timeout {
+        request = request.with_timeout(t);
+    }
+
+    request = request.follow_redirects(follow_redirects);
+
+    // Precedence: command line, then the saved request, then its collection
+    if let Some(auth) = auth.to_auth().or(saved_auth) {
+        request = request.with_auth(auth);
+    }
+
+    let (request, unresolved) = request.resolve_with(&variables(active_env), local_access);
+    check_unresolved(&unresolved, allow_unresolved);
+
+    // Precedence: command line, then the environment, then HTTP_PROXY & co.
+    let mut config = match client_config(active_env, &tls, &proxy, &connect) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e);
+            return;
+        }
+    };
+
+    let cookie_env = env.as_deref().unwrap_or(DEFAULT_COOKIE_JAR);
+    let cookie_jar = match storage.load_cookie_jar(cookie_env) {
+        Ok(jar) => jar,
+        Err(e) => {
+            print_error(&format!("Failed to load cookies: {}", e));
+            return;
+        }
+    };
+
+    config.timeout_seconds = timeout.unwrap_or(30);
+    config.follow_redirects = follow_redirects;
+    config.cookie_jar = Some(cookie_jar.clone());
+    config.retry = retry.to_policy();
+    config.compressed = compressed;
+    config.decompress = !raw;
+
+    // Downloads are streamed to disk, so the in-memory size limit doesn't apply
+    if output.is_some() {
+        config.max_response_size = None;
+    }
+
+    let client = match HttpClient::try_with_config(config) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e.to_string());
+            return;
+        }
+    };
+
+    let start = Instant::now();
+    let result = match &output {
+        Some(path) => {
+            let mut progress = ProgressBar::new();
+            let result =
+                client.download_to_file(&request, Path::new(path), resume, |p| progress.update(p));
+            progress.finish();
+            result
+        }
+        None => client.send(&request),
+    };
+
+    // Cookies from redirect hops are kept even if the request later failed
+    if let Err(e) = storage.save_cookie_jar(cookie_env, &cookie_jar) {
+        eprintln!("Warning: Failed to save cookies: {}", e);
+    }
+
+    match result {
+        Ok(response) => {
+            let duration_ms = start.elapsed().as_millis() as u64;
+
+            let history_request = HistoryRequest {
+                method: request.method.as_str().to_string(),
+                url: request.full_url().unwrap_or_else(|_| request.url.clone()),
+                headers: request.headers.clone(),
+                body: history_body(&request.body),
+            };
+
+            let saved_to = output
+                .as_ref()
+                .filter(|_| response.is_success() || (resume && response.status == 416));
+
+            let mut history_response = HistoryResponse::from_response(&response);
+            if let Some(path) = saved_to {
+                history_response.body_preview = format!("<saved to {}>", path);
+            }
+            let entry = HistoryEntry::new(history_request, history_response, duration_ms)
+                .with_timings(&response.timings)
+                .with_retries(&response.retries);
+
+            if let Err(e) = storage.save_history_entry(&entry) {
+                eprintln!("Warning: Failed to save to history: {}", e);
+            }
+
+            match saved_to {
+                Some(path) => print_download(&response, path, verbose),
+                None => print_response(&response, verbose),
+            }
+
+            if let RequestBody::GraphQL { .. } = request.body {
+                let errors = GraphQLError::from_response(&response);
+                if !errors.is_empty() {
+                    print_graphql_errors(&errors);
+                }
+            }
+
+            if timing {
+                print_timings(&response.timings);
+            }
+        }
+        Err(e) => print_error(&format!("Request failed: {}", e)),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_sse(
+    url: String,
+    headers: Vec<(String, String)>,
+    env: Option<String>,
+    allow_unresolved: bool,
+    timeout: u64,
+    max_events: Option<usize>,
+    last_event_id: Option<String>,
+    no_reconnect: bool,
+    tls: TlsArgs,
+    proxy: ProxyArgs,
+    connect: ConnectArgs,
+    auth: AuthArgs,
+) {
+    let storage = match Storage::new() {
+        Ok(s) => s,
+        Err(e) => {
+            print_error(&format!("Failed to initialize storage: {}", e));
+            return;
+        }
+    };
+
+    let env_set = storage.load_environment_set().unwrap_or_default();
+    let active_env = match &env {
+        Some(env_name) => match env_set.get(env_name) {
+            Some(environment) => Some(environment),
+            None => {
+                print_error(&format!("Environment '{}' not found", env_name));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    // Without a request timeout the client timeout only applies while idle
+    let mut request = HttpRequest::get(&url).without_timeout();
+    for (name, value) in headers {
+        request = request.with_header(&name, &value);
+    }
+    if let Some(auth) = auth.to_auth() {
+        request = request.with_auth(auth);
+    }
+    let (request, unresolved) = prepare_request(request, active_env, true);
+    check_unresolved(&unresolved, allow_unresolved);
+
+    let mut config = match client_config(active_env, &tls, &proxy, &connect) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e);
+            return;
+        }
+    };
+
+    let cookie_env = env.as_deref().unwrap_or(DEFAULT_COOKIE_JAR);
+    let cookie_jar = match storage.load_cookie_jar(cookie_env) {
+        Ok(jar) => jar,
+        Err(e) => {
+            print_error(&format!("Failed to load cookies: {}", e));
+            return;
+        }
+    };
+    config.timeout_seconds = timeout;
+    config.cookie_jar = Some(cookie_jar.clone());
+
+    let client = match HttpClient::try_with_config(config) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e.to_string());
+            return;
+        }
+    };
+
+    let sse_config = SseConfig {
+        reconnect: !no_reconnect,
+        last_event_id,
+        ..Default::default()
+    };
+
+    print_info(&format!("Listening to {} (Ctrl+C to stop)", request.url));
+    let mut stream = client.events(&request, sse_config);
+    let mut received = 0;
+    let mut reconnects = 0;
+
+    while max_events.is_none_or(|max| received < max) {
+        let resume_from = stream.last_event_id().map(str::to_string);
+        let Some(result) = stream.next() else {
+            print_info(&format!("Stream closed after {} events", received));
+            break;
+        };
+
+        if stream.reconnects() > reconnects {
+            reconnects = stream.reconnects();
+            match resume_from {
+                Some(id) => print_info(&format!("Reconnected, resuming after event {}", id)),
+                None => print_info("Reconnected"),
+            }
+        }
+
+        match result {
+            Ok(event) => {
+                received += 1;
+                print_event(&event);
+            }
+            Err(e) => {
+                print_error(&e.to_string());
+                break;
+            }
+        }
+    }
+
+    if let Err(e) = storage.save_cookie_jar(cookie_env, &cookie_jar) {
+        eprintln!("Warning: Failed to save cookies: {}", e);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_gql(
+    url: String,
+    query: Option<String>,
+    vars: Vec<(String, String)>,
+    variables: Option<String>,
+    operation: Option<String>,
+    introspect: bool,
+    output: Option<String>,
+    save: Option<String>,
+    headers: Vec<(String, String)>,
+    env: Option<String>,
+    allow_unresolved: bool,
+    timeout: u64,
+    verbose: bool,
+    tls: TlsArgs,
+    proxy: ProxyArgs,
+    connect: ConnectArgs,
+    auth: AuthArgs,
+) {
+    let storage = match Storage::new() {
+        Ok(s) => s,
+        Err(e) => {
+            print_error(&format!("Failed to initialize storage: {}", e));
+            return;
+        }
+    };
+
+    let env_set = storage.load_environment_set().unwrap_or_default();
+    let active_env = match &env {
+        Some(env_name) => match env_set.get(env_name) {
+            Some(environment) => Some(environment),
+            None => {
+                print_error(&format!("Environment '{}' not found", env_name));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let mut request = HttpRequest::post(&url);
+    if !introspect {
+        let query = match read_arg_file(&query.unwrap_or_default()) {
+            Ok(q) => q,
+            Err(e) => {
+                print_error(&e);
+                return;
+            }
+        };
+        let variables = match graphql_variables(variables.as_deref(), vars) {
+            Ok(v) => v,
+            Err(e) => {
+                print_error(&e);
+                return;
+            }
+        };
+        request = request.with_body(RequestBody::GraphQL {
+            query,
+            variables,
+            operation_name: operation,
+        });
+    }
+    for (name, value) in headers {
+        request = request.with_header(&name, &value);
+    }
+    if let Some(auth) = auth.to_auth() {
+        request = request.with_auth(auth);
+    }
+    let unresolved = request.clone();
+    let (request, placeholders) = prepare_request(request.with_timeout(timeout), active_env, true);
+    check_unresolved(&placeholders, allow_unresolved);
+
+    let mut config = match client_config(active_env, &tls, &proxy, &connect) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e);
+            return;
+        }
+    };
+
+    let cookie_env = env.as_deref().unwrap_or(DEFAULT_COOKIE_JAR);
+    let cookie_jar = match storage.load_cookie_jar(cookie_env) {
+        Ok(jar) => jar,
+        Err(e) => {
+            print_error(&format!("Failed to load cookies: {}", e));
+            return;
+        }
+    };
+    config.timeout_seconds = timeout;
+    config.cookie_jar = Some(cookie_jar.clone());
+
+    let client = match HttpClient::try_with_config(config) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e.to_string());
+            return;
+        }
+    };
+
+    if introspect {
+        let result = client.introspect(&request);
+        if let Err(e) = storage.save_cookie_jar(cookie_env, &cookie_jar) {
+            eprintln!("Warning: Failed to save cookies: {}", e);
+        }
+        match (result, output) {
+            (Ok(sdl), Some(path)) => match std::fs::write(&path, sdl) {
+                Ok(_) => print_success(&format!("Saved schema to {}", path)),
+                Err(e) => print_error(&format!("Failed to write {}: {}", path, e)),
+            },
+            (Ok(sdl), None) => print!("{}", sdl),
+            (Err(e), _) => print_error(&e.to_string()),
+        }
+        return;
+    }
+
+    let start = Instant::now();
+    let result = client.send(&request);
+    if let Err(e) = storage.save_cookie_jar(cookie_env, &cookie_jar) {
+        eprintln!("Warning: Failed to save cookies: {}", e);
+    }
+
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => {
+            print_error(&format!("Request failed: {}", e));
+            return;
+        }
+    };
+
+    let history_request = HistoryRequest {
+        method: request.method.as_str().to_string(),
+        url: request.url.clone(),
+        headers: request.headers.clone(),
+        body: history_body(&request.body),
+    };
+    let entry = HistoryEntry::new(
+        history_request,
+        HistoryResponse::from_response(&response),
+        start.elapsed().as_millis() as u64,
+    )
+    .with_timings(&response.timings)
+    .with_retries(&response.retries);
+    if let Err(e) = storage.save_history_entry(&entry) {
+        eprintln!("Warning: Failed to save to history: {}", e);
+    }
+
+    print_response(&response, verbose);
+    let errors = GraphQLError::from_response(&response);
+    if !errors.is_empty() {
+        print_graphql_errors(&errors);
+    }
+
+    if let Some(target) = save {
+        let Some((collection_name, name)) = target.split_once('/') else {
+            print_error("Expected --save as COLLECTION/NAME");
+            return;
+        };
+        let mut collection = match storage.load_collection(collection_name) {
+            Ok(c) => c,
+            Err(_) => {
+                print_error(&format!("Collection '{}' not found", collection_name));
+                return;
+            }
+        };
+        collection.add_request(SavedRequest::new(
+            uuid::Uuid::new_v4().to_string(),
+            name,
+            &unresolved,
+        ));
+        match storage.save_collection(&collection) {
+            Ok(_) => print_success(&format!(
+                "Request '{}' added to collection '{}'",
+                name, collection_name
+            )),
+            Err(e) => print_error(&format!("Failed to save collection: {}", e)),
+        }
+    }
+}
+
+/// Reads `@FILE` arguments from the file, without the trailing newline, and
+/// returns any other value as is.
+fn read_arg_file(value: &str) -> Result<String, String> {
+    match value.strip_prefix('@') {
+        Some(path) => std::fs::read_to_string(path)
+            .map(|content| content.trim_end().to_string())
+            .map_err(|e| format!("Failed to read '{}': {}", path, e)),
+        None => Ok(value.to_string()),
+    }
+}
+
+/// Builds the variables object from `--variables` with each `--var` set on
+/// top. Values that parse as JSON are kept as JSON, so `--var limit=10`
+/// sends a number and `--var name=Ada` a string.
+fn graphql_variables(
+    variables: Option<&str>,
+    vars: Vec<(String, String)>,
+) -> Result<Option<String>, String> {
+    let mut object = match variables {
+        Some(variables) => {
+            let json = read_arg_file(variables)?;
+            match Json::parse(json) {
+                Ok(Json {
+                    root: JsonElement::Object(object),
+                }) => object,
+                Ok(_) => return Err("--variables must be a JSON object".to_string()),
+                Err(e) => return Err(format!("Invalid --variables JSON: {}", e.message)),
+            }
+        }
+        None if vars.is_empty() => return Ok(None),
+        None => HashMap::new(),
+    };
+
+    for (key, value) in vars {
+        let value = match Json::parse(value.as_str()) {
+            Ok(json) => json.root,
+            Err(_) => JsonElement::String(value),
+        };
+        object.insert(key, value);
+    }
+    Ok(Some(JsonElement::Object(object).to_string()))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_ws(
+    url: Option<String>,
+    saved: Option<String>,
+    headers: Vec<(String, String)>,
+    env: Option<String>,
+    allow_unresolved: bool,
+    protocols: Vec<String>,
+    script: Option<String>,
+    timeout: u64,
+    tls: TlsArgs,
+    proxy: ProxyArgs,
+    connect: ConnectArgs,
+    auth: AuthArgs,
+) {
+    let storage = match Storage::new() {
+        Ok(s) => s,
+        Err(e) => {
+            print_error(&format!("Failed to initialize storage: {}", e));
+            return;
+        }
+    };
+
+    let env_set = storage.load_environment_set().unwrap_or_default();
+    let active_env = match &env {
+        Some(env_name) => match env_set.get(env_name) {
+            Some(environment) => Some(environment),
+            None => {
+                print_error(&format!("Environment '{}' not found", env_name));
+                return;
+            }
+        },
+        None => None,
+    };
+
+    // Ad-hoc connections are the user's own, so only saved ones are limited
+    let (mut request, mut messages, local_access) = match saved {
+        Some(saved) => {
+            let Some((collection_name, name)) = saved.split_once('/') else {
+                print_error("Expected --saved as COLLECTION/NAME");
+                return;
+            };
+            let collection = match storage.load_collection(collection_name) {
+                Ok(c) => c,
+                Err(_) => {
+                    print_error(&format!("Collection '{}' not found", collection_name));
+                    return;
+                }
+            };
+            let Some(websocket) = collection
+                .websockets
+                .iter()
+                .find(|w| w.name == name || w.id == name)
+            else {
+                print_error(&format!(
+                    "WebSocket '{}' not found in collection '{}'",
+                    name, collection_name
+                ));
                 return;
+            };
+            (
+                collection.websocket_request(websocket),
+                websocket.messages.clone(),
+                collection.local_access,
+            )
+        }
+        None => (HttpRequest::get(url.unwrap_or_default()), Vec::new(), true),
+    };
+
+    for (name, value) in headers {
+        request = request.with_header(&name, &value);
+    }
+    if !protocols.is_empty() {
+        request
+            .headers
+            .insert("Sec-WebSocket-Protocol", protocols.join(", "));
+    }
+    if let Some(auth) = auth.to_auth() {
+        request = request.with_auth(auth);
+    }
+    let (request, mut unresolved) = prepare_request(request, active_env, local_access);
+    messages = messages
+        .iter()
+        .map(|message| {
+            let (message, errors) =
+                resolve_checked(active_env, TemplateLocation::Body, message, local_access);
+            unresolved.extend(errors);
+            message
+        })
+        .collect();
+    check_unresolved(&unresolved, allow_unresolved);
+
+    let mut config = match client_config(active_env, &tls, &proxy, &connect) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e);
+            return;
+        }
+    };
+
+    let cookie_env = env.as_deref().unwrap_or(DEFAULT_COOKIE_JAR);
+    let cookie_jar = match storage.load_cookie_jar(cookie_env) {
+        Ok(jar) => jar,
+        Err(e) => {
+            print_error(&format!("Failed to load cookies: {}", e));
+            return;
+        }
+    };
+    config.timeout_seconds = timeout;
+    config.cookie_jar = Some(cookie_jar.clone());
+
+    let client = match HttpClient::try_with_config(config) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e.to_string());
+            return;
+        }
+    };
+
+    let mut ws = match client.websocket(&request) {
+        Ok(ws) => ws,
+        Err(e) => {
+            print_error(&e.to_string());
+            return;
+        }
+    };
+    if let Err(e) = storage.save_cookie_jar(cookie_env, &cookie_jar) {
+        eprintln!("Warning: Failed to save cookies: {}", e);
+    }
+
+    match ws.protocol() {
+        Some(protocol) => print_success(&format!(
+            "Connected to {} (protocol {})",
+            ws.url(),
+            protocol
+        )),
+        None => print_success(&format!("Connected to {}", ws.url())),
+    }
+
+    for message in messages {
+        if let Err(e) = send_ws_message(&mut ws, Message::Text(message)) {
+            print_error(&e.to_string());
+            return;
+        }
+    }
+
+    match script {
+        Some(path) => match std::fs::read_to_string(&path) {
+            Ok(script) => {
+                if !run_ws_script(&mut ws, &script) {
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                print_error(&format!("Failed to read {}: {}", path, e));
+                std::process::exit(1);
+            }
+        },
+        None => run_ws_interactive(&mut ws),
+    }
+}
+
+/// Sends lines from stdin as text messages and prints what arrives, until
+/// either side closes. `/ping` and `/close [code [reason]]` send control
+/// frames; `//` at the start of a line sends a literal `/`.
+fn run_ws_interactive(ws: &mut WebSocket) {
+    print_info("Type a message and press Enter to send it; /close or Ctrl+D to disconnect");
+
+    // Reading stdin blocks, so it happens on its own thread while this one
+    // polls the socket
+    let (lines, input) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    if let Err(e) = ws.set_read_timeout(Some(Duration::from_millis(100))) {
+        print_error(&e.to_string());
+        return;
+    }
+
+    loop {
+        match input.try_recv() {
+            Ok(line) if line.is_empty() => {}
+            Ok(line) => {
+                let sent = if let Some(text) = line.strip_prefix("//") {
+                    send_ws_message(ws, Message::Text(format!("/{}", text)))
+                } else if let Some(data) = line.strip_prefix("/ping") {
+                    send_ws_message(ws, Message::Ping(data.trim().as_bytes().to_vec()))
+                } else if let Some(args) = line.strip_prefix("/close") {
+                    match parse_close_args(args) {
+                        Ok(frame) => return close_ws(ws, frame),
+                        Err(e) => {
+                            print_error(&e);
+                            Ok(())
+                        }
+                    }
+                } else if line.starts_with('/') {
+                    print_error(&format!("Unknown command {}", line));
+                    Ok(())
+                } else {
+                    send_ws_message(ws, Message::Text(line))
+                };
+                if let Err(e) = sent {
+                    print_error(&e.to_string());
+                    return;
+                }
+            }
+            Err(TryRecvError::Disconnected) => return close_ws(ws, Some(CloseFrame::normal())),
+            Err(TryRecvError::Empty) => {}
+        }
+
+        match ws.read() {
+            Ok(message) => {
+                print_ws_message(false, &message);
+                if let Message::Close(_) = message {
+                    return;
+                }
+            }
+            Err(HttpError::Timeout) => {}
+            Err(e) => {
+                print_error(&e.to_string());
+                return;
+            }
+        }
+    }
+}
+
+/// Runs a script of one step per line: `> text` sends a message, `< text`
+/// waits for a reply equal to `text` and `<~ text` for one containing it.
+/// Blank lines and lines starting with `#` are skipped. Returns whether
+/// every step passed; a line that isn't a step stops the script as failed.
+fn run_ws_script(ws: &mut WebSocket, script: &str) -> bool {
+    let mut passed = 0;
+    let mut failed = 0;
+
+    for (number, line) in script.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (expected, contains) = if let Some(text) = line.strip_prefix("<~") {
+            (text.strip_prefix(' ').unwrap_or(text), true)
+        } else if let Some(text) = line.strip_prefix('<') {
+            (text.strip_prefix(' ').unwrap_or(text), false)
+        } else if let Some(text) = line.strip_prefix('>') {
+            let text = text.strip_prefix(' ').unwrap_or(text);
+            if let Err(e) = send_ws_message(ws, Message::Text(text.to_string())) {
+                print_error(&e.to_string());
+                return false;
+            }
+            continue;
+        } else {
+            print_error(&format!(
+                "Line {}: expected '>', '<' or '<~' at the start of '{}'",
+                number + 1,
+                line
+            ));
+            if !ws.is_closed() {
+                close_ws(ws, Some(CloseFrame::normal()));
+            }
+            return false;
+        };
+
+        let reply = match next_data_message(ws) {
+            Ok(reply) => reply,
+            Err(e) => {
+                failed += 1;
+                println!(
+                    "  {} line {}: no reply ({})",
+                    "✗".red().bold(),
+                    number + 1,
+                    e
+                );
+                break;
+            }
+        };
+
+        let text = match &reply {
+            Message::Text(text) => text.clone(),
+            Message::Binary(data) => String::from_utf8_lossy(data).into_owned(),
+            _ => String::new(),
+        };
+        let matched = if contains {
+            text.contains(expected)
+        } else {
+            text == expected
+        };
+
+        if matched {
+            passed += 1;
+            println!("  {} line {}: {}", "✓".green().bold(), number + 1, text);
+        } else {
+            failed += 1;
+            println!(
+                "  {} line {}: expected {}'{}', got '{}'",
+                "✗".red().bold(),
+                number + 1,
+                if contains { "a reply containing " } else { "" },
+                expected,
+                text
+            );
+        }
+    }
+
+    if !ws.is_closed() {
+        close_ws(ws, Some(CloseFrame::normal()));
+    }
+
+    println!(
+        "\n{} passed, {} failed\n",
+        passed.to_string().green().bold(),
+        failed.to_string().red().bold()
+    );
+    failed == 0
+}
+
+/// Waits for the next text or binary message, printing control frames on
+/// the way. A close from the server ends the wait with an error.
+fn next_data_message(ws: &mut WebSocket) -> Result<Message, HttpError> {
+    loop {
+        let message = ws.read()?;
+        print_ws_message(false, &message);
+        match message {
+            Message::Text(_) | Message::Binary(_) => return Ok(message),
+            Message::Close(frame) => {
+                return Err(HttpError::WebSocketError(match frame {
+                    Some(frame) => format!("closed by server with {}", frame),
+                    None => "closed by server".to_string(),
+                }));
             }
+            Message::Ping(_) | Message::Pong(_) => {}
+        }
+    }
+}
 
-            let collection_name = parts[0];
-            let request_name = parts[1];
+fn send_ws_message(ws: &mut WebSocket, message: Message) -> Result<(), HttpError> {
+    print_ws_message(true, &message);
+    ws.send(message)
+}
 
-            let collections = storage.load_collections().unwrap_or_default();
-            let collection = collections.iter().find(|c| c.name == collection_name);
+fn close_ws(ws: &mut WebSocket, frame: Option<CloseFrame>) {
+    print_ws_message(true, &Message::Close(frame.clone()));
+    match ws.close(frame) {
+        Ok(reply) => print_ws_message(false, &Message::Close(reply)),
+        Err(e) => print_error(&e.to_string()),
+    }
+}
 
-            if collection.is_none() {
-                print_error(&format!("Collection '{}' not found", collection_name));
+/// Parses the arguments of `/close`: an optional status code, then an
+/// optional reason.
+fn parse_close_args(args: &str) -> Result<Option<CloseFrame>, String> {
+    let args = args.trim();
+    if args.is_empty() {
+        return Ok(Some(CloseFrame::normal()));
+    }
+
+    let (code, reason) = args.split_once(' ').unwrap_or((args, ""));
+    let code = code
+        .parse()
+        .map_err(|_| format!("Invalid close code '{}'", code))?;
+    Ok(Some(CloseFrame::new(code, reason.trim())))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_grpc(
+    url: Option<String>,
+    method: Option<String>,
+    saved: Option<String>,
+    proto: Vec<String>,
+    import_paths: Vec<String>,
+    descriptor_set: Option<String>,
+    data: Option<String>,
+    list: bool,
+    web: bool,
+    save: Option<String>,
+    headers: Vec<(String, String)>,
+    env: Option<String>,
+    allow_unresolved: bool,
+    timeout: u64,
+    verbose: bool,
+    tls: TlsArgs,
+    proxy: ProxyArgs,
+    connect: ConnectArgs,
+    auth: AuthArgs,
+) {
+    let storage = match Storage::new() {
+        Ok(s) => s,
+        Err(e) => {
+            print_error(&format!("Failed to initialize storage: {}", e));
+            return;
+        }
+    };
+
+    let env_set = storage.load_environment_set().unwrap_or_default();
+    let active_env = match &env {
+        Some(env_name) => match env_set.get(env_name) {
+            Some(environment) => Some(environment),
+            None => {
+                print_error(&format!("Environment '{}' not found", env_name));
                 return;
             }
+        },
+        None => None,
+    };
 
-            let saved_request = collection
-                .unwrap()
-                .requests
+    let (mut call, collection) = match saved {
+        Some(saved) => {
+            let Some((collection_name, name)) = saved.split_once('/') else {
+                print_error("Expected --saved as COLLECTION/NAME");
+                return;
+            };
+            let collection = match storage.load_collection(collection_name) {
+                Ok(c) => c,
+                Err(_) => {
+                    print_error(&format!("Collection '{}' not found", collection_name));
+                    return;
+                }
+            };
+            let Some(call) = collection
+                .grpc_calls
                 .iter()
-                .find(|r| r.name == request_name);
-
-            if saved_request.is_none() {
+                .find(|c| c.name == name || c.id == name)
+            else {
                 print_error(&format!(
-                    "Request '{}' not found in collection '{}'",
-                    request_name, collection_name
+                    "gRPC call '{}' not found in collection '{}'",
+                    name, collection_name
                 ));
                 return;
-            }
-
-            let req = saved_request.unwrap();
-            let loaded_url = req.request.url.clone();
-            let loaded_method = req.request.method.clone();
-            let loaded_headers = req.request.headers.clone();
-            let loaded_body = req.request.body.clone();
-
-            // CLI args override collection values
-            let use_url = url.unwrap_or(loaded_url);
-            let use_headers = if headers.is_empty() {
-                loaded_headers
-            } else {
-                headers
             };
-            let use_body = body.or(loaded_body.clone());
-            let use_json = json.or(loaded_body);
+            (call.clone(), Some(collection))
+        }
+        None => (
+            SavedGrpcCall::new(
+                uuid::Uuid::new_v4().to_string(),
+                "",
+                url.unwrap_or_default(),
+                method.unwrap_or_default(),
+            ),
+            None,
+        ),
+    };
 
-            (use_url, loaded_method, use_headers, use_body, use_json)
-        } else {
-            if url.is_none() {
-                print_error("URL is required when not using --from-collection");
+    // Options given on the command line replace the saved ones
+    if !proto.is_empty() || descriptor_set.is_some() {
+        call.proto_files = proto;
+        call.descriptor_set = descriptor_set;
+    }
+    if !import_paths.is_empty() {
+        call.import_paths = import_paths;
+    }
+    if let Some(data) = data {
+        call.message = match read_arg_file(&data) {
+            Ok(message) => message,
+            Err(e) => {
+                print_error(&e);
                 return;
             }
-            (url.unwrap(), method, headers, body, json)
         };
+    }
+    call.metadata.extend(headers);
+    if let Some(auth) = auth.to_auth() {
+        call.auth = Some(auth);
+    }
+    call.web |= web;
 
-    let env_set = storage.load_environment_set().unwrap_or_default();
-    let resolved_url = if let Some(ref env_name) = env {
-        if let Some(environment) = env_set.get(env_name) {
-            environment.resolve_template(&final_url)
-        } else {
-            print_error(&format!("Environment '{}' not found", env_name));
+    let schema = match load_proto_schema(&call) {
+        Ok(schema) => schema,
+        Err(e) => {
+            print_error(&e);
+            return;
+        }
+    };
+
+    if list {
+        for service in schema.services() {
+            println!("{}", service.full_name.bold());
+            for method in &service.methods {
+                let stream = |streaming: bool, name: &str| {
+                    if streaming {
+                        format!("stream {}", name)
+                    } else {
+                        name.to_string()
+                    }
+                };
+                println!(
+                    "  {} {}",
+                    method.name,
+                    format!(
+                        "({}) returns ({})",
+                        stream(method.client_streaming, &method.input_type),
+                        stream(method.server_streaming, &method.output_type)
+                    )
+                    .bright_black()
+                );
+            }
+        }
+        return;
+    }
+
+    let (service, method) = match schema.find_method(&call.method) {
+        Ok(found) => found,
+        Err(e) => {
+            print_error(&e.to_string());
             return;
         }
-    } else {
-        final_url.clone()
     };
+    if method.client_streaming {
+        print_error(&format!(
+            "{} is a client-streaming method, which isn't supported",
+            method.name
+        ));
+        return;
+    }
+    call.method = format!("{}/{}", service.full_name, method.name);
 
-    let http_method = match final_method.to_uppercase().as_str() {
-        "GET" => HttpMethod::Get,
-        "POST" => HttpMethod::Post,
-        "PUT" => HttpMethod::Put,
-        "PATCH" => HttpMethod::Patch,
-        "DELETE" => HttpMethod::Delete,
-        "HEAD" => HttpMethod::Head,
-        "OPTIONS" => HttpMethod::Options,
-        _ => {
-            print_error(&format!("Invalid HTTP method: {}", final_method));
+    let request = match &collection {
+        Some(collection) => collection.grpc_request(&call),
+        None => call.to_http_request(),
+    };
+    let local_access = collection.as_ref().is_none_or(|c| c.local_access);
+    let (request, mut unresolved) =
+        prepare_request(request.with_timeout(timeout), active_env, local_access);
+    let (message, errors) = resolve_checked(
+        active_env,
+        TemplateLocation::Body,
+        &call.message,
+        local_access,
+    );
+    unresolved.extend(errors);
+    check_unresolved(&unresolved, allow_unresolved);
+
+    let body = match schema.encode(&method.input_type, &message) {
+        Ok(body) => body,
+        Err(e) => {
+            print_error(&e.to_string());
             return;
         }
     };
 
-    let mut request = HttpRequest::new(http_method, &resolved_url);
+    let mut config = match client_config(active_env, &tls, &proxy, &connect) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e);
+            return;
+        }
+    };
+    config.timeout_seconds = timeout;
 
-    for (key, value) in final_headers {
-        let resolved_key = if let Some(ref env_name) = env {
-            if let Some(environment) = env_set.get(env_name) {
-                environment.resolve_template(&key)
-            } else {
-                key.clone()
-            }
-        } else {
-            key.clone()
-        };
+    let client = match HttpClient::try_with_config(config) {
+        Ok(c) => c,
+        Err(e) => {
+            print_error(&e.to_string());
+            return;
+        }
+    };
 
-        let resolved_value = if let Some(ref env_name) = env {
-            if let Some(environment) = env_set.get(env_name) {
-                environment.resolve_template(&value)
-            } else {
-                value.clone()
+    let start = Instant::now();
+    let result = if call.web {
+        client.grpc_web(&request, &body)
+    } else {
+        client.grpc(&request, &body)
+    };
+    let mut grpc = match result {
+        Ok(grpc) => grpc,
+        Err(e) => {
+            print_error(&format!("Call failed: {}", e));
+            return;
+        }
+    };
+    if verbose {
+        print_grpc_metadata("Headers", grpc.headers());
+    }
+
+    let mut messages = 0;
+    loop {
+        match grpc.next_message() {
+            Ok(Some(bytes)) => {
+                messages += 1;
+                match schema.decode(&method.output_type, &bytes) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => print_error(&format!("Failed to decode a response: {}", e)),
+                }
             }
-        } else {
-            value.clone()
-        };
+            Ok(None) | Err(HttpError::GrpcError(_)) => break,
+            Err(e) => {
+                print_error(&format!("Call failed: {}", e));
+                return;
+            }
+        }
+    }
 
-        request = request.with_header(&resolved_key, &resolved_value);
+    if verbose && !grpc.trailers().is_empty() {
+        print_grpc_metadata("Trailers", grpc.trailers());
+    }
+    if let Some(status) = grpc.status() {
+        print_grpc_status(status, messages, start.elapsed());
     }
 
-    if let Some(json_body) = final_json {
-        let resolved_body = if let Some(ref env_name) = env {
-            if let Some(environment) = env_set.get(env_name) {
-                environment.resolve_template(&json_body)
-            } else {
-                json_body.clone()
-            }
-        } else {
-            json_body.clone()
+    if let Some(target) = save {
+        let Some((collection_name, name)) = target.split_once('/') else {
+            print_error("Expected --save as COLLECTION/NAME");
+            return;
         };
-        request = request.with_json_body(&resolved_body);
-    } else if !data.is_empty() {
-        let form_data: HashMap<String, String> = data.into_iter().collect();
-        request = request.with_body(RequestBody::FormUrlEncoded(form_data));
-    } else if let Some(text_body) = final_body {
-        let resolved_body = if let Some(ref env_name) = env {
-            if let Some(environment) = env_set.get(env_name) {
-                environment.resolve_template(&text_body)
-            } else {
-                text_body.clone()
+        let mut collection = match storage.load_collection(collection_name) {
+            Ok(c) => c,
+            Err(_) => {
+                print_error(&format!("Collection '{}' not found", collection_name));
+                return;
             }
-        } else {
-            text_body.clone()
         };
-        request = request.with_text_body(&resolved_body);
+        call.name = name.to_string();
+        collection.add_grpc_call(with_absolute_paths(call));
+        match storage.save_collection(&collection) {
+            Ok(_) => print_success(&format!(
+                "gRPC call '{}' added to collection '{}'",
+                name, collection_name
+            )),
+            Err(e) => print_error(&format!("Failed to save collection: {}", e)),
+        }
     }
+}
 
-    if let Some(t) = timeout {
-        request = request.with_timeout(t);
+/// Loads the schema of a gRPC call from its descriptor set, or from its
+/// `.proto` files and import paths.
+fn load_proto_schema(call: &SavedGrpcCall) -> Result<ProtoSchema, String> {
+    let mut schema = ProtoSchema::new();
+    if let Some(path) = &call.descriptor_set {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+        schema
+            .load_descriptor_set(&bytes)
+            .map_err(|e| format!("{}: {}", path, e))?;
+        return Ok(schema);
+    }
+    if call.proto_files.is_empty() {
+        return Err(
+            "Pass the service's .proto files with --proto, or --descriptor-set".to_string(),
+        );
     }
 
-    request = request.follow_redirects(follow_redirects);
+    for path in &call.import_paths {
+        schema = schema.with_include_path(path);
+    }
+    for path in &call.proto_files {
+        schema.load_file(path).map_err(|e| e.to_string())?;
+    }
+    Ok(schema)
+}
 
-    let config = HttpClientConfig {
-        timeout_seconds: timeout.unwrap_or(30),
-        follow_redirects,
-        ..Default::default()
+/// Makes the schema paths of a call absolute, so the saved call works from
+/// any directory.
+fn with_absolute_paths(mut call: SavedGrpcCall) -> SavedGrpcCall {
+    let absolute = |path: &String| {
+        std::fs::canonicalize(path)
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|_| path.clone())
     };
-
-    let client = HttpClient::with_config(config);
-
-    let start = Instant::now();
-    match client.send(&request) {
-        Ok(response) => {
-            let duration_ms = start.elapsed().as_millis() as u64;
-
-            let history_request = HistoryRequest {
-                method: request.method.as_str().to_string(),
-                url: request.url.clone(),
-                headers: request
-                    .headers
-                    .iter()
-                    .map(|h| (h.name.clone(), h.value.clone()))
-                    .collect(),
-                body: match &request.body {
-                    RequestBody::None => None,
-                    RequestBody::Text(t) => Some(t.clone()),
-                    RequestBody::Json(j) => Some(j.clone()),
-                    _ => Some("<complex body>".to_string()),
-                },
-            };
-
-            let history_response = HistoryResponse::from_response(&response);
-            let entry = HistoryEntry::new(history_request, history_response, duration_ms);
-
-            if let Err(e) = storage.save_history_entry(&entry) {
-                eprintln!("Warning: Failed to save to history: {}", e);
-            }
-
-            print_response(&response, verbose);
-        }
-        Err(e) => print_error(&format!("Request failed: {}", e)),
-    }
+    call.proto_files = call.proto_files.iter().map(absolute).collect();
+    call.import_paths = call.import_paths.iter().map(absolute).collect();
+    call.descriptor_set = call.descriptor_set.as_ref().map(absolute);
+    call
 }
 
 pub fn handle_collection(action: CollectionAction) {
@@ -230,12 +1362,21 @@ pub fn handle_collection(action: CollectionAction) {
     };
 
     match action {
-        CollectionAction::New { name, description } => {
+        CollectionAction::New {
+            name,
+            description,
+            auth,
+        } => {
             let collection = Collection {
                 name: name.clone(),
                 description,
                 folders: Vec::new(),
                 requests: Vec::new(),
+                auth: auth.to_auth(),
+                websockets: Vec::new(),
+                grpc_calls: Vec::new(),
+                // Made here, so its templates are the user's own
+                local_access: true,
             };
 
             match storage.save_collection(&collection) {
@@ -265,74 +1406,369 @@ pub fn handle_collection(action: CollectionAction) {
                 if let Some(desc) = &collection.description {
                     println!("{}: {}", "Description".bold(), desc);
                 }
+                if let Some(auth) = &collection.auth {
+                    println!("{}: {}", "Auth".bold(), auth.scheme());
+                }
                 println!("\n{}:", "Requests".bold().cyan());
                 if collection.requests.is_empty() {
                     println!("  {}", "(no requests)".bright_black());
                 } else {
                     for req in &collection.requests {
-                        println!("  • {} - {}", req.name, req.request.url);
+                        let url = display_url(&req.request.url, &req.request.query);
+                        match &req.request.auth {
+                            Some(auth) => {
+                                println!("  • {} - {} ({})", req.name, url, auth.scheme())
+                            }
+                            None => println!("  • {} - {}", req.name, url),
+                        }
+                    }
+                }
+                if !collection.websockets.is_empty() {
+                    println!("\n{}:", "WebSockets".bold().cyan());
+                    for websocket in &collection.websockets {
+                        if websocket.protocols.is_empty() {
+                            println!("  • {} - {}", websocket.name, websocket.url);
+                        } else {
+                            println!(
+                                "  • {} - {} ({})",
+                                websocket.name,
+                                websocket.url,
+                                websocket.protocols.join(", ")
+                            );
+                        }
+                    }
+                }
+                if !collection.grpc_calls.is_empty() {
+                    println!("\n{}:", "gRPC".bold().cyan());
+                    for call in &collection.grpc_calls {
+                        println!("  • {} - {} {}", call.name, call.url, call.method);
                     }
                 }
                 println!();
             }
-            Err(e) => print_error(&format!("Failed to load collection: {}", e)),
-        },
+            Err(e) => print_error(&format!("Failed to load collection: {}", e)),
+        },
+
+        CollectionAction::Add {
+            collection,
+            name,
+            url,
+            method,
+            query,
+            header,
+            body,
+            description,
+            auth,
+        } => {
+            let mut coll = match storage.load_collection(&collection) {
+                Ok(c) => c,
+                Err(_) => {
+                    print_error(&format!("Collection '{}' not found", collection));
+                    return;
+                }
+            };
+
+            let http_method = match method.parse::<HttpMethod>() {
+                Ok(m) => m,
+                Err(e) => {
+                    print_error(&e.to_string());
+                    return;
+                }
+            };
+
+            let mut request = HttpRequest::new(http_method, &url);
+            request.query = query;
+            for (key, value) in header {
+                request = request.with_header(&key, &value);
+            }
+            if let Some(b) = body {
+                request = request.with_text_body(&b);
+            }
+            if let Some(auth) = auth.to_auth() {
+                request = request.with_auth(auth);
+            }
+
+            let saved_request = SavedRequest {
+                id: uuid::Uuid::new_v4().to_string(),
+                name: name.clone(),
+                description,
+                request: SerializableRequest::from_request(&request),
+            };
+
+            coll.requests.push(saved_request);
+
+            match storage.save_collection(&coll) {
+                Ok(_) => print_success(&format!(
+                    "Request '{}' added to collection '{}'",
+                    name, collection
+                )),
+                Err(e) => print_error(&format!("Failed to save collection: {}", e)),
+            }
+        }
+
+        CollectionAction::AddWs {
+            collection,
+            name,
+            url,
+            header,
+            protocol,
+            message,
+            description,
+            auth,
+        } => {
+            let mut coll = match storage.load_collection(&collection) {
+                Ok(c) => c,
+                Err(_) => {
+                    print_error(&format!("Collection '{}' not found", collection));
+                    return;
+                }
+            };
+
+            let websocket = SavedWebSocket {
+                id: uuid::Uuid::new_v4().to_string(),
+                name: name.clone(),
+                description,
+                url,
+                headers: header,
+                protocols: protocol,
+                messages: message,
+                auth: auth.to_auth(),
+            };
+            coll.add_websocket(websocket);
+
+            match storage.save_collection(&coll) {
+                Ok(_) => print_success(&format!(
+                    "WebSocket '{}' added to collection '{}'",
+                    name, collection
+                )),
+                Err(e) => print_error(&format!("Failed to save collection: {}", e)),
+            }
+        }
+
+        CollectionAction::AddGrpc {
+            collection,
+            name,
+            url,
+            method,
+            proto,
+            import_path,
+            descriptor_set,
+            message,
+            web,
+            header,
+            description,
+            auth,
+        } => {
+            let mut coll = match storage.load_collection(&collection) {
+                Ok(c) => c,
+                Err(_) => {
+                    print_error(&format!("Collection '{}' not found", collection));
+                    return;
+                }
+            };
+
+            let message = match read_arg_file(message.as_deref().unwrap_or("{}")) {
+                Ok(m) => m,
+                Err(e) => {
+                    print_error(&e);
+                    return;
+                }
+            };
+            let call = SavedGrpcCall {
+                id: uuid::Uuid::new_v4().to_string(),
+                name: name.clone(),
+                description,
+                url,
+                method,
+                proto_files: proto,
+                import_paths: import_path,
+                descriptor_set,
+                message,
+                metadata: header,
+                auth: auth.to_auth(),
+                web,
+            };
+            coll.add_grpc_call(with_absolute_paths(call));
+
+            match storage.save_collection(&coll) {
+                Ok(_) => print_success(&format!(
+                    "gRPC call '{}' added to collection '{}'",
+                    name, collection
+                )),
+                Err(e) => print_error(&format!("Failed to save collection: {}", e)),
+            }
+        }
 
-        CollectionAction::Add {
-            collection,
+        CollectionAction::Run {
             name,
-            url,
-            method,
-            header,
-            body,
-            description,
+            env,
+            allow_unresolved,
+            parallel,
         } => {
-            let mut coll = match storage.load_collection(&collection) {
+            let collection = match storage.load_collection(&name) {
                 Ok(c) => c,
                 Err(_) => {
-                    print_error(&format!("Collection '{}' not found", collection));
+                    print_error(&format!("Collection '{}' not found", name));
                     return;
                 }
             };
+            if collection.requests.is_empty() {
+                print_info(&format!("Collection '{}' has no requests", name));
+                return;
+            }
+            if !collection.websockets.is_empty() {
+                print_info(&format!(
+                    "Skipping {} WebSocket connections; open them with 'saffron ws --saved'",
+                    collection.websockets.len()
+                ));
+            }
+            if !collection.grpc_calls.is_empty() {
+                print_info(&format!(
+                    "Skipping {} gRPC calls; make them with 'saffron grpc --saved'",
+                    collection.grpc_calls.len()
+                ));
+            }
 
-            let http_method = match method.to_uppercase().as_str() {
-                "GET" => HttpMethod::Get,
-                "POST" => HttpMethod::Post,
-                "PUT" => HttpMethod::Put,
-                "PATCH" => HttpMethod::Patch,
-                "DELETE" => HttpMethod::Delete,
-                "HEAD" => HttpMethod::Head,
-                "OPTIONS" => HttpMethod::Options,
-                _ => {
-                    print_error(&format!("Invalid HTTP method: {}", method));
-                    return;
-                }
+            let env_set = storage.load_environment_set().unwrap_or_default();
+            let active_env = match &env {
+                Some(env_name) => match env_set.get(env_name) {
+                    Some(environment) => Some(environment),
+                    None => {
+                        print_error(&format!("Environment '{}' not found", env_name));
+                        return;
+                    }
+                },
+                None => None,
             };
 
-            let mut request = HttpRequest::new(http_method, &url);
-            for (key, value) in header {
-                request = request.with_header(&key, &value);
+            let mut requests = Vec::new();
+            let mut any_unresolved = false;
+            for saved in &collection.requests {
+                match collection.http_request(saved) {
+                    Ok(request) => {
+                        let (request, unresolved) =
+                            prepare_request(request, active_env, collection.local_access);
+                        if !unresolved.is_empty() {
+                            if allow_unresolved {
+                                eprintln!(
+                                    "Warning: '{}' has unresolved placeholders, sent as written:",
+                                    saved.name
+                                );
+                            } else {
+                                print_error(&format!(
+                                    "'{}' has unresolved placeholders:",
+                                    saved.name
+                                ));
+                            }
+                            print_unresolved(&unresolved);
+                            any_unresolved = true;
+                        }
+                        requests.push(request);
+                    }
+                    Err(e) => {
+                        print_error(&format!("Request '{}': {}", saved.name, e));
+                        return;
+                    }
+                }
             }
-            if let Some(b) = body {
-                request = request.with_text_body(&b);
+            if any_unresolved && !allow_unresolved {
+                print_unresolved_hint();
+                std::process::exit(1);
             }
 
-            let saved_request = SavedRequest {
-                id: uuid::Uuid::new_v4().to_string(),
-                name: name.clone(),
-                description,
-                request: SerializableRequest::from_request(&request),
+            let mut config = match client_config(
+                active_env,
+                &TlsArgs::default(),
+                &ProxyArgs::default(),
+                &ConnectArgs::default(),
+            ) {
+                Ok(c) => c,
+                Err(e) => {
+                    print_error(&e);
+                    return;
+                }
             };
 
-            coll.requests.push(saved_request);
+            let cookie_env = env.as_deref().unwrap_or(DEFAULT_COOKIE_JAR);
+            let cookie_jar = match storage.load_cookie_jar(cookie_env) {
+                Ok(jar) => jar,
+                Err(e) => {
+                    print_error(&format!("Failed to load cookies: {}", e));
+                    return;
+                }
+            };
 
-            match storage.save_collection(&coll) {
-                Ok(_) => print_success(&format!(
-                    "Request '{}' added to collection '{}'",
-                    name, collection
-                )),
-                Err(e) => print_error(&format!("Failed to save collection: {}", e)),
+            config.cookie_jar = Some(cookie_jar.clone());
+            let client = match HttpClient::try_with_config(config) {
+                Ok(c) => c,
+                Err(e) => {
+                    print_error(&e.to_string());
+                    return;
+                }
+            };
+
+            println!(
+                "\n{} {} ({} requests, {} at a time)\n",
+                "Running".bold().cyan(),
+                collection.name,
+                requests.len(),
+                parallel.clamp(1, requests.len())
+            );
+
+            let start = Instant::now();
+            let mut passed = 0;
+            let mut failed = 0;
+            client.send_batch_with(
+                &requests,
+                &BatchConfig::with_concurrency(parallel),
+                |done| {
+                    let saved = &collection.requests[done.index];
+                    let request = &requests[done.index];
+                    let elapsed = format!("{:>6} ms", done.elapsed.as_millis());
+                    match &done.result {
+                        Ok(response) => {
+                            let ok = response.is_success() || response.is_redirect();
+                            let status = response.status.to_string();
+                            let status = if ok {
+                                passed += 1;
+                                status.green().bold()
+                            } else {
+                                failed += 1;
+                                status.red().bold()
+                            };
+                            println!(
+                                "  {} {} {:<7} {}",
+                                status,
+                                elapsed.bright_black(),
+                                request.method.as_str(),
+                                saved.name
+                            );
+                        }
+                        Err(e) => {
+                            failed += 1;
+                            println!(
+                                "  {} {} {:<7} {} - {}",
+                                "ERR".red().bold(),
+                                elapsed.bright_black(),
+                                request.method.as_str(),
+                                saved.name,
+                                e
+                            );
+                        }
+                    }
+                },
+            );
+
+            if let Err(e) = storage.save_cookie_jar(cookie_env, &cookie_jar) {
+                eprintln!("Warning: Failed to save cookies: {}", e);
             }
+
+            println!(
+                "\n{} passed, {} failed in {} ms\n",
+                passed.to_string().green().bold(),
+                failed.to_string().red().bold(),
+                start.elapsed().as_millis()
+            );
         }
 
         CollectionAction::Delete { name } => match storage.delete_collection(&name) {
@@ -341,7 +1777,9 @@ pub fn handle_collection(action: CollectionAction) {
         },
 
         CollectionAction::Export { name, output } => match storage.load_collection(&name) {
-            Ok(collection) => {
+            Ok(mut collection) => {
+                // Whoever gets the file decides that for themselves
+                collection.local_access = false;
                 let json = match serde_json::to_string_pretty(&collection) {
                     Ok(j) => j,
                     Err(e) => {
@@ -357,7 +1795,10 @@ pub fn handle_collection(action: CollectionAction) {
             Err(e) => print_error(&format!("Failed to load collection: {}", e)),
         },
 
-        CollectionAction::Import { input } => {
+        CollectionAction::Import {
+            input,
+            allow_local_access,
+        } => {
             let contents = match std::fs::read_to_string(&input) {
                 Ok(c) => c,
                 Err(e) => {
@@ -380,7 +1821,16 @@ pub fn handle_collection(action: CollectionAction) {
             let mut error_count = 0;
 
             for imported in imported_collections {
-                let collection = convert_imported_to_collection(imported);
+                let name = imported.name.clone();
+                let mut collection = match convert_imported_to_collection(imported) {
+                    Ok(c) => c,
+                    Err(e) => {
+                        print_error(&format!("Failed to import collection '{}': {}", name, e));
+                        error_count += 1;
+                        continue;
+                    }
+                };
+                collection.local_access = allow_local_access;
                 match storage.save_collection(&collection) {
                     Ok(_) => {
                         print_success(&format!("Imported collection '{}'", collection.name));
@@ -446,13 +1896,36 @@ pub fn handle_env(action: EnvAction) {
             }
         }
 
-        EnvAction::Set { name, variables } => {
-            let vars: HashMap<String, String> = variables.into_iter().collect();
-            let environment = Environment {
-                name: name.clone(),
-                variables: vars,
-            };
-            env_set.add(environment);
+        EnvAction::Set {
+            name,
+            variables,
+            tls,
+            proxy,
+            connect,
+        } => {
+            if env_set.get(&name).is_none() {
+                env_set.add(Environment::new(&name));
+            }
+            let environment = env_set.get_mut(&name).unwrap();
+            environment.variables.extend(variables);
+            tls.apply_to(&mut environment.tls);
+            proxy.apply_to(&mut environment.proxy);
+            connect.apply_to(&mut environment.connect);
+
+            if let Err(e) = TlsConfig::from_settings(&environment.tls) {
+                print_error(&e.to_string());
+                return;
+            }
+
+            if let Err(e) = ProxyConfig::default().with_settings(&environment.proxy) {
+                print_error(&e.to_string());
+                return;
+            }
+
+            if let Err(e) = ConnectConfig::from_settings(&environment.connect) {
+                print_error(&e.to_string());
+                return;
+            }
 
             match storage.save_environment_set(&env_set) {
                 Ok(_) => print_success(&format!("Environment '{}' saved", name)),
@@ -471,6 +1944,15 @@ pub fn handle_env(action: EnvAction) {
                         println!("  {} = {}", key.bright_white(), value);
                     }
                 }
+                if !env.tls.is_empty() {
+                    print_tls_settings(&env.tls);
+                }
+                if !env.proxy.is_empty() {
+                    print_proxy_settings(&env.proxy);
+                }
+                if !env.connect.is_empty() {
+                    print_connect_settings(&env.connect);
+                }
                 println!();
             } else {
                 print_error(&format!("Environment '{}' not found", name));
@@ -496,7 +1978,118 @@ pub fn handle_env(action: EnvAction) {
                 print_error(&format!("Environment '{}' not found", name));
             }
         }
+
+        EnvAction::Check { collection } => {
+            let collection = match storage.load_collection(&collection) {
+                Ok(c) => c,
+                Err(_) => {
+                    print_error(&format!("Collection '{}' not found", collection));
+                    return;
+                }
+            };
+
+            // Resolving without any variables shows which ones are needed;
+            // placeholders with a default don't count
+            let needed = undefined_variables(&collection, &HashMap::new());
+            if needed.is_empty() {
+                print_success(&format!(
+                    "Collection '{}' doesn't need any variables",
+                    collection.name
+                ));
+                return;
+            }
+            let names: Vec<&str> = needed.keys().map(|name| name.as_str()).collect();
+            println!(
+                "\n{} '{}' needs: {}\n",
+                "Collection".bold().cyan(),
+                collection.name,
+                names.join(", ")
+            );
+
+            if env_set.environments.is_empty() {
+                print_info("No environments found");
+                std::process::exit(1);
+            }
+            let mut any_missing = false;
+            for environment in &env_set.environments {
+                let missing = undefined_variables(&collection, &environment.variables);
+                if missing.is_empty() {
+                    println!("  {} {}", "✓".green().bold(), environment.name);
+                    continue;
+                }
+                any_missing = true;
+                let names: Vec<&str> = missing.keys().map(|name| name.as_str()).collect();
+                println!(
+                    "  {} {}: missing {}",
+                    "✗".red().bold(),
+                    environment.name,
+                    names.join(", ").red()
+                );
+                for (name, uses) in &missing {
+                    println!(
+                        "      {}: {}",
+                        name.bright_white(),
+                        uses.join(", ").bright_black()
+                    );
+                }
+            }
+            println!();
+            if any_missing {
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// The variables `collection` uses that aren't in `variables`, each with the
+/// requests and places it's used in, such as `Get Users (URL)`. WebSocket and
+/// gRPC messages count as bodies.
+fn undefined_variables(
+    collection: &Collection,
+    variables: &HashMap<String, String>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut undefined: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut check = |name: &str, request: HttpRequest, messages: &[String]| {
+        let (_, mut unresolved) = request.resolve_with(variables, collection.local_access);
+        for message in messages {
+            let (_, errors) =
+                Template::parse(message).render_checked_with(variables, collection.local_access);
+            unresolved.extend(errors.into_iter().map(|error| Unresolved {
+                location: TemplateLocation::Body,
+                error,
+            }));
+        }
+        for item in unresolved {
+            if let TemplateError::Undefined { name: variable, .. } = item.error {
+                let used = format!("{} ({})", name, item.location);
+                let uses = undefined.entry(variable).or_default();
+                if !uses.contains(&used) {
+                    uses.push(used);
+                }
+            }
+        }
+    };
+
+    for saved in &collection.requests {
+        if let Ok(request) = collection.http_request(saved) {
+            check(&saved.name, request, &[]);
+        }
+    }
+    for websocket in &collection.websockets {
+        check(
+            &websocket.name,
+            collection.websocket_request(websocket),
+            &websocket.messages,
+        );
     }
+    for call in &collection.grpc_calls {
+        check(
+            &call.name,
+            collection.grpc_request(call),
+            std::slice::from_ref(&call.message),
+        );
+    }
+    undefined
 }
 
 pub fn handle_history(action: HistoryAction) {
@@ -533,13 +2126,19 @@ pub fn handle_history(action: HistoryAction) {
                     entry.response.status.to_string().red()
                 };
 
+                let attempts = if entry.retries.is_empty() {
+                    String::new()
+                } else {
+                    format!(", {} attempts", entry.retries.len() + 1)
+                };
+
                 println!(
                     "\n  {} {} {} {} {}",
                     format!("[{}]", i + 1).bright_black(),
                     entry.request.method.bright_white(),
                     entry.request.url,
                     status_color,
-                    format!("({}ms)", entry.duration_ms).bright_black()
+                    format!("({}ms{})", entry.duration_ms, attempts).bright_black()
                 );
                 println!("     {}", entry.format_timestamp().bright_black());
             }
@@ -585,8 +2184,8 @@ pub fn handle_history(action: HistoryAction) {
 
             if !entry.request.headers.is_empty() {
                 println!("\n{}:", "Headers".bold());
-                for (name, value) in &entry.request.headers {
-                    println!("  {}: {}", name.bright_black(), value);
+                for header in &entry.request.headers {
+                    println!("  {}: {}", header.name.bright_black(), header.value);
                 }
             }
 
@@ -603,9 +2202,39 @@ pub fn handle_history(action: HistoryAction) {
             );
             println!("  Duration: {}ms", entry.duration_ms);
 
+            if !entry.response.headers.is_empty() {
+                println!("\n{}:", "Headers".bold());
+                for header in &entry.response.headers {
+                    println!("  {}: {}", header.name.bright_black(), header.value);
+                }
+            }
+
+            if !entry.retries.is_empty() {
+                println!("\n{}:", "Attempts".bold());
+                for (i, attempt) in entry.retries.iter().enumerate() {
+                    let outcome = match (attempt.status, &attempt.error) {
+                        (Some(status), _) => status.to_string(),
+                        (None, Some(error)) => error.clone(),
+                        (None, None) => "failed".to_string(),
+                    };
+                    println!(
+                        "  [{}] {} ({}ms, retried after {}ms)",
+                        i + 1,
+                        outcome,
+                        attempt.duration_ms,
+                        attempt.delay_ms
+                    );
+                }
+                println!("  [{}] {}", entry.retries.len() + 1, entry.response.status);
+            }
+
             println!("\n{}:", "Preview".bold());
             println!("{}", entry.response.body_preview);
             println!();
+
+            if let Some(timings) = &entry.timings {
+                print_timings(&timings.to_timings());
+            }
         }
 
         HistoryAction::Rerun { id, verbose } => {
@@ -639,24 +2268,18 @@ pub fn handle_history(action: HistoryAction) {
                 entry.request.method, entry.request.url
             );
 
-            let method = match entry.request.method.to_uppercase().as_str() {
-                "GET" => HttpMethod::Get,
-                "POST" => HttpMethod::Post,
-                "PUT" => HttpMethod::Put,
-                "PATCH" => HttpMethod::Patch,
-                "DELETE" => HttpMethod::Delete,
-                "HEAD" => HttpMethod::Head,
-                "OPTIONS" => HttpMethod::Options,
-                _ => {
-                    print_error("Invalid HTTP method in history");
+            let method = match entry.request.method.parse::<HttpMethod>() {
+                Ok(m) => m,
+                Err(e) => {
+                    print_error(&e.to_string());
                     return;
                 }
             };
 
             let mut request = HttpRequest::new(method, &entry.request.url);
 
-            for (key, value) in &entry.request.headers {
-                request = request.with_header(key, value);
+            for header in &entry.request.headers {
+                request = request.with_header(&header.name, &header.value);
             }
 
             if let Some(body) = &entry.request.body {
@@ -679,7 +2302,8 @@ pub fn handle_history(action: HistoryAction) {
 
                     let history_response = HistoryResponse::from_response(&response);
                     let new_entry =
-                        HistoryEntry::new(new_history_request, history_response, duration_ms);
+                        HistoryEntry::new(new_history_request, history_response, duration_ms)
+                            .with_timings(&response.timings);
 
                     if let Err(e) = storage.save_history_entry(&new_entry) {
                         eprintln!("Warning: Failed to save to history: {}", e);
@@ -698,28 +2322,397 @@ pub fn handle_history(action: HistoryAction) {
     }
 }
 
-/// Converts an imported collection to native Collection format
-fn convert_imported_to_collection(imported: ImportedCollection) -> Collection {
+pub fn handle_cookies(action: CookieAction) {
+    let storage = match Storage::new() {
+        Ok(s) => s,
+        Err(e) => {
+            print_error(&format!("Failed to initialize storage: {}", e));
+            return;
+        }
+    };
+
+    let env = match &action {
+        CookieAction::List { env, .. }
+        | CookieAction::Set { env, .. }
+        | CookieAction::Delete { env, .. }
+        | CookieAction::Clear { env } => env.as_deref().unwrap_or(DEFAULT_COOKIE_JAR).to_string(),
+    };
+
+    let jar = match storage.load_cookie_jar(&env) {
+        Ok(jar) => jar,
+        Err(e) => {
+            print_error(&format!("Failed to load cookies: {}", e));
+            return;
+        }
+    };
+
+    match action {
+        CookieAction::List { domain, .. } => {
+            let domain = domain.map(|d| d.trim_start_matches('.').to_ascii_lowercase());
+            let cookies: Vec<Cookie> = jar
+                .cookies()
+                .into_iter()
+                .filter(|c| domain.as_ref().is_none_or(|d| c.domain == *d))
+                .collect();
+
+            if cookies.is_empty() {
+                print_info("No cookies found");
+            } else {
+                println!("\n{} ({}):", "Cookies".bold().cyan(), env);
+                for cookie in &cookies {
+                    print_cookie(cookie);
+                }
+                println!();
+            }
+        }
+
+        CookieAction::Set { url, cookie, .. } => {
+            let parsed = match Url::parse(&url) {
+                Ok(u) => u,
+                Err(e) => {
+                    print_error(&format!("Invalid URL '{}': {}", url, e));
+                    return;
+                }
+            };
+
+            let Some(cookie) = Cookie::parse(&cookie, &parsed) else {
+                print_error(&format!("Invalid cookie for {}: '{}'", url, cookie));
+                return;
+            };
+
+            let name = cookie.name.clone();
+            let domain = cookie.domain.clone();
+            jar.insert(cookie);
+
+            match storage.save_cookie_jar(&env, &jar) {
+                Ok(_) => print_success(&format!("Cookie '{}' set for {}", name, domain)),
+                Err(e) => print_error(&format!("Failed to save cookies: {}", e)),
+            }
+        }
+
+        CookieAction::Delete { name, domain, .. } => {
+            let removed = jar.remove(&name, domain.as_deref());
+            if removed == 0 {
+                print_error(&format!("Cookie '{}' not found", name));
+                return;
+            }
+
+            match storage.save_cookie_jar(&env, &jar) {
+                Ok(_) => print_success(&format!("Deleted {} cookie(s) named '{}'", removed, name)),
+                Err(e) => print_error(&format!("Failed to save cookies: {}", e)),
+            }
+        }
+
+        CookieAction::Clear { .. } => {
+            jar.clear();
+            match storage.save_cookie_jar(&env, &jar) {
+                Ok(_) => print_success(&format!("Cookies cleared for '{}'", env)),
+                Err(e) => print_error(&format!("Failed to clear cookies: {}", e)),
+            }
+        }
+    }
+}
+
+fn print_cookie(cookie: &Cookie) {
+    let domain = if cookie.host_only {
+        cookie.domain.clone()
+    } else {
+        format!(".{}", cookie.domain)
+    };
+
+    println!("\n  {} = {}", cookie.name.bright_white(), cookie.value);
+    println!(
+        "     {}{}",
+        domain.bright_black(),
+        cookie.path.bright_black()
+    );
+
+    let mut flags = Vec::new();
+    if cookie.secure {
+        flags.push("Secure".to_string());
+    }
+    if cookie.http_only {
+        flags.push("HttpOnly".to_string());
+    }
+    if let Some(same_site) = cookie.same_site {
+        flags.push(format!("SameSite={}", same_site.as_str()));
+    }
+    flags.push(match cookie.expires {
+        Some(expires) => format!("expires {}", expires.format("%Y-%m-%d %H:%M:%S UTC")),
+        None => "session".to_string(),
+    });
+    println!("     {}", flags.join(", ").bright_black());
+}
+
+/// Fills in `environment`'s variables in a saved request. Saved bodies are
+/// sent as JSON, as with `send --from-collection`. Placeholders that can't
+/// be resolved are left as written and returned.
+fn prepare_request(
+    request: HttpRequest,
+    environment: Option<&Environment>,
+    local_access: bool,
+) -> (HttpRequest, Vec<Unresolved>) {
+    let (mut request, unresolved) = request.resolve_with(&variables(environment), local_access);
+    if let RequestBody::Text(body) = &request.body {
+        request.body = RequestBody::Json(body.clone());
+    }
+    (request, unresolved)
+}
+
+/// `environment`'s variables, or none without one.
+fn variables(environment: Option<&Environment>) -> HashMap<String, String> {
+    environment
+        .map(|environment| environment.variables.clone())
+        .unwrap_or_default()
+}
+
+/// Fills in `template`'s placeholders: variables from `environment`, if
+/// there is one, and template functions such as `{{$uuid}}` either way.
+/// Also returns the placeholders that couldn't be resolved, reported at
+/// `location`.
+fn resolve_checked(
+    environment: Option<&Environment>,
+    location: TemplateLocation,
+    template: &str,
+    local_access: bool,
+) -> (String, Vec<Unresolved>) {
+    let (rendered, errors) =
+        Template::parse(template).render_checked_with(&variables(environment), local_access);
+    let unresolved = errors
+        .into_iter()
+        .map(|error| Unresolved {
+            location: location.clone(),
+            error,
+        })
+        .collect();
+    (rendered, unresolved)
+}
+
+/// Reports placeholders that couldn't be resolved. They stop the request
+/// unless `allow` is set, in which case they're sent as written; returns
+/// whether to go on.
+/// Lists the placeholders that couldn't be resolved and exits with status 1,
+/// unless `allow` sends them as written.
+fn check_unresolved(unresolved: &[Unresolved], allow: bool) {
+    if unresolved.is_empty() {
+        return;
+    }
+    if allow {
+        eprintln!("Warning: Unresolved placeholders are sent as written:");
+        print_unresolved(unresolved);
+        return;
+    }
+    print_error("Unresolved placeholders in the request:");
+    print_unresolved(unresolved);
+    print_unresolved_hint();
+    std::process::exit(1);
+}
+
+fn print_unresolved_hint() {
+    eprintln!(
+        "{}",
+        "Set them with 'saffron env set', or pass --allow-unresolved to send anyway".bright_black()
+    );
+}
+
+/// `url` with its query parameters as they were written, placeholders and
+/// all, for listings.
+fn display_url(url: &str, query: &[(String, String)]) -> String {
+    let mut display = url.to_string();
+    for (name, value) in query {
+        display.push(if display.contains('?') { '&' } else { '?' });
+        display.push_str(&format!("{}={}", name, value));
+    }
+    display
+}
+
+/// The body as recorded in history. GraphQL bodies are kept as the JSON that
+/// was sent; streams and forms only as a placeholder.
+fn history_body(body: &RequestBody) -> Option<String> {
+    match body {
+        RequestBody::None => None,
+        RequestBody::Text(t) => Some(t.clone()),
+        RequestBody::Json(j) => Some(j.clone()),
+        RequestBody::FormUrlEncoded(pairs) => Some(encode_form_urlencoded(pairs)),
+        RequestBody::GraphQL {
+            query,
+            variables,
+            operation_name,
+        } => Some(encode_graphql(
+            query,
+            variables.as_deref(),
+            operation_name.as_deref(),
+        )),
+        _ => Some("<complex body>".to_string()),
+    }
+}
+
+/// Builds a client configuration from an environment's TLS, proxy and
+/// connection settings with the command-line flags layered on top.
+fn client_config(
+    environment: Option<&Environment>,
+    tls: &TlsArgs,
+    proxy: &ProxyArgs,
+    connect: &ConnectArgs,
+) -> Result<HttpClientConfig, String> {
+    let mut tls_settings = environment
+        .map(|environment| environment.tls.clone())
+        .unwrap_or_default();
+    tls.apply_to(&mut tls_settings);
+
+    let mut proxy_settings = environment
+        .map(|environment| environment.proxy.clone())
+        .unwrap_or_default();
+    proxy.apply_to(&mut proxy_settings);
+
+    let mut connect_settings = environment
+        .map(|environment| environment.connect.clone())
+        .unwrap_or_default();
+    connect.apply_to(&mut connect_settings);
+
+    Ok(HttpClientConfig {
+        tls: TlsConfig::from_settings(&tls_settings).map_err(|e| e.to_string())?,
+        proxy: ProxyConfig::from_env()
+            .with_settings(&proxy_settings)
+            .map_err(|e| e.to_string())?,
+        connect: ConnectConfig::from_settings(&connect_settings).map_err(|e| e.to_string())?,
+        ..Default::default()
+    })
+}
+
+/// Opens a curl-style body reference: `-` for stdin, anything else is a path.
+fn open_body_stream(source: &str) -> std::io::Result<BodyStream> {
+    if source == "-" {
+        Ok(BodyStream::stdin())
+    } else {
+        BodyStream::from_file(source)
+    }
+}
+
+/// Builds a multipart part from `-F name=value` or `-F name=@file[;type=mime]`.
+fn form_part(name: String, value: String) -> Result<FormDataPart, String> {
+    let Some(spec) = value.strip_prefix('@') else {
+        return Ok(FormDataPart {
+            name,
+            content: FormDataContent::Text(value),
+        });
+    };
+
+    let (path, content_type) = match spec.split_once(";type=") {
+        Some((path, mime)) => (path, Some(mime.to_string())),
+        None => (spec, None),
+    };
+
+    let stream = open_body_stream(path).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+
+    let filename = match stream.path().and_then(Path::file_name) {
+        Some(filename) => filename.to_string_lossy().into_owned(),
+        None => "stdin".to_string(),
+    };
+    let content_type = content_type.or_else(|| stream.path().map(helpers::guess_content_type));
+
+    Ok(FormDataPart {
+        name,
+        content: FormDataContent::Stream {
+            filename,
+            stream,
+            content_type,
+        },
+    })
+}
+
+fn print_tls_settings(tls: &TlsSettings) {
+    println!("\n{}:", "TLS".bold().cyan());
+    if tls.insecure {
+        println!("  {}", "certificate verification disabled".yellow());
+    }
+    for ca in &tls.ca_certificates {
+        println!("  {} = {}", "cacert".bright_white(), ca);
+    }
+    if let Some(cert) = &tls.client_certificate {
+        println!("  {} = {}", "cert".bright_white(), cert);
+    }
+    if let Some(key) = &tls.client_key {
+        println!("  {} = {}", "key".bright_white(), key);
+    }
+    if let Some(version) = &tls.min_version {
+        println!("  {} = {}", "tls-min".bright_white(), version);
+    }
+}
+
+fn print_proxy_settings(proxy: &ProxySettings) {
+    println!("\n{}:", "Proxy".bold().cyan());
+    if let Some(url) = &proxy.url {
+        println!("  {} = {}", "url".bright_white(), url);
+    }
+    if let Some(user) = &proxy.username {
+        let password = if proxy.password.is_some() {
+            ":****"
+        } else {
+            ""
+        };
+        println!("  {} = {}{}", "user".bright_white(), user, password);
+    }
+    if !proxy.no_proxy.is_empty() {
+        println!(
+            "  {} = {}",
+            "noproxy".bright_white(),
+            proxy.no_proxy.join(",")
+        );
+    }
+}
+
+fn print_connect_settings(connect: &ConnectSettings) {
+    println!("\n{}:", "Connection".bold().cyan());
+    for resolve in &connect.resolve {
+        println!("  {} = {}", "resolve".bright_white(), resolve);
+    }
+    for connect_to in &connect.connect_to {
+        println!("  {} = {}", "connect-to".bright_white(), connect_to);
+    }
+    if let Some(version) = &connect.ip_version {
+        println!("  {} = IPv{}", "ip".bright_white(), version);
+    }
+    if let Some(local) = &connect.local_address {
+        println!("  {} = {}", "interface".bright_white(), local);
+    }
+    if let Some(socket) = &connect.unix_socket {
+        println!("  {} = {}", "unix-socket".bright_white(), socket);
+    }
+}
+
+/// Converts an imported collection to native Collection format.
+///
+/// Fails on a request whose method isn't a valid method name, instead of
+/// importing it as something it isn't.
+fn convert_imported_to_collection(imported: ImportedCollection) -> Result<Collection, String> {
     let mut collection = Collection::new(imported.name);
     if let Some(desc) = imported.description {
         collection = collection.with_description(desc);
     }
 
     for imported_req in imported.requests {
+        let method = imported_req
+            .method
+            .parse::<HttpMethod>()
+            .map_err(|e| format!("request '{}': {}", imported_req.name, e))?;
         let saved_request = SavedRequest {
             id: imported_req.id,
             name: imported_req.name,
             description: imported_req.description,
             request: SerializableRequest {
-                method: imported_req.method,
+                method: method.as_str().to_string(),
                 url: imported_req.url,
+                query: Vec::new(),
                 headers: imported_req.headers,
                 body: imported_req.body,
                 timeout_seconds: None,
+                auth: None,
+                graphql: None,
             },
         };
         collection.add_request(saved_request);
     }
 
-    collection
+    Ok(collection)
 }
@@ -1,5 +1,7 @@
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::response::{RetryAttempt, Timings};
 use serde::{Deserialize, Serialize};
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryEntry {
@@ -8,21 +10,44 @@ pub struct HistoryEntry {
     pub request: HistoryRequest,
     pub response: HistoryResponse,
     pub duration_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<HistoryTimings>,
+    /// Attempts that were retried before `response`, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub retries: Vec<HistoryAttempt>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryRequest {
     pub method: String,
     pub url: String,
-    pub headers: Vec<(String, String)>,
+    pub headers: Headers,
     pub body: Option<String>,
 }
 
+/// Phase timings in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryTimings {
+    pub dns_ms: Option<f64>,
+    pub connect_ms: Option<f64>,
+    pub tls_ms: Option<f64>,
+    pub first_byte_ms: f64,
+    pub download_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryAttempt {
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    pub delay_ms: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HistoryResponse {
     pub status: u16,
     pub status_text: String,
-    pub headers: Vec<(String, String)>,
+    pub headers: Headers,
     pub body_preview: String,
 }
 
@@ -39,9 +64,21 @@ impl HistoryEntry {
             request,
             response,
             duration_ms,
+            timings: None,
+            retries: Vec::new(),
         }
     }
 
+    pub fn with_timings(mut self, timings: &Timings) -> Self {
+        self.timings = Some(HistoryTimings::from_timings(timings));
+        self
+    }
+
+    pub fn with_retries(mut self, retries: &[RetryAttempt]) -> Self {
+        self.retries = retries.iter().map(HistoryAttempt::from_attempt).collect();
+        self
+    }
+
     pub fn format_timestamp(&self) -> String {
         let datetime = chrono::DateTime::from_timestamp(self.timestamp as i64, 0)
             .unwrap_or(chrono::DateTime::UNIX_EPOCH);
@@ -49,6 +86,41 @@ impl HistoryEntry {
     }
 }
 
+impl HistoryAttempt {
+    pub fn from_attempt(attempt: &RetryAttempt) -> Self {
+        Self {
+            status: attempt.status,
+            error: attempt.error.clone(),
+            duration_ms: attempt.elapsed.as_millis() as u64,
+            delay_ms: attempt.delay.as_millis() as u64,
+        }
+    }
+}
+
+impl HistoryTimings {
+    pub fn from_timings(timings: &Timings) -> Self {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        Self {
+            dns_ms: timings.dns.map(ms),
+            connect_ms: timings.connect.map(ms),
+            tls_ms: timings.tls.map(ms),
+            first_byte_ms: ms(timings.first_byte),
+            download_ms: ms(timings.download),
+        }
+    }
+
+    pub fn to_timings(&self) -> Timings {
+        let duration = |ms: f64| Duration::from_secs_f64(ms.max(0.0) / 1000.0);
+        Timings {
+            dns: self.dns_ms.map(duration),
+            connect: self.connect_ms.map(duration),
+            tls: self.tls_ms.map(duration),
+            first_byte: duration(self.first_byte_ms),
+            download: duration(self.download_ms),
+        }
+    }
+}
+
 impl HistoryResponse {
     pub fn from_response(response: &saffron_core::domain::response::HttpResponse) -> Self {
         let body_preview = if let Ok(body_str) = std::str::from_utf8(&response.body) {
@@ -64,11 +136,7 @@ impl HistoryResponse {
         Self {
             status: response.status,
             status_text: response.status_text.clone(),
-            headers: response
-                .headers
-                .iter()
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect(),
+            headers: response.headers.clone(),
             body_preview,
         }
     }
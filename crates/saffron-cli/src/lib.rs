@@ -1,4 +1,5 @@
 pub mod cli;
+pub mod cookies;
 pub mod handlers;
 pub mod history;
 pub mod output;
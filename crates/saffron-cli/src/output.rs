@@ -1,17 +1,17 @@
 use colored::Colorize;
-use saffron_core::domain::response::HttpResponse;
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::response::{HttpResponse, Timings};
+use saffron_core::domain::template::{TemplateError, Unresolved};
 use saffron_data::json::{Json, JsonElement};
 use saffron_data::parse::Parse;
+use saffron_http::{DownloadProgress, GraphQLError, GrpcStatus, Message, SseEvent};
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
 
-pub fn print_response(response: &HttpResponse, verbose: bool) {
-    println!("\n{} {}", "Status:".bold(), format_status(response.status));
+const PROGRESS_WIDTH: usize = 30;
 
-    if verbose {
-        println!("\n{}:", "Headers".bold().cyan());
-        for (name, value) in &response.headers {
-            println!("  {}: {}", name.bright_black(), value);
-        }
-    }
+pub fn print_response(response: &HttpResponse, verbose: bool) {
+    print_head(response, verbose);
 
     println!("\n{}:", "Body".bold().cyan());
 
@@ -38,6 +38,331 @@ pub fn print_response(response: &HttpResponse, verbose: bool) {
     println!();
 }
 
+/// Summary for a body that was written to `path` instead of the terminal.
+pub fn print_download(response: &HttpResponse, path: &str, verbose: bool) {
+    print_head(response, verbose);
+    println!();
+
+    if response.status == 416 {
+        print_info(&format!("{} is already complete", path));
+    } else {
+        let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        print_success(&format!("Saved {} to {}", format_bytes(size), path));
+    }
+    println!();
+}
+
+/// One Server-Sent Event: the time it arrived, its type and ID, then its
+/// data lines indented below.
+pub fn print_event(event: &SseEvent) {
+    let mut head = format!(
+        "{} {}",
+        chrono::Local::now()
+            .format("%H:%M:%S%.3f")
+            .to_string()
+            .bright_black(),
+        event.event.magenta().bold()
+    );
+    if let Some(id) = &event.id {
+        head.push_str(&format!(" {}", format!("id={}", id).bright_black()));
+    }
+    println!("{}", head);
+
+    for line in event.data.lines() {
+        println!("  {}", line);
+    }
+    let _ = io::stdout().flush();
+}
+
+/// The `errors` of a GraphQL response, after its body, so they aren't lost
+/// in a large `data` object.
+pub fn print_graphql_errors(errors: &[GraphQLError]) {
+    println!(
+        "{}",
+        format!("GraphQL errors ({}):", errors.len()).red().bold()
+    );
+    for error in errors {
+        println!("  {} {}", "✗".red().bold(), error.to_string().red());
+    }
+    println!();
+}
+
+/// One WebSocket message: the time, an arrow for its direction and its
+/// contents. Binary payloads are summarised rather than dumped.
+pub fn print_ws_message(outgoing: bool, message: &Message) {
+    let time = chrono::Local::now()
+        .format("%H:%M:%S%.3f")
+        .to_string()
+        .bright_black();
+    let arrow = if outgoing {
+        "→".green().bold()
+    } else {
+        "←".cyan().bold()
+    };
+
+    let text = match message {
+        Message::Text(text) => text.clone(),
+        Message::Binary(data) => format!("<binary data, {} bytes>", data.len())
+            .bright_black()
+            .to_string(),
+        Message::Ping(_) => "ping".magenta().to_string(),
+        Message::Pong(_) => "pong".magenta().to_string(),
+        Message::Close(Some(frame)) => format!("close {}", frame).magenta().to_string(),
+        Message::Close(None) => "close".magenta().to_string(),
+    };
+    println!("{} {} {}", time, arrow, text);
+    let _ = io::stdout().flush();
+}
+
+/// Phase breakdown in the spirit of `curl -w`: each phase's duration and the
+/// elapsed time when it finished.
+/// Headers or trailers of a gRPC call, under `title`.
+pub fn print_grpc_metadata(title: &str, metadata: &Headers) {
+    println!("\n{}:", title.bold().cyan());
+    for header in metadata {
+        println!("  {}: {}", header.name.bright_black(), header.value);
+    }
+    println!();
+}
+
+/// The status a gRPC call ended with, how many messages it returned and
+/// how long it took.
+pub fn print_grpc_status(status: &GrpcStatus, messages: usize, elapsed: Duration) {
+    let summary = format!(
+        "{} message{} in {}",
+        messages,
+        if messages == 1 { "" } else { "s" },
+        format_ms(elapsed)
+    )
+    .bright_black();
+    let status_text = status.to_string();
+    if status.is_ok() {
+        println!("{} {} {}", "Status:".bold(), status_text.green(), summary);
+    } else {
+        println!("{} {} {}", "Status:".bold(), status_text.red(), summary);
+    }
+}
+
+pub fn print_timings(timings: &Timings) {
+    println!("{}:", "Timing".bold().cyan());
+
+    let phases = [
+        ("DNS lookup", timings.dns),
+        ("TCP connect", timings.connect),
+        ("TLS handshake", timings.tls),
+        ("Server processing", Some(timings.waiting())),
+        ("Content transfer", Some(timings.download)),
+    ];
+
+    let mut elapsed = Duration::ZERO;
+    for (name, phase) in phases {
+        match phase {
+            Some(duration) => {
+                elapsed += duration;
+                println!(
+                    "  {:<20}{:>12}  {}",
+                    name,
+                    format_ms(duration),
+                    format!("(at {})", format_ms(elapsed)).bright_black()
+                );
+            }
+            None => println!("  {:<20}{:>12}", name, "-"),
+        }
+    }
+
+    println!(
+        "  {}{:>12}",
+        format!("{:<20}", "Time to first byte").bold(),
+        format_ms(timings.first_byte)
+    );
+    println!(
+        "  {}{:>12}",
+        format!("{:<20}", "Total").bold(),
+        format_ms(timings.total())
+    );
+    println!();
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+fn print_head(response: &HttpResponse, verbose: bool) {
+    println!("\n{} {}", "Status:".bold(), format_status(response.status));
+
+    if !response.retries.is_empty() {
+        println!("{} {}", "Attempts:".bold(), response.attempts());
+    }
+
+    match (
+        &response.decoded_encoding,
+        response.get_header("content-encoding"),
+    ) {
+        (Some(encoding), _) => println!(
+            "{} {} {}",
+            "Size:".bold(),
+            format_bytes(response.decoded_size),
+            format!(
+                "({}, {} received, {})",
+                encoding,
+                format_bytes(response.raw_size),
+                format_ratio(response.raw_size, response.decoded_size)
+            )
+            .bright_black()
+        ),
+        (None, Some(encoding)) if verbose || response.raw_size > 0 => println!(
+            "{} {} {}",
+            "Size:".bold(),
+            format_bytes(response.raw_size),
+            format!("({}, not decoded)", encoding).bright_black()
+        ),
+        (None, _) if verbose => {
+            println!("{} {}", "Size:".bold(), format_bytes(response.raw_size))
+        }
+        _ => {}
+    }
+
+    if verbose && !response.retries.is_empty() {
+        print_retries(response);
+    }
+
+    if verbose && response.was_redirected() {
+        print_redirects(response);
+    }
+
+    if verbose {
+        println!("\n{}:", "Headers".bold().cyan());
+        for header in &response.headers {
+            println!("  {}: {}", header.name.bright_black(), header.value);
+        }
+    }
+}
+
+/// Download progress drawn on stderr, only when stderr is a terminal.
+pub struct ProgressBar {
+    enabled: bool,
+    last_draw: Option<Instant>,
+}
+
+impl ProgressBar {
+    pub fn new() -> Self {
+        Self {
+            enabled: io::stderr().is_terminal(),
+            last_draw: None,
+        }
+    }
+
+    pub fn update(&mut self, progress: DownloadProgress) {
+        let finished = progress.total == Some(progress.downloaded);
+        let throttled = self
+            .last_draw
+            .is_some_and(|last| last.elapsed() < Duration::from_millis(100));
+
+        if !self.enabled || (throttled && !finished) {
+            return;
+        }
+        self.last_draw = Some(Instant::now());
+
+        let line = match (progress.fraction(), progress.total) {
+            (Some(fraction), Some(total)) => {
+                let filled = (fraction * PROGRESS_WIDTH as f64) as usize;
+                format!(
+                    "[{}{}] {:>3}% {} / {}",
+                    "#".repeat(filled).green(),
+                    "-".repeat(PROGRESS_WIDTH - filled).bright_black(),
+                    (fraction * 100.0) as u32,
+                    format_bytes(progress.downloaded),
+                    format_bytes(total)
+                )
+            }
+            _ => format!("{} downloaded", format_bytes(progress.downloaded)),
+        };
+
+        eprint!("\r{}\x1b[K", line);
+        let _ = io::stderr().flush();
+    }
+
+    pub fn finish(&self) {
+        if self.enabled && self.last_draw.is_some() {
+            eprintln!();
+        }
+    }
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How much smaller the encoded body is, e.g. `-72%`.
+fn format_ratio(raw: u64, decoded: u64) -> String {
+    if decoded == 0 {
+        return "-".to_string();
+    }
+    let change = (raw as f64 / decoded as f64 - 1.0) * 100.0;
+    format!("{:+.0}%", change)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+fn print_redirects(response: &HttpResponse) {
+    println!("\n{}:", "Redirects".bold().cyan());
+    for (i, hop) in response.redirects.iter().enumerate() {
+        println!(
+            "  {} {} {} {} {}",
+            format!("[{}]", i + 1).bright_black(),
+            format_status(hop.status),
+            hop.url,
+            "->".bright_black(),
+            hop.location
+        );
+        println!(
+            "      {}",
+            format!("({}ms)", hop.elapsed.as_millis()).bright_black()
+        );
+        for header in &hop.headers {
+            println!("      {}: {}", header.name.bright_black(), header.value);
+        }
+    }
+}
+
+fn print_retries(response: &HttpResponse) {
+    println!("\n{}:", "Retries".bold().cyan());
+    for (i, attempt) in response.retries.iter().enumerate() {
+        let outcome = match (attempt.status, &attempt.error) {
+            (Some(status), _) => format_status(status),
+            (None, Some(error)) => error.red().to_string(),
+            (None, None) => "failed".red().to_string(),
+        };
+        println!(
+            "  {} {} {}",
+            format!("[{}]", i + 1).bright_black(),
+            outcome,
+            format!(
+                "({}ms, retried after {}ms)",
+                attempt.elapsed.as_millis(),
+                attempt.delay.as_millis()
+            )
+            .bright_black()
+        );
+    }
+}
+
 fn format_status(code: u16) -> String {
     let status_str = code.to_string();
     if (200..300).contains(&code) {
@@ -103,6 +428,25 @@ fn format_json(json: &JsonElement, indent: usize) -> String {
     }
 }
 
+/// Placeholders that couldn't be filled in, one per line, such as
+/// `header 'Authorization': 'token' is not defined`.
+pub fn print_unresolved(unresolved: &[Unresolved]) {
+    for item in unresolved {
+        eprintln!("  {} {}", "✗".red().bold(), item);
+    }
+    if unresolved
+        .iter()
+        .any(|item| matches!(item.error, TemplateError::LocalAccess { .. }))
+    {
+        eprintln!(
+            "{}",
+            "{{$env}} and {{$file}} are off for this collection; import it with \
+             --allow-local-access or set \"local_access\": true in its file"
+                .bright_black()
+        );
+    }
+}
+
 pub fn print_error(message: &str) {
     eprintln!("{} {}", "Error:".red().bold(), message);
 }
@@ -1,6 +1,8 @@
+use crate::cookies::StoredCookie;
 use crate::history::HistoryEntry;
 use saffron_core::domain::collection::Collection;
 use saffron_core::domain::environment::EnvironmentSet;
+use saffron_http::CookieJar;
 use std::fs;
 use std::io;
 use std::path::PathBuf;
@@ -45,6 +47,14 @@ impl Storage {
         dir
     }
 
+    pub fn cookies_dir(&self) -> PathBuf {
+        let dir = self.base_path.join("cookies");
+        if !dir.exists() {
+            let _ = fs::create_dir_all(&dir);
+        }
+        dir
+    }
+
     pub fn save_collection(&self, collection: &Collection) -> io::Result<()> {
         let file_name = format!("{}.json", sanitize_filename(&collection.name));
         let path = self.collections_dir().join(file_name);
@@ -117,6 +127,47 @@ impl Storage {
         Ok(env_set)
     }
 
+    fn cookie_file(&self, env: &str) -> PathBuf {
+        self.cookies_dir()
+            .join(format!("{}.json", sanitize_filename(env)))
+    }
+
+    /// Loads the cookie jar of an environment; an environment without saved
+    /// cookies gets an empty jar.
+    pub fn load_cookie_jar(&self, env: &str) -> io::Result<CookieJar> {
+        let path = self.cookie_file(env);
+        if !path.exists() {
+            return Ok(CookieJar::new());
+        }
+        let contents = fs::read_to_string(path)?;
+        let cookies: Vec<StoredCookie> = serde_json::from_str(&contents)?;
+        Ok(CookieJar::from_cookies(
+            cookies.iter().map(StoredCookie::to_cookie),
+        ))
+    }
+
+    /// Saves the unexpired cookies of `jar`, removing the file once the jar
+    /// is empty.
+    pub fn save_cookie_jar(&self, env: &str, jar: &CookieJar) -> io::Result<()> {
+        let path = self.cookie_file(env);
+        let cookies: Vec<StoredCookie> = jar
+            .cookies()
+            .iter()
+            .map(StoredCookie::from_cookie)
+            .collect();
+
+        if cookies.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+
+        let json = serde_json::to_string_pretty(&cookies)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
     pub fn history_file(&self) -> PathBuf {
         self.base_path.join("history.json")
     }
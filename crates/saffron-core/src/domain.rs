@@ -1,5 +1,9 @@
+pub mod auth;
 pub mod collection;
 pub mod environment;
+pub mod headers;
 pub mod request;
 pub mod request_body;
 pub mod response;
+pub mod template;
+pub mod url;
@@ -0,0 +1,161 @@
+use super::environment::Environment;
+use super::template::{Template, TemplateLocation, Unresolved};
+use serde::{Deserialize, Serialize};
+
+/// How a request authenticates. Applied by the HTTP client at send time, so
+/// secrets never need to be written into headers by hand.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Auth {
+    Basic {
+        username: String,
+        password: String,
+    },
+    Bearer {
+        token: String,
+    },
+    ApiKey {
+        name: String,
+        value: String,
+        #[serde(default)]
+        location: ApiKeyLocation,
+    },
+    /// HTTP Digest (RFC 7616), answered after the server's `401` challenge.
+    Digest {
+        username: String,
+        password: String,
+    },
+    /// OAuth 2.0 access token fetched from `token_url` and sent as a bearer
+    /// token. Tokens are cached and refreshed by the client.
+    #[serde(rename = "oauth2")]
+    OAuth2 {
+        token_url: String,
+        client_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_secret: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scope: Option<String>,
+        grant: OAuth2Grant,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyLocation {
+    #[default]
+    Header,
+    Query,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "grant_type", rename_all = "snake_case")]
+pub enum OAuth2Grant {
+    ClientCredentials,
+    Password { username: String, password: String },
+}
+
+impl Auth {
+    pub fn basic(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Auth::Basic {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Auth::Bearer {
+            token: token.into(),
+        }
+    }
+
+    pub fn api_key(
+        name: impl Into<String>,
+        value: impl Into<String>,
+        location: ApiKeyLocation,
+    ) -> Self {
+        Auth::ApiKey {
+            name: name.into(),
+            value: value.into(),
+            location,
+        }
+    }
+
+    pub fn digest(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Auth::Digest {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Short name of the scheme, for display.
+    pub fn scheme(&self) -> &'static str {
+        match self {
+            Auth::Basic { .. } => "basic",
+            Auth::Bearer { .. } => "bearer",
+            Auth::ApiKey { .. } => "apikey",
+            Auth::Digest { .. } => "digest",
+            Auth::OAuth2 { .. } => "oauth2",
+        }
+    }
+
+    /// Substitutes `{{variables}}` from `environment` in every field.
+    /// Fails with every placeholder that couldn't be resolved.
+    pub fn resolve(&self, environment: &Environment) -> Result<Self, Vec<Unresolved>> {
+        let mut unresolved = Vec::new();
+        let auth = self.map_strings(|s| {
+            let (rendered, errors) = Template::parse(s).render_checked(&environment.variables);
+            unresolved.extend(errors.into_iter().map(|error| Unresolved {
+                location: TemplateLocation::Auth,
+                error,
+            }));
+            rendered
+        });
+        if unresolved.is_empty() {
+            Ok(auth)
+        } else {
+            Err(unresolved)
+        }
+    }
+
+    pub(crate) fn map_strings(&self, mut f: impl FnMut(&str) -> String) -> Self {
+        match self {
+            Auth::Basic { username, password } => Auth::Basic {
+                username: f(username),
+                password: f(password),
+            },
+            Auth::Bearer { token } => Auth::Bearer { token: f(token) },
+            Auth::ApiKey {
+                name,
+                value,
+                location,
+            } => Auth::ApiKey {
+                name: f(name),
+                value: f(value),
+                location: *location,
+            },
+            Auth::Digest { username, password } => Auth::Digest {
+                username: f(username),
+                password: f(password),
+            },
+            Auth::OAuth2 {
+                token_url,
+                client_id,
+                client_secret,
+                scope,
+                grant,
+            } => Auth::OAuth2 {
+                token_url: f(token_url),
+                client_id: f(client_id),
+                client_secret: client_secret.as_deref().map(&mut f),
+                scope: scope.as_deref().map(&mut f),
+                grant: match grant {
+                    OAuth2Grant::ClientCredentials => OAuth2Grant::ClientCredentials,
+                    OAuth2Grant::Password { username, password } => OAuth2Grant::Password {
+                        username: f(username),
+                        password: f(password),
+                    },
+                },
+            },
+        }
+    }
+}
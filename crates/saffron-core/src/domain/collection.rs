@@ -1,4 +1,5 @@
-use super::request::HttpRequest;
+use super::auth::Auth;
+use super::request::{HttpRequest, InvalidMethod};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,6 +8,18 @@ pub struct Collection {
     pub description: Option<String>,
     pub folders: Vec<Folder>,
     pub requests: Vec<SavedRequest>,
+    /// Default auth for requests that don't set their own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub websockets: Vec<SavedWebSocket>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grpc_calls: Vec<SavedGrpcCall>,
+    /// Whether `{{$env}}` and `{{$file}}` in its requests and messages may
+    /// read environment variables and files. Off unless turned on, so an
+    /// imported collection can't send them anywhere.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub local_access: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,9 +43,76 @@ pub struct SavedRequest {
 pub struct SerializableRequest {
     pub method: String,
     pub url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub query: Vec<(String, String)>,
     pub headers: Vec<(String, String)>,
     pub body: Option<String>,
     pub timeout_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    /// Set instead of `body` for GraphQL requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graphql: Option<SavedGraphQL>,
+}
+
+/// The GraphQL body of a saved request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedGraphQL {
+    pub query: String,
+    /// The variables as a JSON object.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+}
+
+/// A WebSocket connection saved next to the HTTP requests of a collection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedWebSocket {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    /// Subprotocols to ask for, in order of preference.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protocols: Vec<String>,
+    /// Messages to send once connected.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+}
+
+/// A gRPC call saved next to the HTTP requests of a collection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGrpcCall {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// The server address, such as `http://localhost:50051`.
+    pub url: String,
+    /// The method, as `package.Service/Method`.
+    pub method: String,
+    /// The `.proto` files describing the service.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proto_files: Vec<String>,
+    /// Directories to look up imports in.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub import_paths: Vec<String>,
+    /// A binary descriptor set, used instead of `.proto` files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub descriptor_set: Option<String>,
+    /// The request message as JSON.
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub metadata: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<Auth>,
+    /// Call over gRPC-Web rather than HTTP/2.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub web: bool,
 }
 
 impl Collection {
@@ -42,6 +122,10 @@ impl Collection {
             description: None,
             folders: Vec::new(),
             requests: Vec::new(),
+            auth: None,
+            websockets: Vec::new(),
+            grpc_calls: Vec::new(),
+            local_access: false,
         }
     }
 
@@ -50,6 +134,11 @@ impl Collection {
         self
     }
 
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
     pub fn add_request(&mut self, request: SavedRequest) {
         self.requests.push(request);
     }
@@ -58,12 +147,58 @@ impl Collection {
         self.folders.push(folder);
     }
 
+    pub fn add_websocket(&mut self, websocket: SavedWebSocket) {
+        self.websockets.push(websocket);
+    }
+
+    pub fn add_grpc_call(&mut self, call: SavedGrpcCall) {
+        self.grpc_calls.push(call);
+    }
+
     pub fn find_request(&self, id: &str) -> Option<&SavedRequest> {
         self.requests
             .iter()
             .find(|r| r.id == id)
             .or_else(|| self.folders.iter().find_map(|f| f.find_request(id)))
     }
+
+    pub fn find_websocket(&self, id: &str) -> Option<&SavedWebSocket> {
+        self.websockets.iter().find(|w| w.id == id)
+    }
+
+    /// Builds the upgrade request for `websocket`, with the collection's auth
+    /// applied when it has none of its own.
+    pub fn websocket_request(&self, websocket: &SavedWebSocket) -> HttpRequest {
+        let mut request = websocket.to_http_request();
+        if request.auth.is_none() {
+            request.auth = self.auth.clone();
+        }
+        request
+    }
+
+    pub fn find_grpc_call(&self, id: &str) -> Option<&SavedGrpcCall> {
+        self.grpc_calls.iter().find(|c| c.id == id)
+    }
+
+    /// Builds the request for `call`, with the collection's auth applied
+    /// when it has none of its own.
+    pub fn grpc_request(&self, call: &SavedGrpcCall) -> HttpRequest {
+        let mut request = call.to_http_request();
+        if request.auth.is_none() {
+            request.auth = self.auth.clone();
+        }
+        request
+    }
+
+    /// Builds `request` with the collection's auth applied when the request
+    /// has none of its own.
+    pub fn http_request(&self, request: &SavedRequest) -> Result<HttpRequest, InvalidMethod> {
+        let mut http_request = request.to_http_request()?;
+        if http_request.auth.is_none() {
+            http_request.auth = self.auth.clone();
+        }
+        Ok(http_request)
+    }
 }
 
 impl Folder {
@@ -112,16 +247,151 @@ impl SavedRequest {
         self
     }
 
-    pub fn to_http_request(&self) -> HttpRequest {
+    pub fn to_http_request(&self) -> Result<HttpRequest, InvalidMethod> {
         self.request.to_http_request()
     }
 }
 
+impl SavedWebSocket {
+    pub fn new(id: impl Into<String>, name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            description: None,
+            url: url.into(),
+            headers: Vec::new(),
+            protocols: Vec::new(),
+            messages: Vec::new(),
+            auth: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_protocol(mut self, protocol: impl Into<String>) -> Self {
+        self.protocols.push(protocol.into());
+        self
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.messages.push(message.into());
+        self
+    }
+
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// The upgrade request, with the subprotocols in a
+    /// `Sec-WebSocket-Protocol` header.
+    pub fn to_http_request(&self) -> HttpRequest {
+        let mut req = HttpRequest::get(self.url.clone());
+
+        for (name, value) in &self.headers {
+            req.add_header(name.clone(), value.clone());
+        }
+
+        if !self.protocols.is_empty() {
+            req.add_header("Sec-WebSocket-Protocol", self.protocols.join(", "));
+        }
+
+        req.auth = self.auth.clone();
+        req
+    }
+}
+
+impl SavedGrpcCall {
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        url: impl Into<String>,
+        method: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            description: None,
+            url: url.into(),
+            method: method.into(),
+            proto_files: Vec::new(),
+            import_paths: Vec::new(),
+            descriptor_set: None,
+            message: "{}".to_string(),
+            metadata: Vec::new(),
+            auth: None,
+            web: false,
+        }
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn with_proto_file(mut self, path: impl Into<String>) -> Self {
+        self.proto_files.push(path.into());
+        self
+    }
+
+    pub fn with_import_path(mut self, path: impl Into<String>) -> Self {
+        self.import_paths.push(path.into());
+        self
+    }
+
+    pub fn with_descriptor_set(mut self, path: impl Into<String>) -> Self {
+        self.descriptor_set = Some(path.into());
+        self
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    pub fn with_metadata(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// The request for the call: a POST to the method's path on the server,
+    /// with the metadata as headers.
+    pub fn to_http_request(&self) -> HttpRequest {
+        let url = format!(
+            "{}/{}",
+            self.url.trim_end_matches('/'),
+            self.method.trim_start_matches('/')
+        );
+        let mut req = HttpRequest::post(url);
+
+        for (name, value) in &self.metadata {
+            req.add_header(name.clone(), value.clone());
+        }
+
+        req.auth = self.auth.clone();
+        req
+    }
+}
+
 impl SerializableRequest {
     pub fn from_request(request: &HttpRequest) -> Self {
         Self {
             method: request.method.as_str().to_string(),
             url: request.url.clone(),
+            query: request.query.clone(),
             headers: request
                 .headers
                 .iter()
@@ -134,22 +404,29 @@ impl SerializableRequest {
                 _ => None,
             },
             timeout_seconds: request.timeout_seconds,
+            auth: request.auth.clone(),
+            graphql: match &request.body {
+                super::request::RequestBody::GraphQL {
+                    query,
+                    variables,
+                    operation_name,
+                } => Some(SavedGraphQL {
+                    query: query.clone(),
+                    variables: variables.clone(),
+                    operation_name: operation_name.clone(),
+                }),
+                _ => None,
+            },
         }
     }
 
-    pub fn to_http_request(&self) -> HttpRequest {
-        let method = match self.method.to_uppercase().as_str() {
-            "GET" => super::request::HttpMethod::Get,
-            "POST" => super::request::HttpMethod::Post,
-            "PUT" => super::request::HttpMethod::Put,
-            "PATCH" => super::request::HttpMethod::Patch,
-            "DELETE" => super::request::HttpMethod::Delete,
-            "HEAD" => super::request::HttpMethod::Head,
-            "OPTIONS" => super::request::HttpMethod::Options,
-            _ => super::request::HttpMethod::Get,
-        };
+    /// Fails if the stored method isn't a valid method name, rather than
+    /// sending the request with a different one.
+    pub fn to_http_request(&self) -> Result<HttpRequest, InvalidMethod> {
+        let method = self.method.parse()?;
 
         let mut req = HttpRequest::new(method, self.url.clone());
+        req.query = self.query.clone();
 
         for (name, value) in &self.headers {
             req.add_header(name.clone(), value.clone());
@@ -159,10 +436,20 @@ impl SerializableRequest {
             req.body = super::request::RequestBody::Text(body.clone());
         }
 
+        if let Some(graphql) = &self.graphql {
+            req.body = super::request::RequestBody::GraphQL {
+                query: graphql.query.clone(),
+                variables: graphql.variables.clone(),
+                operation_name: graphql.operation_name.clone(),
+            };
+        }
+
         if let Some(timeout) = self.timeout_seconds {
             req.timeout_seconds = Some(timeout);
         }
 
-        req
+        req.auth = self.auth.clone();
+
+        Ok(req)
     }
 }
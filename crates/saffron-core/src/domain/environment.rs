@@ -1,3 +1,4 @@
+use super::template::{Template, TemplateError};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -5,6 +6,69 @@ use std::collections::HashMap;
 pub struct Environment {
     pub name: String,
     pub variables: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "TlsSettings::is_empty")]
+    pub tls: TlsSettings,
+    #[serde(default, skip_serializing_if = "ProxySettings::is_empty")]
+    pub proxy: ProxySettings,
+    #[serde(default, skip_serializing_if = "ConnectSettings::is_empty")]
+    pub connect: ConnectSettings,
+}
+
+/// TLS options stored with an environment. Paths point to PEM files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsSettings {
+    #[serde(default)]
+    pub insecure: bool,
+    #[serde(default)]
+    pub ca_certificates: Vec<String>,
+    pub client_certificate: Option<String>,
+    pub client_key: Option<String>,
+    pub min_version: Option<String>,
+}
+
+impl TlsSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Proxy options stored with an environment. `no_proxy` uses `NO_PROXY` syntax.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxySettings {
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxySettings {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Connection options stored with an environment, in curl syntax:
+/// `resolve` entries are `host:port:addr[,addr...]` and `connect_to` entries
+/// are `host1:port1:host2:port2`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectSettings {
+    #[serde(default)]
+    pub resolve: Vec<String>,
+    #[serde(default)]
+    pub connect_to: Vec<String>,
+    /// `"4"` or `"6"` to only use that IP version.
+    pub ip_version: Option<String>,
+    /// Local IP address or interface name to connect from.
+    pub local_address: Option<String>,
+    /// Unix domain socket to send requests through instead of TCP.
+    pub unix_socket: Option<String>,
+}
+
+impl ConnectSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 impl Environment {
@@ -12,6 +76,9 @@ impl Environment {
         Self {
             name: name.into(),
             variables: HashMap::new(),
+            tls: TlsSettings::default(),
+            proxy: ProxySettings::default(),
+            connect: ConnectSettings::default(),
         }
     }
 
@@ -31,22 +98,21 @@ impl Environment {
         self.variables.contains_key(key)
     }
 
-    pub fn resolve_template(&self, template: &str) -> String {
-        let mut result = template.to_string();
-
-        for (key, value) in &self.variables {
-            let placeholder = format!("{{{{{}}}}}", key);
-            result = result.replace(&placeholder, value);
+    /// Fills in `template`'s placeholders with this environment's variables
+    /// and template functions; see [`Template`]. Fails with every
+    /// placeholder that couldn't be resolved.
+    pub fn resolve_template(&self, template: &str) -> Result<String, Vec<TemplateError>> {
+        match Template::parse(template).render_checked(&self.variables) {
+            (rendered, errors) if errors.is_empty() => Ok(rendered),
+            (_, errors) => Err(errors),
         }
-
-        result
     }
 
-    pub fn resolve_request_url(&self, url: &str) -> String {
+    pub fn resolve_request_url(&self, url: &str) -> Result<String, Vec<TemplateError>> {
         self.resolve_template(url)
     }
 
-    pub fn resolve_header_value(&self, value: &str) -> String {
+    pub fn resolve_header_value(&self, value: &str) -> Result<String, Vec<TemplateError>> {
         self.resolve_template(value)
     }
 }
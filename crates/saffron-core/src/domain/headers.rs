@@ -0,0 +1,198 @@
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::Index;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpHeader {
+    pub name: String,
+    pub value: String,
+}
+
+impl HttpHeader {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// An ordered header list. Lookups ignore case, and repeated headers such as
+/// `Set-Cookie` keep every value in the order they were sent.
+///
+/// Serializes as a list of `[name, value]` pairs; a JSON object (the older
+/// format) is accepted when deserializing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Headers(Vec<HttpHeader>);
+
+impl Headers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a header after the existing ones, keeping any with the same name.
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.0.push(HttpHeader::new(name, value));
+    }
+
+    /// Replaces every header named `name` with a single one, in the position
+    /// of the first.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let header = HttpHeader::new(name, value);
+        let Some(first) = self.position(&header.name) else {
+            self.0.push(header);
+            return;
+        };
+
+        let mut index = 0;
+        self.0.retain(|h| {
+            let keep = index <= first || !h.name.eq_ignore_ascii_case(&header.name);
+            index += 1;
+            keep
+        });
+        self.0[first] = header;
+    }
+
+    /// The first value of `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.position(name)
+            .map(|index| self.0[index].value.as_str())
+    }
+
+    /// Every value of `name`, in wire order.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.0
+            .iter()
+            .filter(move |h| h.name.eq_ignore_ascii_case(name))
+            .map(|h| h.value.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    /// Removes every header named `name`, returning how many there were.
+    pub fn remove(&mut self, name: &str) -> usize {
+        let before = self.0.len();
+        self.0.retain(|h| !h.name.eq_ignore_ascii_case(name));
+        before - self.0.len()
+    }
+
+    pub fn retain(&mut self, f: impl FnMut(&HttpHeader) -> bool) {
+        self.0.retain(f);
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, HttpHeader> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|h| h.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl Index<usize> for Headers {
+    type Output = HttpHeader;
+
+    fn index(&self, index: usize) -> &HttpHeader {
+        &self.0[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a Headers {
+    type Item = &'a HttpHeader;
+    type IntoIter = std::slice::Iter<'a, HttpHeader>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for Headers {
+    type Item = HttpHeader;
+    type IntoIter = std::vec::IntoIter<HttpHeader>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl FromIterator<HttpHeader> for Headers {
+    fn from_iter<I: IntoIterator<Item = HttpHeader>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Headers {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|(name, value)| HttpHeader::new(name, value))
+            .collect()
+    }
+}
+
+impl Extend<HttpHeader> for Headers {
+    fn extend<I: IntoIterator<Item = HttpHeader>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl From<Vec<HttpHeader>> for Headers {
+    fn from(headers: Vec<HttpHeader>) -> Self {
+        Self(headers)
+    }
+}
+
+impl Serialize for Headers {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for header in &self.0 {
+            seq.serialize_element(&(&header.name, &header.value))?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Headers {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HeadersVisitor;
+
+        impl<'de> Visitor<'de> for HeadersVisitor {
+            type Value = Headers;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of [name, value] pairs or a map of headers")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Headers, A::Error> {
+                let mut headers = Headers::new();
+                while let Some((name, value)) = seq.next_element::<(String, String)>()? {
+                    headers.append(name, value);
+                }
+                Ok(headers)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Headers, A::Error> {
+                let mut headers = Headers::new();
+                while let Some((name, value)) = map.next_entry::<String, String>()? {
+                    headers.append(name, value);
+                }
+                Ok(headers)
+            }
+        }
+
+        deserializer.deserialize_any(HeadersVisitor)
+    }
+}
@@ -1,4 +1,13 @@
+use super::auth::Auth;
+pub use super::headers::{Headers, HttpHeader};
+use super::template::{Template, TemplateLocation, Unresolved};
+use super::url::{InvalidUrl, ParsedUrl, append_query};
 use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum HttpMethod {
@@ -9,6 +18,11 @@ pub enum HttpMethod {
     Delete,
     Head,
     Options,
+    Trace,
+    Connect,
+    /// Any other method, such as `PURGE` or WebDAV's `PROPFIND`. Build it
+    /// with [`str::parse`] so the name is checked to be a valid token.
+    Custom(String),
 }
 
 impl HttpMethod {
@@ -21,28 +35,52 @@ impl HttpMethod {
             HttpMethod::Delete => "DELETE",
             HttpMethod::Head => "HEAD",
             HttpMethod::Options => "OPTIONS",
+            HttpMethod::Trace => "TRACE",
+            HttpMethod::Connect => "CONNECT",
+            HttpMethod::Custom(name) => name,
         }
     }
 }
 
-impl std::fmt::Display for HttpMethod {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.as_str())
+/// A method name that isn't an RFC 7230 token.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid HTTP method: '{0}'")]
+pub struct InvalidMethod(pub String);
+
+impl std::str::FromStr for HttpMethod {
+    type Err = InvalidMethod;
+
+    /// Standard methods are matched case-insensitively; any other token is
+    /// kept as written, since extension methods are case-sensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let method = match s.to_ascii_uppercase().as_str() {
+            "GET" => HttpMethod::Get,
+            "POST" => HttpMethod::Post,
+            "PUT" => HttpMethod::Put,
+            "PATCH" => HttpMethod::Patch,
+            "DELETE" => HttpMethod::Delete,
+            "HEAD" => HttpMethod::Head,
+            "OPTIONS" => HttpMethod::Options,
+            "TRACE" => HttpMethod::Trace,
+            "CONNECT" => HttpMethod::Connect,
+            _ if is_token(s) => HttpMethod::Custom(s.to_string()),
+            _ => return Err(InvalidMethod(s.to_string())),
+        };
+        Ok(method)
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct HttpHeader {
-    pub name: String,
-    pub value: String,
+/// `token` from RFC 7230 §3.2.6: one or more visible ASCII characters other
+/// than delimiters.
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
 }
 
-impl HttpHeader {
-    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
-        Self {
-            name: name.into(),
-            value: value.into(),
-        }
+impl std::fmt::Display for HttpMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
     }
 }
 
@@ -51,9 +89,21 @@ pub enum RequestBody {
     None,
     Text(String),
     Json(String),
-    FormUrlEncoded(HashMap<String, String>),
+    /// Form fields in order; a name may appear more than once.
+    FormUrlEncoded(Vec<(String, String)>),
     FormData(Vec<FormDataPart>),
     Binary(Vec<u8>),
+    /// Body read from a file or reader while it is sent, without buffering.
+    Stream(BodyStream),
+    /// A GraphQL operation, sent as a JSON document (see
+    /// [`encode_graphql`](super::request_body::encode_graphql)).
+    GraphQL {
+        query: String,
+        /// The operation's variables as a JSON object.
+        variables: Option<String>,
+        /// Which operation to run when `query` defines several.
+        operation_name: Option<String>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -70,16 +120,116 @@ pub enum FormDataContent {
         data: Vec<u8>,
         content_type: Option<String>,
     },
+    /// File part streamed from disk or a reader instead of held in memory.
+    Stream {
+        filename: String,
+        stream: BodyStream,
+        content_type: Option<String>,
+    },
+}
+
+type SharedReader = Arc<Mutex<Option<Box<dyn Read + Send>>>>;
+
+#[derive(Clone)]
+enum StreamSource {
+    File(PathBuf),
+    Reader(SharedReader),
+}
+
+/// A request body that is read while the request is being sent.
+///
+/// File-backed streams are reopened on every send, so they survive redirects
+/// and retries. Reader-backed streams (such as stdin) can only be sent once;
+/// clones share the same reader.
+#[derive(Clone)]
+pub struct BodyStream {
+    source: StreamSource,
+    length: Option<u64>,
+}
+
+impl BodyStream {
+    /// Streams the file at `path`. Its size is used as the `Content-Length`.
+    pub fn from_file(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let length = std::fs::metadata(&path)?.len();
+        Ok(Self {
+            source: StreamSource::File(path),
+            length: Some(length),
+        })
+    }
+
+    /// Streams from `reader`. Without a `length` the body is sent with
+    /// chunked transfer encoding.
+    pub fn from_reader(reader: impl Read + Send + 'static, length: Option<u64>) -> Self {
+        Self {
+            source: StreamSource::Reader(Arc::new(Mutex::new(Some(Box::new(reader))))),
+            length,
+        }
+    }
+
+    pub fn stdin() -> Self {
+        Self::from_reader(io::stdin(), None)
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        self.length
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match &self.source {
+            StreamSource::File(path) => Some(path),
+            StreamSource::Reader(_) => None,
+        }
+    }
+
+    /// Opens the stream for sending. Fails if a reader-backed stream was
+    /// already consumed.
+    pub fn open(&self) -> io::Result<Box<dyn Read + Send>> {
+        match &self.source {
+            StreamSource::File(path) => Ok(Box::new(File::open(path)?)),
+            StreamSource::Reader(reader) => reader
+                .lock()
+                .map_err(|_| io::Error::other("body stream lock poisoned"))?
+                .take()
+                .ok_or_else(|| io::Error::other("body stream was already sent")),
+        }
+    }
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("BodyStream");
+        match &self.source {
+            StreamSource::File(path) => debug.field("path", path),
+            StreamSource::Reader(_) => debug.field("reader", &".."),
+        };
+        debug.field("length", &self.length).finish()
+    }
+}
+
+impl PartialEq for BodyStream {
+    fn eq(&self, other: &Self) -> bool {
+        let same_source = match (&self.source, &other.source) {
+            (StreamSource::File(a), StreamSource::File(b)) => a == b,
+            (StreamSource::Reader(a), StreamSource::Reader(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+        same_source && self.length == other.length
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct HttpRequest {
     pub method: HttpMethod,
     pub url: String,
-    pub headers: Vec<HttpHeader>,
+    /// Query parameters added to `url` when the request is sent, encoded
+    /// with [`HttpRequest::full_url`].
+    pub query: Vec<(String, String)>,
+    pub headers: Headers,
     pub body: RequestBody,
     pub timeout_seconds: Option<u64>,
     pub follow_redirects: bool,
+    pub auth: Option<Auth>,
 }
 
 impl HttpRequest {
@@ -87,10 +237,12 @@ impl HttpRequest {
         Self {
             method,
             url: url.into(),
-            headers: Vec::new(),
+            query: Vec::new(),
+            headers: Headers::new(),
             body: RequestBody::None,
             timeout_seconds: Some(30),
             follow_redirects: true,
+            auth: None,
         }
     }
 
@@ -115,7 +267,13 @@ impl HttpRequest {
     }
 
     pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.push(HttpHeader::new(name, value));
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Adds a query parameter. Repeating a name sends it several times.
+    pub fn with_query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((name.into(), value.into()));
         self
     }
 
@@ -139,6 +297,21 @@ impl HttpRequest {
         self
     }
 
+    /// Sets a GraphQL body without variables or an operation name.
+    pub fn with_graphql(mut self, query: impl Into<String>) -> Self {
+        self.body = RequestBody::GraphQL {
+            query: query.into(),
+            variables: None,
+            operation_name: None,
+        };
+        self
+    }
+
+    pub fn with_stream_body(mut self, stream: BodyStream) -> Self {
+        self.body = RequestBody::Stream(stream);
+        self
+    }
+
     pub fn with_timeout(mut self, seconds: u64) -> Self {
         self.timeout_seconds = Some(seconds);
         self
@@ -149,20 +322,113 @@ impl HttpRequest {
         self
     }
 
+    pub fn with_auth(mut self, auth: Auth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
     pub fn follow_redirects(mut self, follow: bool) -> Self {
         self.follow_redirects = follow;
         self
     }
 
     pub fn add_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
-        self.headers.push(HttpHeader::new(name, value));
+        self.headers.append(name, value);
     }
 
-    pub fn get_header(&self, name: &str) -> Option<&str> {
-        self.headers
+    pub fn add_query(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.query.push((name.into(), value.into()));
+    }
+
+    /// The URL to send: `url` with `query` percent-encoded and appended,
+    /// after checking that `url` is valid.
+    pub fn full_url(&self) -> Result<String, InvalidUrl> {
+        ParsedUrl::parse(&self.url)?;
+        Ok(append_query(&self.url, &self.query))
+    }
+
+    /// Fills in the placeholders in the URL, query, headers, body (text,
+    /// JSON, form fields, multipart text parts or GraphQL variables) and
+    /// auth from `variables`, returning the
+    /// request along with every placeholder that couldn't be resolved;
+    /// those are left as written. Each query parameter is resolved on its
+    /// own, so `&` or spaces from a variable are encoded in its value.
+    pub fn resolve(self, variables: &HashMap<String, String>) -> (Self, Vec<Unresolved>) {
+        self.resolve_with(variables, true)
+    }
+
+    /// Like [`resolve`](Self::resolve), with `{{$env}}` and `{{$file}}`
+    /// left unresolved unless `local_access` is set.
+    pub fn resolve_with(
+        mut self,
+        variables: &HashMap<String, String>,
+        local_access: bool,
+    ) -> (Self, Vec<Unresolved>) {
+        let mut unresolved = Vec::new();
+        let mut resolve = |location: TemplateLocation, text: &str| {
+            let (rendered, errors) =
+                Template::parse(text).render_checked_with(variables, local_access);
+            unresolved.extend(errors.into_iter().map(|error| Unresolved {
+                location: location.clone(),
+                error,
+            }));
+            rendered
+        };
+
+        self.url = resolve(TemplateLocation::Url, &self.url);
+        self.query = self
+            .query
             .iter()
-            .find(|h| h.name.eq_ignore_ascii_case(name))
-            .map(|h| h.value.as_str())
+            .map(|(name, value)| {
+                (
+                    resolve(TemplateLocation::QueryName, name),
+                    resolve(TemplateLocation::QueryValue(name.clone()), value),
+                )
+            })
+            .collect();
+        self.headers = self
+            .headers
+            .iter()
+            .map(|h| {
+                (
+                    resolve(TemplateLocation::HeaderName, &h.name),
+                    resolve(TemplateLocation::HeaderValue(h.name.clone()), &h.value),
+                )
+            })
+            .collect();
+        match &mut self.body {
+            RequestBody::Text(body) | RequestBody::Json(body) => {
+                *body = resolve(TemplateLocation::Body, body);
+            }
+            RequestBody::GraphQL {
+                variables: Some(graphql_variables),
+                ..
+            } => *graphql_variables = resolve(TemplateLocation::Body, graphql_variables),
+            RequestBody::FormUrlEncoded(pairs) => {
+                for (name, value) in pairs {
+                    *name = resolve(TemplateLocation::Body, name);
+                    *value = resolve(TemplateLocation::Body, value);
+                }
+            }
+            RequestBody::FormData(parts) => {
+                for part in parts {
+                    part.name = resolve(TemplateLocation::Body, &part.name);
+                    if let FormDataContent::Text(value) = &mut part.content {
+                        *value = resolve(TemplateLocation::Body, value);
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.auth = self
+            .auth
+            .map(|auth| auth.map_strings(|s| resolve(TemplateLocation::Auth, s)));
+
+        (self, unresolved)
+    }
+
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
     }
 
     pub fn content_type(&self) -> Option<&str> {
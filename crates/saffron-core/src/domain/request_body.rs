@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 #[derive(Debug, Clone, PartialEq)]
 pub enum ContentType {
     Json,
@@ -43,21 +41,111 @@ impl std::fmt::Display for ContentType {
     }
 }
 
-pub fn encode_form_urlencoded(data: &HashMap<String, String>) -> String {
-    data.iter()
-        .map(|(k, v)| format!("{}={}", urlencoding::encode(k), urlencoding::encode(v)))
+/// The JSON document for a GraphQL request, as in the GraphQL over HTTP spec:
+/// `query`, then `variables` and `operationName` when set. `variables` is
+/// inserted as is, so it must already be a JSON object.
+pub fn encode_graphql(
+    query: &str,
+    variables: Option<&str>,
+    operation_name: Option<&str>,
+) -> String {
+    let mut json = format!("{{\"query\":{}", json_string(query));
+    if let Some(variables) = variables {
+        json.push_str(&format!(",\"variables\":{}", variables.trim()));
+    }
+    if let Some(operation_name) = operation_name {
+        json.push_str(&format!(
+            ",\"operationName\":{}",
+            json_string(operation_name)
+        ));
+    }
+    json.push('}');
+    json
+}
+
+/// `s` as a quoted JSON string.
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Encodes `pairs` as `application/x-www-form-urlencoded`, in order and
+/// with repeated names kept. Follows the WHATWG URL standard: spaces become
+/// `+` and everything but ASCII letters, digits and `*-._` is percent-encoded
+/// as UTF-8, so `São Paulo` is sent as `S%C3%A3o+Paulo`.
+pub fn encode_form_urlencoded(pairs: &[(String, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{}={}", form_encode(name), form_encode(value)))
         .collect::<Vec<_>>()
         .join("&")
 }
 
-mod urlencoding {
-    pub fn encode(s: &str) -> String {
-        s.chars()
-            .map(|c| match c {
-                'A'..='Z' | 'a'..='z' | '0'..='9' | '-' | '_' | '.' | '~' => c.to_string(),
-                ' ' => "+".to_string(),
-                _ => format!("%{:02X}", c as u8),
-            })
-            .collect()
+/// Decodes an `application/x-www-form-urlencoded` body or a query string
+/// into its pairs, in order. `+` is a space, and percent-encoded bytes are
+/// read as UTF-8 with invalid sequences replaced. Empty pieces between `&`
+/// are skipped and a piece without `=` has an empty value.
+pub fn decode_form_urlencoded(input: &str) -> Vec<(String, String)> {
+    input
+        .split('&')
+        .filter(|piece| !piece.is_empty())
+        .map(|piece| {
+            let (name, value) = piece.split_once('=').unwrap_or((piece, ""));
+            (form_decode(name), form_decode(value))
+        })
+        .collect()
+}
+
+fn form_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                encoded.push(byte as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
     }
+    encoded
+}
+
+/// Decodes `+` and `%XX`. A `%` not followed by two hex digits is kept as
+/// it is.
+fn form_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], hex_byte(&bytes[i + 1..])) {
+            (b'+', _) => decoded.push(b' '),
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 2;
+            }
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The byte written as the two hex digits `bytes` starts with.
+fn hex_byte(bytes: &[u8]) -> Option<u8> {
+    let high = (*bytes.first()? as char).to_digit(16)?;
+    let low = (*bytes.get(1)? as char).to_digit(16)?;
+    Some((high * 16 + low) as u8)
 }
@@ -1,35 +1,136 @@
-use std::collections::HashMap;
+use super::headers::Headers;
+use super::request_body::decode_form_urlencoded;
 use std::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HttpResponse {
     pub status: u16,
     pub status_text: String,
-    pub headers: HashMap<String, String>,
+    pub headers: Headers,
     pub body: Vec<u8>,
+    /// Body size as received, before any `Content-Encoding` was decoded.
+    pub raw_size: u64,
+    /// Body size after decoding. Also set when the body was streamed
+    /// elsewhere and `body` is empty.
+    pub decoded_size: u64,
+    /// The `Content-Encoding` that was decoded, or `None` if `body` is as received.
+    pub decoded_encoding: Option<String>,
     pub elapsed: Duration,
     pub url: String,
+    pub redirects: Vec<RedirectHop>,
+    pub timings: Timings,
+    /// Earlier attempts that failed and were retried, oldest first.
+    pub retries: Vec<RetryAttempt>,
+}
+
+/// Phase timings of the final request.
+///
+/// A phase is `None` when it did not happen, for example DNS and connect on a
+/// pooled connection, or TLS on a plain HTTP request. `connect` is the TCP or
+/// Unix socket connect, to the proxy when the request goes through one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    pub dns: Option<Duration>,
+    pub connect: Option<Duration>,
+    pub tls: Option<Duration>,
+    /// From sending the request to the first response byte, including the
+    /// phases above.
+    pub first_byte: Duration,
+    /// Time spent reading the response body.
+    pub download: Duration,
+}
+
+impl Timings {
+    /// Time between the connection being ready and the first response byte.
+    pub fn waiting(&self) -> Duration {
+        let setup = self.dns.unwrap_or_default()
+            + self.connect.unwrap_or_default()
+            + self.tls.unwrap_or_default();
+        self.first_byte.saturating_sub(setup)
+    }
+
+    pub fn total(&self) -> Duration {
+        self.first_byte + self.download
+    }
+}
+
+/// A single redirect response that was followed before reaching the final response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedirectHop {
+    pub status: u16,
+    pub url: String,
+    pub location: String,
+    pub headers: Headers,
+    pub elapsed: Duration,
+}
+
+/// An attempt that was retried, with the reason and the wait before the next one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryAttempt {
+    /// Status of a retryable response, or `None` if the attempt failed
+    /// before a response arrived.
+    pub status: Option<u16>,
+    /// Transport error of the attempt, when there was no response.
+    pub error: Option<String>,
+    pub elapsed: Duration,
+    /// Wait before the next attempt, from the backoff or `Retry-After`.
+    pub delay: Duration,
 }
 
 impl HttpResponse {
     pub fn new(
         status: u16,
         status_text: String,
-        headers: HashMap<String, String>,
+        headers: Headers,
         body: Vec<u8>,
         elapsed: Duration,
         url: String,
     ) -> Self {
+        let size = body.len() as u64;
         Self {
             status,
             status_text,
             headers,
             body,
+            raw_size: size,
+            decoded_size: size,
+            decoded_encoding: None,
             elapsed,
             url,
+            redirects: Vec::new(),
+            timings: Timings::default(),
+            retries: Vec::new(),
         }
     }
 
+    pub fn with_redirects(mut self, redirects: Vec<RedirectHop>) -> Self {
+        self.redirects = redirects;
+        self
+    }
+
+    pub fn with_timings(mut self, timings: Timings) -> Self {
+        self.timings = timings;
+        self
+    }
+
+    pub fn with_retries(mut self, retries: Vec<RetryAttempt>) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn was_decoded(&self) -> bool {
+        self.decoded_encoding.is_some()
+    }
+
+    /// Number of attempts made, including the one that produced this response.
+    pub fn attempts(&self) -> usize {
+        self.retries.len() + 1
+    }
+
+    pub fn was_redirected(&self) -> bool {
+        !self.redirects.is_empty()
+    }
+
     pub fn is_success(&self) -> bool {
         self.status >= 200 && self.status < 300
     }
@@ -55,17 +156,11 @@ impl HttpResponse {
     }
 
     pub fn content_type(&self) -> Option<&str> {
-        self.headers
-            .iter()
-            .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
-            .map(|(_, v)| v.as_str())
+        self.headers.get("content-type")
     }
 
     pub fn get_header(&self, name: &str) -> Option<&str> {
-        self.headers
-            .iter()
-            .find(|(k, _)| k.eq_ignore_ascii_case(name))
-            .map(|(_, v)| v.as_str())
+        self.headers.get(name)
     }
 
     pub fn is_json(&self) -> bool {
@@ -74,6 +169,17 @@ impl HttpResponse {
             .unwrap_or(false)
     }
 
+    /// The body's fields when it is `application/x-www-form-urlencoded`.
+    pub fn form(&self) -> Option<Vec<(String, String)>> {
+        let is_form = self
+            .content_type()
+            .is_some_and(|ct| ct.contains("application/x-www-form-urlencoded"));
+        if !is_form {
+            return None;
+        }
+        Some(decode_form_urlencoded(self.body_as_str()?))
+    }
+
     pub fn is_html(&self) -> bool {
         self.content_type()
             .map(|ct| ct.contains("text/html"))
@@ -86,6 +192,8 @@ impl HttpResponse {
             .unwrap_or(false)
     }
 
+    /// The `Content-Length` header. For a decoded body this is the encoded
+    /// size; see [`HttpResponse::raw_size`] and [`HttpResponse::decoded_size`].
     pub fn content_length(&self) -> Option<usize> {
         self.get_header("content-length")
             .and_then(|v| v.parse().ok())
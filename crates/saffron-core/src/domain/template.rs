@@ -0,0 +1,380 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{SecondsFormat, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A string with `{{...}}` placeholders, parsed once and rendered against a
+/// set of variables.
+///
+/// A placeholder is either a variable, `{{name}}`, or a function call such
+/// as `{{$uuid}}` or `{{$randomInt 1 100}}`. Either may be followed by a
+/// default used when the variable isn't defined or the call fails:
+/// `{{port | 8080}}`. Function arguments and defaults may contain
+/// placeholders of their own, as in `{{$base64 {{user}}:{{pass}}}}`.
+///
+/// Text that doesn't form a placeholder, such as `{{ }}` or an unclosed
+/// `{{`, is kept as it is.
+///
+/// `{{$env NAME}}` and `{{$file PATH}}` read environment variables and
+/// files, so a template from someone else, such as an imported collection,
+/// can send them anywhere its request goes. Render those with
+/// [`render_checked_with`](Self::render_checked_with) and local access off.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+/// Why a placeholder couldn't be filled in.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TemplateError {
+    #[error("'{name}' is not defined")]
+    Undefined { placeholder: String, name: String },
+    #[error("{placeholder}: {reason}")]
+    Failed { placeholder: String, reason: String },
+    #[error("{placeholder}: reading environment variables and files isn't allowed here")]
+    LocalAccess { placeholder: String },
+}
+
+impl TemplateError {
+    /// The placeholder as written, such as `{{host}}`.
+    pub fn placeholder(&self) -> &str {
+        match self {
+            TemplateError::Undefined { placeholder, .. } => placeholder,
+            TemplateError::Failed { placeholder, .. } => placeholder,
+            TemplateError::LocalAccess { placeholder } => placeholder,
+        }
+    }
+}
+
+/// Where a template sits in a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateLocation {
+    Url,
+    QueryName,
+    /// The value of the named query parameter.
+    QueryValue(String),
+    HeaderName,
+    /// The value of the named header.
+    HeaderValue(String),
+    Body,
+    Auth,
+}
+
+impl std::fmt::Display for TemplateLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateLocation::Url => write!(f, "URL"),
+            TemplateLocation::QueryName => write!(f, "query parameter name"),
+            TemplateLocation::QueryValue(name) => write!(f, "query parameter '{}'", name),
+            TemplateLocation::HeaderName => write!(f, "header name"),
+            TemplateLocation::HeaderValue(name) => write!(f, "header '{}'", name),
+            TemplateLocation::Body => write!(f, "body"),
+            TemplateLocation::Auth => write!(f, "auth"),
+        }
+    }
+}
+
+/// A placeholder in a request that couldn't be filled in.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{location}: {error}")]
+pub struct Unresolved {
+    pub location: TemplateLocation,
+    pub error: TemplateError,
+}
+
+/// The functions that read the local machine, which need local access.
+const LOCAL_FUNCTIONS: &[&str] = &["env", "file"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Placeholder {
+    /// The placeholder as written, braces included.
+    source: String,
+    expression: Expression,
+    default: Option<Template>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expression {
+    Variable(String),
+    Function { name: String, argument: Template },
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Self {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut rest = source;
+
+        while let Some(start) = rest.find("{{") {
+            text.push_str(&rest[..start]);
+            let from = &rest[start..];
+            let placeholder = placeholder_len(from)
+                .and_then(|len| Placeholder::parse(&from[..len]).map(|p| (len, p)));
+
+            match placeholder {
+                Some((len, placeholder)) => {
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Placeholder(placeholder));
+                    rest = &from[len..];
+                }
+                // Not a placeholder from here; one of the braces may still
+                // open one, as in `{{{name}}}`
+                None => {
+                    text.push('{');
+                    rest = &from[1..];
+                }
+            }
+        }
+
+        text.push_str(rest);
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Self { parts }
+    }
+
+    /// Fills in the placeholders. Ones that can't be resolved are left as
+    /// they were written.
+    pub fn render(&self, variables: &HashMap<String, String>) -> String {
+        self.render_checked(variables).0
+    }
+
+    /// Like [`render`](Self::render), also returning why each placeholder
+    /// left as written couldn't be resolved. A placeholder that fails
+    /// because of one nested in it is reported through the nested one.
+    pub fn render_checked(
+        &self,
+        variables: &HashMap<String, String>,
+    ) -> (String, Vec<TemplateError>) {
+        self.render_checked_with(variables, true)
+    }
+
+    /// Like [`render_checked`](Self::render_checked), with `$env` and
+    /// `$file` failing unless `local_access` is set.
+    pub fn render_checked_with(
+        &self,
+        variables: &HashMap<String, String>,
+        local_access: bool,
+    ) -> (String, Vec<TemplateError>) {
+        let mut rendered = String::new();
+        let mut errors = Vec::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => rendered.push_str(text),
+                Part::Placeholder(placeholder) => {
+                    match placeholder.evaluate(variables, local_access) {
+                        Ok(value) => rendered.push_str(&value),
+                        Err(e) => {
+                            rendered.push_str(&placeholder.source);
+                            errors.extend(e);
+                        }
+                    }
+                }
+            }
+        }
+        (rendered, errors)
+    }
+}
+
+impl Placeholder {
+    /// Parses `source`, a whole `{{...}}`.
+    fn parse(source: &str) -> Option<Self> {
+        let inner = source[2..source.len() - 2].trim();
+        let (expression, default) = match split_default(inner) {
+            Some((expression, default)) => {
+                (expression.trim(), Some(Template::parse(default.trim())))
+            }
+            None => (inner, None),
+        };
+
+        let expression = match expression.strip_prefix('$') {
+            Some(call) => {
+                let (name, argument) = call.split_once(char::is_whitespace).unwrap_or((call, ""));
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return None;
+                }
+                Expression::Function {
+                    name: name.to_string(),
+                    argument: Template::parse(argument.trim()),
+                }
+            }
+            None => {
+                if expression.is_empty()
+                    || expression.contains(|c: char| c.is_whitespace() || "{}|".contains(c))
+                {
+                    return None;
+                }
+                Expression::Variable(expression.to_string())
+            }
+        };
+
+        Some(Self {
+            source: source.to_string(),
+            expression,
+            default,
+        })
+    }
+
+    /// The placeholder's value, or why it has none.
+    fn evaluate(
+        &self,
+        variables: &HashMap<String, String>,
+        local_access: bool,
+    ) -> Result<String, Vec<TemplateError>> {
+        let value = match &self.expression {
+            Expression::Variable(name) => variables.get(name).cloned().ok_or_else(|| {
+                vec![TemplateError::Undefined {
+                    placeholder: self.source.clone(),
+                    name: name.clone(),
+                }]
+            }),
+            Expression::Function { name, .. }
+                if !local_access && LOCAL_FUNCTIONS.contains(&name.as_str()) =>
+            {
+                Err(vec![TemplateError::LocalAccess {
+                    placeholder: self.source.clone(),
+                }])
+            }
+            Expression::Function { name, argument } => {
+                match argument.render_checked_with(variables, local_access) {
+                    (argument, errors) if errors.is_empty() => {
+                        call(name, &argument).map_err(|reason| {
+                            vec![TemplateError::Failed {
+                                placeholder: self.source.clone(),
+                                reason,
+                            }]
+                        })
+                    }
+                    (_, errors) => Err(errors),
+                }
+            }
+        };
+
+        match (value, &self.default) {
+            (Err(_), Some(default)) => match default.render_checked_with(variables, local_access) {
+                (value, errors) if errors.is_empty() => Ok(value),
+                (_, errors) => Err(errors),
+            },
+            (value, _) => value,
+        }
+    }
+}
+
+/// The length of the placeholder `s` starts with, through the `}}` that
+/// closes it. Placeholders nested inside are skipped over.
+fn placeholder_len(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut depth = 0;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match &bytes[i..i + 2] {
+            b"{{" => {
+                depth += 1;
+                i += 2;
+            }
+            b"}}" => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+/// Splits `inner` at its first `|` outside nested placeholders.
+fn split_default(inner: &str) -> Option<(&str, &str)> {
+    let bytes = inner.as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"{{") {
+            depth += 1;
+            i += 2;
+        } else if bytes[i..].starts_with(b"}}") {
+            depth = depth.saturating_sub(1);
+            i += 2;
+        } else {
+            if bytes[i] == b'|' && depth == 0 {
+                return Some((&inner[..i], &inner[i + 1..]));
+            }
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Runs the template function `$name`.
+fn call(name: &str, argument: &str) -> Result<String, String> {
+    let no_argument = || {
+        if argument.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("${} takes no argument", name))
+        }
+    };
+    let required = |what: &str| {
+        if argument.is_empty() {
+            Err(format!("${} needs {}", name, what))
+        } else {
+            Ok(argument)
+        }
+    };
+
+    match name {
+        "uuid" => no_argument().map(|_| Uuid::new_v4().to_string()),
+        "timestamp" => no_argument().map(|_| Utc::now().timestamp().to_string()),
+        "isoDate" => no_argument().map(|_| Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+        "randomInt" => random_int(argument).map(|n| n.to_string()),
+        "base64" => Ok(BASE64.encode(argument)),
+        "env" => {
+            let variable = required("a variable name")?;
+            std::env::var(variable)
+                .map_err(|_| format!("environment variable '{}' is not set", variable))
+        }
+        "file" => {
+            let path = required("a path")?;
+            let contents = std::fs::read_to_string(path)
+                .map_err(|e| format!("can't read '{}': {}", path, e))?;
+            let contents = contents.strip_suffix('\n').unwrap_or(&contents);
+            Ok(contents.strip_suffix('\r').unwrap_or(contents).to_string())
+        }
+        _ => Err(format!("unknown function '${}'", name)),
+    }
+}
+
+/// A random integer between two inclusive bounds, `0 1000` when none are
+/// given.
+fn random_int(argument: &str) -> Result<i64, String> {
+    let bounds: Vec<&str> = argument.split_whitespace().collect();
+    let (min, max) = match bounds.as_slice() {
+        [] => (0, 1000),
+        [min, max] => (
+            min.parse::<i64>()
+                .map_err(|_| format!("invalid bound '{}' for $randomInt", min))?,
+            max.parse::<i64>()
+                .map_err(|_| format!("invalid bound '{}' for $randomInt", max))?,
+        ),
+        _ => return Err("$randomInt takes a minimum and a maximum".to_string()),
+    };
+    if min > max {
+        return Err(format!(
+            "$randomInt minimum {} is above maximum {}",
+            min, max
+        ));
+    }
+
+    Ok(rand::random_range(min..=max))
+}
@@ -0,0 +1,211 @@
+use super::request_body::decode_form_urlencoded;
+
+/// A URL that can't be sent, with the reason.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{url} ({reason})")]
+pub struct InvalidUrl {
+    pub url: String,
+    pub reason: String,
+}
+
+/// The parts of an absolute URL, as written: nothing is decoded or
+/// normalized except the scheme, which is lowercased.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedUrl {
+    pub scheme: String,
+    pub userinfo: Option<String>,
+    /// The host, with the brackets of an IPv6 literal. Empty for URLs such
+    /// as `unix:///var/run/docker.sock`.
+    pub host: String,
+    pub port: Option<u16>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl ParsedUrl {
+    /// Splits `url` into its parts, checking the scheme, host and port.
+    /// The scheme must be `http`, `https`, `ws`, `wss` or `unix`; all but
+    /// `unix` need a host.
+    pub fn parse(url: &str) -> Result<Self, InvalidUrl> {
+        let invalid = |reason: String| InvalidUrl {
+            url: url.to_string(),
+            reason,
+        };
+
+        if let Some(c) = url.chars().find(|c| c.is_control()) {
+            return Err(invalid(format!("contains the control character {:?}", c)));
+        }
+
+        let (scheme, rest) = url
+            .split_once(':')
+            .filter(|(scheme, _)| is_scheme(scheme))
+            .ok_or_else(|| invalid("missing a scheme such as https://".to_string()))?;
+        let scheme = scheme.to_ascii_lowercase();
+        if !matches!(scheme.as_str(), "http" | "https" | "ws" | "wss" | "unix") {
+            // `localhost:8080/api` reads as a `localhost` scheme
+            return Err(invalid(if rest.starts_with("//") {
+                format!("unsupported scheme '{}'", scheme)
+            } else {
+                "missing a scheme such as https://".to_string()
+            }));
+        }
+
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment.to_string())),
+            None => (rest, None),
+        };
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query.to_string())),
+            None => (rest, None),
+        };
+
+        let (authority, path) = match rest.strip_prefix("//") {
+            Some(after) => {
+                let end = after.find('/').unwrap_or(after.len());
+                (Some(&after[..end]), &after[end..])
+            }
+            None => (None, rest),
+        };
+        let needs_host = matches!(scheme.as_str(), "http" | "https" | "ws" | "wss");
+
+        let (userinfo, host, port) = match authority {
+            Some(authority) => {
+                let (userinfo, host_port) = match authority.rsplit_once('@') {
+                    Some((userinfo, host_port)) => (Some(userinfo.to_string()), host_port),
+                    None => (None, authority),
+                };
+                let (host, port) = split_port(host_port).map_err(invalid)?;
+                (userinfo, host, port)
+            }
+            None if needs_host => return Err(invalid("expected // after the scheme".to_string())),
+            None => (None, "", None),
+        };
+
+        if needs_host && host.is_empty() {
+            return Err(invalid("missing host".to_string()));
+        }
+        if let Some(c) = host.chars().find(|&c| !is_host_char(c)) {
+            return Err(invalid(format!("invalid character '{}' in host", c)));
+        }
+
+        Ok(Self {
+            scheme,
+            userinfo,
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            query,
+            fragment,
+        })
+    }
+
+    /// The query string decoded into its pairs, in order.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        self.query
+            .as_deref()
+            .map(decode_form_urlencoded)
+            .unwrap_or_default()
+    }
+}
+
+/// `scheme` from RFC 3986 §3.1: a letter, then letters, digits, `+`, `-`
+/// or `.`.
+fn is_scheme(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic())
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Characters of a registered name or IP literal: unreserved, sub-delims,
+/// percent-encoding, and `[`, `]` and `:` for IPv6. Non-ASCII letters are
+/// allowed for internationalized names.
+fn is_host_char(c: char) -> bool {
+    c.is_alphanumeric() || "-._~%!$&'()*+,;=[]:".contains(c)
+}
+
+/// Splits `host:port`, leaving the colons of an IPv6 literal alone. An
+/// empty port is the same as none.
+fn split_port(host_port: &str) -> Result<(&str, Option<u16>), String> {
+    let colon = match host_port.strip_prefix('[') {
+        Some(literal) => {
+            let end = literal
+                .find(']')
+                .ok_or_else(|| "unclosed '[' in host".to_string())?;
+            let after = &literal[end + 1..];
+            if !after.is_empty() && !after.starts_with(':') {
+                return Err(format!("unexpected '{}' after the IPv6 address", after));
+            }
+            (!after.is_empty()).then_some(end + 2)
+        }
+        None => host_port.rfind(':'),
+    };
+
+    let Some(colon) = colon else {
+        return Ok((host_port, None));
+    };
+    let port = &host_port[colon + 1..];
+    if port.is_empty() {
+        return Ok((&host_port[..colon], None));
+    }
+    match port.parse::<u16>() {
+        Ok(number) if port.bytes().all(|b| b.is_ascii_digit()) => {
+            Ok((&host_port[..colon], Some(number)))
+        }
+        _ => Err(format!("invalid port '{}'", port)),
+    }
+}
+
+/// Percent-encodes a query parameter name or value as RFC 3986 asks:
+/// everything but unreserved characters is encoded as UTF-8 bytes, so `&`,
+/// `=`, `+`, `#` and spaces arrive as they were written.
+pub fn encode_query_component(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// `url` with `params` added to its query string, ahead of any fragment.
+/// Parameters already in `url` are kept as they are.
+pub fn append_query(url: &str, params: &[(String, String)]) -> String {
+    if params.is_empty() {
+        return url.to_string();
+    }
+
+    let (base, fragment) = match url.split_once('#') {
+        Some((base, fragment)) => (base, Some(fragment)),
+        None => (url, None),
+    };
+
+    let mut result = base.to_string();
+    if !base.contains('?') {
+        result.push('?');
+    } else if !base.ends_with('?') && !base.ends_with('&') {
+        result.push('&');
+    }
+
+    let pairs: Vec<String> = params
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "{}={}",
+                encode_query_component(name),
+                encode_query_component(value)
+            )
+        })
+        .collect();
+    result.push_str(&pairs.join("&"));
+
+    if let Some(fragment) = fragment {
+        result.push('#');
+        result.push_str(fragment);
+    }
+    result
+}
@@ -1,5 +1,9 @@
-use saffron_core::domain::collection::{Collection, Folder, SavedRequest};
-use saffron_core::domain::request::HttpRequest;
+use saffron_core::domain::auth::Auth;
+use saffron_core::domain::collection::{
+    Collection, Folder, SavedGraphQL, SavedGrpcCall, SavedRequest, SavedWebSocket,
+    SerializableRequest,
+};
+use saffron_core::domain::request::{HttpMethod, HttpRequest, InvalidMethod, RequestBody};
 
 #[test]
 fn test_collection_new() {
@@ -8,6 +12,7 @@ fn test_collection_new() {
     assert!(collection.description.is_none());
     assert!(collection.folders.is_empty());
     assert!(collection.requests.is_empty());
+    assert!(!collection.local_access);
 }
 
 #[test]
@@ -176,13 +181,70 @@ fn test_saved_request_to_http_request() {
         .with_timeout(45);
 
     let saved = SavedRequest::new("create-user", "Create User", &original);
-    let restored = saved.to_http_request();
+    let restored = saved.to_http_request().unwrap();
 
     assert_eq!(restored.url, "https://api.example.com/users");
     assert_eq!(restored.headers.len(), 1);
     assert_eq!(restored.timeout_seconds, Some(45));
 }
 
+#[test]
+fn test_saved_request_keeps_auth() {
+    let original = HttpRequest::get("https://api.example.com").with_auth(Auth::bearer("abc"));
+
+    let saved = SavedRequest::new("me", "Me", &original);
+    assert_eq!(saved.request.auth, Some(Auth::bearer("abc")));
+    assert_eq!(
+        saved.to_http_request().unwrap().auth,
+        Some(Auth::bearer("abc"))
+    );
+}
+
+#[test]
+fn test_collection_auth_applies_to_requests_without_their_own() {
+    let mut collection = Collection::new("API").with_auth(Auth::basic("admin", "secret"));
+    let plain = SavedRequest::new("plain", "Plain", &HttpRequest::get("https://example.com"));
+    let own = SavedRequest::new(
+        "own",
+        "Own",
+        &HttpRequest::get("https://example.com").with_auth(Auth::bearer("abc")),
+    );
+    collection.add_request(plain.clone());
+    collection.add_request(own.clone());
+
+    assert_eq!(
+        collection.http_request(&plain).unwrap().auth,
+        Some(Auth::basic("admin", "secret"))
+    );
+    assert_eq!(
+        collection.http_request(&own).unwrap().auth,
+        Some(Auth::bearer("abc"))
+    );
+}
+
+#[test]
+fn test_saved_request_keeps_extension_method() {
+    let method: HttpMethod = "PURGE".parse().unwrap();
+    let saved = SavedRequest::new("purge", "Purge", &HttpRequest::new(method, "/cache"));
+
+    assert_eq!(saved.request.method, "PURGE");
+    assert_eq!(
+        saved.to_http_request().unwrap().method,
+        HttpMethod::Custom("PURGE".to_string())
+    );
+}
+
+#[test]
+fn test_saved_request_with_invalid_method_fails() {
+    let mut saved = SavedRequest::new("bad", "Bad", &HttpRequest::get("https://example.com"));
+    saved.request.method = "GET /".to_string();
+
+    assert_eq!(
+        saved.to_http_request().unwrap_err(),
+        InvalidMethod("GET /".to_string())
+    );
+}
+
 #[test]
 fn test_serializable_request_from_get() {
     let request = HttpRequest::get("https://example.com/api");
@@ -259,3 +321,129 @@ fn test_nested_folder_structure() {
     assert!(found.is_some());
     assert_eq!(found.unwrap().name, "Get Users");
 }
+
+#[test]
+fn test_websocket_in_collection() {
+    let mut collection = Collection::new("Realtime").with_auth(Auth::bearer("secret"));
+    collection.add_websocket(
+        SavedWebSocket::new("chat", "Chat", "wss://example.com/chat")
+            .with_header("X-Client", "saffron")
+            .with_protocol("chat.v2")
+            .with_protocol("chat.v1")
+            .with_message(r#"{"type":"hello"}"#),
+    );
+
+    let websocket = collection.find_websocket("chat").unwrap();
+    let request = collection.websocket_request(websocket);
+
+    assert_eq!(request.url, "wss://example.com/chat");
+    assert_eq!(request.get_header("X-Client"), Some("saffron"));
+    assert_eq!(
+        request.get_header("Sec-WebSocket-Protocol"),
+        Some("chat.v2, chat.v1")
+    );
+    assert_eq!(request.auth, Some(Auth::bearer("secret")));
+    assert!(collection.find_request("chat").is_none());
+}
+
+#[test]
+fn test_websockets_are_optional_in_json() {
+    let collection: Collection =
+        serde_json::from_str(r#"{"name":"Old","description":null,"folders":[],"requests":[]}"#)
+            .unwrap();
+    assert!(collection.websockets.is_empty());
+    assert!(!collection.local_access);
+
+    let json = serde_json::to_string(&collection).unwrap();
+    assert!(!json.contains("websockets"));
+    assert!(!json.contains("local_access"));
+
+    let mut collection = collection;
+    collection.add_websocket(SavedWebSocket::new("feed", "Feed", "ws://localhost/feed"));
+    let json = serde_json::to_string(&collection).unwrap();
+    let restored: Collection = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.websockets[0].url, "ws://localhost/feed");
+}
+
+#[test]
+fn test_query_saved_separately() {
+    let request = HttpRequest::get("https://api.example.com/search")
+        .with_query("q", "{{term}}")
+        .with_query("page", "2");
+    let saved = SerializableRequest::from_request(&request);
+
+    let json = serde_json::to_string(&saved).unwrap();
+    assert!(json.contains(r#""url":"https://api.example.com/search""#));
+    assert!(json.contains(r#""query":[["q","{{term}}"],["page","2"]]"#));
+    let restored: SerializableRequest = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.to_http_request().unwrap().query, request.query);
+
+    // Requests saved before query parameters existed still load
+    let old = r#"{"method":"GET","url":"https://x.dev/?a=1","headers":[],"body":null,"timeout_seconds":30}"#;
+    let old: SerializableRequest = serde_json::from_str(old).unwrap();
+    assert!(old.query.is_empty());
+    assert!(!serde_json::to_string(&old).unwrap().contains("query"));
+}
+
+#[test]
+fn test_grpc_call_in_collection() {
+    let mut collection = Collection::new("Shop").with_auth(Auth::bearer("secret"));
+    collection.add_grpc_call(
+        SavedGrpcCall::new(
+            "get-order",
+            "Get order",
+            "http://localhost:50051/",
+            "shop.v1.Orders/GetOrder",
+        )
+        .with_proto_file("protos/shop.proto")
+        .with_import_path("protos")
+        .with_message(r#"{"id":"o-1"}"#)
+        .with_metadata("x-tenant", "acme"),
+    );
+
+    let json = serde_json::to_string(&collection).unwrap();
+    let restored: Collection = serde_json::from_str(&json).unwrap();
+    let call = restored.find_grpc_call("get-order").unwrap();
+    assert_eq!(call.proto_files, vec!["protos/shop.proto"]);
+    assert_eq!(call.descriptor_set, None);
+    assert_eq!(call.message, r#"{"id":"o-1"}"#);
+
+    let request = restored.grpc_request(call);
+    assert_eq!(
+        request.url,
+        "http://localhost:50051/shop.v1.Orders/GetOrder"
+    );
+    assert_eq!(request.get_header("x-tenant"), Some("acme"));
+    assert_eq!(request.auth, Some(Auth::bearer("secret")));
+    assert!(restored.find_request("get-order").is_none());
+    assert!(
+        !serde_json::to_string(&Collection::new("Empty"))
+            .unwrap()
+            .contains("grpc_calls")
+    );
+}
+
+#[test]
+fn test_saved_graphql_request() {
+    let body = RequestBody::GraphQL {
+        query: "query User($id: ID!) { user(id: $id) { name } }".to_string(),
+        variables: Some(r#"{"id":"42"}"#.to_string()),
+        operation_name: Some("User".to_string()),
+    };
+    let request = HttpRequest::post("https://api.example.com/graphql").with_body(body.clone());
+
+    let saved = SavedRequest::new("user", "User", &request);
+    assert_eq!(saved.request.body, None);
+    assert_eq!(
+        saved.request.graphql,
+        Some(SavedGraphQL {
+            query: "query User($id: ID!) { user(id: $id) { name } }".to_string(),
+            variables: Some(r#"{"id":"42"}"#.to_string()),
+            operation_name: Some("User".to_string()),
+        })
+    );
+
+    let json = serde_json::to_string(&saved).unwrap();
+    let restored: SavedRequest = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.to_http_request().unwrap().body, body);
+}
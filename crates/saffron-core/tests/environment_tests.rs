@@ -1,4 +1,6 @@
-use saffron_core::domain::environment::{Environment, EnvironmentSet};
+use saffron_core::domain::auth::{Auth, OAuth2Grant};
+use saffron_core::domain::environment::{ConnectSettings, Environment, EnvironmentSet};
+use saffron_core::domain::template::{TemplateError, TemplateLocation};
 
 #[test]
 fn test_environment_new() {
@@ -46,7 +48,7 @@ fn test_environment_resolve_template() {
     env.set("path", "api/v1");
 
     let template = "https://{{host}}:{{port}}/{{path}}/users";
-    let resolved = env.resolve_template(template);
+    let resolved = env.resolve_template(template).unwrap();
 
     assert_eq!(resolved, "https://example.com:8080/api/v1/users");
 }
@@ -55,7 +57,7 @@ fn test_environment_resolve_template() {
 fn test_environment_resolve_template_no_variables() {
     let env = Environment::new("test");
     let template = "https://example.com/api";
-    let resolved = env.resolve_template(template);
+    let resolved = env.resolve_template(template).unwrap();
 
     assert_eq!(resolved, "https://example.com/api");
 }
@@ -66,9 +68,15 @@ fn test_environment_resolve_template_missing_variable() {
     env.set("host", "example.com");
 
     let template = "https://{{host}}/{{missing}}/users";
-    let resolved = env.resolve_template(template);
+    let errors = env.resolve_template(template).unwrap_err();
 
-    assert_eq!(resolved, "https://example.com/{{missing}}/users");
+    assert_eq!(
+        errors,
+        [TemplateError::Undefined {
+            placeholder: "{{missing}}".to_string(),
+            name: "missing".to_string()
+        }]
+    );
 }
 
 #[test]
@@ -78,7 +86,7 @@ fn test_environment_resolve_request_url() {
     env.set("version", "v2");
 
     let url = "{{base_url}}/{{version}}/users";
-    let resolved = env.resolve_request_url(url);
+    let resolved = env.resolve_request_url(url).unwrap();
 
     assert_eq!(resolved, "https://api.example.com/v2/users");
 }
@@ -89,7 +97,7 @@ fn test_environment_resolve_header_value() {
     env.set("token", "Bearer abc123");
 
     let value = "{{token}}";
-    let resolved = env.resolve_header_value(value);
+    let resolved = env.resolve_header_value(value).unwrap();
 
     assert_eq!(resolved, "Bearer abc123");
 }
@@ -191,7 +199,7 @@ fn test_environment_multiple_variables() {
     env.set("var3", "value3");
 
     let template = "{{var1}}-{{var2}}-{{var3}}";
-    let resolved = env.resolve_template(template);
+    let resolved = env.resolve_template(template).unwrap();
 
     assert_eq!(resolved, "value1-value2-value3");
 }
@@ -202,7 +210,66 @@ fn test_environment_nested_braces() {
     env.set("key", "value");
 
     let template = "{{key}} and {{{{nested}}}}";
-    let resolved = env.resolve_template(template);
+    let errors = env.resolve_template(template).unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].placeholder(), "{{nested}}");
+}
 
-    assert_eq!(resolved, "value and {{{{nested}}}}");
+#[test]
+fn test_environment_resolves_auth() {
+    let mut env = Environment::new("prod");
+    env.set("user", "admin");
+    env.set("pass", "s3cret");
+    env.set("host", "auth.example.com");
+
+    assert_eq!(
+        Auth::basic("{{user}}", "{{pass}}").resolve(&env),
+        Ok(Auth::basic("admin", "s3cret"))
+    );
+
+    let oauth = Auth::OAuth2 {
+        token_url: "https://{{host}}/token".to_string(),
+        client_id: "cli".to_string(),
+        client_secret: Some("{{pass}}".to_string()),
+        scope: None,
+        grant: OAuth2Grant::Password {
+            username: "{{user}}".to_string(),
+            password: "{{pass}}".to_string(),
+        },
+    };
+    assert_eq!(
+        oauth.resolve(&env),
+        Ok(Auth::OAuth2 {
+            token_url: "https://auth.example.com/token".to_string(),
+            client_id: "cli".to_string(),
+            client_secret: Some("s3cret".to_string()),
+            scope: None,
+            grant: OAuth2Grant::Password {
+                username: "admin".to_string(),
+                password: "s3cret".to_string(),
+            },
+        })
+    );
+
+    let unresolved = Auth::bearer("{{token}}").resolve(&env).unwrap_err();
+    assert_eq!(unresolved[0].location, TemplateLocation::Auth);
+    assert_eq!(unresolved[0].to_string(), "auth: 'token' is not defined");
+}
+
+#[test]
+fn test_environment_connect_settings_serde() {
+    let env: Environment = serde_json::from_str(r#"{"name":"dev","variables":{}}"#).unwrap();
+    assert!(env.connect.is_empty());
+    assert!(!serde_json::to_string(&env).unwrap().contains("connect"));
+
+    let mut env = Environment::new("staging");
+    env.connect = ConnectSettings {
+        resolve: vec!["api.example.com:443:10.0.0.5".to_string()],
+        ip_version: Some("4".to_string()),
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&env).unwrap();
+    let parsed: Environment = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.connect, env.connect);
 }
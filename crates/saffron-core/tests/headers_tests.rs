@@ -0,0 +1,81 @@
+use saffron_core::domain::headers::{Headers, HttpHeader};
+
+fn cdn_headers() -> Headers {
+    [
+        ("Content-Type", "text/html"),
+        ("Set-Cookie", "a=1"),
+        ("Vary", "Accept-Encoding"),
+        ("set-cookie", "b=2"),
+        ("Vary", "Origin"),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[test]
+fn test_headers_keep_wire_order_and_duplicates() {
+    let headers = cdn_headers();
+
+    let names: Vec<&str> = headers.iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["Content-Type", "Set-Cookie", "Vary", "set-cookie", "Vary"]
+    );
+    assert_eq!(headers.len(), 5);
+    assert_eq!(headers[3], HttpHeader::new("set-cookie", "b=2"));
+}
+
+#[test]
+fn test_headers_lookup_ignores_case() {
+    let headers = cdn_headers();
+
+    assert_eq!(headers.get("content-type"), Some("text/html"));
+    assert_eq!(headers.get("SET-COOKIE"), Some("a=1"));
+    assert_eq!(
+        headers.get_all("Set-Cookie").collect::<Vec<_>>(),
+        ["a=1", "b=2"]
+    );
+    assert!(headers.contains("vary"));
+    assert_eq!(headers.get("Location"), None);
+    assert_eq!(headers.get_all("Location").count(), 0);
+}
+
+#[test]
+fn test_headers_insert_replaces_all_values_in_place() {
+    let mut headers = cdn_headers();
+    headers.insert("SET-COOKIE", "c=3");
+
+    assert_eq!(headers.get_all("set-cookie").collect::<Vec<_>>(), ["c=3"]);
+    assert_eq!(headers[1], HttpHeader::new("SET-COOKIE", "c=3"));
+
+    headers.insert("Location", "/next");
+    assert_eq!(headers[headers.len() - 1].name, "Location");
+}
+
+#[test]
+fn test_headers_remove() {
+    let mut headers = cdn_headers();
+
+    assert_eq!(headers.remove("vary"), 2);
+    assert_eq!(headers.remove("vary"), 0);
+    assert_eq!(headers.len(), 3);
+}
+
+#[test]
+fn test_headers_serde_round_trip() {
+    let headers = cdn_headers();
+
+    let json = serde_json::to_string(&headers).unwrap();
+    assert_eq!(
+        json,
+        r#"[["Content-Type","text/html"],["Set-Cookie","a=1"],["Vary","Accept-Encoding"],["set-cookie","b=2"],["Vary","Origin"]]"#
+    );
+    assert_eq!(serde_json::from_str::<Headers>(&json).unwrap(), headers);
+}
+
+#[test]
+fn test_headers_deserialize_from_map() {
+    let headers: Headers = serde_json::from_str(r#"{"content-type":"text/plain"}"#).unwrap();
+
+    assert_eq!(headers.get("Content-Type"), Some("text/plain"));
+}
@@ -1,7 +1,14 @@
+use saffron_core::domain::auth::Auth;
 use saffron_core::domain::request::{
-    FormDataContent, FormDataPart, HttpHeader, HttpMethod, HttpRequest, RequestBody,
+    BodyStream, FormDataContent, FormDataPart, HttpHeader, HttpMethod, HttpRequest, InvalidMethod,
+    RequestBody,
 };
+use saffron_core::domain::request_body::{
+    decode_form_urlencoded, encode_form_urlencoded, encode_graphql,
+};
+use saffron_core::domain::template::TemplateLocation;
 use std::collections::HashMap;
+use std::io::{Cursor, Read};
 
 #[test]
 fn test_http_method_as_str() {
@@ -12,6 +19,38 @@ fn test_http_method_as_str() {
     assert_eq!(HttpMethod::Delete.as_str(), "DELETE");
     assert_eq!(HttpMethod::Head.as_str(), "HEAD");
     assert_eq!(HttpMethod::Options.as_str(), "OPTIONS");
+    assert_eq!(HttpMethod::Trace.as_str(), "TRACE");
+    assert_eq!(HttpMethod::Connect.as_str(), "CONNECT");
+    assert_eq!(
+        HttpMethod::Custom("PROPFIND".to_string()).as_str(),
+        "PROPFIND"
+    );
+}
+
+#[test]
+fn test_http_method_parse() {
+    assert_eq!("get".parse(), Ok(HttpMethod::Get));
+    assert_eq!("Trace".parse(), Ok(HttpMethod::Trace));
+    assert_eq!("CONNECT".parse(), Ok(HttpMethod::Connect));
+    assert_eq!("PURGE".parse(), Ok(HttpMethod::Custom("PURGE".to_string())));
+    // Extension methods are case-sensitive and kept as written
+    assert_eq!("mkCol".parse(), Ok(HttpMethod::Custom("mkCol".to_string())));
+    assert_eq!(
+        "X-ACL~1".parse(),
+        Ok(HttpMethod::Custom("X-ACL~1".to_string()))
+    );
+}
+
+#[test]
+fn test_http_method_parse_rejects_non_tokens() {
+    for name in ["", "GET /", "PUR(GE)", "LOCK\r\n", "ÜBER", "A:B"] {
+        assert_eq!(
+            name.parse::<HttpMethod>(),
+            Err(InvalidMethod(name.to_string())),
+            "{:?}",
+            name
+        );
+    }
 }
 
 #[test]
@@ -165,20 +204,54 @@ fn test_request_body_json() {
 
 #[test]
 fn test_request_body_form_urlencoded() {
-    let mut data = HashMap::new();
-    data.insert("key1".to_string(), "value1".to_string());
-    data.insert("key2".to_string(), "value2".to_string());
+    let data = vec![
+        ("key1".to_string(), "value1".to_string()),
+        ("key2".to_string(), "value2".to_string()),
+    ];
 
     let body = RequestBody::FormUrlEncoded(data.clone());
     match body {
         RequestBody::FormUrlEncoded(form_data) => {
-            assert_eq!(form_data.get("key1"), Some(&"value1".to_string()));
-            assert_eq!(form_data.get("key2"), Some(&"value2".to_string()));
+            assert_eq!(form_data[0], ("key1".to_string(), "value1".to_string()));
+            assert_eq!(form_data[1], ("key2".to_string(), "value2".to_string()));
         }
         _ => panic!("Expected FormUrlEncoded body"),
     }
 }
 
+#[test]
+fn test_request_body_graphql() {
+    let request =
+        HttpRequest::post("https://api.example.com/graphql").with_graphql("{ me { id } }");
+
+    assert_eq!(
+        request.body,
+        RequestBody::GraphQL {
+            query: "{ me { id } }".to_string(),
+            variables: None,
+            operation_name: None,
+        }
+    );
+}
+
+#[test]
+fn test_encode_graphql() {
+    assert_eq!(
+        encode_graphql("{ me { id } }", None, None),
+        r#"{"query":"{ me { id } }"}"#
+    );
+
+    let query = "query User($id: ID!) {\n\tuser(id: $id) { name(format: \"full\") }\n}";
+    assert_eq!(
+        encode_graphql(query, Some(r#" {"id": "42"} "#), Some("User")),
+        r#"{"query":"query User($id: ID!) {\n\tuser(id: $id) { name(format: \"full\") }\n}","variables":{"id": "42"},"operationName":"User"}"#
+    );
+    assert_eq!(
+        encode_graphql("\\ \u{1}", None, None),
+        r#"{"query":"\\ \u0001"}"#
+    );
+}
+
 #[test]
 fn test_request_body_binary() {
     let data = vec![0x00, 0x01, 0x02, 0x03];
@@ -189,6 +262,46 @@ fn test_request_body_binary() {
     }
 }
 
+#[test]
+fn test_request_body_stream_from_file() {
+    let path = std::env::temp_dir().join(format!("saffron-core-{}-body", std::process::id()));
+    std::fs::write(&path, "abc").unwrap();
+
+    let stream = BodyStream::from_file(&path).unwrap();
+    assert_eq!(stream.content_length(), Some(3));
+    assert_eq!(stream.path(), Some(path.as_path()));
+
+    // File streams reopen, so they can be sent more than once
+    for _ in 0..2 {
+        let mut contents = String::new();
+        stream
+            .open()
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "abc");
+    }
+
+    let request = HttpRequest::put("https://example.com").with_stream_body(stream.clone());
+    assert_eq!(request.body, RequestBody::Stream(stream));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_request_body_stream_from_reader() {
+    let stream = BodyStream::from_reader(Cursor::new(vec![1, 2, 3]), None);
+    let shared = stream.clone();
+
+    assert_eq!(stream, shared);
+    assert_eq!(stream.content_length(), None);
+    assert!(stream.path().is_none());
+
+    let mut bytes = Vec::new();
+    shared.open().unwrap().read_to_end(&mut bytes).unwrap();
+    assert_eq!(bytes, vec![1, 2, 3]);
+    assert!(stream.open().is_err());
+}
+
 #[test]
 fn test_form_data_part_text() {
     let part = FormDataPart {
@@ -256,3 +369,153 @@ fn test_request_default() {
     assert_eq!(request.method, HttpMethod::Get);
     assert_eq!(request.url, "");
 }
+
+#[test]
+fn test_request_query() {
+    let request = HttpRequest::get("https://api.example.com/search?lang=en")
+        .with_query("q", "a&b c")
+        .with_query("q", "100%");
+
+    assert_eq!(request.query.len(), 2);
+    assert_eq!(
+        request.full_url().unwrap(),
+        "https://api.example.com/search?lang=en&q=a%26b%20c&q=100%25"
+    );
+
+    let invalid = HttpRequest::get("/search").with_query("q", "x");
+    assert!(invalid.full_url().is_err());
+}
+
+#[test]
+fn test_request_resolve() {
+    let variables: HashMap<String, String> = [("host", "example.com"), ("term", "a&b")]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+    let request = HttpRequest::post("https://{{host}}/search")
+        .with_query("q", "{{term}}")
+        .with_header("X-Tenant", "{{tenant}}")
+        .with_json_body(r#"{"page": {{page | 1}}}"#)
+        .with_auth(Auth::bearer("{{token}}"));
+    let (request, unresolved) = request.resolve(&variables);
+
+    assert_eq!(
+        request.full_url().unwrap(),
+        "https://example.com/search?q=a%26b"
+    );
+    assert_eq!(request.get_header("X-Tenant"), Some("{{tenant}}"));
+    assert!(matches!(&request.body, RequestBody::Json(body) if body == r#"{"page": 1}"#));
+    assert_eq!(request.auth, Some(Auth::bearer("{{token}}")));
+
+    let locations: Vec<String> = unresolved.iter().map(|u| u.to_string()).collect();
+    assert_eq!(
+        locations,
+        [
+            "header 'X-Tenant': 'tenant' is not defined",
+            "auth: 'token' is not defined"
+        ]
+    );
+    assert_eq!(
+        unresolved[0].location,
+        TemplateLocation::HeaderValue("X-Tenant".to_string())
+    );
+}
+
+#[test]
+fn test_request_resolve_form_bodies() {
+    let variables: HashMap<String, String> = [("user".to_string(), "ana".to_string())].into();
+
+    let request = HttpRequest::post("https://example.com/login").with_body(
+        RequestBody::FormUrlEncoded(pairs(&[("user", "{{user}}"), ("token", "{{secret}}")])),
+    );
+    let (request, unresolved) = request.resolve(&variables);
+
+    assert_eq!(
+        request.body,
+        RequestBody::FormUrlEncoded(pairs(&[("user", "ana"), ("token", "{{secret}}")]))
+    );
+    assert_eq!(unresolved.len(), 1);
+    assert_eq!(unresolved[0].location, TemplateLocation::Body);
+    assert_eq!(unresolved[0].to_string(), "body: 'secret' is not defined");
+
+    let request =
+        HttpRequest::post("https://example.com/upload").with_body(RequestBody::FormData(vec![
+            FormDataPart {
+                name: "{{user}}".to_string(),
+                content: FormDataContent::Text("{{secret}}".to_string()),
+            },
+            FormDataPart {
+                name: "file".to_string(),
+                content: FormDataContent::File {
+                    filename: "{{user}}.txt".to_string(),
+                    data: b"{{secret}}".to_vec(),
+                    content_type: None,
+                },
+            },
+        ]));
+    let (request, unresolved) = request.resolve(&variables);
+
+    let RequestBody::FormData(parts) = &request.body else {
+        panic!("expected a multipart body");
+    };
+    assert_eq!(parts[0].name, "ana");
+    assert_eq!(
+        parts[0].content,
+        FormDataContent::Text("{{secret}}".to_string())
+    );
+    // File contents are sent as they are
+    assert!(
+        matches!(&parts[1].content, FormDataContent::File { data, .. } if data == b"{{secret}}")
+    );
+    assert_eq!(unresolved.len(), 1);
+}
+
+fn pairs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+    items
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_encode_form_urlencoded() {
+    let form = pairs(&[
+        ("cidade", "São Paulo"),
+        ("tag", "a"),
+        ("tag", "b"),
+        ("nota", "1+1=2 & 100%"),
+        ("emoji", "🌶"),
+        ("safe", "*-._~"),
+    ]);
+
+    assert_eq!(
+        encode_form_urlencoded(&form),
+        "cidade=S%C3%A3o+Paulo&tag=a&tag=b&nota=1%2B1%3D2+%26+100%25&emoji=%F0%9F%8C%B6&safe=*-._%7E"
+    );
+    assert_eq!(encode_form_urlencoded(&[]), "");
+}
+
+#[test]
+fn test_decode_form_urlencoded() {
+    assert_eq!(
+        decode_form_urlencoded("cidade=S%C3%A3o+Paulo&tag=a&tag=b&&flag&empty=&a=b=c"),
+        pairs(&[
+            ("cidade", "São Paulo"),
+            ("tag", "a"),
+            ("tag", "b"),
+            ("flag", ""),
+            ("empty", ""),
+            ("a", "b=c"),
+        ])
+    );
+
+    // Lowercase hex, stray `%` and invalid UTF-8
+    assert_eq!(
+        decode_form_urlencoded("a=%c3%a7&b=50%&c=%zz%4&d=%FF"),
+        pairs(&[("a", "ç"), ("b", "50%"), ("c", "%zz%4"), ("d", "\u{FFFD}")])
+    );
+
+    let form = pairs(&[("ação", "coração & razão"), ("x", "+%20")]);
+    assert_eq!(decode_form_urlencoded(&encode_form_urlencoded(&form)), form);
+}
@@ -1,10 +1,10 @@
-use saffron_core::domain::response::HttpResponse;
-use std::collections::HashMap;
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::response::{HttpResponse, RedirectHop, RetryAttempt, Timings};
 use std::time::Duration;
 
 #[test]
 fn test_response_new() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
 
     let response = HttpResponse::new(
@@ -97,7 +97,7 @@ fn test_response_body_as_string() {
     let response = HttpResponse::new(
         200,
         "OK".to_string(),
-        HashMap::new(),
+        Headers::new(),
         "Hello, World!".as_bytes().to_vec(),
         Duration::from_millis(100),
         "https://example.com".to_string(),
@@ -111,7 +111,7 @@ fn test_response_body_as_string_invalid_utf8() {
     let response = HttpResponse::new(
         200,
         "OK".to_string(),
-        HashMap::new(),
+        Headers::new(),
         vec![0xFF, 0xFE, 0xFD],
         Duration::from_millis(100),
         "https://example.com".to_string(),
@@ -125,7 +125,7 @@ fn test_response_body_as_str() {
     let response = HttpResponse::new(
         200,
         "OK".to_string(),
-        HashMap::new(),
+        Headers::new(),
         "Test content".as_bytes().to_vec(),
         Duration::from_millis(100),
         "https://example.com".to_string(),
@@ -136,7 +136,7 @@ fn test_response_body_as_str() {
 
 #[test]
 fn test_response_content_type() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
 
     let response = HttpResponse::new(
@@ -153,7 +153,7 @@ fn test_response_content_type() {
 
 #[test]
 fn test_response_content_type_case_insensitive() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("content-type".to_string(), "text/html".to_string());
 
     let response = HttpResponse::new(
@@ -170,7 +170,7 @@ fn test_response_content_type_case_insensitive() {
 
 #[test]
 fn test_response_get_header() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("X-Custom-Header".to_string(), "custom-value".to_string());
     headers.insert("Authorization".to_string(), "Bearer token".to_string());
 
@@ -190,7 +190,7 @@ fn test_response_get_header() {
 
 #[test]
 fn test_response_get_header_case_insensitive() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
 
     let response = HttpResponse::new(
@@ -214,7 +214,7 @@ fn test_response_get_header_case_insensitive() {
 
 #[test]
 fn test_response_is_json() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
 
     let response = HttpResponse::new(
@@ -231,7 +231,7 @@ fn test_response_is_json() {
 
 #[test]
 fn test_response_is_json_with_charset() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert(
         "Content-Type".to_string(),
         "application/json; charset=utf-8".to_string(),
@@ -251,7 +251,7 @@ fn test_response_is_json_with_charset() {
 
 #[test]
 fn test_response_is_html() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Content-Type".to_string(), "text/html".to_string());
 
     let response = HttpResponse::new(
@@ -268,7 +268,7 @@ fn test_response_is_html() {
 
 #[test]
 fn test_response_is_xml() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Content-Type".to_string(), "application/xml".to_string());
 
     let response = HttpResponse::new(
@@ -285,7 +285,7 @@ fn test_response_is_xml() {
 
 #[test]
 fn test_response_content_length() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Content-Length".to_string(), "1234".to_string());
 
     let response = HttpResponse::new(
@@ -302,7 +302,7 @@ fn test_response_content_length() {
 
 #[test]
 fn test_response_content_length_invalid() {
-    let mut headers = HashMap::new();
+    let mut headers = Headers::new();
     headers.insert("Content-Length".to_string(), "invalid".to_string());
 
     let response = HttpResponse::new(
@@ -317,13 +317,110 @@ fn test_response_content_length_invalid() {
     assert_eq!(response.content_length(), None);
 }
 
+#[test]
+fn test_response_with_redirects() {
+    let response = create_test_response(200);
+    assert!(!response.was_redirected());
+
+    let hop = RedirectHop {
+        status: 301,
+        url: "http://example.com".to_string(),
+        location: "https://example.com".to_string(),
+        headers: Headers::new(),
+        elapsed: Duration::from_millis(20),
+    };
+    let response = response.with_redirects(vec![hop.clone()]);
+
+    assert!(response.was_redirected());
+    assert_eq!(response.redirects, vec![hop]);
+}
+
+#[test]
+fn test_response_timings() {
+    let response = create_test_response(200);
+    assert_eq!(response.timings, Timings::default());
+
+    let timings = Timings {
+        dns: Some(Duration::from_millis(5)),
+        connect: Some(Duration::from_millis(10)),
+        tls: Some(Duration::from_millis(20)),
+        first_byte: Duration::from_millis(100),
+        download: Duration::from_millis(50),
+    };
+    let response = response.with_timings(timings);
+
+    assert_eq!(response.timings.waiting(), Duration::from_millis(65));
+    assert_eq!(response.timings.total(), Duration::from_millis(150));
+}
+
+#[test]
+fn test_timings_without_connection_setup() {
+    let timings = Timings {
+        first_byte: Duration::from_millis(40),
+        ..Default::default()
+    };
+
+    assert_eq!(timings.waiting(), Duration::from_millis(40));
+    assert_eq!(timings.total(), Duration::from_millis(40));
+}
+
+#[test]
+fn test_response_retries() {
+    let response = create_test_response(200);
+    assert_eq!(response.attempts(), 1);
+
+    let response = response.with_retries(vec![
+        RetryAttempt {
+            status: Some(503),
+            error: None,
+            elapsed: Duration::from_millis(12),
+            delay: Duration::from_millis(100),
+        },
+        RetryAttempt {
+            status: None,
+            error: Some("connection reset".to_string()),
+            elapsed: Duration::from_millis(3),
+            delay: Duration::from_millis(200),
+        },
+    ]);
+
+    assert_eq!(response.attempts(), 3);
+    assert_eq!(response.retries[0].status, Some(503));
+}
+
 fn create_test_response(status: u16) -> HttpResponse {
     HttpResponse::new(
         status,
         "Test".to_string(),
-        HashMap::new(),
+        Headers::new(),
         vec![],
         Duration::from_millis(100),
         "https://example.com".to_string(),
     )
 }
+
+#[test]
+fn test_response_form() {
+    let mut headers = Headers::new();
+    headers.insert(
+        "Content-Type",
+        "application/x-www-form-urlencoded; charset=utf-8",
+    );
+    let response = HttpResponse::new(
+        200,
+        "OK".to_string(),
+        headers,
+        b"status=aprovado&cidade=Bel%C3%A9m".to_vec(),
+        Duration::ZERO,
+        String::new(),
+    );
+
+    assert_eq!(
+        response.form(),
+        Some(vec![
+            ("status".to_string(), "aprovado".to_string()),
+            ("cidade".to_string(), "Belém".to_string()),
+        ])
+    );
+    assert_eq!(create_test_response(200).form(), None);
+}
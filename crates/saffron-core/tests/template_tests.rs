@@ -0,0 +1,208 @@
+use saffron_core::domain::environment::Environment;
+use saffron_core::domain::template::{Template, TemplateError};
+use std::collections::HashMap;
+
+fn render(template: &str) -> String {
+    Template::parse(template).render(&HashMap::new())
+}
+
+#[test]
+fn test_template_variables_and_spacing() {
+    let mut env = Environment::new("test");
+    env.set("host", "example.com");
+    env.set("api-version", "v2");
+
+    assert_eq!(
+        env.resolve_template("https://{{ host }}/{{api-version}}")
+            .unwrap(),
+        "https://example.com/v2"
+    );
+    assert_eq!(env.resolve_template("{{{host}}}").unwrap(), "{example.com}");
+}
+
+#[test]
+fn test_template_leaves_non_placeholders() {
+    for text in [
+        "{{ }}",
+        "{{unclosed",
+        "{{two words}}",
+        "{{$}}",
+        r#"{"a":{"b":{}}}"#,
+        "{{missing}}",
+        "{{$unknown}}",
+    ] {
+        assert_eq!(render(text), text);
+    }
+}
+
+#[test]
+fn test_template_defaults() {
+    let mut env = Environment::new("test");
+    env.set("host", "example.com");
+    env.set("fallback", "backup.example.com");
+    env.set("empty", "");
+
+    assert_eq!(
+        env.resolve_template("{{host | localhost}}:{{port | 8080}}")
+            .unwrap(),
+        "example.com:8080"
+    );
+    assert_eq!(
+        env.resolve_template("{{other | {{fallback}}}}").unwrap(),
+        "backup.example.com"
+    );
+    assert_eq!(env.resolve_template("[{{empty | unused}}]").unwrap(), "[]");
+    assert_eq!(env.resolve_template("[{{optional |}}]").unwrap(), "[]");
+    assert_eq!(env.resolve_template("{{$unknown | none}}").unwrap(), "none");
+}
+
+#[test]
+fn test_template_uuid() {
+    let first = render("{{$uuid}}");
+    let second = render("{{$uuid}}");
+
+    assert_eq!(first.len(), 36);
+    assert_eq!(&first[14..15], "4");
+    assert!(matches!(&first[19..20], "8" | "9" | "a" | "b"));
+    assert!(
+        first
+            .chars()
+            .all(|c| c == '-' || c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+    );
+    assert_ne!(first, second);
+    assert_eq!(render("{{$uuid extra}}"), "{{$uuid extra}}");
+}
+
+#[test]
+fn test_template_timestamps() {
+    let timestamp: i64 = render("{{$timestamp}}").parse().unwrap();
+    assert!(timestamp > 1_700_000_000);
+
+    let date = render("{{$isoDate}}");
+    assert_eq!(date.len(), "2024-01-01T00:00:00.000Z".len());
+    assert_eq!(&date[10..11], "T");
+    assert!(date.ends_with('Z'));
+}
+
+#[test]
+fn test_template_random_int() {
+    for _ in 0..50 {
+        let n: i64 = render("{{$randomInt 1 3}}").parse().unwrap();
+        assert!((1..=3).contains(&n));
+    }
+    let n: i64 = render("{{$randomInt}}").parse().unwrap();
+    assert!((0..=1000).contains(&n));
+    assert_eq!(render("{{$randomInt -5 -5}}"), "-5");
+
+    assert_eq!(render("{{$randomInt 9 1}}"), "{{$randomInt 9 1}}");
+    assert_eq!(render("{{$randomInt a b}}"), "{{$randomInt a b}}");
+    assert_eq!(render("{{$randomInt 1}}"), "{{$randomInt 1}}");
+}
+
+#[test]
+fn test_template_base64_with_nested_variables() {
+    let mut env = Environment::new("test");
+    env.set("user", "admin");
+    env.set("pass", "s3cret");
+
+    assert_eq!(
+        env.resolve_template("Basic {{$base64 {{user}}:{{pass}}}}")
+            .unwrap(),
+        "Basic YWRtaW46czNjcmV0"
+    );
+    assert_eq!(render("{{$base64 São Paulo}}"), "U8OjbyBQYXVsbw==");
+}
+
+#[test]
+fn test_template_env_and_file() {
+    let path = std::env::temp_dir().join(format!("saffron-template-{}", std::process::id()));
+    std::fs::write(&path, "tok3n\n").unwrap();
+
+    assert_eq!(
+        render(&format!("{{{{$file {}}}}}", path.display())),
+        "tok3n"
+    );
+    assert_eq!(render("{{$file /no/such/file | none}}"), "none");
+    assert_eq!(render("{{$file}}"), "{{$file}}");
+    std::fs::remove_file(&path).unwrap();
+
+    let path = std::env::var("PATH").unwrap();
+    assert_eq!(render("{{$env PATH}}"), path);
+    assert_eq!(render("{{$env SAFFRON_TEMPLATE_UNSET | unset}}"), "unset");
+}
+
+#[test]
+fn test_template_local_access() {
+    let mut variables = HashMap::new();
+    variables.insert("name".to_string(), "PATH".to_string());
+
+    for template in [
+        "{{$env PATH}}",
+        "{{$file /etc/hostname}}",
+        "{{$env {{name}}}}",
+    ] {
+        let (rendered, errors) = Template::parse(template).render_checked_with(&variables, false);
+        assert_eq!(rendered, template);
+        assert_eq!(
+            errors,
+            [TemplateError::LocalAccess {
+                placeholder: template.to_string()
+            }]
+        );
+    }
+
+    // A default stands in, and other functions still work
+    let (rendered, errors) = Template::parse("{{$env PATH | none}} {{$base64 a}}")
+        .render_checked_with(&variables, false);
+    assert_eq!(rendered, "none YQ==");
+    assert!(errors.is_empty());
+
+    let path = std::env::var("PATH").unwrap_or_default();
+    let (rendered, errors) = Template::parse("{{$env PATH}}").render_checked(&variables);
+    assert_eq!(rendered, path);
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn test_template_render_checked() {
+    let mut variables = HashMap::new();
+    variables.insert("user".to_string(), "admin".to_string());
+
+    let template =
+        Template::parse("{{host}}/{{$base64 {{user}}:{{pass}}}}/{{port | {{fallback}}}}");
+    let (rendered, errors) = template.render_checked(&variables);
+
+    assert_eq!(
+        rendered,
+        "{{host}}/{{$base64 {{user}}:{{pass}}}}/{{port | {{fallback}}}}"
+    );
+    assert_eq!(
+        errors,
+        [
+            TemplateError::Undefined {
+                placeholder: "{{host}}".to_string(),
+                name: "host".to_string()
+            },
+            TemplateError::Undefined {
+                placeholder: "{{pass}}".to_string(),
+                name: "pass".to_string()
+            },
+            TemplateError::Undefined {
+                placeholder: "{{fallback}}".to_string(),
+                name: "fallback".to_string()
+            },
+        ]
+    );
+
+    let (_, errors) = Template::parse("{{$env SAFFRON_TEMPLATE_UNSET}}").render_checked(&variables);
+    assert_eq!(
+        errors[0].to_string(),
+        "{{$env SAFFRON_TEMPLATE_UNSET}}: environment variable 'SAFFRON_TEMPLATE_UNSET' is not set"
+    );
+    assert!(
+        Template::parse("{{user}}")
+            .render_checked(&variables)
+            .1
+            .is_empty()
+    );
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::fmt::{self, Display, Write};
 
 use crate::error::ParseError;
 use crate::tokenizer::TokenKind;
@@ -14,6 +15,56 @@ pub enum JsonElement {
     Null,
 }
 
+/// Writes the element as compact JSON. Numbers that JSON can't represent,
+/// such as NaN, are written as `null`.
+impl Display for JsonElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonElement::Number(n) if n.is_finite() => write!(f, "{}", n),
+            JsonElement::Number(_) | JsonElement::Null => f.write_str("null"),
+            JsonElement::String(s) => write_string(f, s),
+            JsonElement::Boolean(b) => write!(f, "{}", b),
+            JsonElement::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(']')
+            }
+            JsonElement::Object(map) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
 pub struct Json {
     pub root: JsonElement,
 }
@@ -32,10 +83,13 @@ impl Json {
                 }
                 Number => {
                     let t = tokens.advance();
-                    let n = t
-                        .lexeme
-                        .parse::<f64>()
-                        .map_err(|_e| ParseError::new(format!("Invalid number '{}'", t.lexeme)))?;
+                    let invalid = || ParseError::new(format!("Invalid number '{}'", t.lexeme));
+                    // JSON doesn't allow leading zeros, as in `007`
+                    let digits = t.lexeme.trim_start_matches('-').as_bytes();
+                    if digits.len() > 1 && digits[0] == b'0' && digits[1].is_ascii_digit() {
+                        return Err(invalid());
+                    }
+                    let n = t.lexeme.parse::<f64>().map_err(|_e| invalid())?;
                     Ok(JsonElement::Number(n))
                 }
                 Boolean => {
@@ -159,6 +213,13 @@ impl Json {
         }
 
         let value = parse_value(&mut tokens)?;
+        let rest = tokens.current();
+        if rest.kind != TokenKind::EndOfFile {
+            return Err(ParseError::new(format!(
+                "Unexpected {:?} after the value",
+                rest.kind
+            )));
+        }
         Ok(value)
     }
 
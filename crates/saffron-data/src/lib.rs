@@ -2,5 +2,7 @@ pub mod error;
 pub mod importers;
 pub mod json;
 pub mod parse;
+#[cfg(feature = "proto")]
+pub mod proto;
 pub(crate) mod token_stream;
 pub(crate) mod tokenizer;
@@ -0,0 +1,68 @@
+//! Hands JSON parsed by [`crate::json`] to `prost-reflect`, which maps it to
+//! a message through serde.
+
+use crate::json::JsonElement;
+use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+use serde::de::{Deserializer, IntoDeserializer, Visitor};
+
+/// The largest integer a JSON number, read as an `f64`, holds exactly.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+pub(super) struct ElementDeserializer(pub(super) JsonElement);
+
+impl<'de> IntoDeserializer<'de, Error> for ElementDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for ElementDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            JsonElement::Null => visitor.visit_unit(),
+            JsonElement::Boolean(b) => visitor.visit_bool(b),
+            // Whole numbers go to integer fields as integers
+            JsonElement::Number(n) if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER => {
+                if n < 0.0 {
+                    visitor.visit_i64(n as i64)
+                } else {
+                    visitor.visit_u64(n as u64)
+                }
+            }
+            JsonElement::Number(n) => visitor.visit_f64(n),
+            JsonElement::String(s) => visitor.visit_string(s),
+            JsonElement::Array(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter().map(ElementDeserializer));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            JsonElement::Object(map) => {
+                let mut map = MapDeserializer::new(
+                    map.into_iter()
+                        .map(|(key, value)| (key, ElementDeserializer(value))),
+                );
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0 {
+            JsonElement::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple tuple_struct
+        map struct enum identifier ignored_any
+    }
+}
@@ -0,0 +1,279 @@
+//! Protocol Buffers schemas from `.proto` files or descriptor sets, and a
+//! codec between JSON and the protobuf wire format driven by them.
+//!
+//! `.proto` files are parsed with `protobuf-parse`; messages are encoded,
+//! decoded and mapped to JSON by `prost-reflect`. JSON input is read with
+//! [`crate::json`].
+
+mod json;
+
+use crate::json::Json;
+use crate::parse::Parse;
+use json::ElementDeserializer;
+use prost_reflect::prost::Message as _;
+use prost_reflect::{DescriptorPool, DynamicMessage, SerializeOptions};
+use protobuf::Message as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use thiserror::Error;
+
+pub use prost_reflect::{EnumDescriptor, MessageDescriptor};
+
+#[derive(Debug, Error)]
+pub enum ProtoError {
+    #[error("{0}")]
+    Parse(String),
+
+    #[error("Failed to read {0}: {1}")]
+    Io(String, std::io::Error),
+
+    #[error("Unknown type '{0}'")]
+    UnknownType(String),
+
+    #[error("Unknown method '{0}'")]
+    UnknownMethod(String),
+
+    #[error("Invalid descriptor set: {0}")]
+    InvalidDescriptor(String),
+
+    #[error("Invalid {message_type}: {message}")]
+    InvalidValue {
+        message_type: String,
+        message: String,
+    },
+
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Service {
+    /// The fully qualified name, such as `shop.v1.Orders`.
+    pub full_name: String,
+    pub methods: Vec<Method>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Method {
+    pub name: String,
+    /// The request message type, fully qualified.
+    pub input_type: String,
+    /// The response message type, fully qualified.
+    pub output_type: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+}
+
+impl Method {
+    /// The HTTP/2 path of the method in `service`, e.g. `/shop.v1.Orders/Get`.
+    pub fn path(&self, service: &Service) -> String {
+        format!("/{}/{}", service.full_name, self.name)
+    }
+}
+
+/// The message, enum and service definitions of a set of `.proto` files.
+///
+/// Files are parsed with their imports, which are looked up next to the
+/// importing file and in the include paths. The common
+/// `google/protobuf/*.proto` files are built in.
+#[derive(Debug, Default)]
+pub struct ProtoSchema {
+    pool: DescriptorPool,
+    services: Vec<Service>,
+    include_paths: Vec<PathBuf>,
+}
+
+impl ProtoSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a directory to look up imports in, like `protoc -I`.
+    pub fn with_include_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.include_paths.push(path.into());
+        self
+    }
+
+    /// Parses a `.proto` file and the files it imports.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<(), ProtoError> {
+        let path = path.as_ref();
+        // The file must sit in an include path, so its directory is one
+        let path = path
+            .canonicalize()
+            .map_err(|e| ProtoError::Io(path.display().to_string(), e))?;
+        let mut parser = protobuf_parse::Parser::new();
+        parser.pure();
+        if let Some(dir) = path.parent() {
+            parser.include(dir);
+        }
+        parser.includes(&self.include_paths).input(&path);
+
+        let parsed = parser
+            .parse_and_typecheck()
+            // The outermost context only names the parser
+            .map_err(|e| ProtoError::Parse(e.chain().nth(1).unwrap_or(e.as_ref()).to_string()))?;
+        for file in parsed.file_descriptors {
+            let bytes = file
+                .write_to_bytes()
+                .map_err(|e| ProtoError::InvalidDescriptor(e.to_string()))?;
+            self.pool
+                .decode_file_descriptor_proto(bytes.as_slice())
+                .map_err(|e| ProtoError::InvalidDescriptor(e.to_string()))?;
+        }
+        self.update_services();
+        Ok(())
+    }
+
+    /// Parses `.proto` source, as if read from a file called `name`.
+    /// Imports are looked up in the include paths.
+    pub fn add_source(&mut self, name: &str, source: &str) -> Result<(), ProtoError> {
+        // The parser reads files, so the source is written out first
+        let dir = TempDir::new().map_err(|e| ProtoError::Io(name.to_string(), e))?;
+        let path = dir.0.join(name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ProtoError::Io(name.to_string(), e))?;
+        }
+        std::fs::write(&path, source).map_err(|e| ProtoError::Io(name.to_string(), e))?;
+
+        // Errors name the temporary file; report the name the source was given
+        let written = path.canonicalize().unwrap_or(path.clone());
+        self.load_file(&path).map_err(|e| match e {
+            ProtoError::Parse(message) => {
+                ProtoError::Parse(message.replace(&written.display().to_string(), name))
+            }
+            e => e,
+        })
+    }
+
+    /// Loads a binary `FileDescriptorSet`, as written by
+    /// `protoc --include_imports --descriptor_set_out`.
+    pub fn load_descriptor_set(&mut self, bytes: &[u8]) -> Result<(), ProtoError> {
+        self.pool
+            .decode_file_descriptor_set(bytes)
+            .map_err(|e| ProtoError::InvalidDescriptor(e.to_string()))?;
+        self.update_services();
+        Ok(())
+    }
+
+    pub fn message(&self, full_name: &str) -> Option<MessageDescriptor> {
+        self.pool
+            .get_message_by_name(full_name.trim_start_matches('.'))
+    }
+
+    pub fn enum_type(&self, full_name: &str) -> Option<EnumDescriptor> {
+        self.pool
+            .get_enum_by_name(full_name.trim_start_matches('.'))
+    }
+
+    pub fn services(&self) -> &[Service] {
+        &self.services
+    }
+
+    /// Finds a method by `package.Service/Method` or `package.Service.Method`.
+    /// The package can be left out when only one service has that name.
+    pub fn find_method(&self, name: &str) -> Result<(&Service, &Method), ProtoError> {
+        let name = name.trim_start_matches('/');
+        let (service_name, method_name) =
+            name.rsplit_once('/')
+                .or_else(|| name.rsplit_once('.'))
+                .ok_or_else(|| ProtoError::UnknownMethod(name.to_string()))?;
+
+        let mut found = self.services.iter().filter(|s| {
+            s.full_name == service_name || s.full_name.ends_with(&format!(".{}", service_name))
+        });
+        let service = found
+            .next()
+            .filter(|_| found.next().is_none())
+            .ok_or_else(|| ProtoError::UnknownMethod(name.to_string()))?;
+        let method = service
+            .methods
+            .iter()
+            .find(|m| m.name == method_name)
+            .ok_or_else(|| ProtoError::UnknownMethod(name.to_string()))?;
+        Ok((service, method))
+    }
+
+    /// Encodes `json` as a message of type `message_type`, following the
+    /// proto3 JSON mapping: fields by JSON or proto name, 64-bit integers as
+    /// numbers or strings, enums by name or number and bytes as base64.
+    /// JSON numbers are read as `f64`, so integers beyond 2^53 must be
+    /// written as strings to keep every digit.
+    pub fn encode(&self, message_type: &str, json: &str) -> Result<Vec<u8>, ProtoError> {
+        let descriptor = self.message_descriptor(message_type)?;
+        let invalid = |message: String| ProtoError::InvalidValue {
+            message_type: message_type.trim_start_matches('.').to_string(),
+            message,
+        };
+        let json = Json::parse(json).map_err(|e| invalid(e.message))?;
+        let message = DynamicMessage::deserialize(descriptor, ElementDeserializer(json.root))
+            .map_err(|e| invalid(e.to_string()))?;
+        Ok(message.encode_to_vec())
+    }
+
+    /// Decodes a message of type `message_type` to pretty-printed JSON, with
+    /// fields in field number order under their JSON names. Fields missing
+    /// from the message are left out; unknown fields are skipped.
+    pub fn decode(&self, message_type: &str, bytes: &[u8]) -> Result<String, ProtoError> {
+        let descriptor = self.message_descriptor(message_type)?;
+        let message = DynamicMessage::decode(descriptor, bytes)
+            .map_err(|e| ProtoError::InvalidMessage(e.to_string()))?;
+
+        let mut out = Vec::new();
+        let mut serializer = serde_json::Serializer::pretty(&mut out);
+        message
+            .serialize_with_options(&mut serializer, &SerializeOptions::new())
+            .map_err(|e| ProtoError::InvalidMessage(e.to_string()))?;
+        String::from_utf8(out).map_err(|e| ProtoError::InvalidMessage(e.to_string()))
+    }
+
+    fn message_descriptor(&self, message_type: &str) -> Result<MessageDescriptor, ProtoError> {
+        self.message(message_type)
+            .ok_or_else(|| ProtoError::UnknownType(message_type.to_string()))
+    }
+
+    /// Rebuilds the service list after files were added to the pool.
+    fn update_services(&mut self) {
+        self.services = self
+            .pool
+            .services()
+            .map(|service| Service {
+                full_name: service.full_name().to_string(),
+                methods: service
+                    .methods()
+                    .map(|method| Method {
+                        name: method.name().to_string(),
+                        input_type: method.input().full_name().to_string(),
+                        output_type: method.output().full_name().to_string(),
+                        client_streaming: method.is_client_streaming(),
+                        server_streaming: method.is_server_streaming(),
+                    })
+                    .collect(),
+            })
+            .collect();
+    }
+}
+
+/// A directory of its own under the system temporary directory, removed with
+/// its contents when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> std::io::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "saffron-proto-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        // Left behind by an earlier process with the same id
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir(&path)?;
+        Ok(TempDir(path))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -161,6 +161,24 @@ impl Tokenizer {
             }
         }
 
+        if matches!(self.peek(), 'e' | 'E') {
+            let sign = usize::from(matches!(self.peek_next(), '+' | '-'));
+            let digit = self
+                .source
+                .get(self.start + self.length + 1 + sign)
+                .cloned()
+                .unwrap_or('\0');
+            if self.is_digit(digit) {
+                for _ in 0..=sign {
+                    self.advance();
+                }
+
+                while self.is_digit(self.peek()) {
+                    self.advance();
+                }
+            }
+        }
+
         let lexeme: String = self.source[self.start..self.start + self.length]
             .iter()
             .collect();
@@ -188,17 +206,24 @@ impl Tokenizer {
             let c = self.peek();
 
             if escaped {
+                escaped = false;
+                if c == 'u' {
+                    value.push(self.unicode_escape()?);
+                    continue;
+                }
+
                 let escape_char = match c {
                     'n' => '\n',
                     't' => '\t',
                     'r' => '\r',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
                     '\\' => '\\',
                     '"' => '"',
                     '\'' => '\'',
                     other => other,
                 };
                 value.push(escape_char);
-                escaped = false;
             } else if c == '\\' {
                 escaped = true;
             } else if c == end {
@@ -231,6 +256,45 @@ impl Tokenizer {
         Ok(())
     }
 
+    /// Reads a `\uXXXX` escape, with `peek` on the `u`, and the low
+    /// surrogate that must follow a high one.
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let high = self.code_unit()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !(self.check('\\') && self.peek_next() == 'u') {
+                return Err(self.invalid_escape());
+            }
+            self.advance();
+            let low = self.code_unit()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.invalid_escape());
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+
+        char::from_u32(code).ok_or_else(|| self.invalid_escape())
+    }
+
+    /// Reads the `u` and four hex digits of a `\u` escape.
+    fn code_unit(&mut self) -> Result<u32, ParseError> {
+        self.advance();
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self
+                .advance()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.invalid_escape())?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    fn invalid_escape(&self) -> ParseError {
+        ParseError::new(format!("Invalid unicode escape at line {}", self.line))
+    }
+
     fn identifier_or_keyword(&mut self) {
         while self.is_alpha_numeric(self.peek()) {
             self.advance();
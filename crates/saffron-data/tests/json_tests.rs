@@ -333,3 +333,58 @@ fn test_parse_object_with_single_quotes() {
         panic!("Expected Object");
     }
 }
+
+#[test]
+fn test_parse_unicode_escapes() {
+    let result = Json::parse(r#""caf\u00e9 \ud83d\ude00 \b\f\/""#).unwrap();
+    assert_eq!(
+        result.root,
+        JsonElement::String("café 😀 \u{8}\u{c}/".to_string())
+    );
+}
+
+#[test]
+fn test_error_invalid_unicode_escapes() {
+    assert!(Json::parse(r#""\u00g1""#).is_err());
+    assert!(Json::parse(r#""\ud83d""#).is_err());
+    assert!(Json::parse(r#""\ude00""#).is_err());
+}
+
+#[test]
+fn test_parse_number_exponent() {
+    let result = Json::parse("[1e3, -2.5E-2, 4e+1]").unwrap();
+    assert_eq!(
+        result.root,
+        JsonElement::Array(vec![
+            JsonElement::Number(1000.0),
+            JsonElement::Number(-0.025),
+            JsonElement::Number(40.0),
+        ])
+    );
+}
+
+#[test]
+fn test_error_leading_zero() {
+    assert!(Json::parse("007").is_err());
+    assert!(Json::parse("-01").is_err());
+    assert_eq!(Json::parse("0.5").unwrap().root, JsonElement::Number(0.5));
+}
+
+#[test]
+fn test_error_trailing_tokens() {
+    assert!(Json::parse(r#"{"a": 1} {"b": 2}"#).is_err());
+    assert!(Json::parse("1 2").is_err());
+}
+
+#[test]
+fn test_display_round_trips() {
+    let source = r#"{"list": [1, 2.5, -3, true, null], "text": "a \"quote\"\n\u0001"}"#;
+    let json = Json::parse(source).unwrap();
+
+    assert_eq!(Json::parse(json.root.to_string()).unwrap().root, json.root);
+    assert_eq!(
+        JsonElement::String("a \"quote\"\n\u{1}".to_string()).to_string(),
+        r#""a \"quote\"\n\u0001""#
+    );
+    assert_eq!(JsonElement::Number(f64::NAN).to_string(), "null");
+}
@@ -0,0 +1,427 @@
+#![cfg(feature = "proto")]
+
+use prost_reflect::Kind;
+use saffron_data::proto::{ProtoError, ProtoSchema};
+
+const SHOP: &str = r#"
+// Orders for the shop
+syntax = "proto3";
+
+package shop.v1;
+
+import "google/protobuf/timestamp.proto";
+import "google/protobuf/wrappers.proto";
+import "google/protobuf/struct.proto";
+
+option go_package = "example.com/shop;shop";
+
+message Order {
+  string id = 1;
+  int64 total_cents = 2;
+  repeated int32 quantities = 3;
+  Status status = 4;
+  map<string, Item> items = 5;
+  Customer customer = 6 [json_name = "buyer"];
+  bytes signature = 7;
+  google.protobuf.Timestamp placed_at = 8;
+  google.protobuf.StringValue note = 9;
+  google.protobuf.Struct extra = 10;
+  repeated string tags = 11 [deprecated = true];
+  oneof payment {
+    string card = 12;
+    double credit = 13;
+  }
+  sint32 adjustment = 14;
+
+  enum Status {
+    STATUS_UNSPECIFIED = 0;
+    PAID = 1;
+    SHIPPED = 2;
+  }
+
+  message Customer {
+    string name = 1;
+    reserved 2, 3;
+  }
+}
+
+message Item {
+  string sku = 1;
+  float weight = 2;
+}
+
+message GetOrderRequest { string id = 1; }
+
+service Orders {
+  option deprecated = false;
+  rpc GetOrder (GetOrderRequest) returns (Order);
+  rpc WatchOrders (GetOrderRequest) returns (stream Order) {}
+}
+"#;
+
+fn shop() -> ProtoSchema {
+    let mut schema = ProtoSchema::new();
+    schema.add_source("shop.proto", SHOP).unwrap();
+    schema
+}
+
+#[test]
+fn test_parse_schema() {
+    let schema = shop();
+
+    let order = schema.message("shop.v1.Order").unwrap();
+    let names: Vec<String> = order.fields().map(|f| f.json_name().to_string()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "id",
+            "totalCents",
+            "quantities",
+            "status",
+            "items",
+            "buyer",
+            "signature",
+            "placedAt",
+            "note",
+            "extra",
+            "tags",
+            "card",
+            "credit",
+            "adjustment"
+        ]
+    );
+    let field = |name: &str| order.get_field_by_name(name).unwrap();
+    assert!(
+        matches!(field("status").kind(), Kind::Enum(e) if e.full_name() == "shop.v1.Order.Status")
+    );
+    assert!(
+        matches!(field("customer").kind(), Kind::Message(m) if m.full_name() == "shop.v1.Order.Customer")
+    );
+    assert!(field("quantities").is_packed());
+    assert!(!field("tags").is_packed());
+    assert!(field("items").is_map());
+
+    let entry = schema.message("shop.v1.Order.ItemsEntry").unwrap();
+    assert!(entry.is_map_entry());
+    assert!(schema.enum_type("shop.v1.Order.Status").is_some());
+
+    let (service, method) = schema.find_method("shop.v1.Orders/WatchOrders").unwrap();
+    assert_eq!(method.path(service), "/shop.v1.Orders/WatchOrders");
+    assert_eq!(method.input_type, "shop.v1.GetOrderRequest");
+    assert!(method.server_streaming);
+    assert!(!method.client_streaming);
+    assert!(schema.find_method("Orders.GetOrder").is_ok());
+    assert!(matches!(
+        schema.find_method("Orders/Cancel"),
+        Err(ProtoError::UnknownMethod(_))
+    ));
+}
+
+#[test]
+fn test_encode_matches_protobuf_encoding() {
+    let mut schema = ProtoSchema::new();
+    schema
+        .add_source(
+            "test.proto",
+            r#"syntax = "proto3";
+               message Test { int32 a = 1; string b = 2; repeated int32 c = 4; sint64 d = 5; }"#,
+        )
+        .unwrap();
+
+    // The examples from the protobuf encoding guide
+    let bytes = schema
+        .encode(
+            "Test",
+            r#"{"a": 150, "b": "testing", "c": [3, 270], "d": "-2"}"#,
+        )
+        .unwrap();
+
+    assert_eq!(
+        bytes,
+        vec![
+            0x08, 0x96, 0x01, 0x12, 0x07, b't', b'e', b's', b't', b'i', b'n', b'g', 0x22, 0x03,
+            0x03, 0x8E, 0x02, 0x28, 0x03
+        ]
+    );
+}
+
+#[test]
+fn test_round_trip() {
+    let schema = shop();
+    let request = r#"{
+        "adjustment": -3,
+        "id": "o-1",
+        "totalCents": "9007199254740993",
+        "quantities": [1, 2],
+        "status": "SHIPPED",
+        "items": {"a": {"sku": "A", "weight": 1.5}},
+        "buyer": {"name": "Ada"},
+        "signature": "3q2-7w",
+        "placed_at": "2024-02-29T12:30:00.250+01:00",
+        "note": "leave at door",
+        "extra": {"gift": true, "codes": [1, null]},
+        "card": "visa"
+    }"#;
+
+    let bytes = schema.encode("shop.v1.Order", request).unwrap();
+    let decoded = schema.decode("shop.v1.Order", &bytes).unwrap();
+
+    assert_eq!(
+        decoded,
+        r#"{
+  "id": "o-1",
+  "totalCents": "9007199254740993",
+  "quantities": [
+    1,
+    2
+  ],
+  "status": "SHIPPED",
+  "items": {
+    "a": {
+      "sku": "A",
+      "weight": 1.5
+    }
+  },
+  "buyer": {
+    "name": "Ada"
+  },
+  "signature": "3q2+7w==",
+  "placedAt": "2024-02-29T11:30:00.250Z",
+  "note": "leave at door",
+  "extra": {
+    "codes": [
+      1.0,
+      null
+    ],
+    "gift": true
+  },
+  "card": "visa",
+  "adjustment": -3
+}"#
+    );
+}
+
+#[test]
+fn test_encode_errors() {
+    let schema = shop();
+
+    let error = |json: &str| {
+        schema
+            .encode("shop.v1.Order", json)
+            .unwrap_err()
+            .to_string()
+    };
+
+    assert!(matches!(
+        schema.encode("shop.v1.Order", r#"{"nope": 1}"#),
+        Err(ProtoError::InvalidValue { message_type, .. }) if message_type == "shop.v1.Order"
+    ));
+    assert_eq!(
+        error(r#"{"buyer": {"name": 7}}"#),
+        "Invalid shop.v1.Order: invalid type: integer `7`, expected a string"
+    );
+    assert!(error(r#"{"quantities": [4294967296]}"#).contains("out of range"));
+    assert!(error(r#"{"status": "LOST"}"#).contains("unrecognized enum value 'LOST'"));
+    assert!(error(r#"{"id": "x"} trailing"#).contains("Unexpected Identifier after the value"));
+}
+
+#[test]
+fn test_encode_reads_escapes_and_exponents() {
+    let schema = shop();
+
+    let bytes = schema
+        .encode(
+            "shop.v1.Order",
+            r#"{"note": "caf\u00e9 \ud83d\ude00", "quantities": [1e2],
+                "items": {"a": {"weight": 25E-1}}}"#,
+        )
+        .unwrap();
+
+    assert_eq!(
+        schema.decode("shop.v1.Order", &bytes).unwrap(),
+        r#"{
+  "quantities": [
+    100
+  ],
+  "items": {
+    "a": {
+      "weight": 2.5
+    }
+  },
+  "note": "café 😀"
+}"#
+    );
+}
+
+#[test]
+fn test_decode_skips_unknown_fields_and_accepts_unpacked() {
+    let schema = shop();
+
+    // Field 99 is unknown; quantities arrive unpacked
+    let bytes = [0xF8, 0x06, 0x01, 0x18, 0x05, 0x18, 0x06, 0x20, 0x09];
+    let decoded = schema.decode("shop.v1.Order", &bytes).unwrap();
+
+    assert_eq!(
+        decoded,
+        "{\n  \"quantities\": [\n    5,\n    6\n  ],\n  \"status\": 9\n}"
+    );
+    assert!(schema.decode("shop.v1.Order", &[0x0A, 0x05, b'x']).is_err());
+}
+
+#[test]
+fn test_imports_and_syntax_errors() {
+    let dir = std::env::temp_dir().join(format!("saffron-proto-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("common")).unwrap();
+    std::fs::write(
+        dir.join("common/money.proto"),
+        "syntax = \"proto3\";\npackage common;\nmessage Money { int64 units = 1; }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("pay.proto"),
+        "syntax = \"proto3\";\npackage pay;\nimport \"common/money.proto\";\n\
+         message Charge { common.Money amount = 1; }\n\
+         service Payments { rpc Charge (Charge) returns (Charge); }\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("broken.proto"),
+        "syntax = \"proto3\";\nmessage A {\n  int32 x 1;\n}\n",
+    )
+    .unwrap();
+
+    let mut schema = ProtoSchema::new().with_include_path(&dir);
+    schema.load_file(dir.join("pay.proto")).unwrap();
+    let bytes = schema
+        .encode("pay.Charge", r#"{"amount": {"units": 12}}"#)
+        .unwrap();
+    assert_eq!(bytes, vec![0x0A, 0x02, 0x08, 0x0C]);
+
+    let error = ProtoSchema::new()
+        .load_file(dir.join("broken.proto"))
+        .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .ends_with("broken.proto`: at 3:11: While parsing field, expecting char `=`"),
+        "{}",
+        error
+    );
+
+    let broken = ProtoSchema::new()
+        .add_source(
+            "y.proto",
+            "syntax = \"proto3\";\nmessage A {\n  int32 x 1;\n}\n",
+        )
+        .unwrap_err();
+    assert!(
+        broken
+            .to_string()
+            .starts_with("error in `y.proto`: at 3:11")
+    );
+
+    let missing = ProtoSchema::new()
+        .add_source("x.proto", "import \"missing.proto\";")
+        .unwrap_err();
+    assert!(matches!(missing, ProtoError::Parse(_)));
+    assert!(missing.to_string().contains("`missing.proto` is not found"));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_well_known_types() {
+    let mut schema = ProtoSchema::new();
+    schema
+        .add_source(
+            "wkt.proto",
+            r#"syntax = "proto3";
+               import "google/protobuf/duration.proto";
+               import "google/protobuf/field_mask.proto";
+               import "google/protobuf/any.proto";
+               import "google/protobuf/wrappers.proto";
+               message Job {
+                 google.protobuf.Duration timeout = 1;
+                 google.protobuf.FieldMask mask = 2;
+                 google.protobuf.Any detail = 3;
+                 google.protobuf.Int64Value retries = 4;
+               }"#,
+        )
+        .unwrap();
+
+    let request = r#"{"timeout": "-1.5s", "mask": "userId,name", "retries": 0,
+            "detail": {"@type": "type.googleapis.com/google.protobuf.Int64Value", "value": "7"}}"#;
+    let bytes = schema.encode("Job", request).unwrap();
+
+    assert_eq!(
+        schema.decode("Job", &bytes).unwrap(),
+        r#"{
+  "timeout": "-1.500s",
+  "mask": "userId,name",
+  "detail": {
+    "@type": "type.googleapis.com/google.protobuf.Int64Value",
+    "value": "7"
+  },
+  "retries": "0"
+}"#
+    );
+}
+
+#[test]
+fn test_load_descriptor_set() {
+    fn field(number: u32, bytes: &[u8]) -> Vec<u8> {
+        let mut out = vec![(number << 3 | 2) as u8, bytes.len() as u8];
+        out.extend_from_slice(bytes);
+        out
+    }
+    fn varint(number: u32, value: u8) -> Vec<u8> {
+        vec![(number << 3) as u8, value]
+    }
+
+    // A FileDescriptorSet holding:
+    //   package echo; message Ping { repeated uint32 seq = 1; }
+    //   service Echo { rpc Stream (Ping) returns (stream Ping); }
+    let seq = [
+        field(1, b"seq"),
+        varint(3, 1),
+        varint(4, 3),
+        varint(5, 13),
+        field(10, b"seq"),
+    ]
+    .concat();
+    let message = [field(1, b"Ping"), field(2, &seq)].concat();
+    let method = [
+        field(1, b"Stream"),
+        field(2, b".echo.Ping"),
+        field(3, b".echo.Ping"),
+        varint(6, 1),
+    ]
+    .concat();
+    let service = [field(1, b"Echo"), field(2, &method)].concat();
+    let file = [
+        field(1, b"echo.proto"),
+        field(2, b"echo"),
+        field(4, &message),
+        field(6, &service),
+        field(12, b"proto3"),
+    ]
+    .concat();
+    let set = field(1, &file);
+
+    let mut schema = ProtoSchema::new();
+    schema.load_descriptor_set(&set).unwrap();
+
+    let (_, method) = schema.find_method("echo.Echo/Stream").unwrap();
+    assert!(method.server_streaming);
+    assert_eq!(method.output_type, "echo.Ping");
+    let seq = schema.message("echo.Ping").unwrap().get_field(1).unwrap();
+    assert!(seq.is_packed());
+    assert_eq!(
+        schema.encode("echo.Ping", r#"{"seq": [1, 2]}"#).unwrap(),
+        vec![0x0A, 0x02, 0x01, 0x02]
+    );
+    assert!(matches!(
+        ProtoSchema::new().load_descriptor_set(&[0x0A, 0x05]),
+        Err(ProtoError::InvalidDescriptor(_))
+    ));
+}
@@ -1,6 +1,7 @@
 use saffron_core::domain::request::{FormDataContent, FormDataPart, HttpRequest, RequestBody};
-use saffron_http::{HttpClient, HttpClientConfig};
-use std::collections::HashMap;
+use saffron_http::{
+    ConnectConfig, CookieJar, HttpClient, HttpClientConfig, ProxyConfig, RetryPolicy, TlsConfig,
+};
 
 fn main() {
     println!("=== Saffron HTTP - Advanced Features Demo ===\n");
@@ -20,8 +21,14 @@ fn demo_custom_config() {
         follow_redirects: true,
         max_redirects: 5,
         user_agent: Some("Saffron-Custom/1.0".to_string()),
-        accept_invalid_certs: false,
+        tls: TlsConfig::default(),
+        proxy: ProxyConfig::from_env(),
+        connect: ConnectConfig::default(),
         max_response_size: Some(10 * 1024 * 1024),
+        cookie_jar: Some(CookieJar::new()),
+        retry: RetryPolicy::with_retries(2),
+        compressed: true,
+        decompress: true,
     };
 
     let client = HttpClient::with_config(config);
@@ -74,10 +81,11 @@ fn demo_multipart_form() {
 fn demo_form_urlencoded() {
     println!("--- 3. Form URL Encoded ---");
 
-    let mut form_data = HashMap::new();
-    form_data.insert("username".to_string(), "saffron_user".to_string());
-    form_data.insert("email".to_string(), "user@saffron.dev".to_string());
-    form_data.insert("age".to_string(), "25".to_string());
+    let form_data = vec![
+        ("username".to_string(), "saffron_user".to_string()),
+        ("email".to_string(), "user@saffron.dev".to_string()),
+        ("city".to_string(), "São Paulo".to_string()),
+    ];
 
     let request = HttpRequest::post("https://httpbin.org/post")
         .with_body(RequestBody::FormUrlEncoded(form_data));
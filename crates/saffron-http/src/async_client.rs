@@ -0,0 +1,109 @@
+use crate::{HttpClient, HttpError};
+use saffron_core::domain::request::HttpRequest;
+use saffron_core::domain::response::HttpResponse;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, mpsc};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+type Job = Box<dyn FnOnce(&HttpClient) + Send>;
+
+/// A non-blocking front end to [`HttpClient`] that works with any async
+/// runtime.
+///
+/// Requests run on a fixed pool of worker threads, so at most `workers`
+/// are in flight at once; the rest wait in a queue. Each call returns a
+/// [`ResponseFuture`] that resolves when its request finishes. The workers
+/// stop once the client is dropped and the queue is empty.
+pub struct AsyncHttpClient {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl AsyncHttpClient {
+    pub fn new(client: HttpClient, workers: usize) -> Self {
+        let client = Arc::new(client);
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+
+        for _ in 0..workers.max(1) {
+            let client = client.clone();
+            let queue = queue.clone();
+            thread::spawn(move || {
+                loop {
+                    // Release the queue before running the job so other workers can take one
+                    let job = queue.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                    match job {
+                        Ok(job) => job(&client),
+                        Err(_) => break,
+                    }
+                }
+            });
+        }
+
+        Self { jobs }
+    }
+
+    pub fn send(&self, request: HttpRequest) -> ResponseFuture {
+        let future = ResponseFuture::default();
+        let state = future.state.clone();
+
+        let job: Job = Box::new(move |client| complete(&state, client.send(&request)));
+        if self.jobs.send(job).is_err() {
+            complete(
+                &future.state,
+                Err(HttpError::RequestFailed(
+                    "the worker pool has stopped".to_string(),
+                )),
+            );
+        }
+
+        future
+    }
+
+    pub fn get(&self, url: &str) -> ResponseFuture {
+        self.send(HttpRequest::get(url))
+    }
+}
+
+#[derive(Default)]
+struct FutureState {
+    result: Option<Result<HttpResponse, HttpError>>,
+    waker: Option<Waker>,
+}
+
+fn lock(state: &Mutex<FutureState>) -> MutexGuard<'_, FutureState> {
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn complete(state: &Mutex<FutureState>, result: Result<HttpResponse, HttpError>) {
+    let waker = {
+        let mut state = lock(state);
+        state.result = Some(result);
+        state.waker.take()
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+/// The pending result of [`AsyncHttpClient::send`].
+#[derive(Default)]
+pub struct ResponseFuture {
+    state: Arc<Mutex<FutureState>>,
+}
+
+impl Future for ResponseFuture {
+    type Output = Result<HttpResponse, HttpError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = lock(&self.state);
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use crate::{Exchange, HttpClient, HttpError, retry, with_query};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use md5::Md5;
+use saffron_core::domain::auth::{ApiKeyLocation, Auth, OAuth2Grant};
+use saffron_core::domain::request::{HttpRequest, RequestBody};
+use saffron_data::json::{Json, JsonElement};
+use saffron_data::parse::Parse;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Tokens are renewed this long before they expire, so a request never
+/// starts with a token that lapses in flight.
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+/// OAuth2 access tokens obtained by a client, keyed by the grant they came from.
+#[derive(Debug, Default)]
+pub(crate) struct TokenCache {
+    tokens: Mutex<HashMap<String, CachedToken>>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: Option<Instant>,
+}
+
+impl CachedToken {
+    fn is_fresh(&self) -> bool {
+        self.expires_at
+            .is_none_or(|at| at > Instant::now() + TOKEN_EXPIRY_MARGIN)
+    }
+}
+
+impl HttpClient {
+    /// Runs [`Self::execute`] with the request's auth applied, answering a
+    /// Digest challenge and renewing an OAuth2 token the server rejected.
+    ///
+    /// An explicit `Authorization` header on the request takes precedence
+    /// over header-based schemes.
+    pub(crate) fn execute_authorized(&self, request: &HttpRequest) -> Result<Exchange, HttpError> {
+        let Some(auth) = &request.auth else {
+            return self.execute(request);
+        };
+
+        if let Some(credential) = Credential::of(request, auth) {
+            return self.execute(&credential.apply(request)?);
+        }
+
+        let explicit = request.get_header("Authorization").is_some();
+
+        match auth {
+            Auth::Digest { username, password } if !explicit => {
+                let exchange = self.execute(request)?;
+                if exchange.resp.status != 401
+                    || !exchange.redirects.is_empty()
+                    || !retry::is_replayable(&request.body)
+                {
+                    return Ok(exchange);
+                }
+
+                let authorization = exchange
+                    .resp
+                    .headers
+                    .get_all("www-authenticate")
+                    .find_map(DigestChallenge::parse)
+                    .and_then(|challenge| challenge.authorize(request, username, password));
+
+                let Some(authorization) = authorization else {
+                    return Ok(exchange);
+                };
+
+                drain(exchange);
+                self.execute(&with_authorization(request, authorization))
+            }
+            Auth::OAuth2 { .. } if !explicit => {
+                let (token, cached) = self.oauth2_token(auth, false)?;
+                let exchange =
+                    self.execute(&with_authorization(request, format!("Bearer {}", token)))?;
+
+                // A cached token may have been revoked early; fetch a new one once
+                if exchange.resp.status != 401 || !cached || !retry::is_replayable(&request.body) {
+                    return Ok(exchange);
+                }
+
+                drain(exchange);
+                let (token, _) = self.oauth2_token(auth, true)?;
+                self.execute(&with_authorization(request, format!("Bearer {}", token)))
+            }
+            _ => self.execute(request),
+        }
+    }
+
+    /// Applies the request's auth up front, for a request that can't be sent
+    /// twice such as a WebSocket upgrade. Digest needs a challenge from a
+    /// first attempt, so it isn't supported.
+    pub(crate) fn authorize_upfront(
+        &self,
+        request: &HttpRequest,
+    ) -> Result<HttpRequest, HttpError> {
+        let request = &*with_query(request)?;
+        let Some(auth) = &request.auth else {
+            return Ok(request.clone());
+        };
+
+        if let Some(credential) = Credential::of(request, auth) {
+            return credential.apply(request);
+        }
+
+        let explicit = request.get_header("Authorization").is_some();
+
+        match auth {
+            Auth::Digest { .. } if !explicit => Err(HttpError::AuthError(
+                "Digest needs a challenge and can't be sent up front".to_string(),
+            )),
+            Auth::OAuth2 { .. } if !explicit => {
+                let (token, _) = self.oauth2_token(auth, false)?;
+                Ok(with_authorization(request, format!("Bearer {}", token)))
+            }
+            _ => Ok(request.clone()),
+        }
+    }
+
+    /// Returns an access token for `auth`, and whether it came from the cache.
+    /// `renew` skips the cached token, trying its refresh token first.
+    fn oauth2_token(&self, auth: &Auth, renew: bool) -> Result<(String, bool), HttpError> {
+        let Auth::OAuth2 {
+            token_url,
+            client_id,
+            client_secret,
+            scope,
+            grant,
+        } = auth
+        else {
+            return Err(HttpError::AuthError(
+                "not an OAuth2 configuration".to_string(),
+            ));
+        };
+
+        let key = match grant {
+            OAuth2Grant::ClientCredentials => {
+                format!(
+                    "{}\n{}\n{}",
+                    token_url,
+                    client_id,
+                    scope.as_deref().unwrap_or("")
+                )
+            }
+            OAuth2Grant::Password { username, .. } => format!(
+                "{}\n{}\n{}\n{}",
+                token_url,
+                client_id,
+                scope.as_deref().unwrap_or(""),
+                username
+            ),
+        };
+
+        let cached = self.oauth_tokens.lock().get(&key).cloned();
+
+        if let Some(token) = &cached
+            && !renew
+            && token.is_fresh()
+        {
+            return Ok((token.access_token.clone(), true));
+        }
+
+        let client_auth = (client_id.as_str(), client_secret.as_deref());
+
+        if let Some(refresh_token) = cached.and_then(|t| t.refresh_token) {
+            let params = vec![
+                ("grant_type", "refresh_token".to_string()),
+                ("refresh_token", refresh_token),
+            ];
+            if let Ok(token) = self.request_token(token_url, client_auth, params) {
+                let access_token = token.access_token.clone();
+                self.oauth_tokens.lock().insert(key, token);
+                return Ok((access_token, false));
+            }
+        }
+
+        let mut params = match grant {
+            OAuth2Grant::ClientCredentials => {
+                vec![("grant_type", "client_credentials".to_string())]
+            }
+            OAuth2Grant::Password { username, password } => vec![
+                ("grant_type", "password".to_string()),
+                ("username", username.clone()),
+                ("password", password.clone()),
+            ],
+        };
+        if let Some(scope) = scope {
+            params.push(("scope", scope.clone()));
+        }
+
+        let token = self.request_token(token_url, client_auth, params)?;
+        let access_token = token.access_token.clone();
+        self.oauth_tokens.lock().insert(key, token);
+        Ok((access_token, false))
+    }
+
+    /// POSTs a token request (RFC 6749 §4). A client secret is sent with
+    /// HTTP Basic; public clients send their id in the body.
+    fn request_token(
+        &self,
+        token_url: &str,
+        (client_id, client_secret): (&str, Option<&str>),
+        mut params: Vec<(&str, String)>,
+    ) -> Result<CachedToken, HttpError> {
+        let mut request = HttpRequest::post(token_url).with_header("Accept", "application/json");
+
+        match client_secret {
+            Some(secret) => request = request.with_auth(Auth::basic(client_id, secret)),
+            None => params.push(("client_id", client_id.to_string())),
+        }
+
+        let form = params
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        let response = self.send(&request.with_body(RequestBody::FormUrlEncoded(form)))?;
+
+        // Some servers ignore `Accept` and answer with a form, such as
+        // GitHub's `access_token=...&token_type=bearer`
+        let json = match response.form() {
+            Some(form) => Some(JsonElement::Object(
+                form.into_iter()
+                    .map(|(name, value)| (name, JsonElement::String(value)))
+                    .collect(),
+            )),
+            None => {
+                let body = response.body_as_str().unwrap_or_default();
+                Json::parse(body).ok().map(|json| json.root)
+            }
+        };
+        let field = |name: &str| match &json {
+            Some(JsonElement::Object(map)) => map.get(name).cloned(),
+            _ => None,
+        };
+        let string_field = |name: &str| match field(name) {
+            Some(JsonElement::String(value)) => Some(value),
+            _ => None,
+        };
+
+        if !response.is_success() {
+            let reason = match (string_field("error"), string_field("error_description")) {
+                (Some(error), Some(description)) => format!("{}: {}", error, description),
+                (Some(error), None) => error,
+                _ => format!("{} {}", response.status, response.status_text),
+            };
+            return Err(HttpError::AuthError(format!(
+                "token request to {} failed ({})",
+                token_url, reason
+            )));
+        }
+
+        let access_token = string_field("access_token").ok_or_else(|| {
+            HttpError::AuthError(format!("no access_token in response from {}", token_url))
+        })?;
+
+        let expires_at = match field("expires_in") {
+            Some(JsonElement::Number(secs)) if secs > 0.0 => {
+                Some(Instant::now() + Duration::from_secs_f64(secs))
+            }
+            Some(JsonElement::String(secs)) => secs
+                .parse::<u64>()
+                .ok()
+                .filter(|&secs| secs > 0)
+                .map(|secs| Instant::now() + Duration::from_secs(secs)),
+            _ => None,
+        };
+
+        Ok(CachedToken {
+            access_token,
+            refresh_token: string_field("refresh_token"),
+            expires_at,
+        })
+    }
+}
+
+impl TokenCache {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, CachedToken>> {
+        self.tokens.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A credential sent as it is, without a round trip to obtain it: Basic,
+/// Bearer and API keys.
+enum Credential {
+    Header(String, String),
+    Query(String, String),
+}
+
+impl Credential {
+    /// The credential for `auth`, or `None` for schemes that need a round
+    /// trip and when the request already sets the header itself.
+    fn of(request: &HttpRequest, auth: &Auth) -> Option<Self> {
+        let explicit = request.get_header("Authorization").is_some();
+        match auth {
+            Auth::Basic { username, password } if !explicit => {
+                let credentials = BASE64.encode(format!("{}:{}", username, password));
+                Some(Credential::Header(
+                    "Authorization".to_string(),
+                    format!("Basic {}", credentials),
+                ))
+            }
+            Auth::Bearer { token } if !explicit => Some(Credential::Header(
+                "Authorization".to_string(),
+                format!("Bearer {}", token),
+            )),
+            Auth::ApiKey {
+                name,
+                value,
+                location: ApiKeyLocation::Header,
+            } if request.get_header(name).is_none() => {
+                Some(Credential::Header(name.clone(), value.clone()))
+            }
+            Auth::ApiKey {
+                name,
+                value,
+                location: ApiKeyLocation::Query,
+            } => Some(Credential::Query(name.clone(), value.clone())),
+            _ => None,
+        }
+    }
+
+    fn apply(self, request: &HttpRequest) -> Result<HttpRequest, HttpError> {
+        match self {
+            Credential::Header(name, value) => Ok(request.clone().with_header(name, value)),
+            Credential::Query(name, value) => {
+                let mut url = Url::parse(&request.url)
+                    .map_err(|e| HttpError::InvalidUrl(format!("{} ({})", request.url, e)))?;
+                url.query_pairs_mut().append_pair(&name, &value);
+
+                let mut keyed = request.clone();
+                keyed.url = url.to_string();
+                Ok(keyed)
+            }
+        }
+    }
+}
+
+fn with_authorization(request: &HttpRequest, value: String) -> HttpRequest {
+    request.clone().with_header("Authorization", value)
+}
+
+/// Reads and discards the body of a response that is about to be replaced.
+fn drain(exchange: Exchange) {
+    let mut body = exchange.resp.body;
+    let _ = io::copy(&mut body, &mut io::sink());
+}
+
+/// A `WWW-Authenticate: Digest` challenge (RFC 7616 §3.3).
+#[derive(Debug, Default)]
+struct DigestChallenge {
+    realm: String,
+    nonce: String,
+    opaque: Option<String>,
+    algorithm: Option<String>,
+    qop: Vec<String>,
+}
+
+impl DigestChallenge {
+    fn parse(header: &str) -> Option<Self> {
+        let (scheme, params) = header.trim().split_once(' ')?;
+        if !scheme.eq_ignore_ascii_case("digest") {
+            return None;
+        }
+
+        let mut challenge = DigestChallenge::default();
+        for (key, value) in parse_auth_params(params) {
+            match key.to_ascii_lowercase().as_str() {
+                "realm" => challenge.realm = value,
+                "nonce" => challenge.nonce = value,
+                "opaque" => challenge.opaque = Some(value),
+                "algorithm" => challenge.algorithm = Some(value),
+                "qop" => challenge.qop = value.split(',').map(|q| q.trim().to_string()).collect(),
+                _ => {}
+            }
+        }
+
+        (!challenge.nonce.is_empty()).then_some(challenge)
+    }
+
+    /// The `Authorization` header answering this challenge, or `None` for
+    /// unsupported algorithms or `qop=auth-int`-only challenges.
+    fn authorize(&self, request: &HttpRequest, username: &str, password: &str) -> Option<String> {
+        let algorithm = self.algorithm.as_deref().unwrap_or("MD5");
+        let (hash, session): (fn(&str) -> String, bool) =
+            match algorithm.to_ascii_uppercase().as_str() {
+                "MD5" => (md5_hex, false),
+                "MD5-SESS" => (md5_hex, true),
+                "SHA-256" => (sha256_hex, false),
+                "SHA-256-SESS" => (sha256_hex, true),
+                _ => return None,
+            };
+
+        let url = Url::parse(&request.url).ok()?;
+        let uri = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+
+        let qop = if self.qop.is_empty() {
+            None
+        } else if self.qop.iter().any(|q| q.eq_ignore_ascii_case("auth")) {
+            Some("auth")
+        } else {
+            return None;
+        };
+
+        let cnonce = format!("{:016x}", rand::random::<u64>());
+        let nc = "00000001";
+
+        let mut ha1 = hash(&format!("{}:{}:{}", username, self.realm, password));
+        if session {
+            ha1 = hash(&format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+        let ha2 = hash(&format!("{}:{}", request.method.as_str(), uri));
+
+        let response = match qop {
+            Some(qop) => hash(&format!(
+                "{}:{}:{}:{}:{}:{}",
+                ha1, self.nonce, nc, cnonce, qop, ha2
+            )),
+            None => hash(&format!("{}:{}:{}", ha1, self.nonce, ha2)),
+        };
+
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+            quote(username),
+            quote(&self.realm),
+            quote(&self.nonce),
+            quote(&uri),
+            algorithm,
+            response
+        );
+        if let Some(qop) = qop {
+            header.push_str(&format!(", qop={}, nc={}, cnonce=\"{}\"", qop, nc, cnonce));
+        }
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(", opaque=\"{}\"", quote(opaque)));
+        }
+        Some(header)
+    }
+}
+
+/// Splits `key=value, key="quoted, value"` auth parameters.
+fn parse_auth_params(input: &str) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| *c == ',' || c.is_whitespace()).is_some() {}
+
+        let key: String =
+            std::iter::from_fn(|| chars.next_if(|c| *c != '=' && *c != ',')).collect();
+        if key.is_empty() {
+            break;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'=').is_some() {
+            if chars.next_if_eq(&'"').is_some() {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => value.extend(chars.next()),
+                        '"' => break,
+                        _ => value.push(c),
+                    }
+                }
+            } else {
+                value = std::iter::from_fn(|| chars.next_if(|c| *c != ','))
+                    .collect::<String>()
+                    .trim()
+                    .to_string();
+            }
+        }
+
+        params.push((key.trim().to_string(), value));
+    }
+
+    params
+}
+
+fn quote(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn md5_hex(input: &str) -> String {
+    hex(&Md5::digest(input.as_bytes()))
+}
+
+fn sha256_hex(input: &str) -> String {
+    hex(&Sha256::digest(input.as_bytes()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,100 @@
+use crate::{HttpClient, HttpError};
+use saffron_core::domain::request::HttpRequest;
+use saffron_core::domain::response::HttpResponse;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How [`HttpClient::send_batch`] runs a set of requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchConfig {
+    /// Most requests in flight at once. `0` is treated as `1`.
+    pub concurrency: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self { concurrency: 8 }
+    }
+}
+
+impl BatchConfig {
+    pub fn with_concurrency(concurrency: usize) -> Self {
+        Self { concurrency }
+    }
+}
+
+/// The outcome of one request of a batch.
+#[derive(Debug)]
+pub struct BatchResult {
+    /// Position of the request in the batch.
+    pub index: usize,
+    pub result: Result<HttpResponse, HttpError>,
+    /// Time from the request being picked up by a worker to its result.
+    pub elapsed: Duration,
+}
+
+impl HttpClient {
+    /// Sends `requests` on a pool of up to `config.concurrency` threads.
+    ///
+    /// Requests are started in the order given and a failure doesn't stop
+    /// the others. The result list has one entry per request, in the same
+    /// order as `requests`.
+    pub fn send_batch(&self, requests: &[HttpRequest], config: &BatchConfig) -> Vec<BatchResult> {
+        self.send_batch_with(requests, config, |_| {})
+    }
+
+    /// Like [`HttpClient::send_batch`], calling `on_result` on the calling
+    /// thread as each request finishes, in completion order.
+    pub fn send_batch_with(
+        &self,
+        requests: &[HttpRequest],
+        config: &BatchConfig,
+        mut on_result: impl FnMut(&BatchResult),
+    ) -> Vec<BatchResult> {
+        let workers = config.concurrency.clamp(1, requests.len().max(1));
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<Option<BatchResult>> = requests.iter().map(|_| None).collect();
+
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+
+            for _ in 0..workers {
+                let sender = sender.clone();
+                let next = &next;
+                scope.spawn(move || {
+                    // Each worker takes the next unstarted request until none are left
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(request) = requests.get(index) else {
+                            break;
+                        };
+
+                        let start = Instant::now();
+                        let result = BatchResult {
+                            index,
+                            result: self.send(request),
+                            elapsed: start.elapsed(),
+                        };
+                        if sender.send(result).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            for result in receiver {
+                on_result(&result);
+                let index = result.index;
+                results[index] = Some(result);
+            }
+        });
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every request in a batch has a result"))
+            .collect()
+    }
+}
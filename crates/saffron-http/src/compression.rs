@@ -0,0 +1,146 @@
+use flate2::bufread::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use std::cell::Cell;
+use std::io::{self, BufRead, BufReader, Read};
+use std::rc::Rc;
+
+/// `Accept-Encoding` sent when compression is enabled, in order of preference.
+pub(crate) const ACCEPT_ENCODING: &str = "gzip, deflate, br, zstd";
+
+/// A content coding this client can decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Coding {
+    Gzip,
+    Deflate,
+    Brotli,
+    Zstd,
+}
+
+impl Coding {
+    fn parse(token: &str) -> Option<Self> {
+        match token.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Coding::Gzip),
+            "deflate" => Some(Coding::Deflate),
+            "br" => Some(Coding::Brotli),
+            "zstd" => Some(Coding::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// The codings listed in a `Content-Encoding` header, in the order they were
+/// applied. `None` if the body is not encoded or uses a coding we can't
+/// decode, in which case it is passed through untouched.
+fn parse_codings(content_encoding: &str) -> Option<Vec<Coding>> {
+    let tokens: Vec<&str> = content_encoding
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty() && !t.eq_ignore_ascii_case("identity"))
+        .collect();
+
+    if tokens.is_empty() {
+        return None;
+    }
+    tokens.into_iter().map(Coding::parse).collect()
+}
+
+/// Counts the bytes read through it, so the encoded size stays known once
+/// the reader is buried under decoders.
+struct CountingReader<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+/// A response body reader that decodes the body's `Content-Encoding`.
+pub(crate) struct DecodedBody<'a> {
+    reader: Box<dyn Read + 'a>,
+    raw: Rc<Cell<u64>>,
+    /// The `Content-Encoding` that was removed, if any.
+    pub(crate) encoding: Option<String>,
+}
+
+impl<'a> DecodedBody<'a> {
+    /// Wraps `reader`, decoding `content_encoding` when `decode` is set and
+    /// every listed coding is supported.
+    pub(crate) fn new(
+        reader: impl Read + 'a,
+        content_encoding: Option<&str>,
+        decode: bool,
+    ) -> io::Result<Self> {
+        let raw = Rc::new(Cell::new(0));
+        let counted = CountingReader {
+            inner: reader,
+            count: raw.clone(),
+        };
+
+        let codings = content_encoding
+            .filter(|_| decode)
+            .and_then(parse_codings)
+            .unwrap_or_default();
+
+        let mut body = BufReader::new(counted);
+        // Empty bodies (HEAD, 204, 304) carry the header but no encoded data
+        if codings.is_empty() || body.fill_buf()?.is_empty() {
+            return Ok(Self {
+                reader: Box::new(body),
+                raw,
+                encoding: None,
+            });
+        }
+
+        // Codings are listed in the order they were applied, so undo the last first
+        let mut reader: Box<dyn Read + 'a> = Box::new(body);
+        for coding in codings.iter().rev() {
+            reader = decoder(*coding, reader)?;
+        }
+
+        Ok(Self {
+            reader,
+            raw,
+            encoding: content_encoding.map(|e| e.trim().to_string()),
+        })
+    }
+
+    /// Bytes read from the connection so far, before decoding.
+    pub(crate) fn raw_bytes(&self) -> u64 {
+        self.raw.get()
+    }
+}
+
+impl Read for DecodedBody<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+fn decoder<'a>(coding: Coding, reader: Box<dyn Read + 'a>) -> io::Result<Box<dyn Read + 'a>> {
+    let mut reader = BufReader::new(reader);
+
+    Ok(match coding {
+        Coding::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        Coding::Deflate => {
+            // RFC 9110 deflate is zlib-wrapped, but some servers send raw deflate
+            let head = reader.fill_buf()?;
+            let zlib = head.len() >= 2
+                && head[0] & 0x0f == 8
+                && (u16::from(head[0]) << 8 | u16::from(head[1])) % 31 == 0;
+            if zlib {
+                Box::new(ZlibDecoder::new(reader))
+            } else {
+                Box::new(DeflateDecoder::new(reader))
+            }
+        }
+        Coding::Brotli => Box::new(brotli_decompressor::Decompressor::new(reader, 64 * 1024)),
+        Coding::Zstd => Box::new(
+            ruzstd::decoding::StreamingDecoder::new(reader)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?,
+        ),
+    })
+}
@@ -0,0 +1,603 @@
+use crate::http1::{self, TimedSocket};
+use crate::{HttpError, Proxy, ProxyScheme, socks, timing};
+use saffron_core::domain::environment::ConnectSettings;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Shutdown, SocketAddr, TcpStream, ToSocketAddrs};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Which IP versions connections may use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpVersion {
+    #[default]
+    Any,
+    V4,
+    V6,
+}
+
+impl IpVersion {
+    pub fn allows(&self, ip: &IpAddr) -> bool {
+        match self {
+            IpVersion::Any => true,
+            IpVersion::V4 => ip.is_ipv4(),
+            IpVersion::V6 => ip.is_ipv6(),
+        }
+    }
+}
+
+impl FromStr for IpVersion {
+    type Err = HttpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "any" => Ok(IpVersion::Any),
+            "4" | "v4" | "ipv4" => Ok(IpVersion::V4),
+            "6" | "v6" | "ipv6" => Ok(IpVersion::V6),
+            _ => Err(HttpError::InvalidConnectOption(format!(
+                "unknown IP version '{}' (expected 4 or 6)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Pins `host:port` to fixed addresses instead of looking it up, like
+/// curl's `--resolve`. The URL, `Host` header and TLS server name are left
+/// alone.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolveOverride {
+    /// Host name to match, or `*` for any host.
+    pub host: String,
+    pub port: u16,
+    pub addresses: Vec<IpAddr>,
+}
+
+impl ResolveOverride {
+    fn matches(&self, host: &str, port: u16) -> bool {
+        self.port == port && (self.host == "*" || self.host.eq_ignore_ascii_case(host))
+    }
+}
+
+impl FromStr for ResolveOverride {
+    type Err = HttpError;
+
+    /// Parses `host:port:addr[,addr...]`. IPv6 addresses may be bracketed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            HttpError::InvalidConnectOption(format!("--resolve '{}': {}", s, reason))
+        };
+
+        let fields = split_fields(s, 3);
+        let [host, port, addresses] = fields.as_slice() else {
+            return Err(invalid("expected host:port:addr[,addr...]"));
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+
+        let port = port.parse().map_err(|_| invalid("invalid port"))?;
+        let addresses = addresses
+            .split(',')
+            .map(|addr| parse_ip(addr.trim()).ok_or_else(|| invalid("invalid address")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            host: unbracket(host).to_string(),
+            port,
+            addresses,
+        })
+    }
+}
+
+/// Sends connections for one host and port to another, like curl's
+/// `--connect-to`. Empty fields match any host or port, or keep the
+/// original one.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectTo {
+    pub from_host: Option<String>,
+    pub from_port: Option<u16>,
+    pub to_host: Option<String>,
+    pub to_port: Option<u16>,
+}
+
+impl ConnectTo {
+    fn apply(&self, host: &str, port: u16) -> Option<(String, u16)> {
+        let host_matches = self
+            .from_host
+            .as_ref()
+            .is_none_or(|from| from.eq_ignore_ascii_case(host));
+        let port_matches = self.from_port.is_none_or(|from| from == port);
+
+        (host_matches && port_matches).then(|| {
+            (
+                self.to_host.clone().unwrap_or_else(|| host.to_string()),
+                self.to_port.unwrap_or(port),
+            )
+        })
+    }
+}
+
+impl FromStr for ConnectTo {
+    type Err = HttpError;
+
+    /// Parses `host1:port1:host2:port2`. IPv6 addresses must be bracketed.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            HttpError::InvalidConnectOption(format!("--connect-to '{}': {}", s, reason))
+        };
+
+        let fields = split_fields(s, 4);
+        let [from_host, from_port, to_host, to_port] = fields.as_slice() else {
+            return Err(invalid("expected host1:port1:host2:port2"));
+        };
+
+        let host = |h: &str| Some(unbracket(h).to_string()).filter(|h| !h.is_empty());
+        let port = |p: &str| match p {
+            "" => Ok(None),
+            p => p.parse().map(Some).map_err(|_| invalid("invalid port")),
+        };
+
+        Ok(Self {
+            from_host: host(from_host),
+            from_port: port(from_port)?,
+            to_host: host(to_host),
+            to_port: port(to_port)?,
+        })
+    }
+}
+
+/// How connections are set up: address overrides, IP version, the local
+/// address to connect from, or a Unix domain socket to use instead of TCP.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectConfig {
+    pub resolve: Vec<ResolveOverride>,
+    pub connect_to: Vec<ConnectTo>,
+    pub ip_version: IpVersion,
+    /// Bind outgoing connections to this address.
+    pub local_address: Option<IpAddr>,
+    /// Send every request through this Unix domain socket, like curl's
+    /// `--unix-socket`. The URL still supplies the `Host` header and path.
+    pub unix_socket: Option<PathBuf>,
+}
+
+impl ConnectConfig {
+    /// Parses stored settings. A `local_address` that isn't an IP address is
+    /// looked up as a network interface name.
+    pub fn from_settings(settings: &ConnectSettings) -> Result<Self, HttpError> {
+        let ip_version = settings
+            .ip_version
+            .as_deref()
+            .map(IpVersion::from_str)
+            .transpose()?
+            .unwrap_or_default();
+
+        let local_address = match settings.local_address.as_deref() {
+            None => None,
+            Some(local) => Some(match parse_ip(local) {
+                Some(ip) => ip,
+                None => socket::interface_address(local, ip_version)?,
+            }),
+        };
+
+        Ok(Self {
+            resolve: settings
+                .resolve
+                .iter()
+                .map(|r| r.parse())
+                .collect::<Result<_, _>>()?,
+            connect_to: settings
+                .connect_to
+                .iter()
+                .map(|c| c.parse())
+                .collect::<Result<_, _>>()?,
+            ip_version,
+            local_address,
+            unix_socket: settings.unix_socket.as_ref().map(PathBuf::from),
+        })
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The addresses to try for `host:port`, after `--connect-to` and
+    /// `--resolve` and filtered by IP version.
+    pub fn lookup(&self, host: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        let host = unbracket(host);
+        let (host, port) = self
+            .connect_to
+            .iter()
+            .find_map(|rule| rule.apply(host, port))
+            .unwrap_or_else(|| (host.to_string(), port));
+
+        let addrs: Vec<SocketAddr> = match self.resolve.iter().find(|r| r.matches(&host, port)) {
+            Some(pinned) => pinned
+                .addresses
+                .iter()
+                .map(|ip| SocketAddr::new(*ip, port))
+                .collect(),
+            None => (host.as_str(), port).to_socket_addrs()?.collect(),
+        };
+
+        // A socket bound to one IP version can't reach the other
+        let local_version = match self.local_address {
+            Some(IpAddr::V4(_)) => IpVersion::V4,
+            Some(IpAddr::V6(_)) => IpVersion::V6,
+            None => IpVersion::Any,
+        };
+        let usable: Vec<SocketAddr> = addrs
+            .into_iter()
+            .filter(|a| self.ip_version.allows(&a.ip()) && local_version.allows(&a.ip()))
+            .collect();
+
+        if usable.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no usable address for {}:{}", host, port),
+            ));
+        }
+        Ok(usable)
+    }
+
+    /// Opens a connection for `host:port`: to the Unix socket if one is set,
+    /// otherwise over TCP, trying each address from [`lookup`](Self::lookup)
+    /// in turn, from the local address if one is set. The lookup and the
+    /// connect are recorded as timing phases.
+    pub(crate) fn connect(
+        &self,
+        host: &str,
+        port: u16,
+        timeout: Duration,
+    ) -> Result<Socket, HttpError> {
+        if let Some(path) = &self.unix_socket {
+            let start = Instant::now();
+            let socket = socket::connect_unix(path)
+                .map_err(|e| HttpError::NetworkError(format!("{}: {}", path.display(), e)))?;
+            timing::record_connect(start);
+            return Ok(socket);
+        }
+
+        let netloc = format!("{}:{}", host, port);
+        let start = Instant::now();
+        let addrs = self
+            .lookup(host, port)
+            .map_err(|e| HttpError::NetworkError(format!("{}: {}", netloc, e)))?;
+        timing::record_dns(start);
+
+        let start = Instant::now();
+        let mut last_error = None;
+        for addr in addrs {
+            match connect_from(self.local_address, addr, timeout) {
+                Ok(socket) => {
+                    timing::record_connect(start);
+                    return Ok(Socket::Tcp(socket));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(match last_error {
+            Some(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                ) =>
+            {
+                HttpError::Timeout
+            }
+            Some(e) => HttpError::NetworkError(format!("{}: {}", netloc, e)),
+            None => HttpError::NetworkError(format!("{}: no address to connect to", netloc)),
+        })
+    }
+
+    /// Opens a connection to `proxy`, reporting failures as proxy errors.
+    pub(crate) fn connect_proxy(
+        &self,
+        proxy: &Proxy,
+        timeout: Duration,
+    ) -> Result<Socket, HttpError> {
+        self.connect(&proxy.host, proxy.port, timeout)
+            .map_err(|e| match e {
+                HttpError::Timeout => e,
+                HttpError::NetworkError(e) => HttpError::ProxyError(e),
+                e => HttpError::ProxyError(e.to_string()),
+            })
+    }
+
+    /// Opens a connection to `host:port`, tunnelled through `proxy` if one
+    /// is given: with CONNECT for HTTP proxies, or a SOCKS handshake.
+    pub(crate) fn connect_through(
+        &self,
+        proxy: Option<&Proxy>,
+        host: &str,
+        port: u16,
+        timeout: Duration,
+        deadline: Option<Instant>,
+    ) -> Result<TimedSocket, HttpError> {
+        let Some(proxy) = proxy else {
+            let socket = self.connect(host, port, timeout)?;
+            return Ok(TimedSocket::new(socket, timeout, deadline));
+        };
+
+        let socket = self.connect_proxy(proxy, timeout)?;
+        let mut socket = TimedSocket::new(socket, timeout, deadline);
+        match proxy.scheme {
+            ProxyScheme::Http => http1::tunnel(&mut socket, proxy, host, port)?,
+            _ => socks::connect(&mut socket, proxy, self, host, port)?,
+        }
+        Ok(socket)
+    }
+}
+
+/// A connection opened by [`ConnectConfig::connect`].
+pub(crate) enum Socket {
+    Tcp(TcpStream),
+    #[cfg(unix)]
+    Unix(UnixStream),
+}
+
+impl Socket {
+    pub fn try_clone(&self) -> io::Result<Self> {
+        match self {
+            Socket::Tcp(socket) => socket.try_clone().map(Socket::Tcp),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.try_clone().map(Socket::Unix),
+        }
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Socket::Tcp(socket) => socket.set_read_timeout(timeout),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.set_read_timeout(timeout),
+        }
+    }
+
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Socket::Tcp(socket) => socket.set_write_timeout(timeout),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.set_write_timeout(timeout),
+        }
+    }
+
+    /// Turns off Nagle's algorithm; Unix sockets don't have it.
+    pub fn set_nodelay(&self) -> io::Result<()> {
+        match self {
+            Socket::Tcp(socket) => socket.set_nodelay(true),
+            #[cfg(unix)]
+            Socket::Unix(_) => Ok(()),
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        match self {
+            Socket::Tcp(socket) => socket.set_nonblocking(nonblocking),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.set_nonblocking(nonblocking),
+        }
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        match self {
+            Socket::Tcp(socket) => socket.shutdown(how),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.shutdown(how),
+        }
+    }
+}
+
+impl Read for Socket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Socket::Tcp(socket) => socket.read(buf),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.read(buf),
+        }
+    }
+}
+
+impl Write for Socket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Socket::Tcp(socket) => socket.write(buf),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Socket::Tcp(socket) => socket.flush(),
+            #[cfg(unix)]
+            Socket::Unix(socket) => socket.flush(),
+        }
+    }
+}
+
+/// Connects to `remote`, binding the socket to `local` first if given.
+fn connect_from(
+    local: Option<IpAddr>,
+    remote: SocketAddr,
+    timeout: Duration,
+) -> io::Result<TcpStream> {
+    let socket = socket2::Socket::new(
+        socket2::Domain::for_address(remote),
+        socket2::Type::STREAM,
+        Some(socket2::Protocol::TCP),
+    )?;
+    if let Some(local) = local {
+        socket
+            .bind(&SocketAddr::new(local, 0).into())
+            .map_err(|e| io::Error::new(e.kind(), format!("bind to {}: {}", local, e)))?;
+    }
+
+    if timeout.is_zero() {
+        socket.connect(&remote.into())?;
+    } else {
+        socket.connect_timeout(&remote.into(), timeout)?;
+    }
+    Ok(socket.into())
+}
+
+/// Splits a `unix://` URL into the socket it names and the `http://` URL to
+/// request through it.
+///
+/// The socket is the longest leading part of the path that is a socket on
+/// disk, so `unix:///var/run/docker.sock/v1.43/info` asks the Docker socket
+/// for `/v1.43/info`.
+pub(crate) fn split_unix_url(url: &Url) -> Result<(PathBuf, String), HttpError> {
+    let path = url.path();
+    let socket_end = path
+        .match_indices('/')
+        .map(|(i, _)| i)
+        .chain([path.len()])
+        .rev()
+        .find(|&end| end > 0 && socket::is_socket(Path::new(&path[..end])))
+        .ok_or_else(|| {
+            HttpError::InvalidUrl(format!("{} (no Unix socket found in the path)", url))
+        })?;
+
+    let rest = match &path[socket_end..] {
+        "" => "/",
+        rest => rest,
+    };
+    let query = url.query().map(|q| format!("?{}", q)).unwrap_or_default();
+    Ok((
+        PathBuf::from(&path[..socket_end]),
+        format!("http://localhost{}{}", rest, query),
+    ))
+}
+
+/// Splits on `:` outside of `[...]`, into at most `n` fields.
+fn split_fields(s: &str, n: usize) -> Vec<&str> {
+    let mut fields = Vec::new();
+    let mut start = 0;
+    let mut depth = 0;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ':' if depth == 0 && fields.len() + 1 < n => {
+                fields.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    fields.push(&s[start..]);
+    fields
+}
+
+fn unbracket(s: &str) -> &str {
+    s.strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .unwrap_or(s)
+}
+
+fn parse_ip(s: &str) -> Option<IpAddr> {
+    unbracket(s).parse().ok()
+}
+
+#[cfg(unix)]
+mod socket {
+    use super::{IpVersion, Socket};
+    use crate::HttpError;
+    use std::ffi::CStr;
+    use std::io;
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::UnixStream;
+    use std::path::Path;
+
+    /// The first address of interface `name` that `ip_version` allows.
+    pub(super) fn interface_address(
+        name: &str,
+        ip_version: IpVersion,
+    ) -> Result<IpAddr, HttpError> {
+        let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+        if unsafe { libc::getifaddrs(&mut list) } != 0 {
+            return Err(HttpError::InvalidConnectOption(format!(
+                "can't list network interfaces: {}",
+                io::Error::last_os_error()
+            )));
+        }
+
+        let mut found = Vec::new();
+        let mut entry = list;
+        while !entry.is_null() {
+            // SAFETY: `entry` is a node of the list returned by getifaddrs,
+            // which stays valid until freeifaddrs below.
+            let ifa = unsafe { &*entry };
+            let matches = unsafe { CStr::from_ptr(ifa.ifa_name) }.to_bytes() == name.as_bytes();
+            if matches && !ifa.ifa_addr.is_null() {
+                match i32::from(unsafe { (*ifa.ifa_addr).sa_family }) {
+                    libc::AF_INET => {
+                        let sin = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in) };
+                        found.push(IpAddr::V4(Ipv4Addr::from(
+                            sin.sin_addr.s_addr.to_ne_bytes(),
+                        )));
+                    }
+                    libc::AF_INET6 => {
+                        let sin6 = unsafe { &*(ifa.ifa_addr as *const libc::sockaddr_in6) };
+                        found.push(IpAddr::V6(Ipv6Addr::from(sin6.sin6_addr.s6_addr)));
+                    }
+                    _ => {}
+                }
+            }
+            entry = ifa.ifa_next;
+        }
+        unsafe { libc::freeifaddrs(list) };
+
+        found
+            .into_iter()
+            .find(|ip| ip_version.allows(ip))
+            .ok_or_else(|| {
+                HttpError::InvalidConnectOption(format!(
+                    "'{}' is neither an IP address nor an interface with one",
+                    name
+                ))
+            })
+    }
+
+    /// Connects to the Unix socket at `path`.
+    pub(super) fn connect_unix(path: &Path) -> io::Result<Socket> {
+        UnixStream::connect(path).map(Socket::Unix)
+    }
+
+    pub(super) fn is_socket(path: &Path) -> bool {
+        path.metadata()
+            .is_ok_and(|meta| meta.file_type().is_socket())
+    }
+}
+
+#[cfg(not(unix))]
+mod socket {
+    use super::{IpVersion, Socket};
+    use crate::HttpError;
+    use std::io;
+    use std::net::IpAddr;
+    use std::path::Path;
+
+    pub(super) fn interface_address(name: &str, _: IpVersion) -> Result<IpAddr, HttpError> {
+        Err(HttpError::InvalidConnectOption(format!(
+            "'{}' is not an IP address",
+            name
+        )))
+    }
+
+    pub(super) fn connect_unix(_: &Path) -> io::Result<Socket> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Unix domain sockets are only supported on Unix",
+        ))
+    }
+
+    pub(super) fn is_socket(_: &Path) -> bool {
+        false
+    }
+}
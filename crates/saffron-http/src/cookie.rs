@@ -0,0 +1,408 @@
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use url::Url;
+
+/// The `SameSite` attribute of a cookie. Stored for display only; a
+/// command-line client has no notion of cross-site requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "strict" => Some(SameSite::Strict),
+            "lax" => Some(SameSite::Lax),
+            "none" => Some(SameSite::None),
+            _ => None,
+        }
+    }
+}
+
+/// A cookie as stored by a [`CookieJar`] (RFC 6265 §5.3).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// Lowercase domain without a leading dot.
+    pub domain: String,
+    /// Set when the cookie had no `Domain` attribute: it is only sent to
+    /// `domain` itself, not to its subdomains.
+    pub host_only: bool,
+    pub path: String,
+    /// `None` for session cookies.
+    pub expires: Option<DateTime<Utc>>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: Option<SameSite>,
+    pub created: DateTime<Utc>,
+}
+
+impl Cookie {
+    /// Parses a `Set-Cookie` header value received from `url`, following the
+    /// parsing and storage rules of RFC 6265 §5.2–5.3.
+    ///
+    /// Returns `None` when the cookie must be ignored, for example when its
+    /// `Domain` attribute does not cover the host that set it. There is no
+    /// public suffix list; a `Domain` without a dot (such as `com`) is only
+    /// accepted as the request host itself.
+    pub fn parse(set_cookie: &str, url: &Url) -> Option<Self> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        let now = Utc::now();
+
+        let (pair, attributes) = match set_cookie.split_once(';') {
+            Some((pair, attributes)) => (pair, attributes),
+            None => (set_cookie, ""),
+        };
+
+        let (name, value) = pair.split_once('=')?;
+        let (name, value) = (name.trim(), value.trim());
+        if name.is_empty() || has_control_chars(name) || has_control_chars(value) {
+            return None;
+        }
+
+        let mut expires = None;
+        let mut max_age = None;
+        let mut domain = None;
+        let mut path = None;
+        let mut secure = false;
+        let mut http_only = false;
+        let mut same_site = None;
+
+        for attribute in attributes.split(';') {
+            let (key, value) = match attribute.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (attribute.trim(), ""),
+            };
+
+            match key.to_ascii_lowercase().as_str() {
+                "expires" => {
+                    if let Some(date) = parse_cookie_date(value) {
+                        expires = Some(date);
+                    }
+                }
+                "max-age" => {
+                    if let Some(seconds) = parse_max_age(value) {
+                        max_age = Some(if seconds <= 0 {
+                            DateTime::<Utc>::MIN_UTC
+                        } else {
+                            TimeDelta::try_seconds(seconds)
+                                .and_then(|delta| now.checked_add_signed(delta))
+                                .unwrap_or(DateTime::<Utc>::MAX_UTC)
+                        });
+                    }
+                }
+                "domain" if !value.is_empty() => {
+                    domain = Some(value.trim_start_matches('.').to_ascii_lowercase());
+                }
+                "path" => {
+                    path = value.starts_with('/').then(|| value.to_string());
+                }
+                "secure" => secure = true,
+                "httponly" => http_only = true,
+                "samesite" => same_site = SameSite::parse(value),
+                _ => {}
+            }
+        }
+
+        let (domain, host_only) = match domain {
+            Some(domain) if domain.contains('.') && domain_match(&host, &domain) => (domain, false),
+            // A dotless domain is only allowed for the host itself (`localhost`)
+            Some(domain) if domain == host => (domain, true),
+            Some(_) => return None,
+            None => (host, true),
+        };
+
+        Some(Self {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain,
+            host_only,
+            path: path.unwrap_or_else(|| default_path(url)),
+            // Max-Age takes precedence over Expires
+            expires: max_age.or(expires),
+            secure,
+            http_only,
+            same_site,
+            created: now,
+        })
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= Utc::now())
+    }
+
+    /// Whether this cookie should be sent with a request to `url`.
+    pub fn matches(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_ascii_lowercase();
+
+        let domain_ok = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(&host, &self.domain)
+        };
+
+        domain_ok
+            && path_match(url.path(), &self.path)
+            && (!self.secure || matches!(url.scheme(), "https" | "wss"))
+            && !self.is_expired()
+    }
+}
+
+/// A shared, thread-safe cookie store.
+///
+/// Clones share the same cookies, so a jar handed to
+/// [`HttpClientConfig`](crate::HttpClientConfig) can be inspected or saved
+/// after requests were sent.
+#[derive(Debug, Clone, Default)]
+pub struct CookieJar {
+    cookies: Arc<Mutex<Vec<Cookie>>>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a jar from previously saved cookies, dropping expired ones.
+    pub fn from_cookies(cookies: impl IntoIterator<Item = Cookie>) -> Self {
+        let jar = Self::new();
+        for cookie in cookies {
+            jar.insert(cookie);
+        }
+        jar
+    }
+
+    /// Stores every `Set-Cookie` value of a response received from `url`.
+    pub fn store_response_cookies<'a>(
+        &self,
+        url: &Url,
+        set_cookies: impl IntoIterator<Item = &'a str>,
+    ) {
+        for set_cookie in set_cookies {
+            if let Some(cookie) = Cookie::parse(set_cookie, url) {
+                self.insert(cookie);
+            }
+        }
+    }
+
+    /// Adds `cookie`, replacing one with the same name, domain and path.
+    /// An expired cookie only removes the one it replaces.
+    pub fn insert(&self, mut cookie: Cookie) {
+        let mut cookies = self.lock();
+
+        if let Some(index) = cookies.iter().position(|c| {
+            c.name == cookie.name && c.domain == cookie.domain && c.path == cookie.path
+        }) {
+            // RFC 6265 keeps the creation time of the cookie being replaced
+            cookie.created = cookies.remove(index).created;
+        }
+
+        if !cookie.is_expired() {
+            cookies.push(cookie);
+        }
+    }
+
+    /// The `Cookie` header value for a request to `url`, if any cookie
+    /// matches. Cookies with longer paths come first (RFC 6265 §5.4).
+    pub fn cookie_header(&self, url: &Url) -> Option<String> {
+        let mut cookies = self.lock();
+        cookies.retain(|c| !c.is_expired());
+
+        let mut matching: Vec<&Cookie> = cookies.iter().filter(|c| c.matches(url)).collect();
+        if matching.is_empty() {
+            return None;
+        }
+
+        matching.sort_by(|a, b| {
+            b.path
+                .len()
+                .cmp(&a.path.len())
+                .then(a.created.cmp(&b.created))
+        });
+
+        Some(
+            matching
+                .iter()
+                .map(|c| format!("{}={}", c.name, c.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+
+    /// All unexpired cookies, ordered by domain, path and name.
+    pub fn cookies(&self) -> Vec<Cookie> {
+        let mut cookies: Vec<Cookie> = self
+            .lock()
+            .iter()
+            .filter(|c| !c.is_expired())
+            .cloned()
+            .collect();
+        cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+        cookies
+    }
+
+    /// Removes cookies called `name`, optionally only those for `domain`.
+    /// Returns how many were removed.
+    pub fn remove(&self, name: &str, domain: Option<&str>) -> usize {
+        let domain = domain.map(|d| d.trim_start_matches('.').to_ascii_lowercase());
+        let mut cookies = self.lock();
+        let before = cookies.len();
+        cookies.retain(|c| !(c.name == name && domain.as_ref().is_none_or(|d| *d == c.domain)));
+        before - cookies.len()
+    }
+
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().iter().filter(|c| !c.is_expired()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Cookie>> {
+        // A panic while holding the lock can't leave the list half-updated
+        self.cookies.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn has_control_chars(s: &str) -> bool {
+    s.chars().any(|c| c.is_ascii_control() && c != '\t')
+}
+
+/// RFC 6265 §5.1.3: `host` is `domain` or a subdomain of it. IP addresses
+/// only match themselves.
+fn domain_match(host: &str, domain: &str) -> bool {
+    if host == domain {
+        return true;
+    }
+
+    let is_ip = host.starts_with('[') || host.parse::<IpAddr>().is_ok();
+    !is_ip
+        && host.len() > domain.len()
+        && host.ends_with(domain)
+        && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+}
+
+/// RFC 6265 §5.1.4.
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    request_path == cookie_path
+        || (request_path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/')
+                || request_path.as_bytes().get(cookie_path.len()) == Some(&b'/')))
+}
+
+/// RFC 6265 §5.1.4: the directory of the request path.
+fn default_path(url: &Url) -> String {
+    let path = url.path();
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => path[..index].to_string(),
+    }
+}
+
+fn parse_max_age(value: &str) -> Option<i64> {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    // Overlong values are still valid, just very far away
+    Some(value.parse().unwrap_or(if value.starts_with('-') {
+        i64::MIN
+    } else {
+        i64::MAX
+    }))
+}
+
+/// Parses a cookie date with the lenient algorithm of RFC 6265 §5.1.1, which
+/// accepts the RFC 1123, RFC 850 and asctime formats seen in the wild.
+pub fn parse_cookie_date(value: &str) -> Option<DateTime<Utc>> {
+    let is_delimiter = |c: char| matches!(c, '\t' | ' '..='/' | ';'..='@' | '['..='`' | '{'..='~');
+
+    let mut time = None;
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+
+    for token in value.split(is_delimiter).filter(|t| !t.is_empty()) {
+        if time.is_none()
+            && let Some(parsed) = parse_time(token)
+        {
+            time = Some(parsed);
+        } else if day.is_none()
+            && let Some((d, _)) = leading_digits(token, 1, 2)
+        {
+            day = Some(d);
+        } else if month.is_none()
+            && let Some(m) = parse_month(token)
+        {
+            month = Some(m);
+        } else if year.is_none()
+            && let Some((y, _)) = leading_digits(token, 2, 4)
+        {
+            year = Some(y);
+        }
+    }
+
+    let (hour, minute, second) = time?;
+    let year = match year? {
+        y @ 70..=99 => y + 1900,
+        y @ 0..=69 => y + 2000,
+        y => y,
+    };
+
+    if year < 1601 || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+
+    NaiveDate::from_ymd_opt(year as i32, month?, day?)?
+        .and_hms_opt(hour, minute, second)
+        .map(|naive| naive.and_utc())
+}
+
+/// `hms-time` from RFC 6265 §5.1.1, e.g. `08:49:37`.
+fn parse_time(token: &str) -> Option<(u32, u32, u32)> {
+    let (hour, rest) = leading_digits(token, 1, 2)?;
+    let (minute, rest) = leading_digits(rest.strip_prefix(':')?, 1, 2)?;
+    let (second, _) = leading_digits(rest.strip_prefix(':')?, 1, 2)?;
+    Some((hour, minute, second))
+}
+
+fn parse_month(token: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let prefix = token.get(..3)?.to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|m| *m == prefix)
+        .map(|i| i as u32 + 1)
+}
+
+/// Reads `min..=max` leading digits that are not followed by another digit.
+fn leading_digits(token: &str, min: usize, max: usize) -> Option<(u32, &str)> {
+    let count = token.bytes().take_while(u8::is_ascii_digit).count();
+    if count < min || count > max {
+        return None;
+    }
+    Some((token[..count].parse().ok()?, &token[count..]))
+}
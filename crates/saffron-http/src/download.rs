@@ -0,0 +1,247 @@
+use crate::compression::DecodedBody;
+use crate::transport::TransportResponse;
+use crate::{HttpClient, HttpError};
+use saffron_core::domain::request::HttpRequest;
+use saffron_core::domain::response::HttpResponse;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::time::Instant;
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How much of a response body has been received so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub downloaded: u64,
+    /// Expected size from `Content-Length`, when the server sent one.
+    pub total: Option<u64>,
+}
+
+impl DownloadProgress {
+    pub fn fraction(&self) -> Option<f64> {
+        self.total
+            .filter(|&total| total > 0)
+            .map(|total| (self.downloaded as f64 / total as f64).min(1.0))
+    }
+}
+
+impl HttpClient {
+    /// Sends `request` and writes the response body to `writer` as it arrives
+    /// instead of buffering it.
+    ///
+    /// `on_progress` is called after every chunk. The returned response has an
+    /// empty body. Bodies larger than `max_response_size` fail with
+    /// [`HttpError::ResponseTooLarge`].
+    pub fn send_streaming<W: Write + ?Sized>(
+        &self,
+        request: &HttpRequest,
+        writer: &mut W,
+        mut on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<HttpResponse, HttpError> {
+        self.with_middleware_chain(request, &mut |request| {
+            let start = Instant::now();
+            let exchange = self.execute_with_retry(request)?;
+            let mut response = exchange.head(start.elapsed());
+
+            let download_start = Instant::now();
+            self.stream_body(exchange.resp, writer, 0, &mut on_progress)?
+                .apply_to(&mut response);
+            writer.flush()?;
+            response.timings.download = download_start.elapsed();
+
+            Ok(response)
+        })
+    }
+
+    /// Downloads the response body of `request` into the file at `path`.
+    ///
+    /// With `resume`, an existing partial file is continued with a `Range`
+    /// request; servers that ignore the range get the file rewritten from the
+    /// start. Non-2xx responses leave the file untouched and are returned
+    /// with their body in memory.
+    pub fn download_to_file(
+        &self,
+        request: &HttpRequest,
+        path: &Path,
+        resume: bool,
+        mut on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<HttpResponse, HttpError> {
+        self.with_middleware_chain(request, &mut |request| {
+            self.download(request, path, resume, &mut on_progress)
+        })
+    }
+
+    fn download(
+        &self,
+        request: &HttpRequest,
+        path: &Path,
+        resume: bool,
+        on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<HttpResponse, HttpError> {
+        let offset = match fs::metadata(path) {
+            Ok(metadata) if resume => metadata.len(),
+            _ => 0,
+        };
+
+        let ranged;
+        let request = if offset > 0 {
+            // Ranges count encoded bytes, so only an unencoded body can be resumed
+            let mut resumed = request
+                .clone()
+                .with_header("Range", format!("bytes={}-", offset));
+            if resumed.get_header("Accept-Encoding").is_none() {
+                resumed.add_header("Accept-Encoding", "identity");
+            }
+            ranged = resumed;
+            &ranged
+        } else {
+            request
+        };
+
+        let start = Instant::now();
+        let exchange = self.execute_with_retry(request)?;
+        let status = exchange.resp.status;
+
+        // 416 on a resumed download means there is nothing left to fetch
+        if status == 416 && offset > 0 {
+            let response = exchange.head(start.elapsed());
+            let mut body = exchange.resp.body;
+            io::copy(&mut body, &mut io::sink())?;
+            return Ok(response);
+        }
+
+        if !(200..300).contains(&status) {
+            return self.extract_response(exchange, start);
+        }
+
+        let resumed = offset > 0 && status == 206;
+        let mut file = if resumed {
+            OpenOptions::new().append(true).open(path)?
+        } else {
+            File::create(path)?
+        };
+
+        let mut response = exchange.head(start.elapsed());
+
+        let download_start = Instant::now();
+        self.stream_body(
+            exchange.resp,
+            &mut file,
+            if resumed { offset } else { 0 },
+            on_progress,
+        )?
+        .apply_to(&mut response);
+        file.flush()?;
+        response.timings.download = download_start.elapsed();
+
+        Ok(response)
+    }
+
+    /// Copies the body of `resp` into `writer` in chunks, decoding its
+    /// `Content-Encoding` and enforcing `max_response_size` on the decoded
+    /// size. Progress counts bytes received; `already_downloaded` is added to
+    /// it for resumed downloads.
+    pub(crate) fn stream_body<W: Write + ?Sized>(
+        &self,
+        resp: TransportResponse,
+        writer: &mut W,
+        already_downloaded: u64,
+        mut on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<BodyStats, HttpError> {
+        let limit = self.config.max_response_size;
+        let content_length = resp
+            .headers
+            .get("content-length")
+            .and_then(|len| len.trim().parse::<u64>().ok());
+        let content_encoding = resp.headers.get("content-encoding").map(str::to_string);
+
+        let mut progress = DownloadProgress {
+            downloaded: already_downloaded,
+            total: content_length.map(|len| len + already_downloaded),
+        };
+        on_progress(progress);
+
+        let mut body = DecodedBody::new(
+            resp.body,
+            content_encoding.as_deref(),
+            self.config.decompress,
+        )
+        .map_err(|e| {
+            if is_corrupt(&e) {
+                decode_error(&content_encoding, e)
+            } else {
+                e.into()
+            }
+        })?;
+
+        // The declared length only bounds the decoded size without an encoding
+        if body.encoding.is_none()
+            && let (Some(limit), Some(len)) = (limit, content_length)
+            && len > limit as u64
+        {
+            return Err(HttpError::ResponseTooLarge(limit));
+        }
+
+        let mut buf = vec![0; CHUNK_SIZE];
+        let mut decoded = 0u64;
+
+        loop {
+            let n = match body.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) if body.encoding.is_some() && is_corrupt(&e) => {
+                    return Err(decode_error(&body.encoding, e));
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            decoded += n as u64;
+            if let Some(limit) = limit
+                && decoded > limit as u64
+            {
+                return Err(HttpError::ResponseTooLarge(limit));
+            }
+
+            writer.write_all(&buf[..n])?;
+            progress.downloaded = already_downloaded + body.raw_bytes();
+            on_progress(progress);
+        }
+
+        Ok(BodyStats {
+            raw_size: body.raw_bytes(),
+            decoded_size: decoded,
+            encoding: body.encoding,
+        })
+    }
+}
+
+/// Sizes of a response body read by [`HttpClient::stream_body`].
+pub(crate) struct BodyStats {
+    raw_size: u64,
+    decoded_size: u64,
+    encoding: Option<String>,
+}
+
+impl BodyStats {
+    pub(crate) fn apply_to(self, response: &mut HttpResponse) {
+        response.raw_size = self.raw_size;
+        response.decoded_size = self.decoded_size;
+        response.decoded_encoding = self.encoding;
+    }
+}
+
+fn is_corrupt(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput | io::ErrorKind::UnexpectedEof
+    )
+}
+
+fn decode_error(encoding: &Option<String>, error: io::Error) -> HttpError {
+    HttpError::DecodeError(
+        encoding.clone().unwrap_or_else(|| "encoded".to_string()),
+        error.to_string(),
+    )
+}
@@ -0,0 +1,211 @@
+use crate::HttpError;
+use crate::transport::{Transport, TransportResponse};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::HttpRequest;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// A [`Transport`] that records exchanges to a JSON fixture file and replays
+/// them later, so tests written against a real server can run offline.
+///
+/// Requests are matched on method and URL. Recorded responses for the same
+/// request are replayed in order, and the last one keeps answering once the
+/// others are used up.
+pub struct FixtureTransport {
+    path: PathBuf,
+    recorder: Option<Box<dyn Transport>>,
+    state: Mutex<FixtureState>,
+}
+
+#[derive(Default)]
+struct FixtureState {
+    fixture: Fixture,
+    used: Vec<bool>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Fixture {
+    exchanges: Vec<RecordedExchange>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedExchange {
+    method: String,
+    url: String,
+    response: RecordedResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordedResponse {
+    status: u16,
+    status_text: String,
+    headers: Headers,
+    /// The body as text, when it is valid UTF-8.
+    #[serde(default)]
+    body: String,
+    /// The body for anything else, such as compressed or binary responses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl RecordedResponse {
+    fn new(response: &TransportResponse, body: Vec<u8>) -> Self {
+        let (body, body_base64) = match String::from_utf8(body) {
+            Ok(text) => (text, None),
+            Err(e) => (String::new(), Some(BASE64.encode(e.into_bytes()))),
+        };
+
+        Self {
+            status: response.status,
+            status_text: response.status_text.clone(),
+            headers: response.headers.clone(),
+            body,
+            body_base64,
+        }
+    }
+
+    fn to_response(&self, url: &str) -> Result<TransportResponse, HttpError> {
+        let body = match &self.body_base64 {
+            Some(encoded) => BASE64
+                .decode(encoded)
+                .map_err(|e| HttpError::RequestFailed(format!("invalid fixture body: {}", e)))?,
+            None => self.body.clone().into_bytes(),
+        };
+
+        Ok(
+            TransportResponse::new(self.status, self.status_text.clone(), url)
+                .with_headers(self.headers.clone())
+                .with_body(body),
+        )
+    }
+}
+
+impl FixtureTransport {
+    /// Sends requests through `inner` and writes every exchange to `path`,
+    /// replacing any fixture already there.
+    pub fn record(inner: impl Transport + 'static, path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            recorder: Some(Box::new(inner)),
+            state: Mutex::default(),
+        }
+    }
+
+    /// Answers requests from the fixture at `path` without touching the
+    /// network. Requests that weren't recorded fail with
+    /// [`HttpError::RequestFailed`].
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self, HttpError> {
+        let path = path.into();
+        let fixture = load(&path)?;
+
+        Ok(Self {
+            state: Mutex::new(FixtureState {
+                used: vec![false; fixture.exchanges.len()],
+                fixture,
+            }),
+            path,
+            recorder: None,
+        })
+    }
+
+    /// Replays the fixture at `path` if it exists, and records it through
+    /// `inner` otherwise.
+    pub fn replay_or_record(
+        inner: impl Transport + 'static,
+        path: impl Into<PathBuf>,
+    ) -> Result<Self, HttpError> {
+        let path = path.into();
+        if path.exists() {
+            Self::replay(path)
+        } else {
+            Ok(Self::record(inner, path))
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn lock(&self) -> MutexGuard<'_, FixtureState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Transport for FixtureTransport {
+    fn send(&self, request: &HttpRequest) -> Result<TransportResponse, HttpError> {
+        let method = request.method.as_str();
+
+        let Some(inner) = &self.recorder else {
+            let mut state = self.lock();
+            let exchanges = &state.fixture.exchanges;
+            let matches = |e: &RecordedExchange| e.method == method && e.url == request.url;
+
+            let index = (0..exchanges.len())
+                .find(|&i| matches(&exchanges[i]) && !state.used[i])
+                .or_else(|| exchanges.iter().rposition(matches));
+            let Some(index) = index else {
+                return Err(HttpError::RequestFailed(format!(
+                    "no recorded response for {} {} in {}",
+                    method,
+                    request.url,
+                    self.path.display()
+                )));
+            };
+
+            state.used[index] = true;
+            return state.fixture.exchanges[index]
+                .response
+                .to_response(&request.url);
+        };
+
+        let mut response = inner.send(request)?;
+        let mut body = Vec::new();
+        response.body.read_to_end(&mut body)?;
+
+        let mut state = self.lock();
+        state.fixture.exchanges.push(RecordedExchange {
+            method: method.to_string(),
+            url: request.url.clone(),
+            response: RecordedResponse::new(&response, body.clone()),
+        });
+        save(&self.path, &state.fixture)?;
+
+        Ok(response.with_body(body))
+    }
+}
+
+fn load(path: &Path) -> Result<Fixture, HttpError> {
+    let content = fs::read_to_string(path)?;
+    serde_json::from_str(&content)
+        .map_err(|e| HttpError::RequestFailed(format!("invalid fixture {}: {}", path.display(), e)))
+}
+
+/// Writes the fixture next to `path` and renames it into place, so a test
+/// stopped mid-write leaves the previous fixture rather than a truncated one.
+fn save(path: &Path, fixture: &Fixture) -> Result<(), HttpError> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let content = serde_json::to_string_pretty(fixture)
+        .map_err(|e| HttpError::RequestFailed(format!("failed to write fixture: {}", e)))?;
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(format!(".{}.tmp", std::process::id()));
+    let temp = PathBuf::from(temp);
+    if let Err(e) = fs::write(&temp, content).and_then(|_| fs::rename(&temp, path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,428 @@
+use crate::{HttpClient, HttpError};
+use saffron_core::domain::request::{HttpMethod, HttpRequest, RequestBody};
+use saffron_core::domain::response::HttpResponse;
+use serde_json::Value;
+use std::fmt;
+
+/// The standard introspection query, as sent by GraphiQL and most tools.
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType {
+                kind
+                name
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Scalars every schema has, which SDL leaves out.
+const BUILTIN_SCALARS: [&str; 5] = ["String", "Int", "Float", "Boolean", "ID"];
+
+/// Directives every schema has, which SDL leaves out.
+const BUILTIN_DIRECTIVES: [&str; 5] = ["skip", "include", "deprecated", "specifiedBy", "oneOf"];
+
+const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// One entry of the `errors` array of a GraphQL response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphQLError {
+    pub message: String,
+    /// Line and column in the query, both starting at 1.
+    pub locations: Vec<(u64, u64)>,
+    /// The response field the error belongs to, e.g. `user.friends.0.name`.
+    pub path: Vec<String>,
+}
+
+impl GraphQLError {
+    /// The errors of a GraphQL response body. Empty when there are none or
+    /// the body isn't a JSON object.
+    pub fn from_response(response: &HttpResponse) -> Vec<GraphQLError> {
+        let Ok(json) = serde_json::from_slice::<Value>(&response.body) else {
+            return Vec::new();
+        };
+        let Some(errors) = json.get("errors").and_then(Value::as_array) else {
+            return Vec::new();
+        };
+
+        errors
+            .iter()
+            .map(|error| GraphQLError {
+                message: match &error["message"] {
+                    Value::String(message) => message.clone(),
+                    Value::Null => "(no message)".to_string(),
+                    other => other.to_string(),
+                },
+                locations: error["locations"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|l| Some((l["line"].as_u64()?, l["column"].as_u64()?)))
+                    .collect(),
+                path: error["path"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|p| match p {
+                        Value::String(field) => field.clone(),
+                        index => index.to_string(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if !self.path.is_empty() {
+            write!(f, " at {}", self.path.join("."))?;
+        }
+        for (line, column) in &self.locations {
+            write!(f, " (line {}, column {})", line, column)?;
+        }
+        Ok(())
+    }
+}
+
+impl HttpClient {
+    /// Runs the introspection query against the GraphQL endpoint of
+    /// `request` and returns the schema in SDL.
+    ///
+    /// The request's headers, auth and URL are kept; its method and body are
+    /// replaced. Fails with [`HttpError::SchemaError`] when the server
+    /// answers with an error status or GraphQL errors, which is usually the
+    /// case when introspection is turned off.
+    pub fn introspect(&self, request: &HttpRequest) -> Result<String, HttpError> {
+        let mut request = request.clone();
+        request.method = HttpMethod::Post;
+        request.body = RequestBody::GraphQL {
+            query: INTROSPECTION_QUERY.to_string(),
+            variables: None,
+            operation_name: Some("IntrospectionQuery".to_string()),
+        };
+
+        let response = self.send(&request)?;
+        let errors = GraphQLError::from_response(&response);
+        if !errors.is_empty() {
+            let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            return Err(HttpError::SchemaError(messages.join("; ")));
+        }
+        if !response.is_success() {
+            return Err(HttpError::SchemaError(format!(
+                "server responded {} {}",
+                response.status, response.status_text
+            )));
+        }
+
+        let body = String::from_utf8_lossy(&response.body);
+        schema_to_sdl(&body)
+    }
+}
+
+/// Prints the result of the introspection query as SDL. Takes the whole
+/// response (`{"data": {"__schema": ...}}`) or just its `data`.
+///
+/// Built-in scalars and directives are left out, and the `schema` block only
+/// appears when the root types aren't named `Query`, `Mutation` and
+/// `Subscription`.
+pub fn schema_to_sdl(introspection: &str) -> Result<String, HttpError> {
+    let json: Value = serde_json::from_str(introspection)
+        .map_err(|e| HttpError::SchemaError(format!("invalid JSON: {}", e)))?;
+    let schema = json
+        .pointer("/data/__schema")
+        .or_else(|| json.get("__schema"))
+        .ok_or_else(|| invalid("no __schema in the response"))?;
+
+    let mut blocks = Vec::new();
+
+    let roots = [
+        ("query", "queryType", "Query"),
+        ("mutation", "mutationType", "Mutation"),
+        ("subscription", "subscriptionType", "Subscription"),
+    ];
+    let mut custom_roots = false;
+    let mut root_fields = Vec::new();
+    for (operation, key, conventional) in roots {
+        if let Some(name) = schema[key]["name"].as_str() {
+            custom_roots |= name != conventional;
+            root_fields.push(format!("  {}: {}", operation, name));
+        }
+    }
+    if custom_roots {
+        blocks.push(format!("schema {{\n{}\n}}", root_fields.join("\n")));
+    }
+
+    for directive in list(&schema["directives"]) {
+        let name = string(&directive["name"])?;
+        if BUILTIN_DIRECTIVES.contains(&name) {
+            continue;
+        }
+        let locations: Vec<&str> = list(&directive["locations"])
+            .filter_map(Value::as_str)
+            .collect();
+        blocks.push(format!(
+            "{}directive @{}{} on {}",
+            description(&directive["description"], ""),
+            name,
+            arguments(&directive["args"], "")?,
+            locations.join(" | ")
+        ));
+    }
+
+    for ty in list(&schema["types"]) {
+        let name = string(&ty["name"])?;
+        if name.starts_with("__") || BUILTIN_SCALARS.contains(&name) {
+            continue;
+        }
+        blocks.push(format!(
+            "{}{}",
+            description(&ty["description"], ""),
+            type_definition(ty, name)?
+        ));
+    }
+
+    Ok(blocks.join("\n\n") + "\n")
+}
+
+fn type_definition(ty: &Value, name: &str) -> Result<String, HttpError> {
+    let kind = string(&ty["kind"])?;
+    Ok(match kind {
+        "SCALAR" => format!("scalar {}", name),
+        "OBJECT" | "INTERFACE" => {
+            let keyword = if kind == "OBJECT" {
+                "type"
+            } else {
+                "interface"
+            };
+            let mut fields = Vec::new();
+            for field in list(&ty["fields"]) {
+                fields.push(format!(
+                    "{}  {}{}: {}{}",
+                    description(&field["description"], "  "),
+                    string(&field["name"])?,
+                    arguments(&field["args"], "  ")?,
+                    type_ref(&field["type"])?,
+                    deprecation(field)
+                ));
+            }
+            format!(
+                "{} {}{}{}",
+                keyword,
+                name,
+                implements(&ty["interfaces"])?,
+                body(&fields)
+            )
+        }
+        "UNION" => {
+            let members = list(&ty["possibleTypes"])
+                .map(type_ref)
+                .collect::<Result<Vec<_>, _>>()?;
+            format!("union {} = {}", name, members.join(" | "))
+        }
+        "ENUM" => {
+            let mut values = Vec::new();
+            for value in list(&ty["enumValues"]) {
+                values.push(format!(
+                    "{}  {}{}",
+                    description(&value["description"], "  "),
+                    string(&value["name"])?,
+                    deprecation(value)
+                ));
+            }
+            format!("enum {}{}", name, body(&values))
+        }
+        "INPUT_OBJECT" => {
+            let mut fields = Vec::new();
+            for field in list(&ty["inputFields"]) {
+                fields.push(format!(
+                    "{}  {}",
+                    description(&field["description"], "  "),
+                    input_value(field)?
+                ));
+            }
+            format!("input {}{}", name, body(&fields))
+        }
+        other => return Err(invalid(&format!("unknown type kind {}", other))),
+    })
+}
+
+/// ` { ... }` around fields, or nothing for a type without any.
+fn body(lines: &[String]) -> String {
+    if lines.is_empty() {
+        String::new()
+    } else {
+        format!(" {{\n{}\n}}", lines.join("\n"))
+    }
+}
+
+fn implements(interfaces: &Value) -> Result<String, HttpError> {
+    let names = list(interfaces)
+        .map(type_ref)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(if names.is_empty() {
+        String::new()
+    } else {
+        format!(" implements {}", names.join(" & "))
+    })
+}
+
+/// `(a: Int, b: String = "x")`, one per line when any has a description.
+fn arguments(args: &Value, indent: &str) -> Result<String, HttpError> {
+    let args: Vec<&Value> = list(args).collect();
+    if args.is_empty() {
+        return Ok(String::new());
+    }
+
+    if args.iter().all(|arg| arg["description"].is_null()) {
+        let args = args
+            .into_iter()
+            .map(input_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(format!("({})", args.join(", ")));
+    }
+
+    let inner = format!("{}  ", indent);
+    let mut lines = Vec::new();
+    for arg in args {
+        lines.push(format!(
+            "{}{}{}",
+            description(&arg["description"], &inner),
+            inner,
+            input_value(arg)?
+        ));
+    }
+    Ok(format!("(\n{}\n{})", lines.join("\n"), indent))
+}
+
+/// `name: Type = default`, for arguments and input fields.
+fn input_value(value: &Value) -> Result<String, HttpError> {
+    let mut printed = format!("{}: {}", string(&value["name"])?, type_ref(&value["type"])?);
+    if let Some(default) = value["defaultValue"].as_str() {
+        printed.push_str(&format!(" = {}", default));
+    }
+    Ok(printed)
+}
+
+fn type_ref(value: &Value) -> Result<String, HttpError> {
+    match value["kind"].as_str() {
+        Some("NON_NULL") => Ok(format!("{}!", type_ref(&value["ofType"])?)),
+        Some("LIST") => Ok(format!("[{}]", type_ref(&value["ofType"])?)),
+        _ => Ok(string(&value["name"])?.to_string()),
+    }
+}
+
+fn deprecation(value: &Value) -> String {
+    if value["isDeprecated"].as_bool() != Some(true) {
+        return String::new();
+    }
+    match value["deprecationReason"].as_str() {
+        Some(reason) if reason != DEFAULT_DEPRECATION_REASON => {
+            format!(" @deprecated(reason: {})", Value::from(reason))
+        }
+        _ => " @deprecated".to_string(),
+    }
+}
+
+/// A description as a block string on the lines before a definition.
+fn description(value: &Value, indent: &str) -> String {
+    let Some(text) = value.as_str().filter(|t| !t.is_empty()) else {
+        return String::new();
+    };
+    let text = text.replace("\"\"\"", "\\\"\"\"");
+    if text.contains('\n') {
+        let lines: Vec<String> = text
+            .lines()
+            .map(|line| match line {
+                "" => String::new(),
+                line => format!("{}{}", indent, line),
+            })
+            .collect();
+        format!("{0}\"\"\"\n{1}\n{0}\"\"\"\n", indent, lines.join("\n"))
+    } else {
+        format!("{}\"\"\"{}\"\"\"\n", indent, text)
+    }
+}
+
+fn list(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+fn string(value: &Value) -> Result<&str, HttpError> {
+    value
+        .as_str()
+        .ok_or_else(|| invalid(&format!("expected a string, got {}", value)))
+}
+
+fn invalid(message: &str) -> HttpError {
+    HttpError::SchemaError(message.to_string())
+}
@@ -0,0 +1,554 @@
+//! gRPC calls over HTTP/2, and gRPC-Web calls over HTTP/1.1.
+//!
+//! Messages are passed in and out already encoded, so any protobuf codec
+//! can be used; `saffron_data::proto`, behind its `proto` feature, converts
+//! them from and to JSON.
+//! HTTP/2 is spoken with `h2`, on a single-threaded Tokio runtime that
+//! each call owns.
+
+use crate::connect::Socket;
+use crate::{HttpClient, HttpError};
+use bytes::Bytes;
+use h2::RecvStream;
+use h2::client::SendRequest;
+use percent_encoding::percent_decode_str;
+use rustls::pki_types::ServerName;
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::{HttpMethod, HttpRequest, RequestBody};
+use std::fmt;
+use std::io::Read;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::runtime::Runtime;
+use url::Url;
+
+/// The stream and connection flow-control windows advertised to servers,
+/// large enough that a response is rarely held up waiting for an update.
+const WINDOW_SIZE: u32 = 1 << 24;
+
+/// Request headers that belong to HTTP/1.1 connections and must not be
+/// sent over HTTP/2.
+const CONNECTION_HEADERS: &[&str] = &[
+    "connection",
+    "content-length",
+    "host",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// The outcome of a call, from the `grpc-status` and `grpc-message`
+/// trailers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrpcStatus {
+    pub code: u32,
+    pub message: String,
+}
+
+impl GrpcStatus {
+    pub fn new(code: u32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.code == 0
+    }
+
+    /// The name of the status code, such as `NOT_FOUND`.
+    pub fn code_name(&self) -> &'static str {
+        match self.code {
+            0 => "OK",
+            1 => "CANCELLED",
+            2 => "UNKNOWN",
+            3 => "INVALID_ARGUMENT",
+            4 => "DEADLINE_EXCEEDED",
+            5 => "NOT_FOUND",
+            6 => "ALREADY_EXISTS",
+            7 => "PERMISSION_DENIED",
+            8 => "RESOURCE_EXHAUSTED",
+            9 => "FAILED_PRECONDITION",
+            10 => "ABORTED",
+            11 => "OUT_OF_RANGE",
+            12 => "UNIMPLEMENTED",
+            13 => "INTERNAL",
+            14 => "UNAVAILABLE",
+            15 => "DATA_LOSS",
+            16 => "UNAUTHENTICATED",
+            _ => "UNKNOWN",
+        }
+    }
+
+    fn from_headers(headers: &Headers) -> Option<Self> {
+        let code = headers.get("grpc-status")?.trim().parse().unwrap_or(2);
+        let message = headers
+            .get("grpc-message")
+            .map(|m| percent_decode_str(m).decode_utf8_lossy().into_owned())
+            .unwrap_or_default();
+        Some(Self { code, message })
+    }
+
+    /// The status for a response that isn't gRPC, as the gRPC spec maps
+    /// HTTP status codes.
+    fn from_http_status(status: u16) -> Self {
+        let code = match status {
+            400 => 13,
+            401 => 16,
+            403 => 7,
+            404 => 12,
+            429 | 502..=504 => 14,
+            _ => 2,
+        };
+        Self::new(code, format!("HTTP status {}", status))
+    }
+}
+
+impl fmt::Display for GrpcStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.code_name(), self.code)?;
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// A call in progress, from [`HttpClient::grpc`] or
+/// [`HttpClient::grpc_web`]. Response messages are read one at a time, as
+/// the server streams them; iterating yields each message and then, if the
+/// call failed, its status as [`HttpError::GrpcError`].
+pub struct GrpcCall {
+    body: ResponseBody,
+    headers: Headers,
+    trailers: Headers,
+    status: Option<GrpcStatus>,
+    /// Data received but not yet split into messages.
+    buffer: Vec<u8>,
+    max_message_size: Option<usize>,
+    /// Set once iteration has yielded an error.
+    failed: bool,
+}
+
+/// Where the response messages of a call are read from.
+enum ResponseBody {
+    Http2 {
+        /// Drives the connection while the call waits on the stream.
+        runtime: Runtime,
+        stream: RecvStream,
+        /// The longest wait for the next piece of the response.
+        timeout: Duration,
+    },
+    /// A gRPC-Web response body, which carries the trailers in a frame of
+    /// its own after the messages.
+    Web(Box<dyn Read + Send>),
+}
+
+impl HttpClient {
+    /// Calls a gRPC method with one encoded request message.
+    ///
+    /// The URL of `request` is the server address followed by the method
+    /// path, such as `http://localhost:50051/shop.v1.Orders/GetOrder`.
+    /// `http://` connects without TLS (h2c with prior knowledge) and
+    /// `https://` negotiates HTTP/2 with ALPN. The request's headers and
+    /// auth are sent as metadata; its method and body are ignored. The
+    /// client's TLS, proxy and connection settings apply, and its timeout
+    /// limits each read.
+    pub fn grpc(&self, request: &HttpRequest, message: &[u8]) -> Result<GrpcCall, HttpError> {
+        let request = self.authorize_upfront(request)?;
+        let url = grpc_url(&request.url)?;
+        let secure = url.scheme() == "https";
+        let host = url
+            .host_str()
+            .ok_or_else(|| HttpError::InvalidUrl(format!("{} (missing host)", request.url)))?;
+        let port = url
+            .port_or_known_default()
+            .unwrap_or(if secure { 443 } else { 80 });
+
+        let timeout = Duration::from_secs(self.config.timeout_seconds);
+        // h2c can't be forwarded by an HTTP proxy, so it is tunnelled too
+        let proxy = self.config.proxy.proxy_for(&url);
+        let socket = self
+            .config
+            .connect
+            .connect_through(proxy, host, port, timeout, None)?
+            .into_inner();
+        socket.set_nodelay()?;
+        socket.set_nonblocking(true)?;
+
+        let server_name = host.trim_start_matches('[').trim_end_matches(']');
+        let tls = if secure {
+            let name = ServerName::try_from(server_name.to_string())
+                .map_err(|e| HttpError::InvalidUrl(format!("{} ({})", request.url, e)))?;
+            let mut tls = (*self.config.tls.build()?).clone();
+            tls.alpn_protocols = vec![b"h2".to_vec()];
+            Some((name, Arc::new(tls)))
+        } else {
+            None
+        };
+
+        let mut target = url.path().to_string();
+        if let Some(query) = url.query() {
+            target.push('?');
+            target.push_str(query);
+        }
+        let authority = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+
+        let mut head = http::Request::builder()
+            .method(http::Method::POST)
+            .uri(format!("{}://{}{}", url.scheme(), authority, target))
+            .header("content-type", "application/grpc")
+            .header("te", "trailers");
+        if let Some(ua) = &self.config.user_agent
+            && request.get_header("User-Agent").is_none()
+        {
+            head = head.header("user-agent", ua.as_str());
+        }
+        for header in &request.headers {
+            let name = header.name.to_ascii_lowercase();
+            if !CONNECTION_HEADERS.contains(&name.as_str()) && !name.starts_with(':') {
+                head = head.header(name, header.value.as_str());
+            }
+        }
+        let head = head
+            .body(())
+            .map_err(|e| HttpError::Http2Error(format!("invalid request metadata: {}", e)))?;
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .enable_time()
+            .build()?;
+        let response = runtime.block_on(async {
+            let exchange = async {
+                let socket: Box<dyn Io> = match socket {
+                    Socket::Tcp(socket) => Box::new(tokio::net::TcpStream::from_std(socket)?),
+                    #[cfg(unix)]
+                    Socket::Unix(socket) => Box::new(tokio::net::UnixStream::from_std(socket)?),
+                };
+                let mut sender = match tls {
+                    Some((name, config)) => {
+                        let stream = tokio_rustls::TlsConnector::from(config)
+                            .connect(name, socket)
+                            .await
+                            .map_err(|e| crate::websocket::tls_error(e.into(), server_name))?;
+                        if stream.get_ref().1.alpn_protocol() != Some(b"h2") {
+                            return Err(HttpError::Http2Error(format!(
+                                "{} does not support HTTP/2",
+                                server_name
+                            )));
+                        }
+                        handshake(stream).await?
+                    }
+                    None => handshake(socket).await?,
+                };
+
+                let (response, mut send) = sender.send_request(head, false).map_err(h2_error)?;
+                send.send_data(Bytes::from(frame(message)), true)
+                    .map_err(h2_error)?;
+                response.await.map_err(h2_error)
+            };
+            tokio::time::timeout(timeout, exchange)
+                .await
+                .map_err(|_| HttpError::Timeout)?
+        })?;
+
+        let (parts, stream) = response.into_parts();
+        let ended = stream.is_end_stream();
+        let body = ResponseBody::Http2 {
+            runtime,
+            stream,
+            timeout,
+        };
+        self.start_call(
+            body,
+            parts.status.as_u16(),
+            to_headers(&parts.headers),
+            ended,
+        )
+    }
+
+    /// Calls a gRPC method over gRPC-Web, for servers and proxies that
+    /// speak it over HTTP/1.1, such as Envoy. The URL is the same as for
+    /// [`grpc`](Self::grpc); the request is an ordinary POST, so all of
+    /// the client's settings apply, and its timeout limits each read.
+    pub fn grpc_web(&self, request: &HttpRequest, message: &[u8]) -> Result<GrpcCall, HttpError> {
+        grpc_url(&request.url)?;
+        let mut request = request.clone();
+        request.method = HttpMethod::Post;
+        request
+            .headers
+            .insert("Content-Type", "application/grpc-web+proto");
+        request
+            .headers
+            .insert("Accept", "application/grpc-web+proto");
+        request.headers.insert("X-Grpc-Web", "1");
+        // The protocol frames the body itself; a content coding would have
+        // to be undone before the trailers could be found
+        request.headers.insert("Accept-Encoding", "identity");
+        request.body = RequestBody::Binary(frame(message));
+
+        let resp = self.execute_with_retry(&request)?.resp;
+        // A call that fails straight away sends its status in the headers
+        let ended = resp.headers.contains("grpc-status");
+        self.start_call(
+            ResponseBody::Web(resp.body),
+            resp.status,
+            resp.headers,
+            ended,
+        )
+    }
+
+    /// Makes a unary call and returns the response message.
+    pub fn grpc_unary(&self, request: &HttpRequest, message: &[u8]) -> Result<Vec<u8>, HttpError> {
+        let mut call = self.grpc(request, message)?;
+        let response = call.next_message()?.ok_or_else(|| {
+            HttpError::Http2Error("the call ended without a response message".to_string())
+        })?;
+        // Reads the trailers, which may still report a failure
+        while call.next_message()?.is_some() {}
+        Ok(response)
+    }
+
+    /// Checks the response headers of a call. `ended` is set when they
+    /// are all the server sent.
+    fn start_call(
+        &self,
+        body: ResponseBody,
+        status: u16,
+        headers: Headers,
+        ended: bool,
+    ) -> Result<GrpcCall, HttpError> {
+        if status != 200 {
+            return Err(HttpError::GrpcError(GrpcStatus::from_http_status(status)));
+        }
+        let content_type = headers.get("content-type").unwrap_or_default();
+        if !content_type.starts_with("application/grpc") {
+            return Err(HttpError::Http2Error(format!(
+                "expected a gRPC response, got content type '{}'",
+                content_type
+            )));
+        }
+
+        let mut call = GrpcCall {
+            body,
+            headers: Headers::new(),
+            trailers: Headers::new(),
+            status: None,
+            buffer: Vec::new(),
+            max_message_size: self.config.max_response_size,
+            failed: false,
+        };
+        // A failed call may send its status with the headers, and no body
+        if ended {
+            call.finish(headers.clone())?;
+        }
+        call.headers = headers;
+        Ok(call)
+    }
+}
+
+impl GrpcCall {
+    /// The response headers (the initial metadata).
+    pub fn headers(&self) -> &Headers {
+        &self.headers
+    }
+
+    /// The trailing metadata, once the call has finished.
+    pub fn trailers(&self) -> &Headers {
+        &self.trailers
+    }
+
+    /// The status of the call, once it has finished.
+    pub fn status(&self) -> Option<&GrpcStatus> {
+        self.status.as_ref()
+    }
+
+    /// Reads the next response message. Returns `None` when the call
+    /// finished successfully, or [`HttpError::GrpcError`] with its status
+    /// when it didn't.
+    pub fn next_message(&mut self) -> Result<Option<Vec<u8>>, HttpError> {
+        loop {
+            if self.buffer.len() >= 5 {
+                let len = u32::from_be_bytes([
+                    self.buffer[1],
+                    self.buffer[2],
+                    self.buffer[3],
+                    self.buffer[4],
+                ]) as usize;
+                if let Some(max) = self.max_message_size
+                    && len > max
+                {
+                    return Err(HttpError::ResponseTooLarge(max));
+                }
+                if self.buffer.len() >= 5 + len {
+                    let flags = self.buffer[0];
+                    let message = self.buffer[5..5 + len].to_vec();
+                    self.buffer.drain(..5 + len);
+
+                    if flags & 0x80 != 0 && matches!(self.body, ResponseBody::Web(_)) {
+                        self.finish(parse_trailers(&message))?;
+                        continue;
+                    }
+                    if flags & 1 != 0 {
+                        return Err(HttpError::Http2Error(
+                            "compressed messages are not supported".to_string(),
+                        ));
+                    }
+                    return Ok(Some(message));
+                }
+            }
+
+            if let Some(status) = &self.status {
+                if !self.buffer.is_empty() {
+                    return Err(HttpError::Http2Error(
+                        "the call ended in the middle of a message".to_string(),
+                    ));
+                }
+                return if status.is_ok() {
+                    Ok(None)
+                } else {
+                    Err(HttpError::GrpcError(status.clone()))
+                };
+            }
+
+            match &mut self.body {
+                ResponseBody::Http2 {
+                    runtime,
+                    stream,
+                    timeout,
+                } => {
+                    let data = runtime
+                        .block_on(async { tokio::time::timeout(*timeout, stream.data()).await })
+                        .map_err(|_| HttpError::Timeout)?;
+                    match data {
+                        Some(data) => {
+                            let data = data.map_err(h2_error)?;
+                            // Lets the server send more in place of what was read
+                            let _ = stream.flow_control().release_capacity(data.len());
+                            self.buffer.extend_from_slice(&data);
+                        }
+                        None => {
+                            let trailers = runtime
+                                .block_on(async {
+                                    tokio::time::timeout(*timeout, stream.trailers()).await
+                                })
+                                .map_err(|_| HttpError::Timeout)?
+                                .map_err(h2_error)?;
+                            self.finish(trailers.as_ref().map(to_headers).unwrap_or_default())?;
+                        }
+                    }
+                }
+                ResponseBody::Web(body) => {
+                    let mut chunk = [0; 16 * 1024];
+                    let n = body.read(&mut chunk)?;
+                    if n == 0 {
+                        return Err(HttpError::Http2Error(
+                            "the call ended without a grpc-status".to_string(),
+                        ));
+                    }
+                    self.buffer.extend_from_slice(&chunk[..n]);
+                }
+            }
+        }
+    }
+
+    /// Records the trailers that end the call, and its status from them.
+    fn finish(&mut self, trailers: Headers) -> Result<(), HttpError> {
+        self.status = Some(GrpcStatus::from_headers(&trailers).ok_or_else(|| {
+            HttpError::Http2Error("the call ended without a grpc-status".to_string())
+        })?);
+        self.trailers = trailers;
+        Ok(())
+    }
+}
+
+impl Iterator for GrpcCall {
+    type Item = Result<Vec<u8>, HttpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.next_message() {
+            Ok(message) => message.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Parses the URL of a call, which must be `http://` or `https://`.
+fn grpc_url(url: &str) -> Result<Url, HttpError> {
+    let parsed = Url::parse(url).map_err(|e| HttpError::InvalidUrl(format!("{} ({})", url, e)))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        other => Err(HttpError::InvalidUrl(format!(
+            "{} (expected an http:// or https:// URL, not {}://)",
+            url, other
+        ))),
+    }
+}
+
+/// Starts an HTTP/2 connection over `io` and drives it in the background.
+/// A connection for Tokio to drive, over TCP or a Unix socket.
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+async fn handshake<T>(io: T) -> Result<SendRequest<Bytes>, HttpError>
+where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (sender, connection) = h2::client::Builder::new()
+        .enable_push(false)
+        .initial_window_size(WINDOW_SIZE)
+        .initial_connection_window_size(WINDOW_SIZE)
+        .handshake(io)
+        .await
+        .map_err(h2_error)?;
+    // Errors on the connection also fail its streams, where they're reported
+    tokio::spawn(async move {
+        let _ = connection.await;
+    });
+    sender.ready().await.map_err(h2_error)
+}
+
+/// Prefixes a message with its compression flag and length.
+fn frame(message: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(5 + message.len());
+    body.push(0);
+    body.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    body.extend_from_slice(message);
+    body
+}
+
+/// Parses a gRPC-Web trailers frame, `name: value` lines ending in CRLF.
+fn parse_trailers(frame: &[u8]) -> Headers {
+    let mut trailers = Headers::new();
+    for line in String::from_utf8_lossy(frame).split("\r\n") {
+        if let Some((name, value)) = line.split_once(':') {
+            trailers.append(name.trim().to_ascii_lowercase(), value.trim());
+        }
+    }
+    trailers
+}
+
+fn h2_error(error: h2::Error) -> HttpError {
+    HttpError::Http2Error(error.to_string())
+}
+
+fn to_headers(fields: &http::HeaderMap) -> Headers {
+    let mut headers = Headers::new();
+    for (name, value) in fields {
+        headers.append(name.as_str(), String::from_utf8_lossy(value.as_bytes()));
+    }
+    headers
+}
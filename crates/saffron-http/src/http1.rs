@@ -0,0 +1,558 @@
+//! HTTP/1.1 over connections the client opens itself, so sockets can be
+//! bound to a local address and every phase of the connection is timed.
+
+use crate::connect::Socket;
+use crate::proxy::Proxy;
+use crate::{HttpError, TlsError, multipart, timing};
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, StreamOwned};
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::{HttpMethod, HttpRequest, RequestBody};
+use saffron_core::domain::request_body::{encode_form_urlencoded, encode_graphql};
+use std::borrow::Cow;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use url::{Position, Url};
+
+/// Largest response head accepted, status line and headers included.
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// A connection to read and write through, plain or TLS.
+pub(crate) trait Connection: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Connection for T {}
+
+/// A response as read off the connection, with the body still unread.
+pub(crate) struct Response {
+    pub status: u16,
+    pub status_text: String,
+    pub headers: Headers,
+    pub body: Box<dyn Read + Send>,
+}
+
+/// A socket whose reads and writes give up after `timeout` without
+/// progress, and once `deadline` has passed.
+pub(crate) struct TimedSocket {
+    socket: Socket,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+}
+
+impl TimedSocket {
+    pub fn new(socket: Socket, timeout: Duration, deadline: Option<Instant>) -> Self {
+        Self {
+            socket,
+            timeout: Some(timeout).filter(|t| !t.is_zero()),
+            deadline,
+        }
+    }
+
+    pub fn into_inner(self) -> Socket {
+        self.socket
+    }
+
+    /// How long the next read or write may block.
+    fn limit(&self) -> io::Result<Option<Duration>> {
+        let Some(deadline) = self.deadline else {
+            return Ok(self.timeout);
+        };
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "timed out"));
+        }
+        Ok(Some(self.timeout.map_or(left, |t| t.min(left))))
+    }
+}
+
+impl Read for TimedSocket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.socket.set_read_timeout(self.limit()?)?;
+        self.socket.read(buf).map_err(timed_out)
+    }
+}
+
+impl Write for TimedSocket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.socket.set_write_timeout(self.limit()?)?;
+        self.socket.write(buf).map_err(timed_out)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.socket.flush()
+    }
+}
+
+/// Socket timeouts show up as `WouldBlock` on Unix; report them as such.
+fn timed_out(error: io::Error) -> io::Error {
+    match error.kind() {
+        io::ErrorKind::WouldBlock => io::Error::new(io::ErrorKind::TimedOut, "timed out"),
+        _ => error,
+    }
+}
+
+/// Runs the TLS handshake for `host` over `socket` and records how long it
+/// took.
+pub(crate) fn handshake<S: Read + Write + Send>(
+    socket: S,
+    config: &Arc<ClientConfig>,
+    host: &str,
+) -> Result<StreamOwned<ClientConnection, S>, HttpError> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let name = ServerName::try_from(host.to_string())
+        .map_err(|e| HttpError::InvalidUrl(format!("{} ({})", host, e)))?;
+    let connection =
+        ClientConnection::new(config.clone(), name).map_err(|e| TlsError::from_rustls(&e, host))?;
+
+    let start = Instant::now();
+    let mut stream = StreamOwned::new(connection, socket);
+    while stream.conn.is_handshaking() {
+        stream
+            .conn
+            .complete_io(&mut stream.sock)
+            .map_err(|e| network_error(e, host))?;
+    }
+    timing::record_tls(start);
+    Ok(stream)
+}
+
+/// Asks the HTTP proxy at the other end of `stream` for a tunnel to
+/// `host:port`.
+pub(crate) fn tunnel<S: Read + Write>(
+    stream: &mut S,
+    proxy: &Proxy,
+    host: &str,
+    port: u16,
+) -> Result<(), HttpError> {
+    let authority = format!("{}:{}", host, port);
+    let mut head = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+    if let Some(auth) = proxy.authorization() {
+        head.push_str(&format!("Proxy-Authorization: {}\r\n", auth));
+    }
+    head.push_str("\r\n");
+    let proxy_error = |e: io::Error| HttpError::ProxyError(format!("{}: {}", proxy.host, e));
+    stream.write_all(head.as_bytes()).map_err(proxy_error)?;
+    stream.flush().map_err(proxy_error)?;
+
+    // Byte by byte, so nothing the server sends through the tunnel is read
+    let mut reader = BufReader::with_capacity(1, stream);
+    let (status, status_text, _) = read_head(&mut reader).map_err(|e| match e {
+        HttpError::Timeout => e,
+        e => HttpError::ProxyError(e.to_string()),
+    })?;
+    match status {
+        200..=299 => Ok(()),
+        407 => Err(HttpError::ProxyError(
+            "proxy requires authentication (407)".to_string(),
+        )),
+        _ => Err(HttpError::ProxyError(format!(
+            "proxy refused to connect to {}: {} {}",
+            authority, status, status_text
+        ))),
+    }
+}
+
+/// Sends `request` with its encoded `body` over `stream` and reads the
+/// response head. `absolute` puts the whole URL in the request line, as HTTP
+/// proxies expect.
+pub(crate) fn exchange(
+    mut stream: Box<dyn Connection>,
+    request: &HttpRequest,
+    body: Body,
+    url: &Url,
+    absolute: bool,
+    extra_headers: &[(&str, String)],
+) -> Result<Response, HttpError> {
+    let host = url.host_str().unwrap_or_default();
+
+    let mut head = Vec::new();
+    let target = if absolute {
+        &url[..Position::AfterQuery]
+    } else {
+        &url[Position::BeforePath..Position::AfterQuery]
+    };
+    write!(head, "{} {} HTTP/1.1\r\n", request.method.as_str(), target)?;
+    if request.get_header("Host").is_none() {
+        match url.port() {
+            Some(port) => write!(head, "Host: {}:{}\r\n", host, port)?,
+            None => write!(head, "Host: {}\r\n", host)?,
+        }
+    }
+    let replaced = body.replaced_headers();
+    let headers = request
+        .headers
+        .iter()
+        .map(|h| (h.name.as_str(), h.value.as_str()))
+        .filter(|(name, _)| !replaced.iter().any(|r| name.eq_ignore_ascii_case(r)));
+    let added = extra_headers
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()));
+    for (name, value) in headers.chain(added) {
+        check_header(name, value)?;
+        write!(head, "{}: {}\r\n", name, value)?;
+    }
+    if request.get_header("Accept").is_none() {
+        head.extend_from_slice(b"Accept: */*\r\n");
+    }
+    body.write_framing(&mut head, request)?;
+    head.extend_from_slice(b"\r\n");
+
+    let write_error = |e| network_error(e, host);
+    match body {
+        Body::Empty => stream.write_all(&head).map_err(write_error)?,
+        Body::Bytes(bytes) => {
+            head.extend_from_slice(&bytes);
+            stream.write_all(&head).map_err(write_error)?;
+        }
+        Body::Reader {
+            mut reader,
+            chunked,
+            ..
+        } => {
+            stream.write_all(&head).map_err(write_error)?;
+            let mut buf = vec![0; CHUNK_SIZE];
+            loop {
+                let n = match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                if chunked {
+                    write!(stream, "{:x}\r\n", n).map_err(write_error)?;
+                }
+                stream.write_all(&buf[..n]).map_err(write_error)?;
+                if chunked {
+                    stream.write_all(b"\r\n").map_err(write_error)?;
+                }
+            }
+            if chunked {
+                stream.write_all(b"0\r\n\r\n").map_err(write_error)?;
+            }
+        }
+    }
+    stream.flush().map_err(write_error)?;
+
+    let mut reader = BufReader::new(stream);
+    let (status, status_text, headers) = loop {
+        let head = read_head(&mut reader).map_err(|e| match e {
+            HttpError::IoError(e) => network_error(e, host),
+            e => e,
+        })?;
+        // Interim responses such as 100 Continue come ahead of the real one
+        if !(100..200).contains(&head.0) || head.0 == 101 {
+            break head;
+        }
+    };
+
+    let body: Box<dyn Read + Send> =
+        if request.method == HttpMethod::Head || matches!(status, 100..=199 | 204 | 304) {
+            Box::new(io::empty())
+        } else if headers
+            .get("transfer-encoding")
+            .is_some_and(|te| te.rsplit(',').next().unwrap_or(te).trim() == "chunked")
+        {
+            Box::new(ChunkedReader::new(reader))
+        } else if let Some(len) = headers.get("content-length") {
+            let len = len.trim().parse().map_err(|_| {
+                HttpError::NetworkError(format!("{}: invalid Content-Length '{}'", host, len))
+            })?;
+            Box::new(LengthReader {
+                inner: reader,
+                remaining: len,
+            })
+        } else {
+            Box::new(reader)
+        };
+
+    Ok(Response {
+        status,
+        status_text,
+        headers,
+        body,
+    })
+}
+
+/// Maps a failure to talk to a server: TLS errors by their cause, timeouts
+/// to [`HttpError::Timeout`] and anything else to [`HttpError::NetworkError`].
+pub(crate) fn network_error(error: io::Error, host: &str) -> HttpError {
+    if let Some(tls) = TlsError::find(&error, host) {
+        return HttpError::TlsError(tls);
+    }
+    match error.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => HttpError::Timeout,
+        _ => HttpError::NetworkError(format!("{}: {}", host, error)),
+    }
+}
+
+/// Rejects header names that aren't tokens and values with control
+/// characters, so a value can't end the header line and start another.
+pub(crate) fn check_header(name: &str, value: &str) -> Result<(), HttpError> {
+    let valid_name = !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+    if !valid_name {
+        return Err(HttpError::RequestFailed(format!(
+            "invalid header name '{}'",
+            name.escape_debug()
+        )));
+    }
+    if value.chars().any(|c| c.is_ascii_control() && c != '\t') {
+        return Err(HttpError::RequestFailed(format!(
+            "invalid value for header '{}': {:?}",
+            name, value
+        )));
+    }
+    Ok(())
+}
+
+/// Reads a status line and headers, up to the blank line that ends them.
+fn read_head<R: BufRead>(reader: &mut R) -> Result<(u16, String, Headers), HttpError> {
+    let mut size = 0;
+    let mut next_line = |reader: &mut R| -> Result<String, HttpError> {
+        let mut line = Vec::new();
+        let n = reader
+            .take((MAX_HEAD_SIZE - size) as u64 + 1)
+            .read_until(b'\n', &mut line)?;
+        size += n;
+        if size > MAX_HEAD_SIZE {
+            return Err(HttpError::NetworkError(
+                "response head is too large".to_string(),
+            ));
+        }
+        if n == 0 {
+            return Err(HttpError::NetworkError(
+                "connection closed before a response was received".to_string(),
+            ));
+        }
+        let line = String::from_utf8_lossy(&line);
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+
+    let status_line = next_line(reader)?;
+    let mut parts = status_line.splitn(3, ' ');
+    let status = parts
+        .next()
+        .filter(|version| version.starts_with("HTTP/"))
+        .and(parts.next())
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| HttpError::NetworkError(format!("invalid status line '{}'", status_line)))?;
+    let status_text = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = Headers::new();
+    loop {
+        let line = next_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.append(name.trim().to_ascii_lowercase(), value.trim());
+        }
+    }
+    Ok((status, status_text, headers))
+}
+
+/// A request body, ready to write.
+pub(crate) enum Body<'a> {
+    Empty,
+    Bytes(Cow<'a, [u8]>),
+    Reader {
+        reader: Box<dyn Read + 'a>,
+        /// Set for multipart bodies, replacing the request's.
+        content_type: Option<String>,
+        /// Set when known for multipart bodies, replacing the request's.
+        length: Option<u64>,
+        chunked: bool,
+    },
+}
+
+impl<'a> Body<'a> {
+    /// Encodes the body of `request`. Streams are opened here, before
+    /// anything is sent.
+    pub fn new(request: &'a HttpRequest) -> Result<Self, HttpError> {
+        Ok(match &request.body {
+            // POST, PUT and PATCH announce an empty body with Content-Length: 0
+            RequestBody::None => match request.method {
+                HttpMethod::Post | HttpMethod::Put | HttpMethod::Patch => {
+                    Body::Bytes(Cow::Borrowed(&[]))
+                }
+                _ => Body::Empty,
+            },
+            RequestBody::Text(text) | RequestBody::Json(text) => {
+                Body::Bytes(Cow::Borrowed(text.as_bytes()))
+            }
+            RequestBody::FormUrlEncoded(data) => {
+                Body::Bytes(Cow::Owned(encode_form_urlencoded(data).into_bytes()))
+            }
+            RequestBody::GraphQL {
+                query,
+                variables,
+                operation_name,
+            } => Body::Bytes(Cow::Owned(
+                encode_graphql(query, variables.as_deref(), operation_name.as_deref()).into_bytes(),
+            )),
+            RequestBody::Binary(bytes) => Body::Bytes(Cow::Borrowed(bytes)),
+            // Without a Content-Length from the client, the stream is chunked
+            RequestBody::Stream(stream) => Body::Reader {
+                reader: Box::new(stream.open()?),
+                content_type: None,
+                length: None,
+                chunked: request.get_header("Content-Length").is_none(),
+            },
+            RequestBody::FormData(parts) => {
+                let body = multipart::encode(parts)?;
+                Body::Reader {
+                    reader: Box::new(body.reader),
+                    content_type: Some(body.content_type),
+                    length: body.length,
+                    chunked: body.length.is_none(),
+                }
+            }
+        })
+    }
+
+    /// Request headers the body brings its own values for.
+    fn replaced_headers(&self) -> &'static [&'static str] {
+        match self {
+            Body::Reader {
+                content_type: Some(_),
+                ..
+            } => &["Content-Type", "Content-Length"],
+            _ => &[],
+        }
+    }
+
+    /// Writes the headers that frame the body.
+    fn write_framing(&self, head: &mut Vec<u8>, request: &HttpRequest) -> io::Result<()> {
+        match self {
+            Body::Empty => {}
+            Body::Bytes(bytes) => {
+                if request.get_header("Content-Length").is_none() {
+                    write!(head, "Content-Length: {}\r\n", bytes.len())?;
+                }
+            }
+            Body::Reader {
+                content_type,
+                length,
+                chunked,
+                ..
+            } => {
+                if let Some(content_type) = content_type {
+                    write!(head, "Content-Type: {}\r\n", content_type)?;
+                }
+                if let Some(length) = length {
+                    write!(head, "Content-Length: {}\r\n", length)?;
+                }
+                if *chunked {
+                    head.extend_from_slice(b"Transfer-Encoding: chunked\r\n");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A body of known length. Ending early is an error rather than a short
+/// body.
+struct LengthReader<R> {
+    inner: R,
+    remaining: u64,
+}
+
+impl<R: Read> Read for LengthReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let n = self.inner.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "connection closed with {} bytes of the body left",
+                    self.remaining
+                ),
+            ));
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+/// Decodes `Transfer-Encoding: chunked`, skipping chunk extensions and
+/// trailers.
+struct ChunkedReader<R> {
+    inner: R,
+    /// Bytes left in the current chunk.
+    remaining: u64,
+    done: bool,
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+    fn new(inner: R) -> Self {
+        Self {
+            inner,
+            remaining: 0,
+            done: false,
+        }
+    }
+
+    fn read_line(&mut self) -> io::Result<String> {
+        let mut line = Vec::new();
+        (&mut self.inner)
+            .take(MAX_HEAD_SIZE as u64)
+            .read_until(b'\n', &mut line)?;
+        if !line.ends_with(b"\n") {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed inside a chunked body",
+            ));
+        }
+        Ok(String::from_utf8_lossy(&line).trim().to_string())
+    }
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            let line = self.read_line()?;
+            let size = line.split(';').next().unwrap_or_default().trim();
+            self.remaining = u64::from_str_radix(size, 16).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid chunk size '{}'", size),
+                )
+            })?;
+            if self.remaining == 0 {
+                while !self.read_line()?.is_empty() {}
+                self.done = true;
+                return Ok(0);
+            }
+        }
+
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let n = self.inner.read(&mut buf[..max])?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed inside a chunked body",
+            ));
+        }
+        self.remaining -= n as u64;
+        if self.remaining == 0 {
+            self.read_line()?;
+        }
+        Ok(n)
+    }
+}
@@ -1,9 +1,56 @@
-use saffron_core::domain::request::{FormDataContent, FormDataPart, HttpRequest, RequestBody};
-use saffron_core::domain::response::HttpResponse;
-use std::collections::HashMap;
-use std::io::Read;
+use saffron_core::domain::auth::{ApiKeyLocation, Auth};
+use saffron_core::domain::request::{
+    BodyStream, FormDataContent, FormDataPart, HttpMethod, HttpRequest, RequestBody,
+};
+use saffron_core::domain::response::{HttpResponse, RedirectHop, RetryAttempt, Timings};
+use std::borrow::Cow;
+use std::io;
+use std::thread;
 use std::time::{Duration, Instant};
 use thiserror::Error;
+use url::Url;
+
+#[cfg(feature = "async")]
+pub mod async_client;
+mod auth;
+pub mod batch;
+mod compression;
+pub mod connect;
+pub mod cookie;
+pub mod download;
+pub mod fixture;
+pub mod graphql;
+pub mod grpc;
+mod http1;
+pub mod middleware;
+pub mod mock;
+mod multipart;
+pub mod proxy;
+pub mod retry;
+mod socks;
+pub mod sse;
+mod timing;
+pub mod tls;
+pub mod transport;
+pub mod websocket;
+
+#[cfg(feature = "async")]
+pub use async_client::{AsyncHttpClient, ResponseFuture};
+pub use batch::{BatchConfig, BatchResult};
+pub use connect::{ConnectConfig, ConnectTo, IpVersion, ResolveOverride};
+pub use cookie::{Cookie, CookieJar, SameSite};
+pub use download::DownloadProgress;
+pub use fixture::FixtureTransport;
+pub use graphql::GraphQLError;
+pub use grpc::{GrpcCall, GrpcStatus};
+pub use middleware::{DefaultHeaders, Logger, Middleware, Next, RequestId};
+pub use mock::MockTransport;
+pub use proxy::{Proxy, ProxyConfig, ProxyScheme};
+pub use retry::RetryPolicy;
+pub use sse::{EventStream, SseConfig, SseEvent, SseParser};
+pub use tls::{ClientCertificate, TlsConfig, TlsError, TlsVersion};
+pub use transport::{Transport, TransportResponse, UreqTransport};
+pub use websocket::{CloseFrame, Message, WebSocket};
 
 #[derive(Debug, Error)]
 pub enum HttpError {
@@ -13,6 +60,9 @@ pub enum HttpError {
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
 
+    #[error("Invalid HTTP method: '{0}'")]
+    InvalidMethod(String),
+
     #[error("Connection timeout")]
     Timeout,
 
@@ -23,10 +73,40 @@ pub enum HttpError {
     IoError(#[from] std::io::Error),
 
     #[error("TLS error: {0}")]
-    TlsError(String),
+    TlsError(#[from] TlsError),
 
     #[error("Too many redirects")]
     TooManyRedirects,
+
+    #[error("Proxy error: {0}")]
+    ProxyError(String),
+
+    #[error("Invalid connection option: {0}")]
+    InvalidConnectOption(String),
+
+    #[error("Response body exceeds the {0} byte limit")]
+    ResponseTooLarge(usize),
+
+    #[error("Failed to decode {0} response body: {1}")]
+    DecodeError(String, String),
+
+    #[error("Authentication failed: {0}")]
+    AuthError(String),
+
+    #[error("Event stream failed: {0}")]
+    EventStreamError(String),
+
+    #[error("WebSocket error: {0}")]
+    WebSocketError(String),
+
+    #[error("Failed to read GraphQL schema: {0}")]
+    SchemaError(String),
+
+    #[error("HTTP/2 error: {0}")]
+    Http2Error(String),
+
+    #[error("gRPC call failed: {0}")]
+    GrpcError(GrpcStatus),
 }
 
 #[derive(Debug, Clone)]
@@ -35,8 +115,21 @@ pub struct HttpClientConfig {
     pub follow_redirects: bool,
     pub max_redirects: usize,
     pub user_agent: Option<String>,
-    pub accept_invalid_certs: bool,
+    pub tls: TlsConfig,
+    pub proxy: ProxyConfig,
+    /// Address overrides, IP version and local address for connections.
+    pub connect: ConnectConfig,
     pub max_response_size: Option<usize>,
+    /// Cookies received are stored here and sent back on matching requests.
+    /// `None` disables cookie handling.
+    pub cookie_jar: Option<CookieJar>,
+    pub retry: RetryPolicy,
+    /// Ask for compressed responses with `Accept-Encoding: gzip, deflate, br,
+    /// zstd`, unless the request sets its own `Accept-Encoding`.
+    pub compressed: bool,
+    /// Decode gzip, deflate, brotli and zstd bodies. When off, bodies are
+    /// returned exactly as received.
+    pub decompress: bool,
 }
 
 impl Default for HttpClientConfig {
@@ -46,15 +139,23 @@ impl Default for HttpClientConfig {
             follow_redirects: true,
             max_redirects: 10,
             user_agent: Some(format!("Saffron/{}", env!("CARGO_PKG_VERSION"))),
-            accept_invalid_certs: false,
+            tls: TlsConfig::default(),
+            proxy: ProxyConfig::from_env(),
+            connect: ConnectConfig::default(),
             max_response_size: Some(100 * 1024 * 1024),
+            cookie_jar: Some(CookieJar::new()),
+            retry: RetryPolicy::none(),
+            compressed: true,
+            decompress: true,
         }
     }
 }
 
 pub struct HttpClient {
-    agent: ureq::Agent,
+    transport: Box<dyn Transport>,
+    middleware: Vec<Box<dyn Middleware>>,
     config: HttpClientConfig,
+    oauth_tokens: auth::TokenCache,
 }
 
 impl HttpClient {
@@ -62,18 +163,32 @@ impl HttpClient {
         Self::with_config(HttpClientConfig::default())
     }
 
+    /// Creates a client from `config`.
+    ///
+    /// If the TLS or proxy configuration is invalid (for example a missing
+    /// PEM file), every request fails with that error; use
+    /// [`HttpClient::try_with_config`] to find out up front.
     pub fn with_config(config: HttpClientConfig) -> Self {
-        let mut builder = ureq::AgentBuilder::new()
-            .timeout(Duration::from_secs(config.timeout_seconds))
-            .redirects(config.max_redirects as u32);
-
-        if let Some(ua) = &config.user_agent {
-            builder = builder.user_agent(ua);
+        match UreqTransport::new(&config) {
+            Ok(transport) => Self::with_transport(config, transport),
+            Err(_) => Self::with_transport(config.clone(), transport::Misconfigured(config)),
         }
+    }
+
+    pub fn try_with_config(config: HttpClientConfig) -> Result<Self, HttpError> {
+        let transport = UreqTransport::new(&config)?;
+        Ok(Self::with_transport(config, transport))
+    }
 
+    /// Creates a client that sends requests through `transport` instead of
+    /// the network. The TLS, proxy and timeout settings in `config` only
+    /// apply if the transport uses them.
+    pub fn with_transport(config: HttpClientConfig, transport: impl Transport + 'static) -> Self {
         Self {
-            agent: builder.build(),
+            transport: Box::new(transport),
+            middleware: Vec::new(),
             config,
+            oauth_tokens: auth::TokenCache::default(),
         }
     }
 
@@ -85,222 +200,219 @@ impl HttpClient {
         Self::with_config(config)
     }
 
-    pub fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
-        let start = Instant::now();
+    /// Adds `middleware` to the end of the chain. Middleware added first
+    /// sees the request first and the response last.
+    pub fn with_middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
 
-        let method_str = request.method.as_str();
-        let url = &request.url;
+    /// The jar this client stores cookies in, unless cookies are disabled.
+    pub fn cookie_jar(&self) -> Option<&CookieJar> {
+        self.config.cookie_jar.as_ref()
+    }
 
-        let mut req = self.agent.request(method_str, url);
+    pub fn send(&self, request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+        let request = with_query(request)?;
+        self.with_middleware_chain(&request, &mut |request| {
+            let start = Instant::now();
+            let exchange = self.execute_with_retry(request)?;
+            self.extract_response(exchange, start)
+        })
+    }
 
-        for header in &request.headers {
-            req = req.set(&header.name, &header.value);
+    /// Passes `request` through the middleware chain, with `endpoint`
+    /// sending it at the end.
+    fn with_middleware_chain(
+        &self,
+        request: &HttpRequest,
+        endpoint: &mut dyn FnMut(&HttpRequest) -> Result<HttpResponse, HttpError>,
+    ) -> Result<HttpResponse, HttpError> {
+        if self.middleware.is_empty() {
+            return endpoint(request);
         }
+        Next::new(&self.middleware, endpoint).run(request.clone())
+    }
 
-        if let Some(timeout) = request.timeout_seconds {
-            req = req.timeout(Duration::from_secs(timeout));
-        }
+    /// Runs [`Self::execute_authorized`], sending the request again after transient
+    /// failures as allowed by the retry policy.
+    fn execute_with_retry(&self, request: &HttpRequest) -> Result<Exchange, HttpError> {
+        let request = &*with_query(request)?;
+        let policy = &self.config.retry;
+        let allowed = policy.allows(request);
+        let mut retries = Vec::new();
+
+        loop {
+            let attempt_start = Instant::now();
+            let can_retry = allowed && (retries.len() as u32 + 1) < policy.max_attempts;
+
+            let (status, error, retry_after) = match self.execute_authorized(request) {
+                Ok(mut exchange) => {
+                    let status = exchange.resp.status;
+                    if !can_retry || !policy.retries_status(status) {
+                        exchange.retries = retries;
+                        return Ok(exchange);
+                    }
 
-        let response = match &request.body {
-            RequestBody::None => {
-                // For POST/PUT/PATCH requests, send empty body with Content-Length: 0
-                if matches!(request.method.as_str(), "POST" | "PUT" | "PATCH") {
-                    req.send_string("")
-                } else {
-                    req.call()
+                    let retry_after = exchange
+                        .resp
+                        .headers
+                        .get("retry-after")
+                        .and_then(retry::parse_retry_after);
+                    // The body of a failed attempt is of no use; a broken
+                    // read only costs the pooled connection
+                    let _ = io::copy(&mut exchange.resp.body, &mut io::sink());
+                    (Some(status), None, retry_after)
                 }
-            }
-            RequestBody::Text(text) => {
-                if request.get_header("Content-Type").is_none() {
-                    req = req.set("Content-Type", "text/plain; charset=utf-8");
+                Err(e @ (HttpError::Timeout | HttpError::NetworkError(_))) if can_retry => {
+                    (None, Some(e.to_string()), None)
                 }
-                req.send_string(text)
-            }
-            RequestBody::Json(json) => {
-                if request.get_header("Content-Type").is_none() {
-                    req = req.set("Content-Type", "application/json; charset=utf-8");
-                }
-                req.send_string(json)
-            }
-            RequestBody::FormUrlEncoded(data) => {
-                if request.get_header("Content-Type").is_none() {
-                    req = req.set("Content-Type", "application/x-www-form-urlencoded");
-                }
-                let encoded = saffron_core::domain::request_body::encode_form_urlencoded(data);
-                req.send_string(&encoded)
-            }
-            RequestBody::Binary(bytes) => {
-                if request.get_header("Content-Type").is_none() {
-                    req = req.set("Content-Type", "application/octet-stream");
-                }
-                req.send_bytes(bytes)
-            }
-            RequestBody::FormData(parts) => {
-                return self.send_multipart(req, parts, start);
-            }
-        };
-
-        self.process_response(response, start)
+                Err(e) => return Err(e),
+            };
+
+            let delay = policy.delay(retries.len() as u32 + 1, retry_after);
+            retries.push(RetryAttempt {
+                status,
+                error,
+                elapsed: attempt_start.elapsed(),
+                delay,
+            });
+            thread::sleep(delay);
+        }
     }
 
-    fn send_multipart(
-        &self,
-        mut req: ureq::Request,
-        parts: &[FormDataPart],
-        start: Instant,
-    ) -> Result<HttpResponse, HttpError> {
-        let boundary = format!(
-            "----SaffronBoundary{}",
-            chrono::Utc::now().timestamp_millis()
-        );
-        req = req.set(
-            "Content-Type",
-            &format!("multipart/form-data; boundary={}", boundary),
-        );
-
-        let mut body = Vec::new();
-
-        for part in parts {
-            body.extend_from_slice(b"--");
-            body.extend_from_slice(boundary.as_bytes());
-            body.extend_from_slice(b"\r\n");
-
-            match &part.content {
-                FormDataContent::Text(text) => {
-                    body.extend_from_slice(
-                        format!(
-                            "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
-                            part.name
-                        )
-                        .as_bytes(),
-                    );
-                    body.extend_from_slice(text.as_bytes());
+    /// Sends `request`, following redirects, and returns the final response
+    /// with its body still unread.
+    fn execute(&self, request: &HttpRequest) -> Result<Exchange, HttpError> {
+        let follow = self.config.follow_redirects && request.follow_redirects;
+
+        let mut current = Cow::Borrowed(request);
+        let mut redirects = Vec::new();
+
+        loop {
+            let hop_start = Instant::now();
+            let mut resp = self.dispatch(&current)?;
+            let status = resp.status;
+            self.store_cookies(&resp);
+
+            let location = match resp.headers.get("location") {
+                Some(location) if follow && is_redirect_status(status) => location.to_string(),
+                _ => {
+                    let timings = Timings {
+                        first_byte: hop_start.elapsed(),
+                        download: Duration::ZERO,
+                        ..resp.timings
+                    };
+                    return Ok(Exchange {
+                        resp,
+                        redirects,
+                        timings,
+                        retries: Vec::new(),
+                    });
                 }
-                FormDataContent::File {
-                    filename,
-                    data,
-                    content_type,
-                } => {
-                    body.extend_from_slice(
-                        format!(
-                            "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n",
-                            part.name, filename
-                        )
-                        .as_bytes(),
-                    );
-
-                    if let Some(ct) = content_type {
-                        body.extend_from_slice(format!("Content-Type: {}\r\n", ct).as_bytes());
-                    }
+            };
 
-                    body.extend_from_slice(b"\r\n");
-                    body.extend_from_slice(data);
-                }
+            if redirects.len() >= self.config.max_redirects {
+                return Err(HttpError::TooManyRedirects);
             }
-            body.extend_from_slice(b"\r\n");
-        }
 
-        body.extend_from_slice(b"--");
-        body.extend_from_slice(boundary.as_bytes());
-        body.extend_from_slice(b"--\r\n");
+            let url = resp.url.clone();
+            let next_url = resolve_location(&url, &location)?;
+            let headers = resp.headers.clone();
 
-        let response = req.send_bytes(&body);
-        self.process_response(response, start)
-    }
+            // Drain the redirect body so the connection can go back to the pool
+            io::copy(&mut resp.body, &mut io::sink())?;
 
-    fn process_response(
-        &self,
-        response: Result<ureq::Response, ureq::Error>,
-        start: Instant,
-    ) -> Result<HttpResponse, HttpError> {
-        let elapsed = start.elapsed();
+            redirects.push(RedirectHop {
+                status,
+                url,
+                location,
+                headers,
+                elapsed: hop_start.elapsed(),
+            });
 
-        match response {
-            Ok(resp) => self.extract_response(resp, elapsed),
-            Err(ureq::Error::Status(code, resp)) => {
-                self.extract_response_with_code(resp, code, elapsed)
-            }
-            Err(ureq::Error::Transport(transport)) => {
-                let error_msg = transport.to_string();
-                if error_msg.contains("timeout") || error_msg.contains("timed out") {
-                    Err(HttpError::Timeout)
-                } else if error_msg.contains("certificate") || error_msg.contains("tls") {
-                    Err(HttpError::TlsError(error_msg))
-                } else {
-                    Err(HttpError::NetworkError(error_msg))
-                }
-            }
+            current = Cow::Owned(redirected_request(&current, status, next_url));
         }
     }
 
-    fn extract_response(
-        &self,
-        resp: ureq::Response,
-        elapsed: Duration,
-    ) -> Result<HttpResponse, HttpError> {
-        let status = resp.status();
-        let status_text = resp.status_text().to_string();
-        let url = resp.get_url().to_string();
-
-        let mut headers = HashMap::new();
-        for name in resp.headers_names() {
-            if let Some(value) = resp.header(&name) {
-                headers.insert(name.clone(), value.to_string());
-            }
+    /// Adds the headers the client manages (user agent, cookies,
+    /// `Accept-Encoding` and a default `Content-Type`) and hands the request
+    /// to the transport.
+    fn dispatch(&self, request: &HttpRequest) -> Result<TransportResponse, HttpError> {
+        let parsed_url = Url::parse(&request.url)
+            .map_err(|e| HttpError::InvalidUrl(format!("{} ({})", request.url, e)))?;
+        // `HttpMethod::Custom` can be built without going through `parse`
+        if let HttpMethod::Custom(name) = &request.method {
+            name.parse::<HttpMethod>()
+                .map_err(|e| HttpError::InvalidMethod(e.0))?;
         }
+        let mut request = request.clone();
 
-        let body = self.read_response_body(resp)?;
+        if let Some(ua) = &self.config.user_agent
+            && request.get_header("User-Agent").is_none()
+        {
+            request.add_header("User-Agent", ua);
+        }
 
-        Ok(HttpResponse::new(
-            status,
-            status_text,
-            headers,
-            body,
-            elapsed,
-            url,
-        ))
-    }
+        if let Some(cookies) = self
+            .config
+            .cookie_jar
+            .as_ref()
+            .and_then(|jar| jar.cookie_header(&parsed_url))
+        {
+            let value = match request.get_header("Cookie") {
+                Some(explicit) => format!("{}; {}", explicit, cookies),
+                None => cookies,
+            };
+            request.headers.insert("Cookie", value);
+        }
 
-    fn extract_response_with_code(
-        &self,
-        resp: ureq::Response,
-        code: u16,
-        elapsed: Duration,
-    ) -> Result<HttpResponse, HttpError> {
-        let status_text = resp.status_text().to_string();
-        let url = resp.get_url().to_string();
+        if self.config.compressed && request.get_header("Accept-Encoding").is_none() {
+            request.add_header("Accept-Encoding", compression::ACCEPT_ENCODING);
+        }
 
-        let mut headers = HashMap::new();
-        for name in resp.headers_names() {
-            if let Some(value) = resp.header(&name) {
-                headers.insert(name.clone(), value.to_string());
-            }
+        if request.get_header("Content-Type").is_none()
+            && let Some(content_type) = default_content_type(&request.body)
+        {
+            request.add_header("Content-Type", content_type);
         }
 
-        let body = self.read_response_body(resp)?;
+        if let RequestBody::Stream(stream) = &request.body
+            && let Some(len) = stream.content_length()
+            && request.get_header("Content-Length").is_none()
+        {
+            request.add_header("Content-Length", len.to_string());
+        }
 
-        Ok(HttpResponse::new(
-            code,
-            status_text,
-            headers,
-            body,
-            elapsed,
-            url,
-        ))
+        self.transport.send(&request)
     }
 
-    fn read_response_body(&self, resp: ureq::Response) -> Result<Vec<u8>, HttpError> {
-        let mut reader = resp.into_reader();
-        let mut body = Vec::new();
-
-        if let Some(max_size) = self.config.max_response_size {
-            let mut limited_reader = reader.take(max_size as u64);
-            limited_reader.read_to_end(&mut body)?;
-        } else {
-            reader.read_to_end(&mut body)?;
+    /// Saves the `Set-Cookie` headers of `resp` into the cookie jar.
+    fn store_cookies(&self, resp: &TransportResponse) {
+        if let Some(jar) = &self.config.cookie_jar
+            && let Ok(url) = Url::parse(&resp.url)
+        {
+            jar.store_response_cookies(&url, resp.headers.get_all("set-cookie"));
         }
+    }
 
-        Ok(body)
+    /// Reads the whole body of `exchange` into memory. `start` is when the
+    /// request was first sent.
+    fn extract_response(
+        &self,
+        exchange: Exchange,
+        start: Instant,
+    ) -> Result<HttpResponse, HttpError> {
+        let mut response = exchange.head(start.elapsed());
+
+        let download_start = Instant::now();
+        let mut body = Vec::new();
+        self.stream_body(exchange.resp, &mut body, 0, |_| {})?
+            .apply_to(&mut response);
+        response.body = body;
+        response.timings.download = download_start.elapsed();
+
+        Ok(response)
     }
 
     pub fn get(&self, url: &str) -> Result<HttpResponse, HttpError> {
@@ -324,17 +436,11 @@ impl HttpClient {
     }
 
     pub fn head(&self, url: &str) -> Result<HttpResponse, HttpError> {
-        self.send(&HttpRequest::new(
-            saffron_core::domain::request::HttpMethod::Head,
-            url,
-        ))
+        self.send(&HttpRequest::new(HttpMethod::Head, url))
     }
 
     pub fn options(&self, url: &str) -> Result<HttpResponse, HttpError> {
-        self.send(&HttpRequest::new(
-            saffron_core::domain::request::HttpMethod::Options,
-            url,
-        ))
+        self.send(&HttpRequest::new(HttpMethod::Options, url))
     }
 }
 
@@ -344,14 +450,139 @@ impl Default for HttpClient {
     }
 }
 
+/// The final response of a request, with its body still unread.
+struct Exchange {
+    resp: TransportResponse,
+    redirects: Vec<RedirectHop>,
+    timings: Timings,
+    retries: Vec<RetryAttempt>,
+}
+
+impl Exchange {
+    /// Builds an [`HttpResponse`] from the status line and headers, leaving
+    /// the body empty.
+    fn head(&self, elapsed: Duration) -> HttpResponse {
+        HttpResponse::new(
+            self.resp.status,
+            self.resp.status_text.clone(),
+            self.resp.headers.clone(),
+            Vec::new(),
+            elapsed,
+            self.resp.url.clone(),
+        )
+        .with_redirects(self.redirects.clone())
+        .with_timings(self.timings)
+        .with_retries(self.retries.clone())
+    }
+}
+
+/// The `Content-Type` sent for `body` when the request doesn't set one.
+/// Multipart bodies get theirs, with the boundary, from the transport.
+fn default_content_type(body: &RequestBody) -> Option<&'static str> {
+    match body {
+        RequestBody::Text(_) => Some("text/plain; charset=utf-8"),
+        RequestBody::Json(_) | RequestBody::GraphQL { .. } => {
+            Some("application/json; charset=utf-8")
+        }
+        RequestBody::FormUrlEncoded(_) => Some("application/x-www-form-urlencoded"),
+        RequestBody::Binary(_) | RequestBody::Stream(_) => Some("application/octet-stream"),
+        RequestBody::None | RequestBody::FormData(_) => None,
+    }
+}
+
+/// `request` with its query parameters encoded into the URL, so everything
+/// after this point only deals with `url`. Fails before anything is sent
+/// when the URL is invalid.
+fn with_query(request: &HttpRequest) -> Result<Cow<'_, HttpRequest>, HttpError> {
+    let url = request
+        .full_url()
+        .map_err(|e| HttpError::InvalidUrl(e.to_string()))?;
+    if request.query.is_empty() {
+        return Ok(Cow::Borrowed(request));
+    }
+
+    let mut resolved = request.clone();
+    resolved.url = url;
+    resolved.query.clear();
+    Ok(Cow::Owned(resolved))
+}
+
+fn is_redirect_status(status: u16) -> bool {
+    matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+fn resolve_location(base: &str, location: &str) -> Result<Url, HttpError> {
+    Url::parse(base)
+        .and_then(|base| base.join(location))
+        .map_err(|e| HttpError::InvalidUrl(format!("{} ({})", location, e)))
+}
+
+/// Builds the request for the next hop of a redirect chain.
+///
+/// 303 always switches to GET, and 301/302 do so for POST, matching what
+/// browsers and curl do. Credentials are dropped when the redirect leaves
+/// the original origin, including an API key sent as a header.
+fn redirected_request(previous: &HttpRequest, status: u16, next_url: Url) -> HttpRequest {
+    let mut next = previous.clone();
+
+    let switch_to_get = match status {
+        303 => next.method != HttpMethod::Head,
+        301 | 302 => next.method == HttpMethod::Post,
+        _ => false,
+    };
+
+    if switch_to_get {
+        next.method = HttpMethod::Get;
+        next.body = RequestBody::None;
+        next.headers.retain(|h| {
+            !h.name.eq_ignore_ascii_case("content-type")
+                && !h.name.eq_ignore_ascii_case("content-length")
+        });
+    }
+
+    let same_origin = Url::parse(&previous.url)
+        .map(|prev| prev.origin() == next_url.origin())
+        .unwrap_or(false);
+
+    if !same_origin {
+        let api_key = match &previous.auth {
+            Some(Auth::ApiKey {
+                name,
+                location: ApiKeyLocation::Header,
+                ..
+            }) => Some(name.as_str()),
+            _ => None,
+        };
+        next.headers.retain(|h| {
+            !h.name.eq_ignore_ascii_case("authorization")
+                && !h.name.eq_ignore_ascii_case("cookie")
+                && api_key.is_none_or(|name| !h.name.eq_ignore_ascii_case(name))
+        });
+        next.auth = None;
+    }
+
+    next.url = next_url.to_string();
+    next
+}
+
 pub mod helpers {
     use super::*;
 
+    /// Streams `url` into the file at `path` without buffering it in memory.
+    /// Non-2xx responses fail with [`HttpError::RequestFailed`].
     pub fn download_file(url: &str, path: &std::path::Path) -> Result<(), HttpError> {
-        let client = HttpClient::new();
-        let response = client.get(url)?;
-
-        std::fs::write(path, response.body).map_err(HttpError::IoError)?;
+        let client = HttpClient::with_config(HttpClientConfig {
+            max_response_size: None,
+            ..Default::default()
+        });
+        let response = client.download_to_file(&HttpRequest::get(url), path, false, |_| {})?;
+
+        if !response.is_success() {
+            return Err(HttpError::RequestFailed(format!(
+                "{} {}",
+                response.status, response.status_text
+            )));
+        }
 
         Ok(())
     }
@@ -367,13 +598,11 @@ pub mod helpers {
             .unwrap_or("file")
             .to_string();
 
-        let data = std::fs::read(file_path)?;
-
         let part = FormDataPart {
             name: field_name.to_string(),
-            content: FormDataContent::File {
+            content: FormDataContent::Stream {
                 filename,
-                data,
+                stream: BodyStream::from_file(file_path)?,
                 content_type: Some(guess_content_type(file_path)),
             },
         };
@@ -384,7 +613,7 @@ pub mod helpers {
         client.send(&request)
     }
 
-    fn guess_content_type(path: &std::path::Path) -> String {
+    pub fn guess_content_type(path: &std::path::Path) -> String {
         match path.extension().and_then(|e| e.to_str()) {
             Some("txt") => "text/plain",
             Some("html") | Some("htm") => "text/html",
@@ -402,6 +631,8 @@ pub mod helpers {
         .to_string()
     }
 
+    /// Wall-clock time of a whole request. See [`HttpResponse::timings`] for
+    /// a per-phase breakdown.
     pub fn measure_request_time(
         request: &HttpRequest,
     ) -> Result<(HttpResponse, Duration), HttpError> {
@@ -0,0 +1,246 @@
+use crate::HttpError;
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::HttpRequest;
+use saffron_core::domain::response::HttpResponse;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::time::Instant;
+use uuid::Uuid;
+
+/// A step in the chain a request passes through before it is sent.
+///
+/// Middleware runs once per call to [`HttpClient::send`](crate::HttpClient::send)
+/// (or a streaming variant), outside retries, redirects and auth. Each one
+/// can change the request before calling [`Next::run`], answer without
+/// calling it, or inspect and replace the result on the way back.
+///
+/// For streaming calls the response has an empty body, since the body has
+/// already been written out by the time it comes back up the chain.
+pub trait Middleware: Send + Sync {
+    fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, HttpError>;
+}
+
+type Endpoint<'a> = dyn FnMut(&HttpRequest) -> Result<HttpResponse, HttpError> + 'a;
+
+/// The rest of the middleware chain, ending with the request being sent.
+pub struct Next<'a> {
+    middleware: &'a [Box<dyn Middleware>],
+    endpoint: &'a mut Endpoint<'a>,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        middleware: &'a [Box<dyn Middleware>],
+        endpoint: &'a mut Endpoint<'a>,
+    ) -> Self {
+        Self {
+            middleware,
+            endpoint,
+        }
+    }
+
+    /// Passes `request` to the next middleware, or sends it if this was the
+    /// last one.
+    pub fn run(self, request: HttpRequest) -> Result<HttpResponse, HttpError> {
+        match self.middleware.split_first() {
+            Some((first, rest)) => first.handle(
+                request,
+                Next {
+                    middleware: rest,
+                    endpoint: self.endpoint,
+                },
+            ),
+            None => (self.endpoint)(&request),
+        }
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("remaining", &self.middleware.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Wraps a closure as a [`Middleware`].
+pub fn from_fn<F>(f: F) -> FnMiddleware<F>
+where
+    F: Fn(HttpRequest, Next<'_>) -> Result<HttpResponse, HttpError> + Send + Sync,
+{
+    FnMiddleware(f)
+}
+
+/// A [`Middleware`] built with [`from_fn`].
+pub struct FnMiddleware<F>(F);
+
+impl<F> Middleware for FnMiddleware<F>
+where
+    F: Fn(HttpRequest, Next<'_>) -> Result<HttpResponse, HttpError> + Send + Sync,
+{
+    fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, HttpError> {
+        (self.0)(request, next)
+    }
+}
+
+/// Adds headers to every request that doesn't set them itself.
+#[derive(Debug, Clone, Default)]
+pub struct DefaultHeaders {
+    headers: Headers,
+}
+
+impl DefaultHeaders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+}
+
+impl Middleware for DefaultHeaders {
+    fn handle(&self, mut request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, HttpError> {
+        let missing: Vec<_> = self
+            .headers
+            .iter()
+            .filter(|h| !request.headers.contains(&h.name))
+            .cloned()
+            .collect();
+        request.headers.extend(missing);
+        next.run(request)
+    }
+}
+
+/// Tags every request with a random UUID in `X-Request-Id`, unless it
+/// already carries one.
+#[derive(Debug, Clone)]
+pub struct RequestId {
+    header: String,
+}
+
+impl RequestId {
+    pub fn new() -> Self {
+        Self::with_header_name("X-Request-Id")
+    }
+
+    pub fn with_header_name(name: impl Into<String>) -> Self {
+        Self {
+            header: name.into(),
+        }
+    }
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware for RequestId {
+    fn handle(&self, mut request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, HttpError> {
+        if !request.headers.contains(&self.header) {
+            request.add_header(&self.header, Uuid::new_v4().to_string());
+        }
+        next.run(request)
+    }
+}
+
+/// Headers whose values are replaced with `[redacted]` in logs.
+const SENSITIVE_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Writes a line for every request and its outcome, to stderr or any writer.
+pub struct Logger {
+    writer: Mutex<Box<dyn Write + Send>>,
+    headers: bool,
+}
+
+impl Logger {
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            writer: Mutex::new(Box::new(writer)),
+            headers: false,
+        }
+    }
+
+    pub fn stderr() -> Self {
+        Self::new(io::stderr())
+    }
+
+    /// Also log request and response headers. Credentials and cookies are
+    /// redacted.
+    pub fn with_headers(mut self, headers: bool) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    fn write(&self, text: &str) {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        // Logging must never fail the request
+        let _ = writer.write_all(text.as_bytes());
+        let _ = writer.flush();
+    }
+
+    fn format_headers(&self, out: &mut String, headers: &Headers) {
+        if !self.headers {
+            return;
+        }
+        for header in headers {
+            let value = if SENSITIVE_HEADERS.contains(&header.name.to_ascii_lowercase().as_str()) {
+                "[redacted]"
+            } else {
+                header.value.as_str()
+            };
+            out.push_str(&format!("    {}: {}\n", header.name, value));
+        }
+    }
+}
+
+impl Default for Logger {
+    fn default() -> Self {
+        Self::stderr()
+    }
+}
+
+impl fmt::Debug for Logger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Logger")
+            .field("headers", &self.headers)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Middleware for Logger {
+    fn handle(&self, request: HttpRequest, next: Next<'_>) -> Result<HttpResponse, HttpError> {
+        let mut line = format!("--> {} {}\n", request.method, request.url);
+        self.format_headers(&mut line, &request.headers);
+        self.write(&line);
+
+        let method = request.method.clone();
+        let url = request.url.clone();
+        let start = Instant::now();
+        let result = next.run(request);
+        let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+
+        let mut line = match &result {
+            Ok(response) => format!(
+                "<-- {} {} {} ({:.1} ms, {} bytes)\n",
+                response.status, response.status_text, url, elapsed, response.decoded_size
+            ),
+            Err(e) => format!("<-- {} {} failed: {} ({:.1} ms)\n", method, url, e, elapsed),
+        };
+        if let Ok(response) = &result {
+            self.format_headers(&mut line, &response.headers);
+        }
+        self.write(&line);
+
+        result
+    }
+}
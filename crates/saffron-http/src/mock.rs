@@ -0,0 +1,111 @@
+use crate::HttpError;
+use crate::transport::{Transport, TransportResponse};
+use saffron_core::domain::request::{HttpMethod, HttpRequest};
+use saffron_core::domain::response::HttpResponse;
+use std::sync::{Arc, Mutex, MutexGuard};
+use url::Url;
+
+/// An in-memory [`Transport`] that answers requests with canned responses,
+/// for tests that must not touch the network.
+///
+/// Clones share their routes and the list of received requests, so a test
+/// can keep a clone to inspect what the client sent.
+#[derive(Debug, Clone, Default)]
+pub struct MockTransport {
+    state: Arc<Mutex<MockState>>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    routes: Vec<Route>,
+    requests: Vec<HttpRequest>,
+}
+
+#[derive(Debug)]
+struct Route {
+    method: HttpMethod,
+    url: String,
+    response: HttpResponse,
+    used: bool,
+}
+
+impl Route {
+    fn matches(&self, request: &HttpRequest) -> bool {
+        self.method == request.method && url_matches(&self.url, &request.url)
+    }
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers `method` requests for `url` with `response`. A `url` ending
+    /// in `*` matches every URL that starts with the rest of it.
+    ///
+    /// Responses registered for the same request are returned in order, and
+    /// the last one keeps answering once the others are used up.
+    pub fn with_response(
+        self,
+        method: HttpMethod,
+        url: impl Into<String>,
+        response: HttpResponse,
+    ) -> Self {
+        self.lock().routes.push(Route {
+            method,
+            url: url.into(),
+            response,
+            used: false,
+        });
+        self
+    }
+
+    /// Every request received so far, as handed over by the client (with
+    /// cookies, `Accept-Encoding` and default headers added).
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: &HttpRequest) -> Result<TransportResponse, HttpError> {
+        let mut state = self.lock();
+        state.requests.push(request.clone());
+
+        let index = state
+            .routes
+            .iter()
+            .position(|r| r.matches(request) && !r.used)
+            .or_else(|| state.routes.iter().rposition(|r| r.matches(request)));
+        let Some(index) = index else {
+            return Err(HttpError::RequestFailed(format!(
+                "no mock response for {} {}",
+                request.method, request.url
+            )));
+        };
+
+        let route = &mut state.routes[index];
+        route.used = true;
+
+        let mut response = TransportResponse::from(route.response.clone());
+        response.url = request.url.clone();
+        Ok(response)
+    }
+}
+
+/// Compares URLs after normalizing them, so `http://host` matches
+/// `http://host/`.
+fn url_matches(pattern: &str, url: &str) -> bool {
+    if let Some(prefix) = pattern.strip_suffix('*') {
+        return url.starts_with(prefix);
+    }
+
+    match (Url::parse(pattern), Url::parse(url)) {
+        (Ok(pattern), Ok(url)) => pattern == url,
+        _ => pattern == url,
+    }
+}
@@ -0,0 +1,121 @@
+use saffron_core::domain::request::{FormDataContent, FormDataPart};
+use std::collections::VecDeque;
+use std::io::{self, Cursor, Read};
+
+/// A `multipart/form-data` body assembled from per-part readers, so file
+/// parts are streamed instead of copied into one buffer.
+pub(crate) struct MultipartBody<'a> {
+    pub content_type: String,
+    /// Total size in bytes, known when every part has a known size.
+    pub length: Option<u64>,
+    pub reader: Box<dyn Read + 'a>,
+}
+
+pub(crate) fn encode(parts: &[FormDataPart]) -> io::Result<MultipartBody<'_>> {
+    let boundary = format!(
+        "----SaffronBoundary{}",
+        chrono::Utc::now().timestamp_millis()
+    );
+
+    let mut body = Concat::default();
+    let mut length = Some(0u64);
+
+    for part in parts {
+        let mut head = format!("--{}\r\n", boundary);
+
+        let (content, content_length): (Box<dyn Read + '_>, Option<u64>) = match &part.content {
+            FormDataContent::Text(text) => {
+                head.push_str(&format!(
+                    "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                    part.name
+                ));
+                (
+                    Box::new(Cursor::new(text.as_bytes())),
+                    Some(text.len() as u64),
+                )
+            }
+            FormDataContent::File {
+                filename,
+                data,
+                content_type,
+            } => {
+                head.push_str(&file_headers(&part.name, filename, content_type));
+                (
+                    Box::new(Cursor::new(data.as_slice())),
+                    Some(data.len() as u64),
+                )
+            }
+            FormDataContent::Stream {
+                filename,
+                stream,
+                content_type,
+            } => {
+                head.push_str(&file_headers(&part.name, filename, content_type));
+                (stream.open()?, stream.content_length())
+            }
+        };
+
+        length = match (length, content_length) {
+            (Some(total), Some(len)) => Some(total + head.len() as u64 + len + 2),
+            _ => None,
+        };
+
+        body.push_bytes(head.into_bytes());
+        body.push(content);
+        body.push_bytes(b"\r\n".to_vec());
+    }
+
+    let tail = format!("--{}--\r\n", boundary);
+    length = length.map(|total| total + tail.len() as u64);
+    body.push_bytes(tail.into_bytes());
+
+    Ok(MultipartBody {
+        content_type: format!("multipart/form-data; boundary={}", boundary),
+        length,
+        reader: Box::new(body),
+    })
+}
+
+fn file_headers(name: &str, filename: &str, content_type: &Option<String>) -> String {
+    let mut headers = format!(
+        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n",
+        name, filename
+    );
+
+    if let Some(ct) = content_type {
+        headers.push_str(&format!("Content-Type: {}\r\n", ct));
+    }
+
+    headers.push_str("\r\n");
+    headers
+}
+
+/// Reads a sequence of readers back to back.
+#[derive(Default)]
+struct Concat<'a> {
+    readers: VecDeque<Box<dyn Read + 'a>>,
+}
+
+impl<'a> Concat<'a> {
+    fn push(&mut self, reader: Box<dyn Read + 'a>) {
+        self.readers.push_back(reader);
+    }
+
+    fn push_bytes(&mut self, bytes: Vec<u8>) {
+        self.push(Box::new(Cursor::new(bytes)));
+    }
+}
+
+impl Read for Concat<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(reader) = self.readers.front_mut() {
+            match reader.read(buf)? {
+                0 if !buf.is_empty() => {
+                    self.readers.pop_front();
+                }
+                n => return Ok(n),
+            }
+        }
+        Ok(0)
+    }
+}
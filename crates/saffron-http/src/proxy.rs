@@ -0,0 +1,258 @@
+use crate::HttpError;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use percent_encoding::percent_decode_str;
+use saffron_core::domain::environment::ProxySettings;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyScheme {
+    /// Plain HTTP proxy. `https://` targets are tunneled with `CONNECT`.
+    Http,
+    Socks4,
+    Socks4a,
+    Socks5,
+}
+
+impl ProxyScheme {
+    fn default_port(&self) -> u16 {
+        match self {
+            ProxyScheme::Http => 80,
+            _ => 1080,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct Proxy {
+    pub scheme: ProxyScheme,
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Proxy {
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// `Proxy-Authorization` value for plain HTTP requests sent through an
+    /// HTTP proxy, and for the `CONNECT` that opens a tunnel through one.
+    pub(crate) fn authorization(&self) -> Option<String> {
+        if self.scheme != ProxyScheme::Http {
+            return None;
+        }
+
+        self.username.as_ref().map(|user| {
+            let pass = self.password.as_deref().unwrap_or_default();
+            format!("Basic {}", BASE64.encode(format!("{}:{}", user, pass)))
+        })
+    }
+}
+
+// Hand-written so the password stays out of logs and `{:?}` output
+impl fmt::Debug for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Proxy")
+            .field("scheme", &self.scheme)
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl FromStr for Proxy {
+    type Err = HttpError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| HttpError::ProxyError(format!("'{}': {}", s, reason));
+
+        let with_scheme = if s.contains("://") {
+            s.to_string()
+        } else {
+            format!("http://{}", s)
+        };
+        let url = Url::parse(&with_scheme).map_err(|e| invalid(&e.to_string()))?;
+
+        let scheme = match url.scheme() {
+            "http" => ProxyScheme::Http,
+            "socks4" => ProxyScheme::Socks4,
+            "socks4a" => ProxyScheme::Socks4a,
+            "socks5" | "socks5h" | "socks" => ProxyScheme::Socks5,
+            "https" => return Err(invalid("TLS connections to the proxy are not supported")),
+            other => return Err(invalid(&format!("unsupported scheme '{}'", other))),
+        };
+
+        let host = url
+            .host_str()
+            .ok_or_else(|| invalid("missing host"))?
+            .to_string();
+
+        let username = Some(decode(url.username())).filter(|u| !u.is_empty());
+        let password = url.password().map(decode);
+
+        Ok(Self {
+            scheme,
+            host,
+            port: url.port().unwrap_or(scheme.default_port()),
+            username,
+            password,
+        })
+    }
+}
+
+fn decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().into_owned()
+}
+
+/// Which proxy (if any) each request goes through.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProxyConfig {
+    /// Proxy for `http://` URLs.
+    pub http: Option<Proxy>,
+    /// Proxy for `https://` URLs.
+    pub https: Option<Proxy>,
+    /// Hosts that are always contacted directly (`NO_PROXY` syntax).
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Sends every request through `proxy`.
+    pub fn all(proxy: Proxy) -> Self {
+        Self {
+            http: Some(proxy.clone()),
+            https: Some(proxy),
+            no_proxy: Vec::new(),
+        }
+    }
+
+    /// Reads `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`
+    /// (lowercase names take precedence). Unparsable values are ignored.
+    pub fn from_env() -> Self {
+        let all = env_var("all_proxy").and_then(|v| v.parse().ok());
+
+        Self {
+            http: env_var("http_proxy")
+                .and_then(|v| v.parse().ok())
+                .or_else(|| all.clone()),
+            https: env_var("https_proxy").and_then(|v| v.parse().ok()).or(all),
+            no_proxy: env_var("no_proxy")
+                .map(|v| parse_no_proxy(&v))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Layers stored settings on top of this config: an explicit proxy URL
+    /// replaces both schemes, and a non-empty `no_proxy` list replaces ours.
+    pub fn with_settings(mut self, settings: &ProxySettings) -> Result<Self, HttpError> {
+        if let Some(url) = &settings.url {
+            let mut proxy: Proxy = url.parse()?;
+            if let Some(user) = &settings.username {
+                proxy = proxy.with_credentials(user, settings.password.clone().unwrap_or_default());
+            }
+            self.http = Some(proxy.clone());
+            self.https = Some(proxy);
+        }
+
+        if !settings.no_proxy.is_empty() {
+            self.no_proxy = settings.no_proxy.clone();
+        }
+
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.http.is_none() && self.https.is_none()
+    }
+
+    pub fn proxy_for(&self, url: &Url) -> Option<&Proxy> {
+        let host = url.host_str()?;
+        if self.bypasses(host) {
+            return None;
+        }
+
+        match url.scheme() {
+            "https" => self.https.as_ref(),
+            "http" => self.http.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn bypasses(&self, host: &str) -> bool {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        self.no_proxy
+            .iter()
+            .any(|entry| no_proxy_matches(entry, host))
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name)
+        .or_else(|_| std::env::var(name.to_uppercase()))
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+}
+
+pub fn parse_no_proxy(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// Matches one `NO_PROXY` entry: `*`, a domain (which also covers its
+/// subdomains, with or without a leading dot), an IP address or a CIDR range.
+fn no_proxy_matches(entry: &str, host: &str) -> bool {
+    if entry == "*" {
+        return true;
+    }
+
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return match entry.split_once('/') {
+            Some((network, bits)) => match (network.parse::<IpAddr>(), bits.parse::<u32>()) {
+                (Ok(network), Ok(bits)) => cidr_contains(network, bits, ip),
+                _ => false,
+            },
+            None => entry
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .map(|entry_ip| entry_ip == ip)
+                .unwrap_or(false),
+        };
+    }
+
+    let domain = entry.trim_start_matches("*.").trim_start_matches('.');
+    let domain = domain.split(':').next().unwrap_or(domain);
+    let host = host.to_lowercase();
+    let domain = domain.to_lowercase();
+
+    host == domain || host.ends_with(&format!(".{}", domain))
+}
+
+fn cidr_contains(network: IpAddr, bits: u32, ip: IpAddr) -> bool {
+    match (network, ip) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) if bits <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - bits).unwrap_or(0);
+            u32::from(net) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(net), IpAddr::V6(ip)) if bits <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - bits).unwrap_or(0);
+            u128::from(net) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,144 @@
+use crate::cookie::parse_cookie_date;
+use saffron_core::domain::request::{FormDataContent, HttpMethod, HttpRequest, RequestBody};
+use std::time::Duration;
+
+/// When and how often a failed request is sent again.
+///
+/// A request is retried after a transport error (timeout, refused or reset
+/// connection) or a response whose status is in `retry_statuses`. The wait
+/// before retry `n` is `initial_backoff * multiplier^(n-1)`, capped at
+/// `max_backoff` and randomized by `jitter`. A `Retry-After` header replaces
+/// the computed backoff, still capped at `max_backoff`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one. `1` disables retries.
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// Fraction of each delay (`0.0`–`1.0`) that is randomized, so clients
+    /// failing together don't retry together.
+    pub jitter: f64,
+    pub retry_statuses: Vec<u16>,
+    /// Also retry methods that aren't idempotent, such as POST and PATCH.
+    pub retry_non_idempotent: bool,
+    pub respect_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.2,
+            retry_statuses: vec![429, 502, 503, 504],
+            retry_non_idempotent: false,
+            respect_retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// The default policy with `retries` retries after the first attempt.
+    pub fn with_retries(retries: u32) -> Self {
+        Self {
+            max_attempts: retries.saturating_add(1),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    /// Whether `request` may be sent again at all: its method must be
+    /// idempotent (unless allowed otherwise) and its body must be replayable.
+    pub fn allows(&self, request: &HttpRequest) -> bool {
+        self.is_enabled()
+            && (self.retry_non_idempotent || is_idempotent(&request.method))
+            && is_replayable(&request.body)
+    }
+
+    pub fn retries_status(&self, status: u16) -> bool {
+        self.retry_statuses.contains(&status)
+    }
+
+    /// Backoff before retry number `retry` (1-based), without jitter.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(retry.saturating_sub(1) as i32);
+        let secs = self.initial_backoff.as_secs_f64() * factor;
+        Duration::try_from_secs_f64(secs)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// The actual wait before retry number `retry`, given the `Retry-After`
+    /// value of the failed response.
+    pub fn delay(&self, retry: u32, retry_after: Option<Duration>) -> Duration {
+        if self.respect_retry_after
+            && let Some(retry_after) = retry_after
+        {
+            return retry_after.min(self.max_backoff);
+        }
+
+        let backoff = self.backoff(retry);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return backoff;
+        }
+
+        // Spread the delay uniformly over backoff * [1 - jitter, 1 + jitter]
+        let spread = rand::random_range(-1.0..=1.0) * jitter;
+        backoff.mul_f64(1.0 + spread).min(self.max_backoff)
+    }
+}
+
+/// Parses a `Retry-After` value: delay-seconds or an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = parse_cookie_date(value)?;
+    Some(
+        (date - chrono::Utc::now())
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// RFC 9110 §9.2.2.
+fn is_idempotent(method: &HttpMethod) -> bool {
+    matches!(
+        method,
+        HttpMethod::Get
+            | HttpMethod::Head
+            | HttpMethod::Options
+            | HttpMethod::Put
+            | HttpMethod::Delete
+            | HttpMethod::Trace
+    )
+}
+
+/// Bodies read from stdin or another one-shot reader can only be sent once.
+pub(crate) fn is_replayable(body: &RequestBody) -> bool {
+    match body {
+        RequestBody::Stream(stream) => stream.path().is_some(),
+        RequestBody::FormData(parts) => parts.iter().all(|part| match &part.content {
+            FormDataContent::Stream { stream, .. } => stream.path().is_some(),
+            _ => true,
+        }),
+        _ => true,
+    }
+}
@@ -0,0 +1,214 @@
+//! SOCKS4, SOCKS4a and SOCKS5 handshakes, on a connection to the proxy
+//! that the transport opened itself.
+
+use crate::proxy::{Proxy, ProxyScheme};
+use crate::{ConnectConfig, HttpError};
+use std::io::{self, Read, Write};
+use std::net::IpAddr;
+
+/// Asks the SOCKS proxy at the other end of `stream` to connect to
+/// `host:port`. SOCKS4 resolves `host` here, with `config`'s overrides;
+/// SOCKS4a and SOCKS5 leave it to the proxy.
+pub(crate) fn connect<S: Read + Write>(
+    stream: &mut S,
+    proxy: &Proxy,
+    config: &ConnectConfig,
+    host: &str,
+    port: u16,
+) -> Result<(), HttpError> {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let result = match proxy.scheme {
+        ProxyScheme::Socks4 => {
+            let ip = match host.parse::<IpAddr>() {
+                Ok(ip) => ip,
+                Err(_) => config
+                    .lookup(host, port)?
+                    .into_iter()
+                    .map(|addr| addr.ip())
+                    .find(IpAddr::is_ipv4)
+                    .ok_or_else(|| {
+                        HttpError::ProxyError(format!("{}: no IPv4 address for SOCKS4", host))
+                    })?,
+            };
+            socks4(stream, proxy, Target::Ip(ip), port)
+        }
+        ProxyScheme::Socks4a => socks4(stream, proxy, Target::new(host), port),
+        ProxyScheme::Socks5 => socks5(stream, proxy, Target::new(host), port),
+        ProxyScheme::Http => unreachable!("HTTP proxies tunnel with CONNECT"),
+    };
+
+    result.map_err(|e| match e {
+        HttpError::IoError(e) if e.kind() == io::ErrorKind::TimedOut => HttpError::Timeout,
+        HttpError::IoError(e) => HttpError::ProxyError(format!("{}: {}", proxy.host, e)),
+        e => e,
+    })
+}
+
+enum Target<'a> {
+    Ip(IpAddr),
+    Domain(&'a str),
+}
+
+impl<'a> Target<'a> {
+    fn new(host: &'a str) -> Self {
+        match host.parse() {
+            Ok(ip) => Target::Ip(ip),
+            Err(_) => Target::Domain(host),
+        }
+    }
+}
+
+fn socks4<S: Read + Write>(
+    stream: &mut S,
+    proxy: &Proxy,
+    target: Target,
+    port: u16,
+) -> Result<(), HttpError> {
+    let mut request = vec![4, 1];
+    request.extend_from_slice(&port.to_be_bytes());
+    match target {
+        Target::Ip(IpAddr::V4(ip)) => request.extend_from_slice(&ip.octets()),
+        Target::Ip(IpAddr::V6(ip)) => {
+            return Err(HttpError::ProxyError(format!(
+                "SOCKS4 can't connect to the IPv6 address {}",
+                ip
+            )));
+        }
+        // 0.0.0.x asks a SOCKS4a proxy to resolve the name after the user ID
+        Target::Domain(_) => request.extend_from_slice(&[0, 0, 0, 1]),
+    }
+    request.extend_from_slice(proxy.username.as_deref().unwrap_or_default().as_bytes());
+    request.push(0);
+    if let Target::Domain(host) = target {
+        request.extend_from_slice(host.as_bytes());
+        request.push(0);
+    }
+    stream.write_all(&request)?;
+    stream.flush()?;
+
+    let mut reply = [0; 8];
+    stream.read_exact(&mut reply)?;
+    match reply[1] {
+        0x5A => Ok(()),
+        code => Err(HttpError::ProxyError(format!(
+            "SOCKS4 proxy refused the connection (code {:#04x})",
+            code
+        ))),
+    }
+}
+
+fn socks5<S: Read + Write>(
+    stream: &mut S,
+    proxy: &Proxy,
+    target: Target,
+    port: u16,
+) -> Result<(), HttpError> {
+    // No authentication, or username and password (RFC 1929) when given
+    let credentials = proxy.username.as_deref().map(|user| {
+        (
+            user.as_bytes(),
+            proxy.password.as_deref().unwrap_or_default().as_bytes(),
+        )
+    });
+    match credentials {
+        Some(_) => stream.write_all(&[5, 2, 0, 2])?,
+        None => stream.write_all(&[5, 1, 0])?,
+    }
+    stream.flush()?;
+
+    let mut choice = [0; 2];
+    stream.read_exact(&mut choice)?;
+    match (choice[1], credentials) {
+        (0, _) => {}
+        (2, Some((user, pass))) => {
+            let (Ok(user_len), Ok(pass_len)) = (u8::try_from(user.len()), u8::try_from(pass.len()))
+            else {
+                return Err(HttpError::ProxyError(
+                    "SOCKS5 usernames and passwords are limited to 255 bytes".to_string(),
+                ));
+            };
+            let mut auth = vec![1, user_len];
+            auth.extend_from_slice(user);
+            auth.push(pass_len);
+            auth.extend_from_slice(pass);
+            stream.write_all(&auth)?;
+            stream.flush()?;
+
+            let mut status = [0; 2];
+            stream.read_exact(&mut status)?;
+            if status[1] != 0 {
+                return Err(HttpError::ProxyError(
+                    "SOCKS5 proxy rejected the username or password".to_string(),
+                ));
+            }
+        }
+        _ => {
+            return Err(HttpError::ProxyError(
+                "SOCKS5 proxy requires authentication".to_string(),
+            ));
+        }
+    }
+
+    let mut request = vec![5, 1, 0];
+    match target {
+        Target::Ip(IpAddr::V4(ip)) => {
+            request.push(1);
+            request.extend_from_slice(&ip.octets());
+        }
+        Target::Ip(IpAddr::V6(ip)) => {
+            request.push(4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Target::Domain(host) => {
+            let len = u8::try_from(host.len()).map_err(|_| {
+                HttpError::ProxyError(format!("host name too long for SOCKS5: {}", host))
+            })?;
+            request.extend_from_slice(&[3, len]);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request)?;
+    stream.flush()?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply)?;
+    if reply[1] != 0 {
+        return Err(HttpError::ProxyError(format!(
+            "SOCKS5 proxy refused the connection: {}",
+            socks5_reply(reply[1])
+        )));
+    }
+    // The address the proxy bound, which isn't needed
+    let address_len = match reply[3] {
+        1 => 4,
+        4 => 16,
+        3 => {
+            let mut len = [0];
+            stream.read_exact(&mut len)?;
+            usize::from(len[0])
+        }
+        other => {
+            return Err(HttpError::ProxyError(format!(
+                "SOCKS5 proxy sent an unknown address type {}",
+                other
+            )));
+        }
+    };
+    stream.read_exact(&mut vec![0; address_len + 2])?;
+    Ok(())
+}
+
+fn socks5_reply(code: u8) -> &'static str {
+    match code {
+        1 => "general failure",
+        2 => "not allowed by ruleset",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        7 => "command not supported",
+        8 => "address type not supported",
+        _ => "unknown error",
+    }
+}
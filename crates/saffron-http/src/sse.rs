@@ -0,0 +1,372 @@
+use crate::compression::DecodedBody;
+use crate::{HttpClient, HttpError};
+use saffron_core::domain::request::HttpRequest;
+use std::io::{self, BufRead, BufReader};
+use std::thread;
+use std::time::Duration;
+
+/// One event of a `text/event-stream` response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The `event:` field, `"message"` when the server didn't send one.
+    pub event: String,
+    /// The `data:` lines joined with `\n`.
+    pub data: String,
+    /// The last event ID seen on the stream, which is sent back as
+    /// `Last-Event-ID` on reconnect.
+    pub id: Option<String>,
+    /// The reconnection delay, when this event carried a `retry:` field.
+    pub retry: Option<Duration>,
+}
+
+/// Incremental parser for the `text/event-stream` format.
+///
+/// Feed it one line at a time, without the line ending; it returns an event
+/// on each blank line that ends one. Comments and unknown fields are skipped.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+    last_event_id: String,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            // A line starting with a colon is a comment, often a keep-alive
+            "" => {}
+            "event" => self.event = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            "id" if !value.contains('\0') => self.last_event_id = value.to_string(),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(ms) = value.parse() {
+                    self.retry = Some(Duration::from_millis(ms));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// The ID to send as `Last-Event-ID` when reconnecting, if any.
+    pub fn last_event_id(&self) -> Option<&str> {
+        Some(self.last_event_id.as_str()).filter(|id| !id.is_empty())
+    }
+
+    /// Drops a half-received event, as when the connection breaks mid-event.
+    fn reset(&mut self) {
+        self.event = None;
+        self.data = None;
+        self.retry = None;
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        let retry = self.retry.take();
+        // Blocks without data only update the ID and retry delay
+        let data = self.data.take()?;
+
+        Some(SseEvent {
+            event: event
+                .filter(|e| !e.is_empty())
+                .unwrap_or_else(|| "message".to_string()),
+            data,
+            id: self.last_event_id().map(str::to_string),
+            retry,
+        })
+    }
+
+    fn buffered_len(&self) -> usize {
+        self.data.as_ref().map_or(0, String::len) + self.event.as_ref().map_or(0, String::len)
+    }
+}
+
+/// How [`HttpClient::events`] keeps a stream going.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseConfig {
+    /// Reconnect when the stream ends or breaks.
+    pub reconnect: bool,
+    /// Give up after this many reconnects in a row without an event, or keep
+    /// trying when `None`.
+    pub max_reconnects: Option<u32>,
+    /// Wait before reconnecting, until the server sets its own with `retry:`.
+    pub retry: Duration,
+    /// Sent as `Last-Event-ID` on the first connection, to resume a stream.
+    pub last_event_id: Option<String>,
+}
+
+impl Default for SseConfig {
+    fn default() -> Self {
+        Self {
+            reconnect: true,
+            max_reconnects: None,
+            retry: Duration::from_secs(3),
+            last_event_id: None,
+        }
+    }
+}
+
+impl HttpClient {
+    /// Opens a Server-Sent Events stream and returns its events as they
+    /// arrive.
+    ///
+    /// The connection uses the client's redirects, cookies, auth and retries,
+    /// but not its middleware, which needs a complete response. The stream
+    /// has no overall deadline, whatever the request's timeout: it only
+    /// times out when it goes idle for the client timeout. A single line
+    /// longer than `max_response_size` ends it. When the stream ends or
+    /// breaks it is reopened
+    /// with `Last-Event-ID` as set by `config`; a `204 No Content`, a non-2xx
+    /// status or a response that isn't `text/event-stream` ends it for good.
+    pub fn events(&self, request: &HttpRequest, config: SseConfig) -> EventStream<'_> {
+        let mut parser = SseParser::new();
+        if let Some(id) = &config.last_event_id {
+            parser.last_event_id = id.clone();
+        }
+
+        let mut request = request.clone();
+        request.timeout_seconds = None;
+
+        EventStream {
+            client: self,
+            request,
+            retry: config.retry,
+            config,
+            parser,
+            body: None,
+            reconnects: 0,
+            failures: 0,
+            done: false,
+        }
+    }
+}
+
+/// The events of a Server-Sent Events stream, from [`HttpClient::events`].
+///
+/// Yields `Err` once when the stream can't go on, then ends.
+pub struct EventStream<'a> {
+    client: &'a HttpClient,
+    request: HttpRequest,
+    config: SseConfig,
+    retry: Duration,
+    parser: SseParser,
+    body: Option<LineReader>,
+    reconnects: u32,
+    failures: u32,
+    done: bool,
+}
+
+impl EventStream<'_> {
+    /// How many times the stream has been reopened.
+    pub fn reconnects(&self) -> u32 {
+        self.reconnects
+    }
+
+    /// The ID that will be sent as `Last-Event-ID` on the next reconnect.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.parser.last_event_id()
+    }
+
+    /// Sends the request and checks that the response is an event stream.
+    /// `Ok(None)` means the server asked not to reconnect.
+    fn open(&mut self) -> Result<Option<LineReader>, HttpError> {
+        let mut request = self.request.clone();
+        request.headers.insert("Accept", "text/event-stream");
+        request.headers.insert("Cache-Control", "no-cache");
+        if let Some(id) = self.parser.last_event_id() {
+            request.headers.insert("Last-Event-ID", id);
+        }
+
+        let exchange = self.client.execute_with_retry(&request)?;
+        let resp = exchange.resp;
+        if resp.status == 204 {
+            return Ok(None);
+        }
+        if !(200..300).contains(&resp.status) {
+            return Err(HttpError::EventStreamError(format!(
+                "server responded {} {}",
+                resp.status, resp.status_text
+            )));
+        }
+
+        let content_type = resp.headers.get("content-type").unwrap_or_default();
+        let media_type = content_type.split(';').next().unwrap_or_default().trim();
+        if !media_type.eq_ignore_ascii_case("text/event-stream") {
+            return Err(HttpError::EventStreamError(format!(
+                "expected text/event-stream, got '{}'",
+                content_type
+            )));
+        }
+
+        let encoding = resp.headers.get("content-encoding").map(str::to_string);
+        let body = DecodedBody::new(
+            resp.body,
+            encoding.as_deref(),
+            self.client.config.decompress,
+        )?;
+        Ok(Some(LineReader {
+            inner: BufReader::new(body),
+            after_cr: false,
+        }))
+    }
+
+    /// Reads lines until an event is complete. `Ok(None)` means the server
+    /// closed the stream.
+    fn next_event(&mut self) -> Result<Option<SseEvent>, HttpError> {
+        let limit = self.client.config.max_response_size;
+        let Some(body) = &mut self.body else {
+            return Ok(None);
+        };
+
+        let mut line = Vec::new();
+        while body.read_line(&mut line, limit)? {
+            if let Some(event) = self.parser.push_line(&String::from_utf8_lossy(&line)) {
+                return Ok(Some(event));
+            }
+            if let Some(limit) = limit
+                && self.parser.buffered_len() > limit
+            {
+                return Err(HttpError::ResponseTooLarge(limit));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Waits out the retry delay before the next connection attempt, unless
+    /// reconnecting is off or has failed too often.
+    fn wait_to_reconnect(&mut self) -> bool {
+        let allowed = self.config.reconnect
+            && self
+                .config
+                .max_reconnects
+                .is_none_or(|max| self.failures < max);
+        if allowed {
+            self.failures += 1;
+            self.reconnects += 1;
+            thread::sleep(self.retry);
+        }
+        allowed
+    }
+}
+
+impl Iterator for EventStream<'_> {
+    type Item = Result<SseEvent, HttpError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if self.body.is_none() {
+                match self.open() {
+                    Ok(Some(body)) => self.body = Some(body),
+                    Ok(None) => break,
+                    Err(e) if is_transient(&e) && self.wait_to_reconnect() => continue,
+                    Err(e) => {
+                        self.done = true;
+                        return Some(Err(e));
+                    }
+                }
+            }
+
+            match self.next_event() {
+                Ok(Some(event)) => {
+                    if let Some(retry) = event.retry {
+                        self.retry = retry;
+                    }
+                    self.failures = 0;
+                    return Some(Ok(event));
+                }
+                Ok(None) => {}
+                Err(e) if !is_transient(&e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+                Err(_) => {}
+            }
+
+            // The stream ended or broke; start over on a new connection
+            self.body = None;
+            self.parser.reset();
+            if !self.wait_to_reconnect() {
+                break;
+            }
+        }
+
+        self.done = true;
+        None
+    }
+}
+
+/// Errors worth reconnecting after: the connection, not the server, failed.
+fn is_transient(error: &HttpError) -> bool {
+    matches!(
+        error,
+        HttpError::Timeout | HttpError::NetworkError(_) | HttpError::IoError(_)
+    )
+}
+
+/// Splits a body into lines ending in CRLF, LF or a lone CR.
+struct LineReader {
+    inner: BufReader<DecodedBody<'static>>,
+    /// The last line ended in CR, so a leading LF belongs to it. Checked on
+    /// the next read rather than waited for, which would hold back an event.
+    after_cr: bool,
+}
+
+impl LineReader {
+    /// Reads one line of at most `limit` bytes into `line`, without its
+    /// ending. Returns `false` at the end of the stream.
+    fn read_line(&mut self, line: &mut Vec<u8>, limit: Option<usize>) -> Result<bool, HttpError> {
+        line.clear();
+        loop {
+            let buf = match self.inner.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            // A line cut off by the end of the stream is incomplete, so dropped
+            if buf.is_empty() {
+                return Ok(false);
+            }
+            if std::mem::take(&mut self.after_cr) && buf[0] == b'\n' {
+                self.inner.consume(1);
+                continue;
+            }
+
+            let end = buf.iter().position(|&b| b == b'\n' || b == b'\r');
+            let len = end.unwrap_or(buf.len());
+            if let Some(limit) = limit
+                && line.len() + len > limit
+            {
+                return Err(HttpError::ResponseTooLarge(limit));
+            }
+            line.extend_from_slice(&buf[..len]);
+            match end {
+                Some(end) => {
+                    self.after_cr = buf[end] == b'\r';
+                    self.inner.consume(end + 1);
+                    return Ok(true);
+                }
+                None => self.inner.consume(len),
+            }
+        }
+    }
+}
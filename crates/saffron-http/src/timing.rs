@@ -0,0 +1,42 @@
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+/// Connection phases observed while the transport sets up a connection.
+///
+/// The transport resolves, connects and runs the TLS handshake on the calling
+/// thread, so a thread-local is enough to attribute them to the request in
+/// flight.
+#[derive(Debug, Default)]
+pub(crate) struct Phases {
+    pub dns: Option<Duration>,
+    pub connect: Option<Duration>,
+    pub tls: Option<Duration>,
+}
+
+thread_local! {
+    static PHASES: RefCell<Phases> = RefCell::default();
+}
+
+/// Clears the recorded phases before a request is dispatched.
+pub(crate) fn reset() {
+    PHASES.with(|phases| *phases.borrow_mut() = Phases::default());
+}
+
+pub(crate) fn take() -> Phases {
+    PHASES.with(|phases| phases.take())
+}
+
+/// Records a lookup that began at `start` and just finished.
+pub(crate) fn record_dns(start: Instant) {
+    PHASES.with(|phases| phases.borrow_mut().dns = Some(start.elapsed()));
+}
+
+/// Records a connect that began at `start` and just finished.
+pub(crate) fn record_connect(start: Instant) {
+    PHASES.with(|phases| phases.borrow_mut().connect = Some(start.elapsed()));
+}
+
+/// Records a TLS handshake that began at `start` and just finished.
+pub(crate) fn record_tls(start: Instant) {
+    PHASES.with(|phases| phases.borrow_mut().tls = Some(start.elapsed()));
+}
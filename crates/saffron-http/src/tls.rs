@@ -0,0 +1,271 @@
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use saffron_core::domain::environment::TlsSettings;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("server certificate is not trusted (unknown issuer)")]
+    UnknownIssuer,
+
+    #[error("server certificate has expired")]
+    CertificateExpired,
+
+    #[error("server certificate is not valid for {0}")]
+    HostnameMismatch(String),
+
+    #[error("invalid server certificate: {0}")]
+    InvalidCertificate(String),
+
+    #[error("server rejected the handshake: {0}")]
+    AlertReceived(String),
+
+    #[error("no common protocol version with the server")]
+    ProtocolVersion,
+
+    #[error("handshake failed: {0}")]
+    Handshake(String),
+
+    #[error("invalid TLS configuration: {0}")]
+    Config(String),
+}
+
+impl TlsError {
+    pub(crate) fn from_rustls(error: &rustls::Error, host: &str) -> Self {
+        match error {
+            rustls::Error::InvalidCertificate(cert_error) => match cert_error {
+                CertificateError::UnknownIssuer => TlsError::UnknownIssuer,
+                CertificateError::Expired | CertificateError::ExpiredContext { .. } => {
+                    TlsError::CertificateExpired
+                }
+                CertificateError::NotValidForName
+                | CertificateError::NotValidForNameContext { .. } => {
+                    TlsError::HostnameMismatch(host.to_string())
+                }
+                other => TlsError::InvalidCertificate(format!("{:?}", other)),
+            },
+            rustls::Error::AlertReceived(alert) => TlsError::AlertReceived(format!("{:?}", alert)),
+            rustls::Error::PeerIncompatible(
+                rustls::PeerIncompatible::ServerDoesNotSupportTls12Or13,
+            )
+            | rustls::Error::PeerIncompatible(rustls::PeerIncompatible::Tls13RequiredForQuic) => {
+                TlsError::ProtocolVersion
+            }
+            other => TlsError::Handshake(other.to_string()),
+        }
+    }
+
+    /// Finds a rustls error anywhere in the source chain of a transport error.
+    pub(crate) fn find(error: &(dyn std::error::Error + 'static), host: &str) -> Option<Self> {
+        let mut current = Some(error);
+
+        while let Some(err) = current {
+            if let Some(tls) = err.downcast_ref::<rustls::Error>() {
+                return Some(Self::from_rustls(tls, host));
+            }
+
+            // io::Error::source() skips the wrapped error, so look inside explicitly
+            if let Some(inner) = err.downcast_ref::<io::Error>().and_then(|e| e.get_ref())
+                && let Some(tls) = inner.downcast_ref::<rustls::Error>()
+            {
+                return Some(Self::from_rustls(tls, host));
+            }
+
+            current = err.source();
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsVersion {
+    Tls12,
+    Tls13,
+}
+
+impl FromStr for TlsVersion {
+    type Err = TlsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s
+            .to_lowercase()
+            .trim_start_matches("tlsv")
+            .trim_start_matches("tls")
+        {
+            "1.2" => Ok(TlsVersion::Tls12),
+            "1.3" => Ok(TlsVersion::Tls13),
+            _ => Err(TlsError::Config(format!(
+                "unsupported TLS version '{}' (expected 1.2 or 1.3)",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    pub cert_path: PathBuf,
+    /// Private key file. When absent the key is read from `cert_path`.
+    pub key_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub accept_invalid_certs: bool,
+    pub ca_certificates: Vec<PathBuf>,
+    pub client_certificate: Option<ClientCertificate>,
+    pub min_version: Option<TlsVersion>,
+}
+
+impl TlsConfig {
+    pub fn from_settings(settings: &TlsSettings) -> Result<Self, TlsError> {
+        let key_path = settings.client_key.as_ref().map(PathBuf::from);
+        let client_certificate =
+            settings
+                .client_certificate
+                .as_ref()
+                .map(|cert| ClientCertificate {
+                    cert_path: PathBuf::from(cert),
+                    key_path,
+                });
+
+        let min_version = settings
+            .min_version
+            .as_deref()
+            .map(TlsVersion::from_str)
+            .transpose()?;
+
+        Ok(Self {
+            accept_invalid_certs: settings.insecure,
+            ca_certificates: settings.ca_certificates.iter().map(PathBuf::from).collect(),
+            client_certificate,
+            min_version,
+        })
+    }
+
+    /// Whether these are the default settings.
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub(crate) fn build(&self) -> Result<Arc<ClientConfig>, TlsError> {
+        let provider = Arc::new(crypto::ring::default_provider());
+
+        let versions: &[&rustls::SupportedProtocolVersion] = match self.min_version {
+            Some(TlsVersion::Tls13) => &[&rustls::version::TLS13],
+            _ => rustls::DEFAULT_VERSIONS,
+        };
+
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(versions)
+            .map_err(|e| TlsError::Config(e.to_string()))?;
+
+        let builder = if self.accept_invalid_certs {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(NoVerification(provider)))
+        } else {
+            let mut roots = RootCertStore {
+                roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+            };
+            for path in &self.ca_certificates {
+                for cert in load_certificates(path)? {
+                    roots
+                        .add(cert)
+                        .map_err(|e| TlsError::Config(format!("{}: {}", path.display(), e)))?;
+                }
+            }
+            builder.with_root_certificates(roots)
+        };
+
+        let config = match &self.client_certificate {
+            Some(client) => {
+                let chain = load_certificates(&client.cert_path)?;
+                let key_path = client.key_path.as_ref().unwrap_or(&client.cert_path);
+                let key = PrivateKeyDer::from_pem_file(key_path)
+                    .map_err(|e| TlsError::Config(format!("{}: {}", key_path.display(), e)))?;
+                builder
+                    .with_client_auth_cert(chain, key)
+                    .map_err(|e| TlsError::Config(e.to_string()))?
+            }
+            None => builder.with_no_client_auth(),
+        };
+
+        Ok(Arc::new(config))
+    }
+}
+
+fn load_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| TlsError::Config(format!("{}: {}", path.display(), e)))?;
+
+    if certs.is_empty() {
+        return Err(TlsError::Config(format!(
+            "{}: no certificates found",
+            path.display()
+        )));
+    }
+
+    Ok(certs)
+}
+
+/// Certificate verifier used for `--insecure`: accepts any server certificate
+/// but still checks handshake signatures so the connection is well-formed.
+#[derive(Debug)]
+struct NoVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
@@ -0,0 +1,221 @@
+use crate::connect::split_unix_url;
+use crate::http1::{self, Body, Connection, TimedSocket};
+use crate::{ConnectConfig, HttpClientConfig, HttpError, Proxy, ProxyConfig, ProxyScheme, timing};
+use rustls::ClientConfig;
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::HttpRequest;
+use saffron_core::domain::response::{HttpResponse, Timings};
+use std::fmt;
+use std::io::{self, Cursor, Read};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Sends a single request over the wire and returns the response with its
+/// body unread.
+///
+/// [`HttpClient`](crate::HttpClient) handles everything above one hop
+/// (redirects, cookies, retries, auth and decoding) and hands the transport
+/// a request that already carries every header to send. Implement this to
+/// swap out the network layer, for example with [`MockTransport`](crate::MockTransport)
+/// in tests.
+pub trait Transport: Send + Sync {
+    fn send(&self, request: &HttpRequest) -> Result<TransportResponse, HttpError>;
+}
+
+/// A response as received by a [`Transport`], before the client reads the body.
+pub struct TransportResponse {
+    pub status: u16,
+    pub status_text: String,
+    /// The URL that was requested.
+    pub url: String,
+    pub headers: Headers,
+    /// The body exactly as received, still encoded.
+    pub body: Box<dyn Read + Send>,
+    /// DNS, connect and TLS phases, when the transport measures them.
+    pub timings: Timings,
+}
+
+impl TransportResponse {
+    pub fn new(status: u16, status_text: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            status,
+            status_text: status_text.into(),
+            url: url.into(),
+            headers: Headers::new(),
+            body: Box::new(io::empty()),
+            timings: Timings::default(),
+        }
+    }
+
+    pub fn with_headers(mut self, headers: Headers) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Box::new(Cursor::new(body.into()));
+        self
+    }
+}
+
+impl From<HttpResponse> for TransportResponse {
+    fn from(response: HttpResponse) -> Self {
+        Self::new(response.status, response.status_text, response.url)
+            .with_headers(response.headers)
+            .with_body(response.body)
+    }
+}
+
+impl fmt::Debug for TransportResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransportResponse")
+            .field("status", &self.status)
+            .field("status_text", &self.status_text)
+            .field("url", &self.url)
+            .field("headers", &self.headers)
+            .field("timings", &self.timings)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The default transport, speaking HTTP/1.1 over connections it opens
+/// itself with the client's TLS, proxy, connection and timeout settings.
+///
+/// `unix://` URLs are sent through the Unix domain socket they name; see
+/// [`ConnectConfig::unix_socket`](crate::ConnectConfig::unix_socket) for
+/// sending `http://` URLs through a socket instead.
+pub struct UreqTransport {
+    connect: ConnectConfig,
+    proxy: ProxyConfig,
+    timeout: Duration,
+    tls: Arc<ClientConfig>,
+}
+
+impl UreqTransport {
+    pub fn new(config: &HttpClientConfig) -> Result<Self, HttpError> {
+        Ok(Self {
+            connect: config.connect.clone(),
+            proxy: config.proxy.clone(),
+            timeout: Duration::from_secs(config.timeout_seconds),
+            tls: config.tls.build()?,
+        })
+    }
+
+    /// Sends `request` over a connection of our own, directly or through a
+    /// proxy.
+    fn exchange(
+        &self,
+        connect: &ConnectConfig,
+        request: &HttpRequest,
+        url: &Url,
+        proxy: Option<&Proxy>,
+        deadline: Option<Instant>,
+    ) -> Result<http1::Response, HttpError> {
+        let host = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default().unwrap_or(80);
+        let https = url.scheme() == "https";
+        // A stream that can't be opened fails before anything is connected
+        let body = Body::new(request)?;
+
+        let socket = match proxy {
+            // Plain HTTP is forwarded by an HTTP proxy as is
+            Some(proxy) if proxy.scheme == ProxyScheme::Http && !https => {
+                let socket = connect.connect_proxy(proxy, self.timeout)?;
+                let authorization = proxy
+                    .authorization()
+                    .filter(|_| request.get_header("Proxy-Authorization").is_none())
+                    .map(|auth| ("Proxy-Authorization", auth));
+                return http1::exchange(
+                    Box::new(TimedSocket::new(socket, self.timeout, deadline)),
+                    request,
+                    body,
+                    url,
+                    true,
+                    authorization.as_slice(),
+                );
+            }
+            _ => connect.connect_through(proxy, host, port, self.timeout, deadline)?,
+        };
+
+        let stream: Box<dyn Connection> = if https {
+            Box::new(http1::handshake(socket, &self.tls, host)?)
+        } else {
+            Box::new(socket)
+        };
+        http1::exchange(stream, request, body, url, false, &[])
+    }
+}
+
+/// Stands in for a [`UreqTransport`] that couldn't be built from its
+/// config. Each request tries again, so it fails with the same error.
+pub(crate) struct Misconfigured(pub(crate) HttpClientConfig);
+
+impl Transport for Misconfigured {
+    fn send(&self, request: &HttpRequest) -> Result<TransportResponse, HttpError> {
+        UreqTransport::new(&self.0)?.send(request)
+    }
+}
+
+impl Transport for UreqTransport {
+    fn send(&self, request: &HttpRequest) -> Result<TransportResponse, HttpError> {
+        let parsed_url = Url::parse(&request.url)
+            .map_err(|e| HttpError::InvalidUrl(format!("{} ({})", request.url, e)))?;
+
+        // An event stream or gRPC-Web call stays open as long as the server
+        // keeps sending, so without a timeout of its own only idle time is
+        // limited
+        let deadline = match request.timeout_seconds {
+            Some(timeout) => Some(Duration::from_secs(timeout)),
+            None if is_stream(request) => None,
+            None => Some(self.timeout),
+        };
+
+        let unix_connect;
+        let (connect, url, proxy) = if parsed_url.scheme() == "unix" {
+            let (socket, url) = split_unix_url(&parsed_url)?;
+            unix_connect = ConnectConfig {
+                unix_socket: Some(socket),
+                ..ConnectConfig::default()
+            };
+            let url = Url::parse(&url)
+                .map_err(|e| HttpError::InvalidUrl(format!("{} ({})", request.url, e)))?;
+            (&unix_connect, url, None)
+        } else {
+            let proxy = self.proxy.proxy_for(&parsed_url);
+            (&self.connect, parsed_url, proxy)
+        };
+
+        timing::reset();
+        let response = self.exchange(
+            connect,
+            request,
+            &url,
+            proxy,
+            deadline.map(|d| Instant::now() + d),
+        )?;
+        let phases = timing::take();
+
+        Ok(TransportResponse {
+            status: response.status,
+            status_text: response.status_text,
+            // The unix:// URL rather than the one sent through the socket
+            url: request.url.clone(),
+            headers: response.headers,
+            body: response.body,
+            timings: Timings {
+                dns: phases.dns,
+                connect: phases.connect,
+                tls: phases.tls,
+                ..Timings::default()
+            },
+        })
+    }
+}
+
+/// Whether `request` opens a Server-Sent Events stream or a gRPC-Web call.
+fn is_stream(request: &HttpRequest) -> bool {
+    request.get_header("Accept").is_some_and(|accept| {
+        accept.contains("text/event-stream") || accept.contains("application/grpc-web")
+    })
+}
@@ -0,0 +1,651 @@
+use crate::connect::Socket;
+use crate::{HttpClient, HttpError, TlsError, http1};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rustls::pki_types::ServerName;
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::HttpRequest;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::time::Duration;
+use url::Url;
+
+/// Appended to the client key to compute `Sec-WebSocket-Accept` (RFC 6455 §1.3).
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// Largest handshake response accepted, headers included.
+const MAX_HANDSHAKE_SIZE: usize = 64 * 1024;
+
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// A WebSocket message or control frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    /// The closing handshake, with the peer's status code and reason if it
+    /// sent one.
+    Close(Option<CloseFrame>),
+}
+
+/// The status code and reason of a close frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CloseFrame {
+    pub code: u16,
+    pub reason: String,
+}
+
+impl CloseFrame {
+    pub fn new(code: u16, reason: impl Into<String>) -> Self {
+        Self {
+            code,
+            reason: reason.into(),
+        }
+    }
+
+    /// Code 1000, a normal closure.
+    pub fn normal() -> Self {
+        Self::new(1000, "")
+    }
+
+    /// The meaning of the status code, for the codes RFC 6455 and the IANA
+    /// registry define.
+    pub fn description(&self) -> Option<&'static str> {
+        Some(match self.code {
+            1000 => "normal closure",
+            1001 => "going away",
+            1002 => "protocol error",
+            1003 => "unsupported data",
+            1007 => "invalid payload",
+            1008 => "policy violation",
+            1009 => "message too big",
+            1010 => "missing extension",
+            1011 => "internal error",
+            1012 => "service restart",
+            1013 => "try again later",
+            1014 => "bad gateway",
+            _ => return None,
+        })
+    }
+
+    fn is_valid_code(code: u16) -> bool {
+        matches!(code, 1000..=1003 | 1007..=1014 | 3000..=4999)
+    }
+}
+
+impl fmt::Display for CloseFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)?;
+        if let Some(description) = self.description() {
+            write!(f, " ({})", description)?;
+        }
+        if !self.reason.is_empty() {
+            write!(f, ": {}", self.reason)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+/// An open WebSocket connection, from [`HttpClient::websocket`].
+///
+/// Reads block for up to the client timeout and fail with
+/// [`HttpError::Timeout`] when nothing arrives; the connection stays usable,
+/// so a short timeout can be used to poll. Pings are answered automatically.
+pub struct WebSocket {
+    stream: Box<dyn Stream>,
+    /// The underlying socket, to change timeouts and shut down under TLS.
+    socket: Socket,
+    url: String,
+    protocol: Option<String>,
+    response_headers: Headers,
+    /// Bytes received but not yet parsed into frames.
+    buffer: Vec<u8>,
+    /// The opcode and payload of a fragmented message being received.
+    fragments: Option<(u8, Vec<u8>)>,
+    max_message_size: Option<usize>,
+    close_sent: bool,
+    close_received: bool,
+}
+
+impl HttpClient {
+    /// Opens a WebSocket connection to the `ws://` or `wss://` URL of
+    /// `request` (`http://` and `https://` work too).
+    ///
+    /// The upgrade request carries the request's headers, auth, cookies and
+    /// the client's user agent, and uses the client's TLS, proxy and
+    /// connection settings; proxies are always tunnelled through, with
+    /// CONNECT or SOCKS. Add a `Sec-WebSocket-Protocol` header to ask for
+    /// subprotocols. Redirects and Digest auth aren't supported.
+    pub fn websocket(&self, request: &HttpRequest) -> Result<WebSocket, HttpError> {
+        let request = self.authorize_upfront(request)?;
+        let url = Url::parse(&request.url)
+            .map_err(|e| HttpError::InvalidUrl(format!("{} ({})", request.url, e)))?;
+        let secure = match url.scheme() {
+            "ws" | "http" => false,
+            "wss" | "https" => true,
+            other => {
+                return Err(HttpError::InvalidUrl(format!(
+                    "{} (expected a ws:// or wss:// URL, not {}://)",
+                    request.url, other
+                )));
+            }
+        };
+        let host = url
+            .host_str()
+            .ok_or_else(|| HttpError::InvalidUrl(format!("{} (missing host)", request.url)))?;
+        let port = url
+            .port_or_known_default()
+            .unwrap_or(if secure { 443 } else { 80 });
+
+        let key = BASE64.encode(rand::random::<[u8; 16]>());
+        let handshake = self.upgrade_request(&request, &url, &key)?;
+
+        // ws:// uses the proxy for http:// and wss:// the one for https://
+        let mut proxy_url = url.clone();
+        let _ = proxy_url.set_scheme(if secure { "https" } else { "http" });
+        let proxy = self.config.proxy.proxy_for(&proxy_url);
+
+        let timeout = Duration::from_secs(self.config.timeout_seconds);
+        // Same --resolve, --connect-to, local address and Unix socket as
+        // HTTP requests
+        let socket = self
+            .config
+            .connect
+            .connect_through(proxy, host, port, timeout, None)?
+            .into_inner();
+        socket.set_read_timeout(Some(timeout))?;
+        socket.set_write_timeout(Some(timeout))?;
+        socket.set_nodelay()?;
+
+        let server_name = host.trim_start_matches('[').trim_end_matches(']');
+        let stream: Box<dyn Stream> = if secure {
+            let name = ServerName::try_from(server_name.to_string())
+                .map_err(|e| HttpError::InvalidUrl(format!("{} ({})", request.url, e)))?;
+            let connection = rustls::ClientConnection::new(self.config.tls.build()?, name)
+                .map_err(|e| TlsError::from_rustls(&e, server_name))?;
+            Box::new(rustls::StreamOwned::new(connection, socket.try_clone()?))
+        } else {
+            Box::new(socket.try_clone()?)
+        };
+
+        let mut ws = WebSocket {
+            stream,
+            socket,
+            url: request.url.clone(),
+            protocol: None,
+            response_headers: Headers::new(),
+            buffer: Vec::new(),
+            fragments: None,
+            max_message_size: self.config.max_response_size,
+            close_sent: false,
+            close_received: false,
+        };
+
+        ws.handshake(&handshake, &key)
+            .map_err(|e| tls_error(e, server_name))?;
+
+        if let Some(jar) = &self.config.cookie_jar {
+            jar.store_response_cookies(&url, ws.response_headers.get_all("set-cookie"));
+        }
+
+        Ok(ws)
+    }
+
+    fn upgrade_request(
+        &self,
+        request: &HttpRequest,
+        url: &Url,
+        key: &str,
+    ) -> Result<String, HttpError> {
+        let mut target = url.path().to_string();
+        if let Some(query) = url.query() {
+            target.push('?');
+            target.push_str(query);
+        }
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let mut head = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n",
+            target, host, key
+        );
+        // A CR or LF would end the header early and start one of its own
+        let mut push = |name: &str, value: &str| {
+            http1::check_header(name, value)?;
+            head.push_str(&format!("{}: {}\r\n", name, value));
+            Ok::<_, HttpError>(())
+        };
+        if let Some(ua) = &self.config.user_agent
+            && request.get_header("User-Agent").is_none()
+        {
+            push("User-Agent", ua)?;
+        }
+        if let Some(cookies) = self
+            .config
+            .cookie_jar
+            .as_ref()
+            .and_then(|jar| jar.cookie_header(url))
+            && request.get_header("Cookie").is_none()
+        {
+            push("Cookie", &cookies)?;
+        }
+        for header in &request.headers {
+            push(&header.name, &header.value)?;
+        }
+        head.push_str("\r\n");
+        Ok(head)
+    }
+}
+
+impl WebSocket {
+    /// The URL the connection was opened with.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The subprotocol the server picked from `Sec-WebSocket-Protocol`.
+    pub fn protocol(&self) -> Option<&str> {
+        self.protocol.as_deref()
+    }
+
+    /// The headers of the server's `101 Switching Protocols` response.
+    pub fn response_headers(&self) -> &Headers {
+        &self.response_headers
+    }
+
+    /// Whether the closing handshake has started from either side.
+    pub fn is_closed(&self) -> bool {
+        self.close_sent || self.close_received
+    }
+
+    /// How long [`WebSocket::read`] waits for data; `None` waits forever.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), HttpError> {
+        Ok(self.socket.set_read_timeout(timeout)?)
+    }
+
+    pub fn send(&mut self, message: Message) -> Result<(), HttpError> {
+        if self.close_sent {
+            return Err(HttpError::WebSocketError(
+                "the connection is closing".to_string(),
+            ));
+        }
+
+        match message {
+            Message::Text(text) => self.write_frame(OP_TEXT, text.as_bytes()),
+            Message::Binary(data) => self.write_frame(OP_BINARY, &data),
+            Message::Ping(data) => self.write_control(OP_PING, &data),
+            Message::Pong(data) => self.write_control(OP_PONG, &data),
+            Message::Close(frame) => self.send_close(frame),
+        }
+    }
+
+    pub fn send_text(&mut self, text: impl Into<String>) -> Result<(), HttpError> {
+        self.send(Message::Text(text.into()))
+    }
+
+    pub fn send_binary(&mut self, data: impl Into<Vec<u8>>) -> Result<(), HttpError> {
+        self.send(Message::Binary(data.into()))
+    }
+
+    pub fn ping(&mut self, data: impl Into<Vec<u8>>) -> Result<(), HttpError> {
+        self.send(Message::Ping(data.into()))
+    }
+
+    /// Reads the next message, joining fragmented ones.
+    ///
+    /// Pings are answered before they are returned. A close frame from the
+    /// server is answered and returned as [`Message::Close`]; reading after
+    /// that fails.
+    pub fn read(&mut self) -> Result<Message, HttpError> {
+        if self.close_received {
+            return Err(HttpError::WebSocketError(
+                "the connection is closed".to_string(),
+            ));
+        }
+
+        loop {
+            let (fin, opcode, payload) = self.read_frame()?;
+
+            match opcode {
+                OP_TEXT | OP_BINARY if self.fragments.is_some() => {
+                    return Err(self.fail(1002, "new message inside a fragmented one"));
+                }
+                OP_TEXT | OP_BINARY if !fin => self.fragments = Some((opcode, payload)),
+                OP_TEXT | OP_BINARY => return self.message(opcode, payload),
+                OP_CONTINUATION => {
+                    let Some((_, data)) = &mut self.fragments else {
+                        return Err(self.fail(1002, "continuation without a message"));
+                    };
+                    data.extend_from_slice(&payload);
+                    if let Some(limit) = self.max_message_size
+                        && data.len() > limit
+                    {
+                        return Err(self.fail(1009, &format!("message exceeds {} bytes", limit)));
+                    }
+                    if fin {
+                        let (opcode, data) = self.fragments.take().unwrap_or_default();
+                        return self.message(opcode, data);
+                    }
+                }
+                OP_PING => {
+                    if !self.close_sent {
+                        self.write_control(OP_PONG, &payload)?;
+                    }
+                    return Ok(Message::Ping(payload));
+                }
+                OP_PONG => return Ok(Message::Pong(payload)),
+                OP_CLOSE => return self.receive_close(&payload),
+                other => {
+                    return Err(self.fail(1002, &format!("unknown opcode {:#x}", other)));
+                }
+            }
+        }
+    }
+
+    /// Starts the closing handshake and waits for the server's close frame,
+    /// discarding messages that arrive in the meantime.
+    pub fn close(&mut self, frame: Option<CloseFrame>) -> Result<Option<CloseFrame>, HttpError> {
+        if !self.close_sent {
+            self.send_close(frame)?;
+        }
+
+        let mut reply = None;
+        while !self.close_received {
+            match self.read() {
+                Ok(Message::Close(frame)) => reply = frame,
+                Ok(_) => {}
+                // The server may drop the connection instead of answering
+                Err(HttpError::Timeout | HttpError::IoError(_) | HttpError::WebSocketError(_)) => {
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let _ = self.socket.shutdown(Shutdown::Both);
+        Ok(reply)
+    }
+
+    /// Sends the upgrade request and checks the server's answer. Bytes after
+    /// the response head are kept as the first frames.
+    fn handshake(&mut self, request: &str, key: &str) -> Result<(), HttpError> {
+        self.stream.write_all(request.as_bytes())?;
+        self.stream.flush()?;
+
+        let head_end = loop {
+            if let Some(pos) = find(&self.buffer, b"\r\n\r\n") {
+                break pos;
+            }
+            if self.buffer.len() > MAX_HANDSHAKE_SIZE {
+                return Err(HttpError::WebSocketError(
+                    "handshake response is too large".to_string(),
+                ));
+            }
+            self.fill()?;
+        };
+
+        let head = String::from_utf8_lossy(&self.buffer[..head_end]).into_owned();
+        self.buffer.drain(..head_end + 4);
+
+        let mut lines = head.split("\r\n");
+        let status_line = lines.next().unwrap_or_default();
+        let mut parts = status_line.splitn(3, ' ');
+        let _version = parts.next();
+        let status: u16 = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+        let status_text = parts.next().unwrap_or_default();
+
+        for line in lines {
+            if let Some((name, value)) = line.split_once(':') {
+                self.response_headers.append(name.trim(), value.trim());
+            }
+        }
+
+        if status != 101 {
+            return Err(HttpError::WebSocketError(format!(
+                "server responded {} {} instead of switching protocols",
+                status, status_text
+            )));
+        }
+
+        let headers = &self.response_headers;
+        if !headers
+            .get("upgrade")
+            .is_some_and(|v| v.eq_ignore_ascii_case("websocket"))
+        {
+            return Err(HttpError::WebSocketError(
+                "server didn't upgrade to websocket".to_string(),
+            ));
+        }
+        if headers.get("sec-websocket-accept") != Some(accept_key(key).as_str()) {
+            return Err(HttpError::WebSocketError(
+                "server sent a wrong Sec-WebSocket-Accept".to_string(),
+            ));
+        }
+
+        self.protocol = headers.get("sec-websocket-protocol").map(str::to_string);
+        Ok(())
+    }
+
+    /// Reads more bytes into the buffer. A timeout leaves the buffer as it
+    /// was, so a partly received frame isn't lost.
+    fn fill(&mut self) -> Result<(), HttpError> {
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => {
+                    self.close_received = true;
+                    return Err(HttpError::WebSocketError(
+                        "connection closed without a close frame".to_string(),
+                    ));
+                }
+                Ok(n) => {
+                    self.buffer.extend_from_slice(&chunk[..n]);
+                    return Ok(());
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Err(HttpError::Timeout);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Reads one frame and returns its FIN bit, opcode and unmasked payload.
+    fn read_frame(&mut self) -> Result<(bool, u8, Vec<u8>), HttpError> {
+        loop {
+            if let Some(frame) = self.parse_frame()? {
+                return Ok(frame);
+            }
+            self.fill()?;
+        }
+    }
+
+    fn parse_frame(&mut self) -> Result<Option<(bool, u8, Vec<u8>)>, HttpError> {
+        let buf = &self.buffer;
+        if buf.len() < 2 {
+            return Ok(None);
+        }
+
+        let fin = buf[0] & 0x80 != 0;
+        let rsv = buf[0] & 0x70;
+        let opcode = buf[0] & 0x0F;
+        let masked = buf[1] & 0x80 != 0;
+
+        let (len, offset): (u64, usize) = match buf[1] & 0x7F {
+            126 if buf.len() >= 4 => (u16::from_be_bytes([buf[2], buf[3]]) as u64, 4),
+            127 if buf.len() >= 10 => {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(&buf[2..10]);
+                (u64::from_be_bytes(bytes), 10)
+            }
+            126 | 127 => return Ok(None),
+            len => (len as u64, 2),
+        };
+
+        if rsv != 0 {
+            return Err(self.fail(1002, "reserved bits set without an extension"));
+        }
+        if masked {
+            return Err(self.fail(1002, "server frames must not be masked"));
+        }
+        if opcode >= OP_CLOSE && (!fin || len > 125) {
+            return Err(self.fail(1002, "control frames can't be fragmented or over 125 bytes"));
+        }
+        if let Some(limit) = self.max_message_size
+            && len > limit as u64
+        {
+            return Err(self.fail(1009, &format!("message exceeds {} bytes", limit)));
+        }
+
+        // A length from the wire may not fit in memory, let alone the buffer
+        let Some(end) = usize::try_from(len)
+            .ok()
+            .and_then(|len| offset.checked_add(len))
+        else {
+            return Err(self.fail(1009, &format!("frame of {} bytes is too large", len)));
+        };
+        if self.buffer.len() < end {
+            return Ok(None);
+        }
+        let payload = self.buffer[offset..end].to_vec();
+        self.buffer.drain(..end);
+
+        Ok(Some((fin, opcode, payload)))
+    }
+
+    fn message(&mut self, opcode: u8, payload: Vec<u8>) -> Result<Message, HttpError> {
+        if opcode == OP_BINARY {
+            return Ok(Message::Binary(payload));
+        }
+        match String::from_utf8(payload) {
+            Ok(text) => Ok(Message::Text(text)),
+            Err(_) => Err(self.fail(1007, "text message is not valid UTF-8")),
+        }
+    }
+
+    fn receive_close(&mut self, payload: &[u8]) -> Result<Message, HttpError> {
+        let frame = match payload {
+            [] => None,
+            [_] => return Err(self.fail(1002, "close frame with a one-byte payload")),
+            [hi, lo, reason @ ..] => {
+                let code = u16::from_be_bytes([*hi, *lo]);
+                let Ok(reason) = std::str::from_utf8(reason) else {
+                    return Err(self.fail(1007, "close reason is not valid UTF-8"));
+                };
+                if !CloseFrame::is_valid_code(code) {
+                    return Err(self.fail(1002, &format!("invalid close code {}", code)));
+                }
+                Some(CloseFrame::new(code, reason))
+            }
+        };
+
+        self.close_received = true;
+        if !self.close_sent {
+            // Echo the code, as RFC 6455 §5.5.1 suggests
+            let echo = frame.as_ref().map(|f| CloseFrame::new(f.code, ""));
+            let _ = self.send_close(echo);
+        }
+        Ok(Message::Close(frame))
+    }
+
+    fn send_close(&mut self, frame: Option<CloseFrame>) -> Result<(), HttpError> {
+        let mut payload = Vec::new();
+        if let Some(frame) = &frame {
+            payload.extend_from_slice(&frame.code.to_be_bytes());
+            payload.extend_from_slice(frame.reason.as_bytes());
+        }
+        self.write_control(OP_CLOSE, &payload)?;
+        self.close_sent = true;
+        Ok(())
+    }
+
+    /// Closes the connection after a protocol violation by the server.
+    fn fail(&mut self, code: u16, reason: &str) -> HttpError {
+        if !self.close_sent {
+            let _ = self.send_close(Some(CloseFrame::new(code, "")));
+        }
+        self.close_received = true;
+        let _ = self.socket.shutdown(Shutdown::Both);
+        HttpError::WebSocketError(reason.to_string())
+    }
+
+    fn write_control(&mut self, opcode: u8, payload: &[u8]) -> Result<(), HttpError> {
+        if payload.len() > 125 {
+            return Err(HttpError::WebSocketError(
+                "control frame payloads are limited to 125 bytes".to_string(),
+            ));
+        }
+        self.write_frame(opcode, payload)
+    }
+
+    /// Writes a single, final frame masked with a fresh key, as clients must.
+    fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<(), HttpError> {
+        let mut frame = Vec::with_capacity(payload.len() + 14);
+        frame.push(0x80 | opcode);
+        match payload.len() {
+            len @ 0..=125 => frame.push(0x80 | len as u8),
+            len @ 126..=0xFFFF => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+
+        let mask: [u8; 4] = rand::random();
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+
+        self.stream.write_all(&frame)?;
+        self.stream.flush()?;
+        Ok(())
+    }
+}
+
+/// The `Sec-WebSocket-Accept` value a server must send for `key`.
+fn accept_key(key: &str) -> String {
+    let digest = ring::digest::digest(
+        &ring::digest::SHA1_FOR_LEGACY_USE_ONLY,
+        format!("{}{}", key, ACCEPT_GUID).as_bytes(),
+    );
+    BASE64.encode(digest.as_ref())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Surfaces a TLS failure during the handshake as [`HttpError::TlsError`].
+pub(crate) fn tls_error(error: HttpError, host: &str) -> HttpError {
+    match &error {
+        HttpError::IoError(e) => match TlsError::find(e, host) {
+            Some(tls) => HttpError::TlsError(tls),
+            None => error,
+        },
+        _ => error,
+    }
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "async")]
+
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::HttpMethod;
+use saffron_core::domain::response::HttpResponse;
+use saffron_http::{AsyncHttpClient, HttpClient, HttpClientConfig, HttpError, MockTransport};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+/// Wakes the test thread that is blocked on a future.
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs `future` to completion on the current thread, without a runtime.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+fn client() -> AsyncHttpClient {
+    let mock = MockTransport::new().with_response(
+        HttpMethod::Get,
+        "https://api.example.com/*",
+        HttpResponse::new(
+            200,
+            "OK".to_string(),
+            Headers::new(),
+            b"pong".to_vec(),
+            Duration::ZERO,
+            String::new(),
+        ),
+    );
+    AsyncHttpClient::new(
+        HttpClient::with_transport(HttpClientConfig::default(), mock),
+        4,
+    )
+}
+
+#[test]
+fn test_async_send() {
+    let client = client();
+
+    let response = block_on(client.get("https://api.example.com/ping")).unwrap();
+
+    assert_eq!(response.body_as_str(), Some("pong"));
+}
+
+#[test]
+fn test_async_many_in_flight() {
+    let client = client();
+
+    let futures: Vec<_> = (0..16)
+        .map(|i| client.get(&format!("https://api.example.com/{}", i)))
+        .collect();
+    let responses: Vec<_> = futures.into_iter().map(block_on).collect();
+
+    assert!(responses.iter().all(|r| r.as_ref().unwrap().status == 200));
+}
+
+#[test]
+fn test_async_errors_resolve() {
+    let client = client();
+
+    let result = block_on(client.get("https://other.example.com/"));
+
+    assert!(matches!(result, Err(HttpError::RequestFailed(_))));
+}
@@ -0,0 +1,114 @@
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::{HttpMethod, HttpRequest};
+use saffron_core::domain::response::HttpResponse;
+use saffron_http::{
+    BatchConfig, HttpClient, HttpClientConfig, HttpError, MockTransport, Transport,
+    TransportResponse,
+};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+fn canned(body: &str) -> HttpResponse {
+    HttpResponse::new(
+        200,
+        "OK".to_string(),
+        Headers::new(),
+        body.as_bytes().to_vec(),
+        Duration::ZERO,
+        String::new(),
+    )
+}
+
+/// Answers every request after a short pause, tracking how many are in
+/// flight at once.
+#[derive(Default)]
+struct SlowTransport {
+    in_flight: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+impl Transport for SlowTransport {
+    fn send(&self, request: &HttpRequest) -> Result<TransportResponse, HttpError> {
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(now, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(20));
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        Ok(TransportResponse::new(200, "OK", &request.url))
+    }
+}
+
+#[test]
+fn test_batch_results_keep_request_order() {
+    let mut mock = MockTransport::new();
+    let mut requests = Vec::new();
+    for i in 0..20 {
+        let url = format!("https://api.example.com/{}", i);
+        mock = mock.with_response(HttpMethod::Get, &url, canned(&i.to_string()));
+        requests.push(HttpRequest::get(url));
+    }
+    let client = HttpClient::with_transport(HttpClientConfig::default(), mock);
+
+    let results = client.send_batch(&requests, &BatchConfig::with_concurrency(4));
+
+    assert_eq!(results.len(), 20);
+    for (i, result) in results.iter().enumerate() {
+        assert_eq!(result.index, i);
+        let response = result.result.as_ref().unwrap();
+        assert_eq!(response.body_as_str(), Some(i.to_string().as_str()));
+    }
+}
+
+#[test]
+fn test_batch_failures_are_per_request() {
+    let mock = MockTransport::new().with_response(
+        HttpMethod::Get,
+        "https://api.example.com/ok",
+        canned("ok"),
+    );
+    let client = HttpClient::with_transport(HttpClientConfig::default(), mock);
+    let requests = [
+        HttpRequest::get("https://api.example.com/ok"),
+        HttpRequest::get("https://api.example.com/missing"),
+        HttpRequest::get("not a url"),
+        HttpRequest::get("https://api.example.com/ok"),
+    ];
+
+    let results = client.send_batch(&requests, &BatchConfig::default());
+
+    assert!(results[0].result.is_ok());
+    assert!(matches!(
+        results[1].result,
+        Err(HttpError::RequestFailed(_))
+    ));
+    assert!(matches!(results[2].result, Err(HttpError::InvalidUrl(_))));
+    assert!(results[3].result.is_ok());
+}
+
+#[test]
+fn test_batch_concurrency_is_bounded() {
+    let transport = SlowTransport::default();
+    let peak = transport.peak.clone();
+    let client = HttpClient::with_transport(HttpClientConfig::default(), transport);
+    let requests: Vec<_> = (0..12)
+        .map(|i| HttpRequest::get(format!("https://api.example.com/{}", i)))
+        .collect();
+
+    let mut finished = 0;
+    let results = client.send_batch_with(&requests, &BatchConfig::with_concurrency(3), |_| {
+        finished += 1;
+    });
+
+    assert_eq!(finished, 12);
+    assert!(results.iter().all(|r| r.result.is_ok()));
+    let peak = peak.load(Ordering::SeqCst);
+    assert!((2..=3).contains(&peak), "peak concurrency was {}", peak);
+}
+
+#[test]
+fn test_empty_batch() {
+    let client = HttpClient::with_transport(HttpClientConfig::default(), MockTransport::new());
+    assert!(client.send_batch(&[], &BatchConfig::default()).is_empty());
+}
@@ -0,0 +1,114 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::{self, JoinHandle};
+
+/// Starts a throwaway HTTP server on localhost that answers each incoming
+/// connection with the next canned response, in order.
+///
+/// Returns the base URL and a handle yielding the raw requests received.
+pub fn serve(responses: Vec<impl Into<Vec<u8>>>) -> (String, JoinHandle<Vec<String>>) {
+    let responses: Vec<Vec<u8>> = responses.into_iter().map(Into::into).collect();
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind test server");
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let mut received = Vec::new();
+
+        for response in responses {
+            let (mut stream, _) = match listener.accept() {
+                Ok(conn) => conn,
+                Err(_) => break,
+            };
+
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut raw = String::new();
+            let mut content_length = 0;
+            let mut chunked = false;
+
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap_or(0);
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("transfer-encoding")
+                    && value.trim().eq_ignore_ascii_case("chunked")
+                {
+                    chunked = true;
+                }
+                raw.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+
+            let body = if chunked {
+                read_chunked(&mut reader)
+            } else {
+                let mut body = vec![0; content_length];
+                let _ = reader.read_exact(&mut body);
+                body
+            };
+            raw.push_str(&String::from_utf8_lossy(&body));
+            received.push(raw);
+
+            let _ = stream.write_all(&response);
+            let _ = stream.flush();
+        }
+
+        received
+    });
+
+    (base_url, handle)
+}
+
+/// Decodes a chunked request body.
+fn read_chunked(reader: &mut impl BufRead) -> Vec<u8> {
+    let mut body = Vec::new();
+
+    loop {
+        let mut size_line = String::new();
+        if reader.read_line(&mut size_line).unwrap_or(0) == 0 {
+            break;
+        }
+        let size = usize::from_str_radix(size_line.trim(), 16).unwrap_or(0);
+
+        let mut chunk = vec![0; size + 2];
+        if reader.read_exact(&mut chunk).is_err() {
+            break;
+        }
+        if size == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..size]);
+    }
+
+    body
+}
+
+/// Builds a `Connection: close` response with the given status line, extra
+/// headers and body.
+pub fn response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+    String::from_utf8(binary_response(status, headers, body.as_bytes())).unwrap()
+}
+
+/// Like [`response`], for bodies that aren't text.
+pub fn binary_response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+    let mut out = format!("HTTP/1.1 {}\r\n", status);
+    for (name, value) in headers {
+        out.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    out.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    ));
+
+    let mut out = out.into_bytes();
+    out.extend_from_slice(body);
+    out
+}
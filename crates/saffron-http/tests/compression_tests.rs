@@ -0,0 +1,177 @@
+mod common;
+
+use common::{binary_response, response, serve};
+use flate2::Compression;
+use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+use saffron_core::domain::request::HttpRequest;
+use saffron_http::{HttpClient, HttpClientConfig, HttpError};
+use std::io::Write;
+
+const PAYLOAD: &str = r#"{"items": ["cdn", "cdn", "cdn", "cdn", "cdn", "cdn", "cdn", "cdn"]}"#;
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn brotli(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut out, 4096, 5, 22);
+        writer.write_all(data).unwrap();
+    }
+    out
+}
+
+fn zstd(data: &[u8]) -> Vec<u8> {
+    ruzstd::encoding::compress_to_vec(data, ruzstd::encoding::CompressionLevel::Fastest)
+}
+
+/// Serves `body` with `Content-Encoding: encoding` and returns the decoded
+/// response and the raw request.
+fn fetch_encoded(
+    encoding: &str,
+    body: &[u8],
+) -> (saffron_core::domain::response::HttpResponse, String) {
+    let (base, server) = serve(vec![binary_response(
+        "200 OK",
+        &[("Content-Encoding", encoding)],
+        body,
+    )]);
+
+    let response = HttpClient::new().get(&base).unwrap();
+    (response, server.join().unwrap().remove(0))
+}
+
+#[test]
+fn test_advertises_supported_encodings() {
+    let (_, request) = fetch_encoded("gzip", &gzip(PAYLOAD.as_bytes()));
+
+    assert!(request.contains("Accept-Encoding: gzip, deflate, br, zstd\r\n"));
+}
+
+#[test]
+fn test_decodes_gzip_with_sizes() {
+    let encoded = gzip(PAYLOAD.as_bytes());
+    let (response, _) = fetch_encoded("gzip", &encoded);
+
+    assert_eq!(response.body_as_str(), Some(PAYLOAD));
+    assert_eq!(response.decoded_encoding.as_deref(), Some("gzip"));
+    assert_eq!(response.raw_size, encoded.len() as u64);
+    assert_eq!(response.decoded_size, PAYLOAD.len() as u64);
+    assert_eq!(response.content_length(), Some(encoded.len()));
+}
+
+#[test]
+fn test_decodes_zlib_and_raw_deflate() {
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+    zlib.write_all(PAYLOAD.as_bytes()).unwrap();
+    let (response, _) = fetch_encoded("deflate", &zlib.finish().unwrap());
+    assert_eq!(response.body_as_str(), Some(PAYLOAD));
+
+    let mut raw = DeflateEncoder::new(Vec::new(), Compression::default());
+    raw.write_all(PAYLOAD.as_bytes()).unwrap();
+    let (response, _) = fetch_encoded("deflate", &raw.finish().unwrap());
+    assert_eq!(response.body_as_str(), Some(PAYLOAD));
+}
+
+#[test]
+fn test_decodes_brotli_and_zstd() {
+    let (response, _) = fetch_encoded("br", &brotli(PAYLOAD.as_bytes()));
+    assert_eq!(response.body_as_str(), Some(PAYLOAD));
+    assert_eq!(response.decoded_encoding.as_deref(), Some("br"));
+
+    let (response, _) = fetch_encoded("zstd", &zstd(PAYLOAD.as_bytes()));
+    assert_eq!(response.body_as_str(), Some(PAYLOAD));
+}
+
+#[test]
+fn test_decodes_stacked_encodings_in_reverse() {
+    let encoded = brotli(&gzip(PAYLOAD.as_bytes()));
+    let (response, _) = fetch_encoded("gzip, br", &encoded);
+
+    assert_eq!(response.body_as_str(), Some(PAYLOAD));
+    assert_eq!(response.decoded_encoding.as_deref(), Some("gzip, br"));
+}
+
+#[test]
+fn test_unknown_encoding_is_left_alone() {
+    let (response, _) = fetch_encoded("compress", b"\x1f\x9d\x90");
+
+    assert_eq!(response.body, b"\x1f\x9d\x90");
+    assert!(!response.was_decoded());
+    assert_eq!(response.raw_size, 3);
+}
+
+#[test]
+fn test_raw_mode_skips_negotiation_and_decoding() {
+    let encoded = gzip(PAYLOAD.as_bytes());
+    let (base, server) = serve(vec![binary_response(
+        "200 OK",
+        &[("Content-Encoding", "gzip")],
+        &encoded,
+    )]);
+
+    let client = HttpClient::with_config(HttpClientConfig {
+        compressed: false,
+        decompress: false,
+        ..Default::default()
+    });
+    let response = client.get(&base).unwrap();
+
+    assert_eq!(response.body, encoded);
+    assert!(!response.was_decoded());
+    assert!(!server.join().unwrap()[0].contains("Accept-Encoding"));
+}
+
+#[test]
+fn test_explicit_accept_encoding_wins() {
+    let (base, server) = serve(vec![response("200 OK", &[], "plain")]);
+
+    let request = HttpRequest::get(&base).with_header("Accept-Encoding", "br");
+    HttpClient::new().send(&request).unwrap();
+
+    let received = server.join().unwrap();
+    assert!(received[0].contains("Accept-Encoding: br\r\n"));
+    assert!(!received[0].contains("gzip"));
+}
+
+#[test]
+fn test_empty_encoded_body() {
+    let (response, _) = fetch_encoded("gzip", b"");
+
+    assert!(response.body.is_empty());
+    assert!(!response.was_decoded());
+}
+
+#[test]
+fn test_corrupt_body_is_a_decode_error() {
+    let (base, _server) = serve(vec![binary_response(
+        "200 OK",
+        &[("Content-Encoding", "gzip")],
+        b"definitely not gzip",
+    )]);
+
+    let result = HttpClient::new().get(&base);
+    assert!(matches!(result, Err(HttpError::DecodeError(encoding, _)) if encoding == "gzip"));
+}
+
+#[test]
+fn test_size_limit_applies_to_decoded_body() {
+    let bomb = gzip(&vec![b'a'; 64 * 1024]);
+    let (base, _server) = serve(vec![binary_response(
+        "200 OK",
+        &[("Content-Encoding", "gzip")],
+        &bomb,
+    )]);
+
+    let client = HttpClient::with_config(HttpClientConfig {
+        max_response_size: Some(1024),
+        ..Default::default()
+    });
+    assert!(matches!(
+        client.get(&base),
+        Err(HttpError::ResponseTooLarge(1024))
+    ));
+}
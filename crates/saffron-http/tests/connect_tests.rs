@@ -0,0 +1,310 @@
+mod common;
+
+use common::{response, serve};
+use saffron_core::domain::environment::ConnectSettings;
+use saffron_http::{
+    ConnectConfig, ConnectTo, HttpClient, HttpClientConfig, HttpError, IpVersion, ResolveOverride,
+};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+fn client(connect: ConnectConfig) -> HttpClient {
+    HttpClient::with_config(HttpClientConfig {
+        connect,
+        ..Default::default()
+    })
+}
+
+fn port(base: &str) -> u16 {
+    base.rsplit(':').next().unwrap().parse().unwrap()
+}
+
+#[test]
+fn test_parse_resolve() {
+    let pinned: ResolveOverride = "api.example.com:443:10.0.0.1,[2001:db8::1]"
+        .parse()
+        .unwrap();
+
+    assert_eq!(pinned.host, "api.example.com");
+    assert_eq!(pinned.port, 443);
+    assert_eq!(
+        pinned.addresses,
+        [
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            "2001:db8::1".parse::<IpAddr>().unwrap()
+        ]
+    );
+
+    for invalid in [
+        "api.example.com:443",
+        ":443:10.0.0.1",
+        "host:https:10.0.0.1",
+        "host:443:nope",
+    ] {
+        assert!(
+            matches!(
+                invalid.parse::<ResolveOverride>(),
+                Err(HttpError::InvalidConnectOption(_))
+            ),
+            "{}",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_parse_connect_to() {
+    let rule: ConnectTo = "example.com:443:[::1]:8443".parse().unwrap();
+    assert_eq!(rule.from_host.as_deref(), Some("example.com"));
+    assert_eq!(rule.from_port, Some(443));
+    assert_eq!(rule.to_host.as_deref(), Some("::1"));
+    assert_eq!(rule.to_port, Some(8443));
+
+    // Empty fields match anything and keep the original host or port
+    assert_eq!(
+        "::backend:".parse::<ConnectTo>().unwrap(),
+        ConnectTo {
+            to_host: Some("backend".to_string()),
+            ..Default::default()
+        }
+    );
+
+    assert!("example.com:443:backend".parse::<ConnectTo>().is_err());
+    assert!("example.com:x:backend:80".parse::<ConnectTo>().is_err());
+}
+
+#[test]
+fn test_lookup_applies_connect_to_then_resolve() {
+    let config = ConnectConfig {
+        connect_to: vec!["www.example.com:443:blue.internal:8443".parse().unwrap()],
+        resolve: vec!["blue.internal:8443:10.0.0.7".parse().unwrap()],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        config.lookup("www.example.com", 443).unwrap(),
+        ["10.0.0.7:8443".parse::<SocketAddr>().unwrap()]
+    );
+}
+
+#[test]
+fn test_lookup_filters_by_ip_version() {
+    let mut config = ConnectConfig {
+        resolve: vec!["dual.test:80:10.0.0.1,[::1]".parse().unwrap()],
+        ..Default::default()
+    };
+
+    config.ip_version = IpVersion::V6;
+    assert_eq!(
+        config.lookup("dual.test", 80).unwrap(),
+        [SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 80)]
+    );
+
+    config.ip_version = IpVersion::V4;
+    assert_eq!(config.lookup("dual.test", 80).unwrap().len(), 1);
+
+    // Binding to an IPv6 address rules out IPv4 targets
+    config.local_address = Some(IpAddr::V6(Ipv6Addr::LOCALHOST));
+    assert!(config.lookup("dual.test", 80).is_err());
+}
+
+#[test]
+fn test_resolve_keeps_host_header() {
+    let (base, server) = serve(vec![response("200 OK", &[], "blue")]);
+    let port = port(&base);
+    let client = client(ConnectConfig {
+        resolve: vec![
+            format!("api.example.test:{}:127.0.0.1", port)
+                .parse()
+                .unwrap(),
+        ],
+        ..Default::default()
+    });
+
+    let response = client
+        .get(&format!("http://api.example.test:{}/health", port))
+        .unwrap();
+
+    assert_eq!(response.body_as_str(), Some("blue"));
+    let request = &server.join().unwrap()[0];
+    assert!(
+        request
+            .to_lowercase()
+            .contains(&format!("host: api.example.test:{}", port))
+    );
+}
+
+#[test]
+fn test_connect_to_another_port() {
+    let (base, server) = serve(vec![response("200 OK", &[], "green")]);
+    let client = client(ConnectConfig {
+        connect_to: vec![
+            format!("example.invalid:80:127.0.0.1:{}", port(&base))
+                .parse()
+                .unwrap(),
+        ],
+        ..Default::default()
+    });
+
+    let response = client.get("http://example.invalid/").unwrap();
+
+    assert_eq!(response.body_as_str(), Some("green"));
+    assert!(
+        server.join().unwrap()[0]
+            .to_lowercase()
+            .contains("host: example.invalid\r\n")
+    );
+}
+
+#[test]
+fn test_settings_are_validated() {
+    let settings = ConnectSettings {
+        resolve: vec!["a.test:80:127.0.0.1".to_string()],
+        connect_to: vec!["::b.test:".to_string()],
+        ip_version: Some("4".to_string()),
+        local_address: Some("127.0.0.1".to_string()),
+        unix_socket: Some("/var/run/docker.sock".to_string()),
+    };
+    let config = ConnectConfig::from_settings(&settings).unwrap();
+    assert_eq!(config.ip_version, IpVersion::V4);
+    assert_eq!(config.local_address, Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+    assert_eq!(
+        config.unix_socket.as_deref(),
+        Some(std::path::Path::new("/var/run/docker.sock"))
+    );
+
+    let bad_version = ConnectSettings {
+        ip_version: Some("5".to_string()),
+        ..Default::default()
+    };
+    assert!(ConnectConfig::from_settings(&bad_version).is_err());
+
+    let no_such_interface = ConnectSettings {
+        local_address: Some("saffron-none0".to_string()),
+        ..Default::default()
+    };
+    assert!(matches!(
+        ConnectConfig::from_settings(&no_such_interface),
+        Err(HttpError::InvalidConnectOption(_))
+    ));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_local_address_binding() {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    // All of 127.0.0.0/8 is loopback on Linux, so the peer address shows the bind
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = std::thread::spawn(move || {
+        let (mut stream, peer) = listener.accept().unwrap();
+        let mut buf = [0; 1024];
+        let _ = stream.read(&mut buf).unwrap();
+        let body = peer.ip().to_string();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+    });
+
+    let settings = ConnectSettings {
+        local_address: Some("127.0.0.2".to_string()),
+        ..Default::default()
+    };
+    let client = client(ConnectConfig::from_settings(&settings).unwrap());
+
+    let response = client.get(&format!("http://localhost:{}/", port)).unwrap();
+
+    assert_eq!(response.body_as_str(), Some("127.0.0.2"));
+    server.join().unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_interface_name() {
+    let settings = ConnectSettings {
+        local_address: Some("lo".to_string()),
+        ip_version: Some("4".to_string()),
+        ..Default::default()
+    };
+
+    let config = ConnectConfig::from_settings(&settings).unwrap();
+
+    assert_eq!(config.local_address, Some(IpAddr::V4(Ipv4Addr::LOCALHOST)));
+}
+
+/// Serves one response on a fresh Unix socket and returns the request it got.
+#[cfg(unix)]
+fn serve_unix(name: &str) -> (std::path::PathBuf, std::thread::JoinHandle<String>) {
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixListener;
+
+    let path = std::env::temp_dir().join(format!("saffron-{}-{}.sock", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let n = stream.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        stream
+            .write_all(response("200 OK", &[("Content-Type", "application/json")], "{}").as_bytes())
+            .unwrap();
+        String::from_utf8(request).unwrap()
+    });
+
+    (path, server)
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix_socket() {
+    let (path, server) = serve_unix("config");
+    let client = client(ConnectConfig {
+        unix_socket: Some(path.clone()),
+        ..Default::default()
+    });
+
+    let response = client.get("http://docker/v1.43/info").unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body_as_str(), Some("{}"));
+    let request = server.join().unwrap().to_lowercase();
+    assert!(request.starts_with("get /v1.43/info http/1.1"));
+    assert!(request.contains("host: docker"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix_url() {
+    let (path, server) = serve_unix("url");
+    let url = format!("unix://{}/containers/json?all=1", path.display());
+
+    let response = HttpClient::new().get(&url).unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.url, url);
+    let request = server.join().unwrap().to_lowercase();
+    assert!(request.starts_with("get /containers/json?all=1 http/1.1"));
+    assert!(request.contains("host: localhost"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_unix_url_without_socket() {
+    let result = HttpClient::new().get("unix:///nonexistent/saffron.sock/info");
+
+    assert!(matches!(result, Err(HttpError::InvalidUrl(_))));
+}
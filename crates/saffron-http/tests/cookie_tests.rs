@@ -0,0 +1,221 @@
+mod common;
+
+use chrono::{TimeZone, Utc};
+use common::{response, serve};
+use saffron_core::domain::request::HttpRequest;
+use saffron_http::cookie::parse_cookie_date;
+use saffron_http::{Cookie, CookieJar, HttpClient, HttpClientConfig, SameSite};
+use url::Url;
+
+fn url(s: &str) -> Url {
+    Url::parse(s).unwrap()
+}
+
+#[test]
+fn test_parse_attributes() {
+    let cookie = Cookie::parse(
+        "session=abc123; Path=/app; Secure; HttpOnly; SameSite=Lax",
+        &url("https://example.com/login"),
+    )
+    .unwrap();
+
+    assert_eq!(cookie.name, "session");
+    assert_eq!(cookie.value, "abc123");
+    assert_eq!(cookie.domain, "example.com");
+    assert!(cookie.host_only);
+    assert_eq!(cookie.path, "/app");
+    assert_eq!(cookie.expires, None);
+    assert!(cookie.secure);
+    assert!(cookie.http_only);
+    assert_eq!(cookie.same_site, Some(SameSite::Lax));
+}
+
+#[test]
+fn test_parse_default_path_and_domain_attribute() {
+    let cookie = Cookie::parse(
+        "id=1; Domain=.Example.com",
+        &url("http://api.example.com/v1/users"),
+    )
+    .unwrap();
+
+    assert_eq!(cookie.domain, "example.com");
+    assert!(!cookie.host_only);
+    assert_eq!(cookie.path, "/v1");
+}
+
+#[test]
+fn test_parse_rejects_foreign_domains() {
+    let origin = url("http://api.example.com/");
+
+    assert!(Cookie::parse("id=1; Domain=other.com", &origin).is_none());
+    assert!(Cookie::parse("id=1; Domain=com", &origin).is_none());
+    assert!(Cookie::parse("no-equals-sign", &origin).is_none());
+    assert!(Cookie::parse("=value", &origin).is_none());
+}
+
+#[test]
+fn test_parse_cookie_dates() {
+    let expected = Utc.with_ymd_and_hms(2015, 10, 21, 7, 28, 0).unwrap();
+
+    assert_eq!(
+        parse_cookie_date("Wed, 21 Oct 2015 07:28:00 GMT"),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_cookie_date("Wednesday, 21-Oct-15 07:28:00 GMT"),
+        Some(expected)
+    );
+    assert_eq!(
+        parse_cookie_date("Wed Oct 21 07:28:00 2015"),
+        Some(expected)
+    );
+    assert_eq!(parse_cookie_date("Wed, 31 Feb 2015 07:28:00 GMT"), None);
+    assert_eq!(parse_cookie_date("not a date"), None);
+}
+
+#[test]
+fn test_max_age_takes_precedence_over_expires() {
+    let origin = url("http://example.com/");
+
+    let expired = Cookie::parse(
+        "id=1; Max-Age=0; Expires=Fri, 01 Jan 2100 00:00:00 GMT",
+        &origin,
+    )
+    .unwrap();
+    assert!(expired.is_expired());
+
+    let alive = Cookie::parse(
+        "id=1; Max-Age=3600; Expires=Thu, 01 Jan 1970 00:00:00 GMT",
+        &origin,
+    )
+    .unwrap();
+    assert!(!alive.is_expired());
+}
+
+#[test]
+fn test_jar_matching_and_order() {
+    let jar = CookieJar::new();
+    let origin = url("https://example.com/app/page");
+
+    jar.store_response_cookies(
+        &origin,
+        [
+            "root=1; Path=/",
+            "app=2; Path=/app",
+            "secure=3; Path=/; Secure",
+            "wide=4; Domain=example.com; Path=/",
+        ],
+    );
+
+    assert_eq!(
+        jar.cookie_header(&url("https://example.com/app/x")),
+        Some("app=2; root=1; secure=3; wide=4".to_string())
+    );
+    assert_eq!(
+        jar.cookie_header(&url("http://example.com/application")),
+        Some("root=1; wide=4".to_string())
+    );
+    assert_eq!(
+        jar.cookie_header(&url("http://sub.example.com/")),
+        Some("wide=4".to_string())
+    );
+    assert_eq!(jar.cookie_header(&url("http://other.com/")), None);
+}
+
+#[test]
+fn test_jar_replaces_and_expires_cookies() {
+    let jar = CookieJar::new();
+    let origin = url("http://example.com/");
+
+    jar.store_response_cookies(&origin, ["id=1"]);
+    jar.store_response_cookies(&origin, ["id=2"]);
+    assert_eq!(jar.len(), 1);
+    assert_eq!(jar.cookie_header(&origin), Some("id=2".to_string()));
+
+    jar.store_response_cookies(&origin, ["id=gone; Max-Age=0"]);
+    assert!(jar.is_empty());
+}
+
+#[test]
+fn test_jar_remove_by_name_and_domain() {
+    let jar = CookieJar::new();
+    jar.store_response_cookies(&url("http://a.com/"), ["id=1"]);
+    jar.store_response_cookies(&url("http://b.com/"), ["id=2", "other=3"]);
+
+    assert_eq!(jar.remove("id", Some("a.com")), 1);
+    assert_eq!(jar.remove("id", None), 1);
+    assert_eq!(jar.cookies().len(), 1);
+}
+
+#[test]
+fn test_client_stores_and_sends_cookies() {
+    let (base, server) = serve(vec![
+        response(
+            "200 OK",
+            &[("Set-Cookie", "session=abc"), ("Set-Cookie", "theme=dark")],
+            "",
+        ),
+        response("200 OK", &[], ""),
+    ]);
+
+    let client = HttpClient::new();
+    client
+        .send(&HttpRequest::get(format!("{}/login", base)))
+        .unwrap();
+    client
+        .send(&HttpRequest::get(format!("{}/profile", base)))
+        .unwrap();
+
+    let received = server.join().unwrap();
+    assert!(!received[0].contains("Cookie:"));
+    assert!(received[1].contains("Cookie: session=abc; theme=dark\r\n"));
+    assert_eq!(client.cookie_jar().unwrap().len(), 2);
+}
+
+#[test]
+fn test_cookie_from_redirect_is_sent_to_next_hop() {
+    let (base, server) = serve(vec![
+        response(
+            "302 Found",
+            &[("Location", "/dashboard"), ("Set-Cookie", "session=abc")],
+            "",
+        ),
+        response("200 OK", &[], ""),
+    ]);
+
+    let client = HttpClient::new();
+    let request = HttpRequest::post(format!("{}/login", base)).with_header("Cookie", "lang=en");
+    client.send(&request).unwrap();
+
+    let received = server.join().unwrap();
+    assert!(received[1].contains("Cookie: lang=en; session=abc\r\n"));
+}
+
+#[test]
+fn test_shared_jar_and_disabled_cookies() {
+    let (base, server) = serve(vec![
+        response("200 OK", &[("Set-Cookie", "session=abc")], ""),
+        response("200 OK", &[], ""),
+    ]);
+
+    let jar = CookieJar::new();
+    let config = HttpClientConfig {
+        cookie_jar: Some(jar.clone()),
+        ..Default::default()
+    };
+    HttpClient::with_config(config)
+        .send(&HttpRequest::get(&base))
+        .unwrap();
+    assert_eq!(jar.len(), 1);
+
+    let config = HttpClientConfig {
+        cookie_jar: None,
+        ..Default::default()
+    };
+    HttpClient::with_config(config)
+        .send(&HttpRequest::get(&base))
+        .unwrap();
+
+    let received = server.join().unwrap();
+    assert!(!received[1].contains("Cookie:"));
+}
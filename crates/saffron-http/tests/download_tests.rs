@@ -0,0 +1,130 @@
+mod common;
+
+use common::{response, serve};
+use saffron_core::domain::request::HttpRequest;
+use saffron_http::{DownloadProgress, HttpClient, HttpClientConfig, HttpError};
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("saffron-{}-{}", std::process::id(), name))
+}
+
+fn limited_client(limit: usize) -> HttpClient {
+    HttpClient::with_config(HttpClientConfig {
+        max_response_size: Some(limit),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_send_streaming_reports_progress() {
+    let (base, _server) = serve(vec![response("200 OK", &[], "0123456789")]);
+
+    let client = HttpClient::new();
+    let mut body = Vec::new();
+    let mut updates = Vec::new();
+    let response = client
+        .send_streaming(&HttpRequest::get(base), &mut body, |p| updates.push(p))
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert!(response.body.is_empty());
+    assert_eq!(body, b"0123456789");
+    assert_eq!(
+        updates.first(),
+        Some(&DownloadProgress {
+            downloaded: 0,
+            total: Some(10)
+        })
+    );
+    assert_eq!(
+        updates.last(),
+        Some(&DownloadProgress {
+            downloaded: 10,
+            total: Some(10)
+        })
+    );
+}
+
+#[test]
+fn test_size_limit_from_content_length() {
+    let (base, _server) = serve(vec![response("200 OK", &[], "0123456789")]);
+
+    let result = limited_client(4).send(&HttpRequest::get(base));
+    assert!(matches!(result, Err(HttpError::ResponseTooLarge(4))));
+}
+
+#[test]
+fn test_size_limit_without_content_length() {
+    let (base, _server) = serve(vec![
+        "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n0123456789".to_string(),
+    ]);
+
+    let mut body = Vec::new();
+    let result = limited_client(4).send_streaming(&HttpRequest::get(base), &mut body, |_| {});
+    assert!(matches!(result, Err(HttpError::ResponseTooLarge(4))));
+}
+
+#[test]
+fn test_download_resumes_with_range() {
+    let path = temp_path("resume.txt");
+    fs::write(&path, "hello ").unwrap();
+
+    let (base, server) = serve(vec![response(
+        "206 Partial Content",
+        &[("Content-Range", "bytes 6-10/11")],
+        "world",
+    )]);
+
+    let mut last = None;
+    let response = HttpClient::new()
+        .download_to_file(&HttpRequest::get(base), &path, true, |p| last = Some(p))
+        .unwrap();
+
+    assert_eq!(response.status, 206);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "hello world");
+    assert_eq!(
+        last,
+        Some(DownloadProgress {
+            downloaded: 11,
+            total: Some(11)
+        })
+    );
+
+    let received = server.join().unwrap();
+    assert!(received[0].contains("Range: bytes=6-\r\n"));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_download_restarts_when_range_is_ignored() {
+    let path = temp_path("restart.txt");
+    fs::write(&path, "stale").unwrap();
+
+    let (base, _server) = serve(vec![response("200 OK", &[], "fresh content")]);
+
+    HttpClient::new()
+        .download_to_file(&HttpRequest::get(base), &path, true, |_| {})
+        .unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "fresh content");
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_download_error_leaves_file_untouched() {
+    let path = temp_path("error.txt");
+    fs::write(&path, "partial").unwrap();
+
+    let (base, _server) = serve(vec![response("404 Not Found", &[], "missing")]);
+
+    let response = HttpClient::new()
+        .download_to_file(&HttpRequest::get(base), &path, false, |_| {})
+        .unwrap();
+
+    assert_eq!(response.status, 404);
+    assert_eq!(response.body_as_str(), Some("missing"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "partial");
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,211 @@
+mod common;
+
+use common::{response, serve};
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::{HttpRequest, RequestBody};
+use saffron_core::domain::response::HttpResponse;
+use saffron_http::graphql::schema_to_sdl;
+use saffron_http::{GraphQLError, HttpClient, HttpError};
+use std::time::Duration;
+
+const INTROSPECTION: &str = r#"{"data": {"__schema": {
+  "queryType": {"name": "Query"},
+  "mutationType": {"name": "Mutation"},
+  "subscriptionType": null,
+  "directives": [
+    {"name": "include", "description": "builtin", "locations": ["FIELD"], "args": []},
+    {"name": "cached", "description": null, "locations": ["FIELD_DEFINITION", "OBJECT"],
+     "args": [{"name": "ttl", "description": null, "defaultValue": "60",
+               "type": {"kind": "SCALAR", "name": "Int", "ofType": null}}]}
+  ],
+  "types": [
+    {"kind": "OBJECT", "name": "Query", "description": null, "interfaces": [],
+     "fields": [
+       {"name": "user", "description": "Look up a user.", "isDeprecated": false, "deprecationReason": null,
+        "args": [{"name": "id", "description": null, "defaultValue": null,
+                  "type": {"kind": "NON_NULL", "name": null, "ofType": {"kind": "SCALAR", "name": "ID", "ofType": null}}}],
+        "type": {"kind": "OBJECT", "name": "User", "ofType": null}},
+       {"name": "search", "description": null, "isDeprecated": false, "deprecationReason": null,
+        "args": [{"name": "filter", "description": "What to match", "defaultValue": null,
+                  "type": {"kind": "INPUT_OBJECT", "name": "Filter", "ofType": null}}],
+        "type": {"kind": "NON_NULL", "name": null, "ofType": {"kind": "LIST", "name": null,
+                 "ofType": {"kind": "NON_NULL", "name": null, "ofType": {"kind": "UNION", "name": "Result", "ofType": null}}}}}
+     ]},
+    {"kind": "OBJECT", "name": "Mutation", "description": null, "interfaces": [],
+     "fields": [{"name": "ping", "description": null, "args": [], "isDeprecated": false, "deprecationReason": null,
+                 "type": {"kind": "SCALAR", "name": "Boolean", "ofType": null}}]},
+    {"kind": "INTERFACE", "name": "Node", "description": null, "interfaces": [],
+     "fields": [{"name": "id", "description": null, "args": [], "isDeprecated": false, "deprecationReason": null,
+                 "type": {"kind": "NON_NULL", "name": null, "ofType": {"kind": "SCALAR", "name": "ID", "ofType": null}}}]},
+    {"kind": "OBJECT", "name": "User", "description": "Someone with an account.\nMay be suspended.",
+     "interfaces": [{"kind": "INTERFACE", "name": "Node", "ofType": null}],
+     "fields": [
+       {"name": "id", "description": null, "args": [], "isDeprecated": false, "deprecationReason": null,
+        "type": {"kind": "NON_NULL", "name": null, "ofType": {"kind": "SCALAR", "name": "ID", "ofType": null}}},
+       {"name": "login", "description": null, "args": [], "isDeprecated": true, "deprecationReason": "Use `handle`.",
+        "type": {"kind": "SCALAR", "name": "String", "ofType": null}},
+       {"name": "role", "description": null, "args": [], "isDeprecated": false, "deprecationReason": null,
+        "type": {"kind": "ENUM", "name": "Role", "ofType": null}}
+     ]},
+    {"kind": "UNION", "name": "Result", "description": null,
+     "possibleTypes": [{"kind": "OBJECT", "name": "User", "ofType": null}, {"kind": "OBJECT", "name": "Mutation", "ofType": null}]},
+    {"kind": "ENUM", "name": "Role", "description": null,
+     "enumValues": [{"name": "ADMIN", "description": null, "isDeprecated": false, "deprecationReason": null},
+                    {"name": "GUEST", "description": null, "isDeprecated": true, "deprecationReason": "No longer supported"}]},
+    {"kind": "INPUT_OBJECT", "name": "Filter", "description": null,
+     "inputFields": [{"name": "text", "description": null, "defaultValue": "\"\"",
+                      "type": {"kind": "SCALAR", "name": "String", "ofType": null}}]},
+    {"kind": "SCALAR", "name": "DateTime", "description": null},
+    {"kind": "SCALAR", "name": "String", "description": null},
+    {"kind": "OBJECT", "name": "__Type", "description": null, "interfaces": [], "fields": []}
+  ]
+}}}"#;
+
+const SDL: &str = r#"directive @cached(ttl: Int = 60) on FIELD_DEFINITION | OBJECT
+
+type Query {
+  """Look up a user."""
+  user(id: ID!): User
+  search(
+    """What to match"""
+    filter: Filter
+  ): [Result!]!
+}
+
+type Mutation {
+  ping: Boolean
+}
+
+interface Node {
+  id: ID!
+}
+
+"""
+Someone with an account.
+May be suspended.
+"""
+type User implements Node {
+  id: ID!
+  login: String @deprecated(reason: "Use `handle`.")
+  role: Role
+}
+
+union Result = User | Mutation
+
+enum Role {
+  ADMIN
+  GUEST @deprecated
+}
+
+input Filter {
+  text: String = ""
+}
+
+scalar DateTime
+"#;
+
+fn json_response(body: &str) -> String {
+    response("200 OK", &[("Content-Type", "application/json")], body)
+}
+
+#[test]
+fn test_graphql_body_is_sent_as_json() {
+    let (base, server) = serve(vec![json_response(r#"{"data":{"user":null}}"#)]);
+    let request = HttpRequest::post(format!("{}/graphql", base)).with_body(RequestBody::GraphQL {
+        query: "query User($id: ID!) { user(id: $id) { name } }".to_string(),
+        variables: Some(r#"{"id":"7"}"#.to_string()),
+        operation_name: Some("User".to_string()),
+    });
+
+    let response = HttpClient::new().send(&request).unwrap();
+
+    assert!(GraphQLError::from_response(&response).is_empty());
+    let sent = server.join().unwrap().remove(0);
+    assert!(
+        sent.to_lowercase()
+            .contains("content-type: application/json; charset=utf-8")
+    );
+    assert!(sent.ends_with(
+        r#"{"query":"query User($id: ID!) { user(id: $id) { name } }","variables":{"id":"7"},"operationName":"User"}"#
+    ));
+}
+
+#[test]
+fn test_errors_from_response() {
+    let body = r#"{"data": {"user": null}, "errors": [
+        {"message": "Not authorized", "locations": [{"line": 2, "column": 3}], "path": ["user", "friends", 0]},
+        {"message": "Rate limited"}
+    ]}"#;
+    let response = HttpResponse::new(
+        200,
+        "OK".to_string(),
+        Headers::new(),
+        body.as_bytes().to_vec(),
+        Duration::ZERO,
+        "http://localhost/graphql".to_string(),
+    );
+
+    let errors = GraphQLError::from_response(&response);
+
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].locations, vec![(2, 3)]);
+    assert_eq!(errors[0].path, vec!["user", "friends", "0"]);
+    assert_eq!(
+        errors[0].to_string(),
+        "Not authorized at user.friends.0 (line 2, column 3)"
+    );
+    assert_eq!(errors[1].to_string(), "Rate limited");
+}
+
+#[test]
+fn test_schema_to_sdl() {
+    assert_eq!(schema_to_sdl(INTROSPECTION).unwrap(), SDL);
+}
+
+#[test]
+fn test_schema_block_for_custom_root_names() {
+    let introspection = r#"{"__schema": {
+        "queryType": {"name": "Root"}, "mutationType": null, "subscriptionType": null,
+        "directives": [],
+        "types": [{"kind": "OBJECT", "name": "Root", "description": null, "interfaces": [],
+                   "fields": [{"name": "ok", "description": null, "args": [], "isDeprecated": false,
+                               "type": {"kind": "SCALAR", "name": "Boolean", "ofType": null}}]}]
+    }}"#;
+
+    assert_eq!(
+        schema_to_sdl(introspection).unwrap(),
+        "schema {\n  query: Root\n}\n\ntype Root {\n  ok: Boolean\n}\n"
+    );
+    assert!(matches!(
+        schema_to_sdl(r#"{"data": null}"#),
+        Err(HttpError::SchemaError(_))
+    ));
+}
+
+#[test]
+fn test_introspect() {
+    let (base, server) = serve(vec![json_response(INTROSPECTION)]);
+    let request = HttpRequest::get(format!("{}/graphql", base)).with_header("X-Api-Key", "k");
+
+    let sdl = HttpClient::new().introspect(&request).unwrap();
+
+    assert_eq!(sdl, SDL);
+    let sent = server.join().unwrap().remove(0);
+    assert!(sent.starts_with("POST /graphql "));
+    assert!(sent.to_lowercase().contains("x-api-key: k"));
+    assert!(sent.contains(r#""operationName":"IntrospectionQuery""#));
+}
+
+#[test]
+fn test_introspection_disabled() {
+    let (base, _server) = serve(vec![json_response(
+        r#"{"errors": [{"message": "GraphQL introspection is not allowed"}]}"#,
+    )]);
+
+    let result = HttpClient::new().introspect(&HttpRequest::post(base));
+
+    match result {
+        Err(HttpError::SchemaError(message)) => assert!(message.contains("not allowed")),
+        other => panic!("expected a schema error, got {:?}", other),
+    }
+}
@@ -0,0 +1,388 @@
+mod common;
+
+use bytes::Bytes;
+use common::{binary_response, response, serve};
+use h2::RecvStream;
+use h2::server::SendResponse;
+use http::{HeaderMap, Request, Response};
+use saffron_core::domain::auth::Auth;
+use saffron_core::domain::request::HttpRequest;
+use saffron_data::proto::ProtoSchema;
+use saffron_http::{GrpcStatus, HttpClient, HttpClientConfig, HttpError, ProxyConfig};
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+
+const ECHO: &str = r#"
+syntax = "proto3";
+package echo;
+message Ping { string text = 1; int32 count = 2; }
+service Echo {
+  rpc Say (Ping) returns (Ping);
+  rpc Repeat (Ping) returns (stream Ping);
+}
+"#;
+
+/// Accepts one h2c connection and hands its first request to `handler`,
+/// then keeps the connection going until the client hangs up.
+fn serve_h2<F, Fut>(handler: F) -> (String, JoinHandle<()>)
+where
+    F: FnOnce(Request<RecvStream>, SendResponse<Bytes>) -> Fut + Send + 'static,
+    Fut: Future<Output = ()>,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async move {
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut connection = h2::server::handshake(socket).await.unwrap();
+            let (request, respond) = connection.accept().await.unwrap().unwrap();
+            let driver =
+                tokio::spawn(async move { while let Some(Ok(_)) = connection.accept().await {} });
+            handler(request, respond).await;
+            let _ = driver.await;
+        });
+    });
+
+    (url, handle)
+}
+
+async fn read_body(body: &mut RecvStream) -> Vec<u8> {
+    let mut out = Vec::new();
+    while let Some(data) = body.data().await {
+        let data = data.unwrap();
+        let _ = body.flow_control().release_capacity(data.len());
+        out.extend_from_slice(&data);
+    }
+    out
+}
+
+fn frame_messages(messages: &[&[u8]]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for message in messages {
+        data.push(0);
+        data.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        data.extend_from_slice(message);
+    }
+    data
+}
+
+fn ok_response() -> Response<()> {
+    Response::builder()
+        .header("content-type", "application/grpc+proto")
+        .body(())
+        .unwrap()
+}
+
+fn status_trailers(code: &str, message: &str) -> HeaderMap {
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", code.parse().unwrap());
+    if !message.is_empty() {
+        trailers.insert("grpc-message", message.parse().unwrap());
+    }
+    trailers
+}
+
+/// An HTTP proxy that accepts one CONNECT and relays the tunnel until both
+/// ends hang up, then returns the request head it was sent.
+fn serve_connect_proxy() -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (mut client, _) = listener.accept().unwrap();
+        let mut head = Vec::new();
+        let mut byte = [0];
+        while !head.ends_with(b"\r\n\r\n") {
+            client.read_exact(&mut byte).unwrap();
+            head.push(byte[0]);
+        }
+        let head = String::from_utf8(head).unwrap();
+        let target = head.split_whitespace().nth(1).unwrap();
+        let mut upstream = TcpStream::connect(target).unwrap();
+        client
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .unwrap();
+
+        let mut from_client = client.try_clone().unwrap();
+        let mut to_upstream = upstream.try_clone().unwrap();
+        let forward = thread::spawn(move || {
+            let _ = io::copy(&mut from_client, &mut to_upstream);
+            let _ = to_upstream.shutdown(Shutdown::Write);
+        });
+        let _ = io::copy(&mut upstream, &mut client);
+        forward.join().unwrap();
+        head
+    });
+
+    (url, handle)
+}
+
+fn echo_schema() -> ProtoSchema {
+    let mut schema = ProtoSchema::new();
+    schema.add_source("echo.proto", ECHO).unwrap();
+    schema
+}
+
+#[test]
+fn test_unary_call() {
+    let schema = echo_schema();
+    let reply = schema
+        .encode("echo.Ping", r#"{"text": "hi back", "count": 2}"#)
+        .unwrap();
+
+    let (url, server) = serve_h2(|mut request, mut respond| async move {
+        assert_eq!(request.method(), "POST");
+        assert_eq!(request.uri().scheme_str(), Some("http"));
+        assert_eq!(request.uri().path(), "/echo.Echo/Say");
+        let header = |name: &str| request.headers().get(name).unwrap().to_str().unwrap();
+        assert_eq!(header("content-type"), "application/grpc");
+        assert_eq!(header("te"), "trailers");
+        assert_eq!(header("x-tenant"), "acme");
+        assert_eq!(header("authorization"), "Bearer t0ken");
+        let body = read_body(request.body_mut()).await;
+        assert_eq!(body, frame_messages(&[&[0x0A, 0x02, b'h', b'i']]));
+
+        let mut response = ok_response();
+        response
+            .headers_mut()
+            .insert("x-host", "www.example.com".parse().unwrap());
+        let mut send = respond.send_response(response, false).unwrap();
+        // The message arrives split over two frames
+        let data = frame_messages(&[&reply]);
+        send.send_data(Bytes::copy_from_slice(&data[..3]), false)
+            .unwrap();
+        send.send_data(Bytes::copy_from_slice(&data[3..]), false)
+            .unwrap();
+        send.send_trailers(status_trailers("0", "")).unwrap();
+    });
+
+    let request = HttpRequest::post(format!("{}/echo.Echo/Say", url))
+        .with_header("X-Tenant", "acme")
+        .with_auth(Auth::bearer("t0ken"));
+    let message = schema.encode("echo.Ping", r#"{"text": "hi"}"#).unwrap();
+
+    let mut call = HttpClient::new().grpc(&request, &message).unwrap();
+    assert_eq!(call.headers().get("x-host"), Some("www.example.com"));
+    let response = call.next_message().unwrap().unwrap();
+    assert!(call.next_message().unwrap().is_none());
+    assert_eq!(call.status(), Some(&GrpcStatus::new(0, "")));
+    drop(call);
+
+    assert_eq!(
+        schema.decode("echo.Ping", &response).unwrap(),
+        "{\n  \"text\": \"hi back\",\n  \"count\": 2\n}"
+    );
+    server.join().unwrap();
+}
+
+#[test]
+fn test_server_streaming() {
+    let (url, server) = serve_h2(|mut request, mut respond| async move {
+        read_body(request.body_mut()).await;
+        let mut send = respond.send_response(ok_response(), false).unwrap();
+        send.send_data(frame_messages(&[b"one", b"two"]).into(), false)
+            .unwrap();
+        send.send_data(frame_messages(&[b"three"]).into(), false)
+            .unwrap();
+        send.send_trailers(status_trailers("0", "")).unwrap();
+    });
+
+    let request = HttpRequest::post(format!("{}/echo.Echo/Repeat", url));
+    let call = HttpClient::new().grpc(&request, b"").unwrap();
+    let messages: Vec<Vec<u8>> = call.map(Result::unwrap).collect();
+
+    assert_eq!(
+        messages,
+        vec![b"one".to_vec(), b"two".to_vec(), b"three".to_vec()]
+    );
+    server.join().unwrap();
+}
+
+#[test]
+fn test_error_status() {
+    let (url, server) = serve_h2(|mut request, mut respond| async move {
+        read_body(request.body_mut()).await;
+        // A trailers-only response
+        let mut response = ok_response();
+        response
+            .headers_mut()
+            .extend(status_trailers("5", "order%20not%20found"));
+        respond.send_response(response, true).unwrap();
+    });
+
+    let request = HttpRequest::post(format!("{}/shop.Orders/Get", url));
+    let result = HttpClient::new().grpc_unary(&request, b"");
+
+    match result {
+        Err(HttpError::GrpcError(status)) => {
+            assert_eq!(status, GrpcStatus::new(5, "order not found"));
+            assert_eq!(status.to_string(), "NOT_FOUND (5): order not found");
+        }
+        other => panic!("expected a gRPC error, got {:?}", other),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn test_http_status_maps_to_grpc_status() {
+    let (url, server) = serve_h2(|_, mut respond| async move {
+        let response = Response::builder().status(404).body(()).unwrap();
+        respond.send_response(response, true).unwrap();
+    });
+
+    let request = HttpRequest::post(format!("{}/shop.Orders/Get", url));
+    let result = HttpClient::new().grpc(&request, b"");
+
+    match result {
+        Err(HttpError::GrpcError(status)) => assert_eq!(status.code_name(), "UNIMPLEMENTED"),
+        Err(e) => panic!("expected a gRPC error, got {}", e),
+        Ok(_) => panic!("expected the call to fail"),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn test_large_messages() {
+    // Both are bigger than the default 65,535 byte flow-control window
+    let message = vec![7u8; 70_000];
+    let expected = frame_messages(&[&message]);
+    let reply = vec![9u8; 100_000];
+    let reply_frame = frame_messages(&[&reply]);
+
+    let (url, server) = serve_h2(move |mut request, mut respond| async move {
+        assert_eq!(read_body(request.body_mut()).await, expected);
+        let mut send = respond.send_response(ok_response(), false).unwrap();
+        send.send_data(reply_frame.into(), false).unwrap();
+        send.send_trailers(status_trailers("0", "")).unwrap();
+    });
+
+    let request = HttpRequest::post(format!("{}/files.Store/Put", url));
+    let response = HttpClient::new().grpc_unary(&request, &message).unwrap();
+
+    assert_eq!(response, reply);
+    server.join().unwrap();
+}
+
+#[test]
+fn test_call_through_http_proxy() {
+    let (url, server) = serve_h2(|mut request, mut respond| async move {
+        read_body(request.body_mut()).await;
+        let mut send = respond.send_response(ok_response(), false).unwrap();
+        send.send_data(frame_messages(&[b"pong"]).into(), false)
+            .unwrap();
+        send.send_trailers(status_trailers("0", "")).unwrap();
+    });
+    let (proxy_url, proxy) = serve_connect_proxy();
+
+    let client = HttpClient::with_config(HttpClientConfig {
+        proxy: ProxyConfig::all(proxy_url.parse().unwrap()),
+        ..Default::default()
+    });
+    let request = HttpRequest::post(format!("{}/echo.Echo/Say", url));
+    let response = client.grpc_unary(&request, b"ping").unwrap();
+
+    assert_eq!(response, b"pong");
+    server.join().unwrap();
+    let head = proxy.join().unwrap();
+    let authority = url.trim_start_matches("http://");
+    assert!(
+        head.starts_with(&format!("CONNECT {} HTTP/1.1\r\n", authority)),
+        "{}",
+        head
+    );
+}
+
+#[test]
+fn test_rejects_invalid_max_frame_size() {
+    for size in [0u32, 16_383, 1 << 24] {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            // A SETTINGS frame with only SETTINGS_MAX_FRAME_SIZE
+            let mut frame = vec![0, 0, 6, 0x4, 0, 0, 0, 0, 0, 0, 0x5];
+            frame.extend_from_slice(&size.to_be_bytes());
+            stream.write_all(&frame).unwrap();
+            let _ = io::copy(&mut stream, &mut io::sink());
+        });
+
+        let request = HttpRequest::post(format!("{}/echo.Echo/Say", url));
+        let result = HttpClient::new().grpc(&request, b"");
+
+        assert!(
+            matches!(result, Err(HttpError::Http2Error(_))),
+            "frame size {} was accepted",
+            size
+        );
+        server.join().unwrap();
+    }
+}
+
+#[test]
+fn test_rejects_non_http_url() {
+    let result = HttpClient::new().grpc(&HttpRequest::post("grpc://localhost/a.B/C"), b"");
+
+    assert!(matches!(result, Err(HttpError::InvalidUrl(_))));
+}
+
+#[test]
+fn test_grpc_web_call() {
+    let mut body = frame_messages(&[b"pong"]);
+    let trailers = b"grpc-status: 0\r\ngrpc-message: \r\n";
+    body.push(0x80);
+    body.extend_from_slice(&(trailers.len() as u32).to_be_bytes());
+    body.extend_from_slice(trailers);
+    let (url, server) = serve(vec![binary_response(
+        "200 OK",
+        &[("Content-Type", "application/grpc-web+proto")],
+        &body,
+    )]);
+
+    let request =
+        HttpRequest::post(format!("{}/echo.Echo/Say", url)).with_header("X-Tenant", "acme");
+    let mut call = HttpClient::new().grpc_web(&request, b"ping").unwrap();
+    assert_eq!(call.next_message().unwrap(), Some(b"pong".to_vec()));
+    assert!(call.next_message().unwrap().is_none());
+    assert_eq!(call.trailers().get("grpc-status"), Some("0"));
+
+    let received = server.join().unwrap();
+    let raw = received[0].to_ascii_lowercase();
+    assert!(raw.starts_with("post /echo.echo/say http/1.1\r\n"));
+    assert!(raw.contains("content-type: application/grpc-web+proto\r\n"));
+    assert!(raw.contains("x-grpc-web: 1\r\n"));
+    assert!(raw.contains("x-tenant: acme\r\n"));
+    assert!(raw.ends_with("\r\n\r\n\0\0\0\0\u{4}ping"));
+}
+
+#[test]
+fn test_grpc_web_error_status() {
+    // A trailers-only response carries the status in its headers
+    let (url, server) = serve(vec![response(
+        "200 OK",
+        &[
+            ("Content-Type", "application/grpc-web+proto"),
+            ("grpc-status", "7"),
+            ("grpc-message", "no%20access"),
+        ],
+        "",
+    )]);
+
+    let request = HttpRequest::post(format!("{}/echo.Echo/Say", url));
+    let mut call = HttpClient::new().grpc_web(&request, b"").unwrap();
+
+    match call.next_message() {
+        Err(HttpError::GrpcError(status)) => {
+            assert_eq!(status, GrpcStatus::new(7, "no access"))
+        }
+        other => panic!("expected a gRPC error, got {:?}", other),
+    }
+    server.join().unwrap();
+}
@@ -1,6 +1,11 @@
+mod common;
+
+use common::{response, serve};
 use saffron_core::domain::request::{HttpMethod, HttpRequest, RequestBody};
-use saffron_http::{HttpClient, HttpClientConfig, HttpError};
-use std::collections::HashMap;
+use saffron_http::{
+    ConnectConfig, CookieJar, HttpClient, HttpClientConfig, HttpError, ProxyConfig, RetryPolicy,
+    TlsConfig, TlsError, TlsVersion,
+};
 
 #[test]
 fn test_http_client_new() {
@@ -19,7 +24,8 @@ fn test_http_client_config_default() {
     assert!(config.follow_redirects);
     assert_eq!(config.max_redirects, 10);
     assert!(config.user_agent.is_some());
-    assert!(!config.accept_invalid_certs);
+    assert!(!config.tls.accept_invalid_certs);
+    assert!(config.tls.is_default());
     assert_eq!(config.max_response_size, Some(100 * 1024 * 1024));
 }
 
@@ -30,16 +36,26 @@ fn test_http_client_config_custom() {
         follow_redirects: false,
         max_redirects: 5,
         user_agent: Some("TestAgent/1.0".to_string()),
-        accept_invalid_certs: true,
+        tls: TlsConfig {
+            accept_invalid_certs: true,
+            ..Default::default()
+        },
+        proxy: ProxyConfig::default(),
+        connect: ConnectConfig::default(),
         max_response_size: Some(10 * 1024 * 1024),
+        cookie_jar: None,
+        retry: RetryPolicy::none(),
+        compressed: true,
+        decompress: true,
     };
 
     assert_eq!(config.timeout_seconds, 15);
     assert!(!config.follow_redirects);
     assert_eq!(config.max_redirects, 5);
     assert_eq!(config.user_agent, Some("TestAgent/1.0".to_string()));
-    assert!(config.accept_invalid_certs);
+    assert!(config.tls.accept_invalid_certs);
     assert_eq!(config.max_response_size, Some(10 * 1024 * 1024));
+    assert!(config.cookie_jar.is_none());
 }
 
 #[test]
@@ -49,8 +65,14 @@ fn test_http_client_with_config() {
         follow_redirects: true,
         max_redirects: 3,
         user_agent: Some("Custom/1.0".to_string()),
-        accept_invalid_certs: false,
+        tls: TlsConfig::default(),
+        proxy: ProxyConfig::default(),
+        connect: ConnectConfig::default(),
         max_response_size: Some(5 * 1024 * 1024),
+        cookie_jar: Some(CookieJar::new()),
+        retry: RetryPolicy::none(),
+        compressed: true,
+        decompress: true,
     };
 
     let _client = HttpClient::with_config(config);
@@ -67,11 +89,123 @@ fn test_http_error_display() {
     let error = HttpError::NetworkError("connection refused".to_string());
     assert_eq!(error.to_string(), "Network error: connection refused");
 
-    let error = HttpError::TlsError("certificate error".to_string());
-    assert_eq!(error.to_string(), "TLS error: certificate error");
+    let error = HttpError::TlsError(TlsError::UnknownIssuer);
+    assert_eq!(
+        error.to_string(),
+        "TLS error: server certificate is not trusted (unknown issuer)"
+    );
+
+    let error = HttpError::TlsError(TlsError::Handshake("certificate error".to_string()));
+    assert_eq!(
+        error.to_string(),
+        "TLS error: handshake failed: certificate error"
+    );
 
     let error = HttpError::TooManyRedirects;
     assert_eq!(error.to_string(), "Too many redirects");
+
+    let error = HttpError::ProxyError("connection refused".to_string());
+    assert_eq!(error.to_string(), "Proxy error: connection refused");
+
+    let error = HttpError::ResponseTooLarge(1024);
+    assert_eq!(
+        error.to_string(),
+        "Response body exceeds the 1024 byte limit"
+    );
+}
+
+#[test]
+fn test_tls_version_parsing() {
+    assert_eq!("1.2".parse::<TlsVersion>().unwrap(), TlsVersion::Tls12);
+    assert_eq!("1.3".parse::<TlsVersion>().unwrap(), TlsVersion::Tls13);
+    assert_eq!("TLSv1.3".parse::<TlsVersion>().unwrap(), TlsVersion::Tls13);
+    assert!(matches!(
+        "1.1".parse::<TlsVersion>(),
+        Err(TlsError::Config(_))
+    ));
+}
+
+#[test]
+fn test_client_with_insecure_tls() {
+    let config = HttpClientConfig {
+        tls: TlsConfig {
+            accept_invalid_certs: true,
+            min_version: Some(TlsVersion::Tls13),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    assert!(HttpClient::try_with_config(config).is_ok());
+}
+
+#[test]
+fn test_client_with_missing_ca_file() {
+    let config = HttpClientConfig {
+        tls: TlsConfig {
+            ca_certificates: vec!["does/not/exist.pem".into()],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let result = HttpClient::try_with_config(config.clone());
+    assert!(matches!(
+        result,
+        Err(HttpError::TlsError(TlsError::Config(_)))
+    ));
+
+    // `with_config` reports the error when a request is sent
+    let client = HttpClient::with_config(config);
+    let result = client.send(&HttpRequest::get("https://example.com/"));
+    assert!(matches!(
+        result,
+        Err(HttpError::TlsError(TlsError::Config(_)))
+    ));
+}
+
+#[test]
+fn test_handshake_failure_maps_to_tls_error() {
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    // A plain-text server on the other end of an https:// URL breaks the handshake
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        if let Ok((mut stream, _)) = listener.accept() {
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        }
+    });
+
+    let client = HttpClient::new();
+    let result = client.get(&format!("https://{}/", addr));
+
+    assert!(matches!(result, Err(HttpError::TlsError(_))));
+}
+
+#[test]
+fn test_response_headers_keep_order_and_duplicates() {
+    let (base, _server) = serve(vec![response(
+        "200 OK",
+        &[
+            ("Vary", "Accept-Encoding"),
+            ("Link", "</a>; rel=next"),
+            ("Vary", "Origin"),
+            ("Link", "</b>; rel=last"),
+        ],
+        "",
+    )]);
+
+    let response = HttpClient::new().get(&base).unwrap();
+
+    let names: Vec<&str> = response.headers.iter().map(|h| h.name.as_str()).collect();
+    assert_eq!(&names[..4], ["vary", "link", "vary", "link"]);
+    assert_eq!(
+        response.headers.get_all("Link").collect::<Vec<_>>(),
+        ["</a>; rel=next", "</b>; rel=last"]
+    );
+    assert_eq!(response.get_header("VARY"), Some("Accept-Encoding"));
 }
 
 #[test]
@@ -97,17 +231,18 @@ fn test_request_body_handling() {
 
 #[test]
 fn test_form_urlencoded_body() {
-    let mut form = HashMap::new();
-    form.insert("username".to_string(), "testuser".to_string());
-    form.insert("password".to_string(), "secret".to_string());
+    let form = vec![
+        ("username".to_string(), "testuser".to_string()),
+        ("password".to_string(), "secret".to_string()),
+    ];
 
     let request =
         HttpRequest::post("https://httpbin.org/post").with_body(RequestBody::FormUrlEncoded(form));
 
     match request.body {
         RequestBody::FormUrlEncoded(ref data) => {
-            assert_eq!(data.get("username"), Some(&"testuser".to_string()));
-            assert_eq!(data.get("password"), Some(&"secret".to_string()));
+            assert_eq!(data[0], ("username".to_string(), "testuser".to_string()));
+            assert_eq!(data[1], ("password".to_string(), "secret".to_string()));
         }
         _ => panic!("Expected FormUrlEncoded body"),
     }
@@ -243,3 +378,35 @@ fn test_content_type_auto_detection() {
         _ => panic!("Expected Text body"),
     }
 }
+
+#[test]
+fn test_extension_methods_are_sent_as_written() {
+    let (base, server) = serve(vec![
+        response("200 OK", &[], ""),
+        response("207 Multi-Status", &[], ""),
+        response("200 OK", &[], ""),
+    ]);
+    let client = HttpClient::new();
+
+    for method in ["PURGE", "PROPFIND", "TRACE"] {
+        let request = HttpRequest::new(method.parse().unwrap(), format!("{}/cache", base));
+        client.send(&request).unwrap();
+    }
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("PURGE /cache HTTP/1.1"));
+    assert!(requests[1].starts_with("PROPFIND /cache HTTP/1.1"));
+    assert!(requests[2].starts_with("TRACE /cache HTTP/1.1"));
+}
+
+#[test]
+fn test_invalid_custom_method_is_rejected() {
+    let request = HttpRequest::new(
+        HttpMethod::Custom("GET /admin".to_string()),
+        "http://127.0.0.1:1/",
+    );
+
+    let result = HttpClient::new().send(&request);
+
+    assert!(matches!(result, Err(HttpError::InvalidMethod(name)) if name == "GET /admin"));
+}
@@ -0,0 +1,181 @@
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::{HttpMethod, HttpRequest};
+use saffron_core::domain::response::HttpResponse;
+use saffron_http::middleware::from_fn;
+use saffron_http::{
+    DefaultHeaders, HttpClient, HttpClientConfig, HttpError, Logger, MockTransport, RequestId,
+};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const URL: &str = "https://api.example.com/items";
+
+fn canned(status: u16, body: &str) -> HttpResponse {
+    HttpResponse::new(
+        status,
+        "OK".to_string(),
+        Headers::new(),
+        body.as_bytes().to_vec(),
+        Duration::ZERO,
+        String::new(),
+    )
+}
+
+fn mock_client() -> (HttpClient, MockTransport) {
+    let mock = MockTransport::new().with_response(HttpMethod::Get, URL, canned(200, "items"));
+    let client = HttpClient::with_transport(HttpClientConfig::default(), mock.clone());
+    (client, mock)
+}
+
+/// A writer tests can read back after handing a clone to a [`Logger`].
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SharedBuffer {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+#[test]
+fn test_middleware_runs_in_order() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let (client, _) = mock_client();
+
+    let tracer = |name: &'static str| {
+        let calls = calls.clone();
+        from_fn(move |request, next| {
+            calls.lock().unwrap().push(format!("{} in", name));
+            let result = next.run(request);
+            calls.lock().unwrap().push(format!("{} out", name));
+            result
+        })
+    };
+    let client = client
+        .with_middleware(tracer("a"))
+        .with_middleware(tracer("b"));
+
+    client.get(URL).unwrap();
+
+    assert_eq!(*calls.lock().unwrap(), ["a in", "b in", "b out", "a out"]);
+}
+
+#[test]
+fn test_middleware_can_short_circuit() {
+    let (client, mock) = mock_client();
+    let client = client.with_middleware(from_fn(|_, _| Ok(canned(299, "cached"))));
+
+    let response = client.get(URL).unwrap();
+
+    assert_eq!(response.status, 299);
+    assert_eq!(response.body_as_str(), Some("cached"));
+    assert!(mock.requests().is_empty());
+}
+
+#[test]
+fn test_middleware_transforms_response_and_error() {
+    let (client, _) = mock_client();
+    let client = client.with_middleware(from_fn(|request, next| match next.run(request) {
+        Ok(mut response) => {
+            response.headers.append("X-Seen", "yes");
+            Ok(response)
+        }
+        Err(HttpError::RequestFailed(_)) => Ok(canned(404, "")),
+        Err(e) => Err(e),
+    }));
+
+    assert_eq!(client.get(URL).unwrap().get_header("X-Seen"), Some("yes"));
+
+    // The mock has no route for this one
+    let fallback = client.get("https://api.example.com/missing").unwrap();
+    assert_eq!(fallback.status, 404);
+}
+
+#[test]
+fn test_default_headers_do_not_override() {
+    let (client, mock) = mock_client();
+    let client = client.with_middleware(
+        DefaultHeaders::new()
+            .with_header("X-Team", "payments")
+            .with_header("Accept", "application/json"),
+    );
+
+    client
+        .send(&HttpRequest::get(URL).with_header("Accept", "text/csv"))
+        .unwrap();
+
+    let sent = &mock.requests()[0];
+    assert_eq!(sent.get_header("X-Team"), Some("payments"));
+    assert_eq!(
+        sent.headers.get_all("Accept").collect::<Vec<_>>(),
+        ["text/csv"]
+    );
+}
+
+#[test]
+fn test_request_id() {
+    let (client, mock) = mock_client();
+    let client = client.with_middleware(RequestId::new());
+
+    client.get(URL).unwrap();
+    client.get(URL).unwrap();
+    client
+        .send(&HttpRequest::get(URL).with_header("X-Request-Id", "fixed"))
+        .unwrap();
+
+    let ids: Vec<String> = mock
+        .requests()
+        .iter()
+        .map(|r| r.get_header("x-request-id").unwrap().to_string())
+        .collect();
+
+    assert_eq!(ids[0].len(), 36);
+    assert_eq!(ids[0].as_bytes()[14], b'4');
+    assert_ne!(ids[0], ids[1]);
+    assert_eq!(ids[2], "fixed");
+}
+
+#[test]
+fn test_logger_redacts_credentials() {
+    let buffer = SharedBuffer::default();
+    let (client, _) = mock_client();
+    let client = client.with_middleware(Logger::new(buffer.clone()).with_headers(true));
+
+    client
+        .send(&HttpRequest::get(URL).with_header("Authorization", "Bearer secret"))
+        .unwrap();
+    let _ = client.delete(URL);
+
+    let log = buffer.contents();
+    assert!(log.contains(&format!("--> GET {}", URL)));
+    assert!(log.contains(&format!("<-- 200 OK {}", URL)));
+    assert!(log.contains("Authorization: [redacted]"));
+    assert!(!log.contains("secret"));
+    assert!(log.contains(&format!("<-- DELETE {} failed", URL)));
+}
+
+#[test]
+fn test_middleware_wraps_streaming() {
+    let (client, mock) = mock_client();
+    let client = client.with_middleware(DefaultHeaders::new().with_header("X-Stream", "1"));
+
+    let mut body = Vec::new();
+    let response = client
+        .send_streaming(&HttpRequest::get(URL), &mut body, |_| {})
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(body, b"items");
+    assert_eq!(mock.requests()[0].get_header("X-Stream"), Some("1"));
+}
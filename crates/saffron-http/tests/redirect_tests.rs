@@ -0,0 +1,118 @@
+mod common;
+
+use common::{response, serve};
+use saffron_core::domain::request::HttpRequest;
+use saffron_http::{HttpClient, HttpClientConfig, HttpError};
+
+#[test]
+fn test_follows_redirect_and_records_hop() {
+    let (base, server) = serve(vec![
+        response("302 Found", &[("Location", "/final")], ""),
+        response("200 OK", &[], "done"),
+    ]);
+
+    let client = HttpClient::new();
+    let response = client
+        .send(&HttpRequest::get(format!("{}/start", base)))
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body_as_str(), Some("done"));
+    assert!(response.url.ends_with("/final"));
+    assert_eq!(response.redirects.len(), 1);
+
+    let hop = &response.redirects[0];
+    assert_eq!(hop.status, 302);
+    assert_eq!(hop.location, "/final");
+    assert!(hop.url.ends_with("/start"));
+    assert_eq!(hop.headers.get("Location"), Some("/final"));
+
+    let received = server.join().unwrap();
+    assert!(received[1].starts_with("GET /final "));
+}
+
+#[test]
+fn test_request_can_disable_redirects() {
+    let (base, _server) = serve(vec![response(
+        "301 Moved Permanently",
+        &[("Location", "/elsewhere")],
+        "",
+    )]);
+
+    let client = HttpClient::new();
+    let request = HttpRequest::get(format!("{}/start", base)).follow_redirects(false);
+    let response = client.send(&request).unwrap();
+
+    assert_eq!(response.status, 301);
+    assert_eq!(response.get_header("location"), Some("/elsewhere"));
+    assert!(!response.was_redirected());
+}
+
+#[test]
+fn test_config_can_disable_redirects() {
+    let (base, _server) = serve(vec![response(
+        "302 Found",
+        &[("Location", "/elsewhere")],
+        "",
+    )]);
+
+    let client = HttpClient::with_config(HttpClientConfig {
+        follow_redirects: false,
+        ..Default::default()
+    });
+    let response = client.send(&HttpRequest::get(base)).unwrap();
+
+    assert_eq!(response.status, 302);
+    assert!(response.redirects.is_empty());
+}
+
+#[test]
+fn test_too_many_redirects() {
+    let (base, _server) = serve(vec![
+        response("302 Found", &[("Location", "/a")], ""),
+        response("302 Found", &[("Location", "/b")], ""),
+    ]);
+
+    let client = HttpClient::with_config(HttpClientConfig {
+        max_redirects: 1,
+        ..Default::default()
+    });
+    let result = client.send(&HttpRequest::get(base));
+
+    assert!(matches!(result, Err(HttpError::TooManyRedirects)));
+}
+
+#[test]
+fn test_see_other_switches_post_to_get() {
+    let (base, server) = serve(vec![
+        response("303 See Other", &[("Location", "/result")], ""),
+        response("200 OK", &[], "ok"),
+    ]);
+
+    let client = HttpClient::new();
+    let request = HttpRequest::post(format!("{}/submit", base)).with_json_body(r#"{"a": 1}"#);
+    let response = client.send(&request).unwrap();
+
+    assert_eq!(response.status, 200);
+
+    let received = server.join().unwrap();
+    assert!(received[0].starts_with("POST /submit "));
+    assert!(received[1].starts_with("GET /result "));
+    assert!(!received[1].contains(r#"{"a": 1}"#));
+}
+
+#[test]
+fn test_temporary_redirect_preserves_method_and_body() {
+    let (base, server) = serve(vec![
+        response("307 Temporary Redirect", &[("Location", "/retry")], ""),
+        response("200 OK", &[], "ok"),
+    ]);
+
+    let client = HttpClient::new();
+    let request = HttpRequest::post(format!("{}/submit", base)).with_text_body("payload");
+    client.send(&request).unwrap();
+
+    let received = server.join().unwrap();
+    assert!(received[1].starts_with("POST /retry "));
+    assert!(received[1].ends_with("payload"));
+}
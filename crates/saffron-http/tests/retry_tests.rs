@@ -0,0 +1,172 @@
+mod common;
+
+use common::{response, serve};
+use saffron_core::domain::request::{BodyStream, HttpRequest};
+use saffron_http::retry::parse_retry_after;
+use saffron_http::{HttpClient, HttpClientConfig, RetryPolicy};
+use std::io::Cursor;
+use std::time::Duration;
+
+fn fast_policy(retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        initial_backoff: Duration::from_millis(1),
+        jitter: 0.0,
+        ..RetryPolicy::with_retries(retries)
+    }
+}
+
+fn retrying_client(policy: RetryPolicy) -> HttpClient {
+    HttpClient::with_config(HttpClientConfig {
+        retry: policy,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_backoff_curve_is_capped() {
+    let policy = RetryPolicy {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(500),
+        multiplier: 2.0,
+        ..Default::default()
+    };
+
+    assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3), Duration::from_millis(400));
+    assert_eq!(policy.backoff(4), Duration::from_millis(500));
+    assert_eq!(policy.backoff(100), Duration::from_millis(500));
+}
+
+#[test]
+fn test_delay_jitter_and_retry_after() {
+    let policy = RetryPolicy {
+        initial_backoff: Duration::from_millis(1000),
+        jitter: 0.5,
+        ..Default::default()
+    };
+
+    for _ in 0..50 {
+        let delay = policy.delay(1, None);
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_millis(1500));
+    }
+
+    assert_eq!(
+        policy.delay(1, Some(Duration::from_secs(7))),
+        Duration::from_secs(7)
+    );
+    assert_eq!(
+        policy.delay(1, Some(Duration::from_secs(3600))),
+        policy.max_backoff
+    );
+}
+
+#[test]
+fn test_parse_retry_after() {
+    assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+    assert_eq!(
+        parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+        Some(Duration::ZERO)
+    );
+    assert_eq!(parse_retry_after("soon"), None);
+}
+
+#[test]
+fn test_retries_status_until_success() {
+    let (base, server) = serve(vec![
+        response("503 Service Unavailable", &[], "deploying"),
+        response("502 Bad Gateway", &[], ""),
+        response("200 OK", &[], "up"),
+    ]);
+
+    let response = retrying_client(fast_policy(3))
+        .send(&HttpRequest::get(base))
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body_as_str(), Some("up"));
+    assert_eq!(response.attempts(), 3);
+    assert_eq!(response.retries[0].status, Some(503));
+    assert_eq!(response.retries[1].status, Some(502));
+    assert_eq!(server.join().unwrap().len(), 3);
+}
+
+#[test]
+fn test_gives_up_after_max_attempts() {
+    let (base, _server) = serve(vec![
+        response("503 Service Unavailable", &[], ""),
+        response("503 Service Unavailable", &[], "still down"),
+    ]);
+
+    let response = retrying_client(fast_policy(1))
+        .send(&HttpRequest::get(base))
+        .unwrap();
+
+    assert_eq!(response.status, 503);
+    assert_eq!(response.body_as_str(), Some("still down"));
+    assert_eq!(response.retries.len(), 1);
+}
+
+#[test]
+fn test_honors_retry_after() {
+    let (base, _server) = serve(vec![
+        response("429 Too Many Requests", &[("Retry-After", "0")], ""),
+        response("200 OK", &[], ""),
+    ]);
+
+    let policy = RetryPolicy {
+        initial_backoff: Duration::from_secs(60),
+        ..RetryPolicy::with_retries(1)
+    };
+    let response = retrying_client(policy)
+        .send(&HttpRequest::get(base))
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.retries[0].delay, Duration::ZERO);
+}
+
+#[test]
+fn test_retries_transport_errors() {
+    // The first connection is closed without a response
+    let (base, _server) = serve(vec![String::new(), response("200 OK", &[], "")]);
+
+    let response = retrying_client(fast_policy(1))
+        .send(&HttpRequest::get(base))
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.retries[0].status, None);
+    assert!(response.retries[0].error.is_some());
+}
+
+#[test]
+fn test_non_idempotent_methods_need_opt_in() {
+    let (base, _server) = serve(vec![
+        response("503 Service Unavailable", &[], ""),
+        response("200 OK", &[], ""),
+    ]);
+
+    let client = retrying_client(fast_policy(1));
+    let response = client.send(&HttpRequest::post(&base)).unwrap();
+    assert_eq!(response.status, 503);
+
+    let client = retrying_client(RetryPolicy {
+        retry_non_idempotent: true,
+        ..fast_policy(1)
+    });
+    let response = client.send(&HttpRequest::post(&base)).unwrap();
+    assert_eq!(response.status, 200);
+}
+
+#[test]
+fn test_one_shot_streams_are_not_retried() {
+    let (base, _server) = serve(vec![response("503 Service Unavailable", &[], "")]);
+
+    let stream = BodyStream::from_reader(Cursor::new(b"once".to_vec()), Some(4));
+    let request = HttpRequest::put(base).with_stream_body(stream);
+    let response = retrying_client(fast_policy(3)).send(&request).unwrap();
+
+    assert_eq!(response.status, 503);
+    assert!(response.retries.is_empty());
+}
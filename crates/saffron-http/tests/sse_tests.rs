@@ -0,0 +1,261 @@
+mod common;
+
+use common::{response, serve};
+use saffron_core::domain::request::HttpRequest;
+use saffron_http::{HttpClient, HttpClientConfig, HttpError, SseConfig, SseEvent, SseParser};
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+fn event_stream(body: &str) -> String {
+    response("200 OK", &[("Content-Type", "text/event-stream")], body)
+}
+
+fn once() -> SseConfig {
+    SseConfig {
+        reconnect: false,
+        ..Default::default()
+    }
+}
+
+/// Serves one event stream that sends an event every 400ms, taking longer
+/// than a one-second timeout in all.
+fn slow_stream() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        let (mut socket, _) = listener.accept().unwrap();
+        let mut request = [0; 4096];
+        let _ = socket.read(&mut request);
+        let _ = socket.write_all(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+        );
+        for i in 0..4 {
+            thread::sleep(Duration::from_millis(400));
+            let _ = socket.write_all(format!("data: {}\n\n", i).as_bytes());
+        }
+    });
+    base
+}
+
+fn parse(lines: &[&str]) -> Vec<SseEvent> {
+    let mut parser = SseParser::new();
+    lines.iter().filter_map(|l| parser.push_line(l)).collect()
+}
+
+#[test]
+fn test_parser_fields() {
+    let events = parse(&[
+        ": keep-alive",
+        "event: update",
+        "id: 7",
+        "retry: 1500",
+        "data: first",
+        "data:second",
+        "",
+        "data",
+        "",
+    ]);
+
+    assert_eq!(
+        events,
+        vec![
+            SseEvent {
+                event: "update".to_string(),
+                data: "first\nsecond".to_string(),
+                id: Some("7".to_string()),
+                retry: Some(Duration::from_millis(1500)),
+            },
+            SseEvent {
+                event: "message".to_string(),
+                data: String::new(),
+                id: Some("7".to_string()),
+                retry: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parser_skips_blocks_without_data() {
+    let mut parser = SseParser::new();
+
+    assert_eq!(parser.push_line("id: 3"), None);
+    assert_eq!(parser.push_line("retry: soon"), None);
+    assert_eq!(parser.push_line(""), None);
+    assert_eq!(parser.last_event_id(), Some("3"));
+
+    parser.push_line("unknown: field");
+    parser.push_line("data:  two spaces");
+    let event = parser.push_line("").unwrap();
+    assert_eq!(event.data, " two spaces");
+    assert_eq!(event.retry, None);
+}
+
+#[test]
+fn test_events_from_server() {
+    let body = "event: greeting\ndata: hello\n\n: comment\r\ndata: a\r\n\r\ndata: b\r\r";
+    let (base, server) = serve(vec![event_stream(body)]);
+    let client = HttpClient::new();
+
+    let events: Vec<SseEvent> = client
+        .events(&HttpRequest::get(format!("{}/stream", base)), once())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    let data: Vec<&str> = events.iter().map(|e| e.data.as_str()).collect();
+    assert_eq!(data, ["hello", "a", "b"]);
+    assert_eq!(events[0].event, "greeting");
+
+    let requests = server.join().unwrap();
+    let request = requests[0].to_lowercase();
+    assert!(request.contains("accept: text/event-stream"));
+    assert!(request.contains("cache-control: no-cache"));
+    assert!(!request.contains("last-event-id"));
+}
+
+#[test]
+fn test_reconnect_sends_last_event_id() {
+    let (base, server) = serve(vec![
+        event_stream("retry: 10\nid: 41\ndata: one\n\ndata: cut off"),
+        event_stream("id: 42\ndata: two\n\n"),
+    ]);
+    let client = HttpClient::new();
+
+    let mut stream = client.events(
+        &HttpRequest::get(format!("{}/stream", base)),
+        SseConfig::default(),
+    );
+    let first = stream.next().unwrap().unwrap();
+    let second = stream.next().unwrap().unwrap();
+
+    assert_eq!(first.data, "one");
+    assert_eq!(first.retry, Some(Duration::from_millis(10)));
+    assert_eq!(second.data, "two");
+    assert_eq!(second.id.as_deref(), Some("42"));
+    assert_eq!(stream.reconnects(), 1);
+
+    let requests = server.join().unwrap();
+    assert!(requests[1].to_lowercase().contains("last-event-id: 41"));
+}
+
+#[test]
+fn test_resume_from_last_event_id() {
+    let (base, server) = serve(vec![event_stream("data: resumed\n\n")]);
+    let config = SseConfig {
+        last_event_id: Some("99".to_string()),
+        ..once()
+    };
+
+    let events: Vec<_> = HttpClient::new()
+        .events(&HttpRequest::get(base), config)
+        .collect();
+
+    assert_eq!(events.len(), 1);
+    assert!(
+        server.join().unwrap()[0]
+            .to_lowercase()
+            .contains("last-event-id: 99")
+    );
+}
+
+#[test]
+fn test_no_content_ends_stream() {
+    let (base, _server) = serve(vec![response("204 No Content", &[], "")]);
+
+    let client = HttpClient::new();
+    let mut stream = client.events(&HttpRequest::get(base), SseConfig::default());
+
+    assert!(stream.next().is_none());
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn test_wrong_content_type_fails() {
+    let (base, _server) = serve(vec![response(
+        "200 OK",
+        &[("Content-Type", "application/json")],
+        "{}",
+    )]);
+
+    let client = HttpClient::new();
+    let mut stream = client.events(&HttpRequest::get(base), SseConfig::default());
+
+    assert!(matches!(
+        stream.next(),
+        Some(Err(HttpError::EventStreamError(_)))
+    ));
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn test_error_status_fails() {
+    let (base, _server) = serve(vec![response("401 Unauthorized", &[], "")]);
+
+    let result = HttpClient::new()
+        .events(&HttpRequest::get(base), SseConfig::default())
+        .next()
+        .unwrap();
+
+    match result {
+        Err(HttpError::EventStreamError(message)) => assert!(message.contains("401")),
+        other => panic!("expected an event stream error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_event_stream_outlives_client_timeout() {
+    let client = HttpClient::try_with_config(HttpClientConfig {
+        timeout_seconds: 1,
+        ..Default::default()
+    })
+    .unwrap();
+    let request = HttpRequest::get(slow_stream()).without_timeout();
+
+    let events: Vec<SseEvent> = client
+        .events(&request, once())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(events.len(), 4);
+
+    // Any other request must finish within the client timeout
+    let result = client.send(&HttpRequest::get(slow_stream()).without_timeout());
+    let error = result.unwrap_err();
+    assert!(error.to_string().contains("timed out"), "{:?}", error);
+}
+
+#[test]
+fn test_event_stream_ignores_request_timeout() {
+    // Every request has a timeout unless told otherwise
+    let request = HttpRequest::get(slow_stream()).with_timeout(1);
+
+    let events: Vec<SseEvent> = HttpClient::new()
+        .events(&request, once())
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+    assert_eq!(events.len(), 4);
+}
+
+#[test]
+fn test_line_longer_than_max_response_size_fails() {
+    let long_line = format!("data: {}\n\n", "x".repeat(64 * 1024));
+    let (base, _server) = serve(vec![response(
+        "200 OK",
+        &[("Content-Type", "text/event-stream")],
+        &long_line,
+    )]);
+    let client = HttpClient::try_with_config(HttpClientConfig {
+        max_response_size: Some(1024),
+        ..Default::default()
+    })
+    .unwrap();
+
+    let result = client.events(&HttpRequest::get(base), once()).next();
+
+    assert!(matches!(
+        result,
+        Some(Err(HttpError::ResponseTooLarge(1024)))
+    ));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{response, serve};
+use saffron_core::domain::request::HttpRequest;
+use saffron_http::{ConnectConfig, HttpClient, HttpClientConfig};
+use std::io::{self, Read, Write};
+
+#[test]
+fn test_plain_http_timings() {
+    let (base, _server) = serve(vec![response("200 OK", &[], "timed")]);
+
+    let response = HttpClient::new().send(&HttpRequest::get(base)).unwrap();
+    let timings = response.timings;
+
+    assert!(timings.dns.is_some());
+    assert!(timings.connect.is_some());
+    assert!(timings.tls.is_none());
+    assert!(timings.first_byte >= timings.dns.unwrap() + timings.connect.unwrap());
+    assert_eq!(timings.total(), timings.first_byte + timings.download);
+}
+
+#[test]
+fn test_streaming_records_download_time() {
+    let (base, _server) = serve(vec![response("200 OK", &[], "streamed")]);
+
+    let response = HttpClient::new()
+        .send_streaming(&HttpRequest::get(base), &mut io::sink(), |_| {})
+        .unwrap();
+
+    assert!(response.timings.first_byte > std::time::Duration::ZERO);
+    assert!(response.timings.total() >= response.timings.first_byte);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix_socket_timings() {
+    let path = std::env::temp_dir().join(format!("saffron-{}-timing.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let _ = stream.read(&mut [0; 1024]).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .unwrap();
+    });
+
+    let client = HttpClient::with_config(HttpClientConfig {
+        connect: ConnectConfig {
+            unix_socket: Some(path.clone()),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let timings = client.get("http://docker/_ping").unwrap().timings;
+
+    // Nothing is looked up, but the connect still takes place
+    assert!(timings.dns.is_none());
+    assert!(timings.connect.is_some());
+    assert!(timings.tls.is_none());
+    server.join().unwrap();
+    std::fs::remove_file(path).unwrap();
+}
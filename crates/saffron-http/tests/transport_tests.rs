@@ -0,0 +1,248 @@
+mod common;
+
+use common::{binary_response, response, serve};
+use saffron_core::domain::headers::Headers;
+use saffron_core::domain::request::{HttpMethod, HttpRequest};
+use saffron_core::domain::response::HttpResponse;
+use saffron_http::{
+    FixtureTransport, HttpClient, HttpClientConfig, HttpError, MockTransport, RetryPolicy,
+    UreqTransport,
+};
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("saffron-{}-{}", std::process::id(), name))
+}
+
+fn canned(status: u16, headers: &[(&str, &str)], body: &str) -> HttpResponse {
+    HttpResponse::new(
+        status,
+        String::new(),
+        headers.iter().copied().collect::<Headers>(),
+        body.as_bytes().to_vec(),
+        Duration::ZERO,
+        String::new(),
+    )
+}
+
+#[test]
+fn test_mock_returns_canned_response() {
+    let mock = MockTransport::new().with_response(
+        HttpMethod::Get,
+        "https://api.example.com/users",
+        canned(200, &[("Content-Type", "application/json")], "[]"),
+    );
+    let client = HttpClient::with_transport(HttpClientConfig::default(), mock.clone());
+
+    let response = client.get("https://api.example.com/users").unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body_as_str(), Some("[]"));
+    assert_eq!(response.url, "https://api.example.com/users");
+    assert!(response.is_json());
+}
+
+#[test]
+fn test_mock_sees_client_headers() {
+    let mock = MockTransport::new().with_response(
+        HttpMethod::Post,
+        "https://api.example.com/*",
+        canned(201, &[], ""),
+    );
+    let client = HttpClient::with_transport(HttpClientConfig::default(), mock.clone());
+
+    client
+        .send(&HttpRequest::post("https://api.example.com/items").with_json_body("{}"))
+        .unwrap();
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(
+        requests[0]
+            .get_header("User-Agent")
+            .unwrap()
+            .starts_with("Saffron/")
+    );
+    assert_eq!(
+        requests[0].content_type(),
+        Some("application/json; charset=utf-8")
+    );
+    assert!(requests[0].get_header("Accept-Encoding").is_some());
+}
+
+#[test]
+fn test_query_encoded_at_send_time() {
+    let mock = MockTransport::new().with_response(
+        HttpMethod::Get,
+        "https://api.example.com/search*",
+        canned(200, &[], ""),
+    );
+    let client = HttpClient::with_transport(HttpClientConfig::default(), mock.clone());
+
+    let request = HttpRequest::get("https://api.example.com/search?lang=en")
+        .with_query("q", "fish & chips")
+        .with_query("tag", "a=b");
+    let response = client.send(&request).unwrap();
+
+    let expected = "https://api.example.com/search?lang=en&q=fish%20%26%20chips&tag=a%3Db";
+    assert_eq!(mock.requests()[0].url, expected);
+    assert!(mock.requests()[0].query.is_empty());
+    assert_eq!(response.url, expected);
+}
+
+#[test]
+fn test_invalid_url_fails_before_sending() {
+    let mock = MockTransport::new();
+    let client = HttpClient::with_transport(HttpClientConfig::default(), mock.clone());
+
+    let result = client.send(&HttpRequest::get("https://{{host}}/users"));
+
+    match result {
+        Err(HttpError::InvalidUrl(message)) => assert_eq!(
+            message,
+            "https://{{host}}/users (invalid character '{' in host)"
+        ),
+        other => panic!("expected InvalidUrl, got {:?}", other.map(|r| r.status)),
+    }
+    assert!(mock.requests().is_empty());
+}
+
+#[test]
+fn test_mock_unmatched_request_fails() {
+    let mock = MockTransport::new().with_response(
+        HttpMethod::Get,
+        "https://api.example.com/users",
+        canned(200, &[], ""),
+    );
+    let client = HttpClient::with_transport(HttpClientConfig::default(), mock);
+
+    let result = client.delete("https://api.example.com/users");
+    assert!(matches!(result, Err(HttpError::RequestFailed(msg)) if msg.contains("DELETE")));
+}
+
+#[test]
+fn test_mock_responses_in_order_with_retries() {
+    let url = "https://api.example.com/flaky";
+    let mock = MockTransport::new()
+        .with_response(HttpMethod::Get, url, canned(503, &[], "busy"))
+        .with_response(HttpMethod::Get, url, canned(200, &[], "ok"));
+    let client = HttpClient::with_transport(
+        HttpClientConfig {
+            retry: RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                jitter: 0.0,
+                ..RetryPolicy::with_retries(2)
+            },
+            ..Default::default()
+        },
+        mock.clone(),
+    );
+
+    let response = client.get(url).unwrap();
+    assert_eq!(response.body_as_str(), Some("ok"));
+    assert_eq!(response.retries.len(), 1);
+
+    // The last response keeps answering
+    assert_eq!(client.get(url).unwrap().status, 200);
+    assert_eq!(mock.requests().len(), 3);
+}
+
+#[test]
+fn test_mock_redirects_and_cookies() {
+    let mock = MockTransport::new()
+        .with_response(
+            HttpMethod::Get,
+            "https://example.com/login",
+            canned(
+                302,
+                &[("Location", "/home"), ("Set-Cookie", "session=abc; Path=/")],
+                "",
+            ),
+        )
+        .with_response(
+            HttpMethod::Get,
+            "https://example.com/home",
+            canned(200, &[], "welcome"),
+        );
+    let client = HttpClient::with_transport(HttpClientConfig::default(), mock.clone());
+
+    let response = client.get("https://example.com/login").unwrap();
+
+    assert_eq!(response.body_as_str(), Some("welcome"));
+    assert_eq!(response.redirects.len(), 1);
+    assert_eq!(mock.requests()[1].get_header("Cookie"), Some("session=abc"));
+}
+
+#[test]
+fn test_fixture_record_then_replay() {
+    let path = temp_path("fixture-roundtrip.json");
+    let _ = std::fs::remove_file(&path);
+
+    let (base, server) = serve(vec![
+        response("200 OK", &[("Content-Type", "text/plain")], "first").into_bytes(),
+        binary_response("200 OK", &[], &[0xff, 0x00, 0xfe]),
+    ]);
+    let config = HttpClientConfig::default();
+
+    let recorder = FixtureTransport::record(UreqTransport::new(&config).unwrap(), &path);
+    let client = HttpClient::with_transport(config.clone(), recorder);
+    assert_eq!(client.get(&base).unwrap().body_as_str(), Some("first"));
+    assert_eq!(client.get(&base).unwrap().body, [0xff, 0x00, 0xfe]);
+    server.join().unwrap();
+
+    // Each save goes through a temporary file that is renamed over the fixture
+    let temp = format!("{}.{}.tmp", path.display(), std::process::id());
+    assert!(!std::path::Path::new(&temp).exists());
+
+    // The server is gone; answers now come from the fixture
+    let replay = FixtureTransport::replay(&path).unwrap();
+    assert!(!replay.is_recording());
+    let client = HttpClient::with_transport(config, replay);
+
+    let first = client.get(&base).unwrap();
+    assert_eq!(first.body_as_str(), Some("first"));
+    assert_eq!(first.content_type(), Some("text/plain"));
+    assert_eq!(client.get(&base).unwrap().body, [0xff, 0x00, 0xfe]);
+    assert!(matches!(
+        client.get(&format!("{}/other", base)),
+        Err(HttpError::RequestFailed(_))
+    ));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_replay_or_record() {
+    let path = temp_path("fixture-auto.json");
+    let _ = std::fs::remove_file(&path);
+    let config = HttpClientConfig::default();
+
+    let mock = MockTransport::new().with_response(
+        HttpMethod::Get,
+        "https://api.example.com/",
+        canned(200, &[], "recorded"),
+    );
+    let transport = FixtureTransport::replay_or_record(mock.clone(), &path).unwrap();
+    assert!(transport.is_recording());
+    HttpClient::with_transport(config.clone(), transport)
+        .get("https://api.example.com/")
+        .unwrap();
+
+    let transport = FixtureTransport::replay_or_record(mock.clone(), &path).unwrap();
+    assert!(!transport.is_recording());
+    let response = HttpClient::with_transport(config, transport)
+        .get("https://api.example.com/")
+        .unwrap();
+
+    assert_eq!(response.body_as_str(), Some("recorded"));
+    assert_eq!(mock.requests().len(), 1);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_replay_missing_fixture() {
+    let result = FixtureTransport::replay(temp_path("missing-fixture.json"));
+    assert!(matches!(result, Err(HttpError::IoError(_))));
+}
@@ -0,0 +1,88 @@
+//! Kept apart from `connect_tests` so no other test has TCP sockets open in
+//! this process while they are listed.
+#![cfg(target_os = "linux")]
+
+use saffron_http::{ConnectConfig, HttpClient, HttpClientConfig};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpStream};
+use std::os::unix::net::UnixListener;
+
+/// The state (`0A` is listening) and local port of each TCP socket this
+/// process has open.
+fn tcp_sockets() -> Vec<(String, u16)> {
+    let inodes: HashSet<String> = std::fs::read_dir("/proc/self/fd")
+        .unwrap()
+        .filter_map(|fd| std::fs::read_link(fd.ok()?.path()).ok())
+        .filter_map(|link| {
+            let link = link.to_string_lossy().into_owned();
+            Some(
+                link.strip_prefix("socket:[")?
+                    .strip_suffix(']')?
+                    .to_string(),
+            )
+        })
+        .collect();
+
+    let mut sockets = Vec::new();
+    for table in ["/proc/self/net/tcp", "/proc/self/net/tcp6"] {
+        let Ok(table) = std::fs::read_to_string(table) else {
+            continue;
+        };
+        for line in table.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if inodes.contains(fields[9]) {
+                let port = fields[1].rsplit(':').next().unwrap();
+                sockets.push((
+                    fields[3].to_string(),
+                    u16::from_str_radix(port, 16).unwrap(),
+                ));
+            }
+        }
+    }
+    sockets
+}
+
+#[test]
+fn test_unix_socket_is_not_reachable_over_tcp() {
+    let path = std::env::temp_dir().join(format!("saffron-{}-relay.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+
+    // While the request is in flight, look for a TCP bridge to the socket
+    // and try to connect to anything listening
+    let server = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 1024];
+        let _ = stream.read(&mut buf).unwrap();
+
+        let sockets = tcp_sockets();
+        let reachable: Vec<u16> = sockets
+            .iter()
+            .filter(|(state, _)| state == "0A")
+            .filter(|(_, port)| TcpStream::connect((Ipv4Addr::LOCALHOST, *port)).is_ok())
+            .map(|(_, port)| *port)
+            .collect();
+
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        (sockets, reachable)
+    });
+
+    let client = HttpClient::try_with_config(HttpClientConfig {
+        connect: ConnectConfig {
+            unix_socket: Some(path.clone()),
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .unwrap();
+    let response = client.get("http://docker/_ping").unwrap();
+
+    assert_eq!(response.status, 200);
+    let (sockets, reachable) = server.join().unwrap();
+    assert!(reachable.is_empty(), "accepted on {:?}", reachable);
+    assert!(sockets.is_empty(), "TCP sockets open: {:?}", sockets);
+    std::fs::remove_file(path).unwrap();
+}
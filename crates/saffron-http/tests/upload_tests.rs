@@ -0,0 +1,96 @@
+mod common;
+
+use common::{response, serve};
+use saffron_core::domain::request::{
+    BodyStream, FormDataContent, FormDataPart, HttpRequest, RequestBody,
+};
+use saffron_http::{HttpClient, HttpError};
+use std::fs;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("saffron-{}-{}", std::process::id(), name));
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_file_stream_sends_content_length() {
+    let path = temp_file("upload.bin", "file contents");
+    let (base, server) = serve(vec![response("200 OK", &[], "")]);
+
+    let request = HttpRequest::put(base).with_stream_body(BodyStream::from_file(&path).unwrap());
+    HttpClient::new().send(&request).unwrap();
+
+    let received = server.join().unwrap();
+    assert!(received[0].contains("Content-Length: 13\r\n"));
+    assert!(received[0].contains("Content-Type: application/octet-stream\r\n"));
+    assert!(received[0].ends_with("\r\n\r\nfile contents"));
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_reader_stream_without_length_is_chunked() {
+    let (base, server) = serve(vec![response("200 OK", &[], "")]);
+
+    let stream = BodyStream::from_reader(Cursor::new(b"streamed".to_vec()), None);
+    let request = HttpRequest::post(base).with_stream_body(stream);
+    HttpClient::new().send(&request).unwrap();
+
+    let received = server.join().unwrap();
+    assert!(received[0].contains("Transfer-Encoding: chunked\r\n"));
+    assert!(received[0].ends_with("streamed"));
+}
+
+#[test]
+fn test_reader_stream_can_only_be_sent_once() {
+    let (base, _server) = serve(vec![response("200 OK", &[], "")]);
+
+    let stream = BodyStream::from_reader(Cursor::new(b"once".to_vec()), Some(4));
+    let request = HttpRequest::post(base).with_stream_body(stream);
+    let client = HttpClient::new();
+
+    client.send(&request).unwrap();
+    assert!(matches!(client.send(&request), Err(HttpError::IoError(_))));
+}
+
+#[test]
+fn test_multipart_streams_file_parts() {
+    let path = temp_file("report.txt", "report body");
+    let (base, server) = serve(vec![response("200 OK", &[], "")]);
+
+    let parts = vec![
+        FormDataPart {
+            name: "title".to_string(),
+            content: FormDataContent::Text("Q3".to_string()),
+        },
+        FormDataPart {
+            name: "file".to_string(),
+            content: FormDataContent::Stream {
+                filename: "report.txt".to_string(),
+                stream: BodyStream::from_file(&path).unwrap(),
+                content_type: Some("text/plain".to_string()),
+            },
+        },
+    ];
+    let request = HttpRequest::post(base).with_body(RequestBody::FormData(parts));
+    HttpClient::new().send(&request).unwrap();
+
+    let received = server.join().unwrap();
+    let (head, body) = received[0].split_once("\r\n\r\n").unwrap();
+
+    let content_length = head
+        .lines()
+        .find_map(|line| line.strip_prefix("Content-Length: "))
+        .unwrap();
+    assert_eq!(content_length.parse::<usize>().unwrap(), body.len());
+
+    assert!(body.contains("Content-Disposition: form-data; name=\"title\"\r\n\r\nQ3\r\n"));
+    assert!(body.contains(
+        "Content-Disposition: form-data; name=\"file\"; filename=\"report.txt\"\r\n\
+         Content-Type: text/plain\r\n\r\nreport body\r\n"
+    ));
+    assert!(body.ends_with("--\r\n"));
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,377 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use saffron_core::domain::auth::Auth;
+use saffron_core::domain::request::HttpRequest;
+use saffron_http::{CloseFrame, HttpClient, HttpClientConfig, HttpError, Message, ProxyConfig};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The server side of a test connection. Frames are written unmasked and
+/// read with the client's mask removed.
+struct Peer {
+    stream: TcpStream,
+}
+
+impl Peer {
+    fn send(&mut self, fin: bool, opcode: u8, payload: &[u8]) {
+        let mut frame = vec![if fin { 0x80 } else { 0 } | opcode];
+        match payload.len() {
+            len @ 0..=125 => frame.push(len as u8),
+            len => {
+                frame.push(126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(payload);
+        self.stream.write_all(&frame).unwrap();
+    }
+
+    fn receive(&mut self) -> (u8, Vec<u8>) {
+        let mut head = [0; 2];
+        self.stream.read_exact(&mut head).unwrap();
+        assert_ne!(head[1] & 0x80, 0, "client frames must be masked");
+
+        let len = match head[1] & 0x7F {
+            126 => {
+                let mut len = [0; 2];
+                self.stream.read_exact(&mut len).unwrap();
+                u16::from_be_bytes(len) as usize
+            }
+            len => len as usize,
+        };
+        let mut mask = [0; 4];
+        self.stream.read_exact(&mut mask).unwrap();
+        let mut payload = vec![0; len];
+        self.stream.read_exact(&mut payload).unwrap();
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+        (head[0] & 0x0F, payload)
+    }
+}
+
+/// Accepts one WebSocket connection, answers the handshake with `extra`
+/// response headers and hands the connection to `script`. Returns the URL
+/// and a handle yielding the raw upgrade request.
+fn serve_ws(
+    extra: &'static str,
+    script: impl FnOnce(Peer) + Send + 'static,
+) -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}/chat", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            request.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+
+        let key = request
+            .lines()
+            .find_map(|l| l.strip_prefix("Sec-WebSocket-Key: "))
+            .unwrap();
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Accept: {}\r\n{}\r\n",
+            accept(key),
+            extra
+        )
+        .unwrap();
+
+        script(Peer { stream });
+        request
+    });
+
+    (url, handle)
+}
+
+/// An HTTP proxy that accepts one CONNECT and relays the tunnel until both
+/// ends hang up. Returns the proxy URL and a handle yielding the CONNECT
+/// request.
+fn serve_connect_proxy() -> (String, JoinHandle<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = thread::spawn(move || {
+        let (client, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        let mut request = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            request.push_str(&line);
+            if line == "\r\n" {
+                break;
+            }
+        }
+        let target = request.split_whitespace().nth(1).unwrap();
+        let mut upstream = TcpStream::connect(target).unwrap();
+        let mut client = client;
+        client
+            .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .unwrap();
+
+        let mut to_upstream = upstream.try_clone().unwrap();
+        let forward = thread::spawn(move || {
+            let _ = io::copy(&mut reader, &mut to_upstream);
+            let _ = to_upstream.shutdown(Shutdown::Write);
+        });
+        let _ = io::copy(&mut upstream, &mut client);
+        let _ = client.shutdown(Shutdown::Write);
+        forward.join().unwrap();
+        request
+    });
+
+    (url, handle)
+}
+
+fn accept(key: &str) -> String {
+    let digest = ring::digest::digest(
+        &ring::digest::SHA1_FOR_LEGACY_USE_ONLY,
+        format!("{}258EAFA5-E914-47DA-95CA-C5AB0DC85B11", key).as_bytes(),
+    );
+    BASE64.encode(digest.as_ref())
+}
+
+#[test]
+fn test_accept_key_example() {
+    // The example from RFC 6455 §1.3
+    assert_eq!(
+        accept("dGhlIHNhbXBsZSBub25jZQ=="),
+        "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+    );
+}
+
+#[test]
+fn test_handshake_and_echo() {
+    let (url, server) = serve_ws("Sec-WebSocket-Protocol: chat.v2\r\n", |mut peer| {
+        for _ in 0..2 {
+            let (opcode, payload) = peer.receive();
+            peer.send(true, opcode, &payload);
+        }
+    });
+
+    let request = HttpRequest::get(&url)
+        .with_header("Sec-WebSocket-Protocol", "chat.v2, chat.v1")
+        .with_auth(Auth::bearer("t0ken"));
+    let mut ws = HttpClient::new().websocket(&request).unwrap();
+
+    assert_eq!(ws.protocol(), Some("chat.v2"));
+    ws.send_text("hello").unwrap();
+    assert_eq!(ws.read().unwrap(), Message::Text("hello".to_string()));
+    ws.send_binary(vec![0u8; 300]).unwrap();
+    assert_eq!(ws.read().unwrap(), Message::Binary(vec![0u8; 300]));
+
+    let upgrade = server.join().unwrap();
+    assert!(upgrade.starts_with("GET /chat HTTP/1.1\r\n"));
+    assert!(upgrade.contains("Upgrade: websocket\r\n"));
+    assert!(upgrade.contains("Sec-WebSocket-Version: 13\r\n"));
+    assert!(upgrade.contains("Sec-WebSocket-Protocol: chat.v2, chat.v1\r\n"));
+    assert!(upgrade.contains("Authorization: Bearer t0ken\r\n"));
+}
+
+#[test]
+fn test_fragments_and_ping() {
+    let (url, server) = serve_ws("", |mut peer| {
+        peer.send(false, 0x1, b"hel");
+        peer.send(true, 0x9, b"are you there");
+        peer.send(true, 0x0, b"lo");
+
+        // The ping is answered with a pong carrying the same data
+        assert_eq!(peer.receive(), (0xA, b"are you there".to_vec()));
+    });
+
+    let mut ws = HttpClient::new()
+        .websocket(&HttpRequest::get(&url))
+        .unwrap();
+
+    assert_eq!(ws.read().unwrap(), Message::Ping(b"are you there".to_vec()));
+    assert_eq!(ws.read().unwrap(), Message::Text("hello".to_string()));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_server_close() {
+    let (url, server) = serve_ws("", |mut peer| {
+        let mut payload = 1001u16.to_be_bytes().to_vec();
+        payload.extend_from_slice(b"restarting");
+        peer.send(true, 0x8, &payload);
+
+        let (opcode, reply) = peer.receive();
+        assert_eq!(opcode, 0x8);
+        assert_eq!(reply, 1001u16.to_be_bytes());
+    });
+
+    let mut ws = HttpClient::new()
+        .websocket(&HttpRequest::get(&url))
+        .unwrap();
+
+    let frame = CloseFrame::new(1001, "restarting");
+    assert_eq!(ws.read().unwrap(), Message::Close(Some(frame.clone())));
+    assert_eq!(frame.to_string(), "1001 (going away): restarting");
+    assert!(ws.is_closed());
+    assert!(matches!(ws.read(), Err(HttpError::WebSocketError(_))));
+    assert!(ws.send_text("too late").is_err());
+    server.join().unwrap();
+}
+
+#[test]
+fn test_client_close() {
+    let (url, server) = serve_ws("", |mut peer| {
+        let (opcode, payload) = peer.receive();
+        assert_eq!(opcode, 0x8);
+        assert_eq!(&payload[..2], 4000u16.to_be_bytes());
+        peer.send(true, 0x1, b"in flight");
+        peer.send(true, 0x8, &payload);
+    });
+
+    let mut ws = HttpClient::new()
+        .websocket(&HttpRequest::get(&url))
+        .unwrap();
+    let reply = ws.close(Some(CloseFrame::new(4000, "done"))).unwrap();
+
+    assert_eq!(reply, Some(CloseFrame::new(4000, "done")));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_frame_length_overflow_closes_with_1009() {
+    let (url, server) = serve_ws("", |mut peer| {
+        // A 64-bit length that doesn't fit in memory
+        let mut frame = vec![0x82, 127];
+        frame.extend_from_slice(&u64::MAX.to_be_bytes());
+        peer.stream.write_all(&frame).unwrap();
+
+        let (opcode, payload) = peer.receive();
+        assert_eq!(opcode, 0x8);
+        assert_eq!(payload, 1009u16.to_be_bytes());
+    });
+
+    let client = HttpClient::try_with_config(HttpClientConfig {
+        max_response_size: None,
+        ..Default::default()
+    })
+    .unwrap();
+    let mut ws = client.websocket(&HttpRequest::get(&url)).unwrap();
+
+    assert!(matches!(ws.read(), Err(HttpError::WebSocketError(_))));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_read_timeout_keeps_partial_frame() {
+    let (url, server) = serve_ws("", |mut peer| {
+        peer.stream.write_all(&[0x81, 0x05, b'h', b'e']).unwrap();
+        thread::sleep(Duration::from_millis(300));
+        peer.stream.write_all(b"llo").unwrap();
+        thread::sleep(Duration::from_millis(100));
+    });
+
+    let mut ws = HttpClient::new()
+        .websocket(&HttpRequest::get(&url))
+        .unwrap();
+    ws.set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+
+    let mut timeouts = 0;
+    let message = loop {
+        match ws.read() {
+            Err(HttpError::Timeout) => timeouts += 1,
+            other => break other.unwrap(),
+        }
+    };
+
+    assert!(timeouts > 0);
+    assert_eq!(message, Message::Text("hello".to_string()));
+    server.join().unwrap();
+}
+
+#[test]
+fn test_rejected_upgrade() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("ws://{}/", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut buf = [0; 1024];
+        let _ = stream.read(&mut buf).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+    });
+
+    let result = HttpClient::new().websocket(&HttpRequest::get(&url));
+
+    match result {
+        Err(HttpError::WebSocketError(message)) => assert!(message.contains("403 Forbidden")),
+        Err(e) => panic!("expected a WebSocket error, got {}", e),
+        Ok(_) => panic!("expected the upgrade to fail"),
+    }
+    server.join().unwrap();
+}
+
+#[test]
+fn test_rejects_non_websocket_url() {
+    let result = HttpClient::new().websocket(&HttpRequest::get("ftp://example.com/"));
+
+    assert!(matches!(result, Err(HttpError::InvalidUrl(_))));
+}
+
+#[test]
+fn test_rejects_line_breaks_in_headers() {
+    // Nothing listens on the discard port, so only an early error passes
+    let request =
+        HttpRequest::get("ws://127.0.0.1:9/").with_header("X-Note", "hi\r\nX-Injected: yes");
+    let result = HttpClient::new().websocket(&request);
+
+    match result {
+        Err(HttpError::RequestFailed(message)) => assert!(message.contains("X-Note")),
+        Err(e) => panic!("expected the header to be rejected, got {}", e),
+        Ok(_) => panic!("expected the header to be rejected"),
+    }
+
+    let client = HttpClient::with_config(HttpClientConfig {
+        user_agent: Some("agent\nX-Injected: yes".to_string()),
+        ..Default::default()
+    });
+    let result = client.websocket(&HttpRequest::get("ws://127.0.0.1:9/"));
+    assert!(matches!(result, Err(HttpError::RequestFailed(_))));
+}
+
+#[test]
+fn test_connects_through_http_proxy() {
+    let (url, server) = serve_ws("", |mut peer| {
+        let (opcode, payload) = peer.receive();
+        peer.send(true, opcode, &payload);
+    });
+    let (proxy_url, proxy) = serve_connect_proxy();
+
+    let client = HttpClient::with_config(HttpClientConfig {
+        proxy: ProxyConfig::all(proxy_url.parse().unwrap()),
+        ..Default::default()
+    });
+    let mut ws = client.websocket(&HttpRequest::get(&url)).unwrap();
+    ws.send_text("hello").unwrap();
+    assert_eq!(ws.read().unwrap(), Message::Text("hello".to_string()));
+    drop(ws);
+
+    let upgrade = server.join().unwrap();
+    assert!(upgrade.starts_with("GET /chat HTTP/1.1\r\n"));
+    let connect = proxy.join().unwrap();
+    let authority = url.trim_start_matches("ws://").trim_end_matches("/chat");
+    assert!(
+        connect.starts_with(&format!("CONNECT {} HTTP/1.1\r\n", authority)),
+        "{}",
+        connect
+    );
+}
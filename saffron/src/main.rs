@@ -1,6 +1,9 @@
 use clap::Parser;
 use saffron_cli::cli::{Cli, Commands};
-use saffron_cli::handlers::{handle_collection, handle_env, handle_history, handle_send};
+use saffron_cli::handlers::{
+    handle_collection, handle_cookies, handle_env, handle_gql, handle_grpc, handle_history,
+    handle_send, handle_sse, handle_ws,
+};
 
 fn main() {
     let cli = Cli::parse();
@@ -10,27 +13,193 @@ fn main() {
             url,
             method,
             header,
+            query,
             body,
             json,
             data,
+            form,
             timeout,
             follow_redirects,
             env,
+            allow_unresolved,
             verbose,
+            timing,
             from_collection,
+            output,
+            resume,
+            compressed,
+            raw,
+            tls,
+            proxy,
+            connect,
+            retry,
+            auth,
         } => {
             handle_send(
                 url,
                 method,
                 header,
+                query,
                 body,
                 json,
                 data,
+                form,
                 timeout,
                 follow_redirects,
                 env,
+                allow_unresolved,
                 verbose,
+                timing,
                 from_collection,
+                output,
+                resume,
+                compressed,
+                raw,
+                tls,
+                proxy,
+                connect,
+                retry,
+                auth,
+            );
+        }
+        Commands::Sse {
+            url,
+            header,
+            env,
+            allow_unresolved,
+            timeout,
+            max_events,
+            last_event_id,
+            no_reconnect,
+            tls,
+            proxy,
+            connect,
+            auth,
+        } => {
+            handle_sse(
+                url,
+                header,
+                env,
+                allow_unresolved,
+                timeout,
+                max_events,
+                last_event_id,
+                no_reconnect,
+                tls,
+                proxy,
+                connect,
+                auth,
+            );
+        }
+        Commands::Gql {
+            url,
+            query,
+            var,
+            variables,
+            operation,
+            introspect,
+            output,
+            save,
+            header,
+            env,
+            allow_unresolved,
+            timeout,
+            verbose,
+            tls,
+            proxy,
+            connect,
+            auth,
+        } => {
+            handle_gql(
+                url,
+                query,
+                var,
+                variables,
+                operation,
+                introspect,
+                output,
+                save,
+                header,
+                env,
+                allow_unresolved,
+                timeout,
+                verbose,
+                tls,
+                proxy,
+                connect,
+                auth,
+            );
+        }
+        Commands::Ws {
+            url,
+            saved,
+            header,
+            env,
+            allow_unresolved,
+            protocol,
+            script,
+            timeout,
+            tls,
+            proxy,
+            connect,
+            auth,
+        } => {
+            handle_ws(
+                url,
+                saved,
+                header,
+                env,
+                allow_unresolved,
+                protocol,
+                script,
+                timeout,
+                tls,
+                proxy,
+                connect,
+                auth,
+            );
+        }
+        Commands::Grpc {
+            url,
+            method,
+            saved,
+            proto,
+            import_path,
+            descriptor_set,
+            data,
+            list,
+            web,
+            save,
+            header,
+            env,
+            allow_unresolved,
+            timeout,
+            verbose,
+            tls,
+            proxy,
+            connect,
+            auth,
+        } => {
+            handle_grpc(
+                url,
+                method,
+                saved,
+                proto,
+                import_path,
+                descriptor_set,
+                data,
+                list,
+                web,
+                save,
+                header,
+                env,
+                allow_unresolved,
+                timeout,
+                verbose,
+                tls,
+                proxy,
+                connect,
+                auth,
             );
         }
         Commands::Collection { action } => {
@@ -42,5 +211,8 @@ fn main() {
         Commands::History { action } => {
             handle_history(action);
         }
+        Commands::Cookies { action } => {
+            handle_cookies(action);
+        }
     }
 }